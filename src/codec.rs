@@ -15,24 +15,48 @@
 //
 
 mod argument;
+#[allow(clippy::module_inception)]
+mod codec;
 pub mod consts;
 mod decoder;
 mod encoder;
 mod error;
 mod frame;
 mod options;
+mod tn3270e;
+mod transform;
 
-pub use self::argument::TelnetArgument;
+pub use self::argument::{
+    Authentication, AuthenticationModifiers, AuthenticationPair,
+    AuthenticationType, ByteMacro, ByteMacroReason, ComPortCommand,
+    ComPortControl, Control, DataEntry, Encryption, EncryptionType,
+    ExtendedOptions, FlowControl, ForwardX, GenericMudMessage, Kermit,
+    LineState, ModemState, OutputMarking, Parity, Purge, RCTEClasses,
+    SUPDUPDisplay, SUPDUPDisplayDecoder, SUPDUPOutput, SUPDUPParameters,
+    SendLocation, StopSize, TN3270EFunction, TN3270EReason, TN3270EResource,
+    TelnetArgument, TerminalLocation, TerminalSpeed, TerminalType,
+    TerminalUserId, WindowSize, X3Parameter, X3Setting, XDisplayLocation, RCTE,
+    TN3270E, X3PAD,
+};
+pub use self::codec::TelnetCodec;
 pub use self::decoder::TelnetDecoder;
 pub use self::encoder::TelnetEncoder;
 pub use self::error::TelnetError;
 pub use self::frame::TelnetFrame;
-pub use self::options::TelnetOption;
+pub use self::options::{ExtendedOption, TelnetOption};
+pub use self::tn3270e::{
+    TN3270EAssembler, TN3270EDataType, TN3270EHeader, TN3270ERecord,
+    TN3270ERecords,
+};
+pub use self::transform::StreamTransformer;
 
 #[cfg(test)]
 mod tests {
     use super::consts;
-    use super::{TelnetDecoder, TelnetEncoder, TelnetFrame, TelnetOption};
+    use super::{
+        StreamTransformer, TelnetDecoder, TelnetEncoder, TelnetFrame,
+        TelnetOption,
+    };
     use bytes::BytesMut;
 
     #[test]
@@ -103,6 +127,50 @@ mod tests {
         );
     }
 
+    struct Xor(u8);
+
+    impl StreamTransformer for Xor {
+        fn transform(&mut self, data: &mut [u8]) {
+            data.iter_mut().for_each(|byte| *byte ^= self.0);
+        }
+    }
+
+    #[test]
+    fn telnet_transform() {
+        let mut encoder = TelnetEncoder::default();
+        let mut decoder = TelnetDecoder::default();
+        let mut buffer = BytesMut::with_capacity(16);
+
+        encoder
+            .encode(TelnetFrame::Data(b'a'), &mut buffer)
+            .unwrap();
+        encoder.set_transformer(Some(Box::new(Xor(0x5A))));
+        encoder
+            .encode(TelnetFrame::Data(consts::IAC), &mut buffer)
+            .unwrap();
+        encoder.encode(TelnetFrame::GoAhead, &mut buffer).unwrap();
+        assert_eq!(
+            &[b'a', 0xA5, 0xA5, 0xA5, consts::GA ^ 0x5A][..],
+            &buffer[..],
+            "encoder transformer didn't apply"
+        );
+
+        let mut actual_output = Vec::new();
+        actual_output.push(decoder.decode(&mut buffer).unwrap());
+        decoder.set_transformer(Some(Box::new(Xor(0x5A))));
+        while let Some(frame) = decoder.decode(&mut buffer).unwrap() {
+            actual_output.push(Some(frame))
+        }
+        assert_eq!(
+            vec![
+                Some(TelnetFrame::Data(b'a')),
+                Some(TelnetFrame::Data(consts::IAC)),
+                Some(TelnetFrame::GoAhead),
+            ],
+            actual_output
+        );
+    }
+
     #[test]
     fn decode_iac_activation() {
         let mut codec = TelnetDecoder::default();
@@ -14,38 +14,251 @@
 // limitations under the License.
 //
 
+mod authentication;
+mod bm;
+mod comport;
+mod det;
+mod encryption;
+mod exopl;
+mod forwardx;
+mod gmcp;
+mod kermit;
+mod lflow;
 mod msdp;
 mod mssp;
 mod naocrd;
 mod naohts;
+mod naws;
+mod outmrk;
+mod rcte;
+mod sndloc;
 mod status;
+mod supdup;
+mod tn3270e;
+mod tspeed;
+mod ttyloc;
+mod ttype;
+mod tuid;
+mod x3pad;
+mod xdisploc;
 
 use super::{consts, TelnetError};
+use crate::codec::argument::msdp::{
+    decode_msdp, encode_msdp, encoded_msdp_length,
+};
+use crate::codec::TelnetOption;
 use bytes::BufMut;
-use crate::codec::argument::msdp::{encoded_msdp_length, encode_msdp};
+
+pub use self::authentication::{
+    Authentication, AuthenticationModifiers, AuthenticationPair,
+    AuthenticationType,
+};
+pub use self::bm::{ByteMacro, ByteMacroReason};
+pub use self::comport::{
+    ComPortCommand, ComPortControl, Control, LineState, ModemState, Parity,
+    Purge, StopSize,
+};
+pub use self::det::DataEntry;
+pub use self::encryption::{Encryption, EncryptionType};
+pub use self::exopl::ExtendedOptions;
+pub use self::forwardx::ForwardX;
+pub use self::gmcp::GenericMudMessage;
+pub use self::kermit::Kermit;
+pub use self::lflow::FlowControl;
+pub use self::naws::WindowSize;
+pub use self::outmrk::OutputMarking;
+pub use self::rcte::{RCTEClasses, RCTE};
+pub use self::sndloc::SendLocation;
+pub use self::supdup::{
+    SUPDUPDisplay, SUPDUPDisplayDecoder, SUPDUPOutput, SUPDUPParameters,
+};
+pub use self::tn3270e::{
+    TN3270EFunction, TN3270EReason, TN3270EResource, TN3270E,
+};
+pub use self::tspeed::TerminalSpeed;
+pub use self::ttyloc::TerminalLocation;
+pub use self::ttype::TerminalType;
+pub use self::tuid::TerminalUserId;
+pub use self::x3pad::{X3Parameter, X3Setting, X3PAD};
+pub use self::xdisploc::XDisplayLocation;
 
 /// Telnet Subnegotiation Argument
 #[derive(Clone, Debug, PartialEq)]
 pub enum TelnetArgument {
+    Authentication(Authentication),
+    ComPortControl(ComPortControl),
+    Encryption(Encryption),
+    ExtendedOptions(ExtendedOptions),
+    ByteMacro(ByteMacro),
+    FlowControl(FlowControl),
+    DataEntry(DataEntry),
+    SendLocation(SendLocation),
+    TerminalUserId(TerminalUserId),
+    OutputMarking(OutputMarking),
+    TerminalType(TerminalType),
+    WindowSize(WindowSize),
+    GMCP(GenericMudMessage),
+    X3PAD(X3PAD),
+    RCTE(RCTE),
+    ForwardX(ForwardX),
+    Kermit(Kermit),
     MSDP(msdp::MudServerData),
     MSSP(mssp::MudServerStatus),
     NAOCRD(naocrd::NAOCRD),
     NAOHTS(naohts::NAOHTS),
     Status(status::OptionStatus),
+    SUPDUPOutput(SUPDUPOutput),
+    TerminalLocation(TerminalLocation),
+    TerminalSpeed(TerminalSpeed),
+    TN3270E(TN3270E),
     Unknown(Vec<u8>),
+    XDisplayLocation(XDisplayLocation),
 }
 
-
 pub fn encoded_argument_length(argument: &TelnetArgument) -> usize {
     match argument {
+        TelnetArgument::Authentication(authentication) => authentication.len(),
+        TelnetArgument::ComPortControl(control) => control.len(),
+        TelnetArgument::Encryption(encryption) => encryption.len(),
+        TelnetArgument::ExtendedOptions(exopl) => exopl.len(),
+        TelnetArgument::ByteMacro(bm) => bm.len(),
+        TelnetArgument::FlowControl(flow) => flow.len(),
+        TelnetArgument::DataEntry(entry) => entry.len(),
+        TelnetArgument::SendLocation(location) => location.len(),
+        TelnetArgument::TerminalUserId(user) => user.len(),
+        TelnetArgument::OutputMarking(marking) => marking.len(),
+        TelnetArgument::TerminalType(ttype) => ttype.len(),
+        TelnetArgument::WindowSize(size) => size.len(),
+        TelnetArgument::GMCP(message) => message.len(),
+        TelnetArgument::X3PAD(pad) => pad.len(),
+        TelnetArgument::RCTE(rcte) => rcte.len(),
+        TelnetArgument::ForwardX(forward) => forward.len(),
+        TelnetArgument::Kermit(kermit) => kermit.len(),
         TelnetArgument::MSDP(msdp) => encoded_msdp_length(msdp),
-        _ => 0,
+        TelnetArgument::MSSP(status) => status.len(),
+        TelnetArgument::NAOCRD(naocrd) => naocrd.len(),
+        TelnetArgument::NAOHTS(naohts) => naohts.len(),
+        TelnetArgument::Status(status) => status.len(),
+        TelnetArgument::SUPDUPOutput(output) => output.len(),
+        TelnetArgument::TerminalLocation(location) => location.len(),
+        TelnetArgument::TerminalSpeed(speed) => speed.len(),
+        TelnetArgument::TN3270E(tn3270e) => tn3270e.len(),
+        TelnetArgument::Unknown(data) => data.len(),
+        TelnetArgument::XDisplayLocation(display) => display.len(),
     }
 }
 
-pub fn encode_argument<T: BufMut>(argument: &TelnetArgument, dst: &mut T) {
+pub fn encode_argument<T: BufMut>(
+    argument: &TelnetArgument,
+    dst: &mut T,
+) -> Result<(), TelnetError> {
     match argument {
-        TelnetArgument::MSDP(msdp) => encode_msdp(msdp, dst),
-        _ => unimplemented!(),
+        TelnetArgument::Authentication(authentication) => {
+            authentication.encode(dst)
+        },
+        TelnetArgument::ComPortControl(control) => control.encode(dst),
+        TelnetArgument::Encryption(encryption) => encryption.encode(dst),
+        TelnetArgument::ExtendedOptions(exopl) => exopl.encode(dst),
+        TelnetArgument::ByteMacro(bm) => bm.encode(dst),
+        TelnetArgument::FlowControl(flow) => flow.encode(dst),
+        TelnetArgument::DataEntry(entry) => entry.encode(dst),
+        TelnetArgument::SendLocation(location) => location.encode(dst),
+        TelnetArgument::TerminalUserId(user) => user.encode(dst),
+        TelnetArgument::OutputMarking(marking) => marking.encode(dst),
+        TelnetArgument::TerminalType(ttype) => ttype.encode(dst),
+        TelnetArgument::WindowSize(size) => size.encode(dst),
+        TelnetArgument::GMCP(message) => message.encode(dst),
+        TelnetArgument::X3PAD(pad) => pad.encode(dst),
+        TelnetArgument::RCTE(rcte) => rcte.encode(dst),
+        TelnetArgument::ForwardX(forward) => forward.encode(dst),
+        TelnetArgument::Kermit(kermit) => kermit.encode(dst),
+        TelnetArgument::MSDP(msdp) => {
+            encode_msdp(msdp, dst);
+            Ok(())
+        },
+        TelnetArgument::MSSP(status) => status.encode(dst),
+        TelnetArgument::NAOCRD(naocrd) => naocrd.encode(dst),
+        TelnetArgument::NAOHTS(naohts) => naohts.encode(dst),
+        TelnetArgument::Status(status) => status.encode(dst),
+        TelnetArgument::SUPDUPOutput(output) => output.encode(dst),
+        TelnetArgument::TerminalLocation(location) => location.encode(dst),
+        TelnetArgument::TerminalSpeed(speed) => speed.encode(dst),
+        TelnetArgument::TN3270E(tn3270e) => tn3270e.encode(dst),
+        TelnetArgument::Unknown(data) => {
+            dst.put(data.as_slice());
+            Ok(())
+        },
+        TelnetArgument::XDisplayLocation(display) => display.encode(dst),
     }
 }
+
+/// Decode a Subnegotiation payload, falling back to `TelnetArgument::Unknown`
+/// for unsupported options or malformed payloads.
+pub fn decode_argument(
+    option: TelnetOption,
+    buffer: Vec<u8>,
+) -> TelnetArgument {
+    let decoded = match option {
+        TelnetOption::Authentication => {
+            Authentication::decode(&mut buffer.as_slice())
+                .map(TelnetArgument::Authentication)
+        },
+        TelnetOption::ByteMacro => ByteMacro::decode(&mut buffer.as_slice())
+            .map(TelnetArgument::ByteMacro),
+        TelnetOption::CPCO => ComPortControl::decode(&mut buffer.as_slice())
+            .map(TelnetArgument::ComPortControl),
+        TelnetOption::DET => DataEntry::decode(&mut buffer.as_slice())
+            .map(TelnetArgument::DataEntry),
+        TelnetOption::Encryption => Encryption::decode(&mut buffer.as_slice())
+            .map(TelnetArgument::Encryption),
+        TelnetOption::EXOPL => ExtendedOptions::decode(&mut buffer.as_slice())
+            .map(TelnetArgument::ExtendedOptions),
+        TelnetOption::ForwardX => ForwardX::decode(&mut buffer.as_slice())
+            .map(TelnetArgument::ForwardX),
+        TelnetOption::GMCP => GenericMudMessage::decode(&mut buffer.as_slice())
+            .map(TelnetArgument::GMCP),
+        TelnetOption::Kermit => {
+            Kermit::decode(&mut buffer.as_slice()).map(TelnetArgument::Kermit)
+        },
+        TelnetOption::LFLOW => FlowControl::decode(&mut buffer.as_slice())
+            .map(TelnetArgument::FlowControl),
+        TelnetOption::MSDP => {
+            decode_msdp(buffer.as_slice()).map(TelnetArgument::MSDP)
+        },
+        TelnetOption::NAWS => WindowSize::decode(&mut buffer.as_slice())
+            .map(TelnetArgument::WindowSize),
+        TelnetOption::OUTMRK => OutputMarking::decode(&mut buffer.as_slice())
+            .map(TelnetArgument::OutputMarking),
+        TelnetOption::RCTE => {
+            RCTE::decode(&mut buffer.as_slice()).map(TelnetArgument::RCTE)
+        },
+        TelnetOption::SNDLOC => SendLocation::decode(&mut buffer.as_slice())
+            .map(TelnetArgument::SendLocation),
+        TelnetOption::SUPDUPOutput => {
+            SUPDUPOutput::decode(&mut buffer.as_slice())
+                .map(TelnetArgument::SUPDUPOutput)
+        },
+        TelnetOption::TN3270E => {
+            TN3270E::decode(&mut buffer.as_slice()).map(TelnetArgument::TN3270E)
+        },
+        TelnetOption::TSPEED => TerminalSpeed::decode(&mut buffer.as_slice())
+            .map(TelnetArgument::TerminalSpeed),
+        TelnetOption::TTYLOC => {
+            TerminalLocation::decode(&mut buffer.as_slice())
+                .map(TelnetArgument::TerminalLocation)
+        },
+        TelnetOption::TTYPE => TerminalType::decode(&mut buffer.as_slice())
+            .map(TelnetArgument::TerminalType),
+        TelnetOption::TUID => TerminalUserId::decode(&mut buffer.as_slice())
+            .map(TelnetArgument::TerminalUserId),
+        TelnetOption::X3PAD => {
+            X3PAD::decode(&mut buffer.as_slice()).map(TelnetArgument::X3PAD)
+        },
+        TelnetOption::XDISPLOC => {
+            XDisplayLocation::decode(&mut buffer.as_slice())
+                .map(TelnetArgument::XDisplayLocation)
+        },
+        _ => return TelnetArgument::Unknown(buffer),
+    };
+    decoded.unwrap_or(TelnetArgument::Unknown(buffer))
+}
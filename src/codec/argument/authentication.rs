@@ -0,0 +1,338 @@
+//
+// Copyright 2019 Hans W. Uhlig. All Rights Reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+
+use super::{consts, TelnetError};
+use bytes::{Buf, BufMut};
+
+///
+/// Authentication Subnegotiation [RFC2941](http://www.iana.org/go/rfc2941)
+///
+#[derive(Clone, Debug, PartialEq)]
+pub enum Authentication {
+    /// Authentication data sent by the client for the selected type pair.
+    Is(AuthenticationPair, Vec<u8>),
+    /// List of type pairs the server is willing to accept, in preference order.
+    Send(Vec<AuthenticationPair>),
+    /// Authentication data sent by the server in reply to `Is`.
+    Reply(AuthenticationPair, Vec<u8>),
+    /// Name of the account the client wishes to log in as.
+    Name(String),
+}
+
+impl Authentication {
+    /// Get Encoded Length of `Authentication`
+    #[allow(clippy::len_without_is_empty)]
+    pub fn len(&self) -> usize {
+        1 + match self {
+            Authentication::Is(_, data) => 2 + data.len(),
+            Authentication::Send(pairs) => 2 * pairs.len(),
+            Authentication::Reply(_, data) => 2 + data.len(),
+            Authentication::Name(name) => name.len(),
+        }
+    }
+    /// Encode `Authentication` to `BufMut`
+    pub fn encode<T: BufMut>(&self, dst: &mut T) -> Result<(), TelnetError> {
+        match self {
+            Authentication::Is(pair, data) => {
+                dst.put_u8(consts::option::authentication::IS);
+                pair.encode(dst);
+                dst.put(data.as_slice());
+            },
+            Authentication::Send(pairs) => {
+                dst.put_u8(consts::option::authentication::SEND);
+                for pair in pairs {
+                    pair.encode(dst);
+                }
+            },
+            Authentication::Reply(pair, data) => {
+                dst.put_u8(consts::option::authentication::REPLY);
+                pair.encode(dst);
+                dst.put(data.as_slice());
+            },
+            Authentication::Name(name) => {
+                dst.put_u8(consts::option::authentication::NAME);
+                dst.put(name.as_bytes());
+            },
+        }
+        Ok(())
+    }
+    /// Decode `Authentication` from `Buf`
+    pub fn decode<T: Buf>(src: &mut T) -> Result<Self, TelnetError> {
+        if !src.has_remaining() {
+            return Err(TelnetError::DecodingError(
+                "Authentication subnegotiation is empty".to_string(),
+            ));
+        }
+        match src.get_u8() {
+            consts::option::authentication::IS => {
+                let pair = AuthenticationPair::decode(src)?;
+                Ok(Authentication::Is(pair, src.to_bytes().to_vec()))
+            },
+            consts::option::authentication::SEND => {
+                let mut pairs = Vec::with_capacity(src.remaining() / 2);
+                while src.has_remaining() {
+                    pairs.push(AuthenticationPair::decode(src)?);
+                }
+                Ok(Authentication::Send(pairs))
+            },
+            consts::option::authentication::REPLY => {
+                let pair = AuthenticationPair::decode(src)?;
+                Ok(Authentication::Reply(pair, src.to_bytes().to_vec()))
+            },
+            consts::option::authentication::NAME => {
+                let name = String::from_utf8(src.to_bytes().to_vec()).map_err(
+                    |error| TelnetError::DecodingError(error.to_string()),
+                )?;
+                Ok(Authentication::Name(name))
+            },
+            command => Err(TelnetError::DecodingError(format!(
+                "Unknown Authentication command {:#X}",
+                command
+            ))),
+        }
+    }
+}
+
+///
+/// Authentication Type and Modifier pair
+///
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct AuthenticationPair {
+    /// Authentication Mechanism
+    pub kind: AuthenticationType,
+    /// Mechanism Modifiers
+    pub modifiers: AuthenticationModifiers,
+}
+
+impl AuthenticationPair {
+    /// Create a new `AuthenticationPair`
+    pub fn new(
+        kind: AuthenticationType,
+        modifiers: AuthenticationModifiers,
+    ) -> AuthenticationPair {
+        AuthenticationPair { kind, modifiers }
+    }
+    fn encode<T: BufMut>(&self, dst: &mut T) {
+        dst.put_u8(self.kind.into());
+        dst.put_u8(self.modifiers.into());
+    }
+    fn decode<T: Buf>(src: &mut T) -> Result<Self, TelnetError> {
+        if src.remaining() < 2 {
+            return Err(TelnetError::DecodingError(
+                "Truncated Authentication type pair".to_string(),
+            ));
+        }
+        let kind = AuthenticationType::from(src.get_u8());
+        let modifiers = AuthenticationModifiers::from(src.get_u8());
+        Ok(AuthenticationPair { kind, modifiers })
+    }
+}
+
+///
+/// [Telnet Authentication Types](https://www.iana.org/assignments/telnet-options/telnet-options.xhtml#telnet-options-3)
+///
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum AuthenticationType {
+    /// No authentication. Sent by a client to refuse all offered types.
+    Null,
+    /// Kerberos Version 4 [RFC2942](http://www.iana.org/go/rfc2942)
+    KerberosV4,
+    /// Kerberos Version 5 [RFC2942](http://www.iana.org/go/rfc2942)
+    KerberosV5,
+    /// SPX
+    SPX,
+    /// MINK
+    MINK,
+    /// Secure Remote Password [RFC2944](http://www.iana.org/go/rfc2944)
+    SRP,
+    /// RSA
+    RSA,
+    /// SSL
+    SSL,
+    /// LOKI
+    LOKI,
+    /// SSA
+    SSA,
+    /// KEA_SJ [RFC2951](http://www.iana.org/go/rfc2951)
+    KEASJ,
+    /// KEA_SJ_INTEG [RFC2951](http://www.iana.org/go/rfc2951)
+    KEASJIntegrity,
+    /// DSS [RFC2943](http://www.iana.org/go/rfc2943)
+    DSS,
+    /// NTLM
+    NTLM,
+    /// Unknown or Unassigned
+    Unknown(u8),
+}
+
+impl From<u8> for AuthenticationType {
+    fn from(byte: u8) -> Self {
+        use consts::option::authentication::kind;
+        match byte {
+            kind::NULL => AuthenticationType::Null,
+            kind::KERBEROS_V4 => AuthenticationType::KerberosV4,
+            kind::KERBEROS_V5 => AuthenticationType::KerberosV5,
+            kind::SPX => AuthenticationType::SPX,
+            kind::MINK => AuthenticationType::MINK,
+            kind::SRP => AuthenticationType::SRP,
+            kind::RSA => AuthenticationType::RSA,
+            kind::SSL => AuthenticationType::SSL,
+            kind::LOKI => AuthenticationType::LOKI,
+            kind::SSA => AuthenticationType::SSA,
+            kind::KEA_SJ => AuthenticationType::KEASJ,
+            kind::KEA_SJ_INTEG => AuthenticationType::KEASJIntegrity,
+            kind::DSS => AuthenticationType::DSS,
+            kind::NTLM => AuthenticationType::NTLM,
+            byte => AuthenticationType::Unknown(byte),
+        }
+    }
+}
+
+impl From<AuthenticationType> for u8 {
+    fn from(kind: AuthenticationType) -> Self {
+        use consts::option::authentication::kind;
+        match kind {
+            AuthenticationType::Null => kind::NULL,
+            AuthenticationType::KerberosV4 => kind::KERBEROS_V4,
+            AuthenticationType::KerberosV5 => kind::KERBEROS_V5,
+            AuthenticationType::SPX => kind::SPX,
+            AuthenticationType::MINK => kind::MINK,
+            AuthenticationType::SRP => kind::SRP,
+            AuthenticationType::RSA => kind::RSA,
+            AuthenticationType::SSL => kind::SSL,
+            AuthenticationType::LOKI => kind::LOKI,
+            AuthenticationType::SSA => kind::SSA,
+            AuthenticationType::KEASJ => kind::KEA_SJ,
+            AuthenticationType::KEASJIntegrity => kind::KEA_SJ_INTEG,
+            AuthenticationType::DSS => kind::DSS,
+            AuthenticationType::NTLM => kind::NTLM,
+            AuthenticationType::Unknown(byte) => byte,
+        }
+    }
+}
+
+///
+/// Authentication Modifier bit field
+///
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct AuthenticationModifiers(u8);
+
+impl AuthenticationModifiers {
+    /// Client authenticates to the server, one way, without encryption.
+    pub const NONE: AuthenticationModifiers = AuthenticationModifiers(0);
+
+    /// True if the server authenticates to the client.
+    pub fn server_to_client(self) -> bool {
+        use consts::option::authentication::modifier;
+        self.0 & modifier::WHO_MASK == modifier::SERVER_TO_CLIENT
+    }
+    /// True if both sides authenticate to each other.
+    pub fn mutual(self) -> bool {
+        use consts::option::authentication::modifier;
+        self.0 & modifier::HOW_MASK == modifier::MUTUAL
+    }
+    /// Encryption negotiation bits.
+    pub fn encryption(self) -> u8 {
+        self.0 & consts::option::authentication::modifier::ENCRYPT_MASK
+    }
+    /// True if the client will forward its initial credentials.
+    pub fn forward_credentials(self) -> bool {
+        use consts::option::authentication::modifier;
+        self.0 & modifier::INI_CRED_FWD_MASK == modifier::INI_CRED_FWD_ON
+    }
+    /// Set the mutual authentication bit.
+    pub fn with_mutual(self, mutual: bool) -> AuthenticationModifiers {
+        use consts::option::authentication::modifier;
+        match mutual {
+            true => AuthenticationModifiers(self.0 | modifier::MUTUAL),
+            false => AuthenticationModifiers(self.0 & !modifier::HOW_MASK),
+        }
+    }
+    /// Set the encryption negotiation bits.
+    pub fn with_encryption(self, encryption: u8) -> AuthenticationModifiers {
+        use consts::option::authentication::modifier;
+        AuthenticationModifiers(
+            (self.0 & !modifier::ENCRYPT_MASK)
+                | (encryption & modifier::ENCRYPT_MASK),
+        )
+    }
+}
+
+impl From<u8> for AuthenticationModifiers {
+    fn from(byte: u8) -> Self {
+        AuthenticationModifiers(byte)
+    }
+}
+
+impl From<AuthenticationModifiers> for u8 {
+    fn from(modifiers: AuthenticationModifiers) -> Self {
+        modifiers.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        Authentication, AuthenticationModifiers, AuthenticationPair,
+        AuthenticationType,
+    };
+    use bytes::BytesMut;
+
+    #[test]
+    fn test_send_round_trip() {
+        let argument = Authentication::Send(vec![
+            AuthenticationPair::new(
+                AuthenticationType::SRP,
+                AuthenticationModifiers::NONE.with_mutual(true),
+            ),
+            AuthenticationPair::new(
+                AuthenticationType::Unknown(200),
+                AuthenticationModifiers::NONE,
+            ),
+        ]);
+        let mut encoded = BytesMut::with_capacity(argument.len());
+        argument.encode(&mut encoded).unwrap();
+        assert_eq!(&[1, 5, 2, 200, 0][..], &encoded[..]);
+        assert_eq!(argument.len(), encoded.len());
+
+        let decoded = Authentication::decode(&mut &encoded[..]).unwrap();
+        assert_eq!(argument, decoded);
+    }
+
+    #[test]
+    fn test_decode_is_and_name() {
+        let is = Authentication::decode(&mut &[0u8, 5, 2, 9, 8, 7][..]);
+        assert_eq!(
+            Authentication::Is(
+                AuthenticationPair::new(
+                    AuthenticationType::SRP,
+                    AuthenticationModifiers::from(2)
+                ),
+                vec![9, 8, 7]
+            ),
+            is.unwrap()
+        );
+        let name = Authentication::decode(&mut &b"\x03guest"[..]);
+        assert_eq!(Authentication::Name("guest".to_string()), name.unwrap());
+    }
+
+    #[test]
+    fn test_decode_truncated() {
+        assert!(Authentication::decode(&mut &[0u8, 5][..]).is_err());
+        assert!(Authentication::decode(&mut &[9u8][..]).is_err());
+        assert!(Authentication::decode(&mut &[][..]).is_err());
+    }
+}
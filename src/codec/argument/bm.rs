@@ -0,0 +1,203 @@
+//
+// Copyright 2019 Hans W. Uhlig. All Rights Reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+use super::{consts, TelnetError};
+use bytes::{Buf, BufMut};
+
+///
+/// Byte Macro Subnegotiation [RFC735](http://www.iana.org/go/rfc735)
+///
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ByteMacro {
+    /// Macro byte is replaced by the string in the sender's data stream.
+    Define(u8, Vec<u8>),
+    /// Accept a macro definition.
+    Accept(u8),
+    /// Refuse a macro definition.
+    Refuse(u8, ByteMacroReason),
+    /// Next occurrence of the macro byte is taken literally.
+    Literal(u8),
+    /// Cancel a macro definition.
+    Cancel(u8),
+}
+
+///
+/// Reason for refusing a Byte Macro definition.
+///
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ByteMacroReason {
+    /// Any other reason
+    OtherReason,
+    /// Macro byte may not be used
+    BadChoice,
+    /// Replacement string is too long
+    TooLong,
+    /// Count does not match the replacement string length
+    WrongLength,
+    /// Unknown reason
+    Unknown(u8),
+}
+
+impl From<u8> for ByteMacroReason {
+    fn from(reason: u8) -> Self {
+        use consts::option::bm::*;
+        match reason {
+            OTHER_REASON => ByteMacroReason::OtherReason,
+            BAD_CHOICE => ByteMacroReason::BadChoice,
+            TOO_LONG => ByteMacroReason::TooLong,
+            WRONG_LENGTH => ByteMacroReason::WrongLength,
+            reason => ByteMacroReason::Unknown(reason),
+        }
+    }
+}
+
+impl From<ByteMacroReason> for u8 {
+    fn from(reason: ByteMacroReason) -> Self {
+        use consts::option::bm::*;
+        match reason {
+            ByteMacroReason::OtherReason => OTHER_REASON,
+            ByteMacroReason::BadChoice => BAD_CHOICE,
+            ByteMacroReason::TooLong => TOO_LONG,
+            ByteMacroReason::WrongLength => WRONG_LENGTH,
+            ByteMacroReason::Unknown(reason) => reason,
+        }
+    }
+}
+
+impl ByteMacro {
+    /// Get Encoded Length of `ByteMacro`
+    #[allow(clippy::len_without_is_empty)]
+    pub fn len(&self) -> usize {
+        match self {
+            ByteMacro::Define(_, replacement) => 3 + replacement.len(),
+            ByteMacro::Refuse(_, _) => 3,
+            _ => 2,
+        }
+    }
+    /// Encode `ByteMacro` to `BufMut`
+    pub fn encode<T: BufMut>(&self, dst: &mut T) -> Result<(), TelnetError> {
+        use consts::option::bm::*;
+        match self {
+            ByteMacro::Define(byte, replacement) => {
+                if replacement.len() > usize::from(u8::MAX) {
+                    return Err(TelnetError::EncodingError(format!(
+                        "Byte Macro {:#X} replacement is {} bytes, over 255",
+                        byte,
+                        replacement.len()
+                    )));
+                }
+                dst.put_u8(DEFINE);
+                dst.put_u8(*byte);
+                dst.put_u8(replacement.len() as u8);
+                dst.put_slice(replacement);
+            },
+            ByteMacro::Accept(byte) => {
+                dst.put_u8(ACCEPT);
+                dst.put_u8(*byte);
+            },
+            ByteMacro::Refuse(byte, reason) => {
+                dst.put_u8(REFUSE);
+                dst.put_u8(*byte);
+                dst.put_u8(u8::from(*reason));
+            },
+            ByteMacro::Literal(byte) => {
+                dst.put_u8(LITERAL);
+                dst.put_u8(*byte);
+            },
+            ByteMacro::Cancel(byte) => {
+                dst.put_u8(CANCEL);
+                dst.put_u8(*byte);
+            },
+        }
+        Ok(())
+    }
+    /// Decode `ByteMacro` from `Buf`
+    pub fn decode<T: Buf>(src: &mut T) -> Result<Self, TelnetError> {
+        use consts::option::bm::*;
+        if src.remaining() < 2 {
+            return Err(TelnetError::DecodingError(
+                "Byte Macro subnegotiation is too short".to_string(),
+            ));
+        }
+        let command = src.get_u8();
+        let byte = src.get_u8();
+        match command {
+            DEFINE if src.has_remaining() => {
+                let count = usize::from(src.get_u8());
+                if count != src.remaining() {
+                    return Err(TelnetError::DecodingError(format!(
+                        "Byte Macro {:#X} count {} doesn't match {} bytes",
+                        byte,
+                        count,
+                        src.remaining()
+                    )));
+                }
+                Ok(ByteMacro::Define(byte, src.to_bytes().to_vec()))
+            },
+            REFUSE if src.remaining() == 1 => {
+                Ok(ByteMacro::Refuse(byte, ByteMacroReason::from(src.get_u8())))
+            },
+            ACCEPT if !src.has_remaining() => Ok(ByteMacro::Accept(byte)),
+            LITERAL if !src.has_remaining() => Ok(ByteMacro::Literal(byte)),
+            CANCEL if !src.has_remaining() => Ok(ByteMacro::Cancel(byte)),
+            DEFINE | REFUSE | ACCEPT | LITERAL | CANCEL => {
+                Err(TelnetError::DecodingError(format!(
+                    "Byte Macro command {:#X} has wrong length",
+                    command
+                )))
+            },
+            command => Err(TelnetError::DecodingError(format!(
+                "Unknown Byte Macro command {:#X}",
+                command
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ByteMacro, ByteMacroReason};
+    use bytes::BytesMut;
+
+    #[test]
+    fn test_round_trip() {
+        for (argument, expected) in &[
+            (
+                ByteMacro::Define(0x80, b"ab".to_vec()),
+                &[1u8, 0x80, 2, b'a', b'b'][..],
+            ),
+            (ByteMacro::Accept(0x80), &[2u8, 0x80][..]),
+            (
+                ByteMacro::Refuse(0x80, ByteMacroReason::TooLong),
+                &[3u8, 0x80, 2][..],
+            ),
+            (ByteMacro::Cancel(0x80), &[5u8, 0x80][..]),
+        ] {
+            let mut encoded = BytesMut::with_capacity(argument.len());
+            argument.encode(&mut encoded).unwrap();
+            assert_eq!(*expected, &encoded[..]);
+            assert_eq!(
+                *argument,
+                ByteMacro::decode(&mut &encoded[..]).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn test_decode_wrong_length() {
+        assert!(ByteMacro::decode(&mut &[1u8, 0x80, 3, b'a'][..]).is_err());
+        assert!(ByteMacro::decode(&mut &[4u8, 0x80, 0][..]).is_err());
+    }
+}
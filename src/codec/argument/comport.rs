@@ -0,0 +1,515 @@
+//
+// Copyright 2019 Hans W. Uhlig. All Rights Reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+
+use super::{consts, TelnetError};
+use bytes::{Buf, BufMut};
+
+///
+/// Com Port Control Subnegotiation [RFC2217](http://www.iana.org/go/rfc2217)
+///
+/// The same commands flow in both directions, the access server answers
+/// with the command code offset by 100.
+///
+#[derive(Clone, Debug, PartialEq)]
+pub struct ComPortControl {
+    /// True if sent by the access server.
+    pub server: bool,
+    /// Command and its value.
+    pub command: ComPortCommand,
+}
+
+impl ComPortControl {
+    /// Create a `ComPortControl` sent by the client.
+    pub fn client(command: ComPortCommand) -> ComPortControl {
+        ComPortControl {
+            server: false,
+            command,
+        }
+    }
+    /// Create a `ComPortControl` sent by the access server.
+    pub fn server(command: ComPortCommand) -> ComPortControl {
+        ComPortControl {
+            server: true,
+            command,
+        }
+    }
+    /// Get Encoded Length of `ComPortControl`
+    #[allow(clippy::len_without_is_empty)]
+    pub fn len(&self) -> usize {
+        1 + match &self.command {
+            ComPortCommand::Signature(signature) => signature.len(),
+            ComPortCommand::SetBaudRate(_) => 4,
+            ComPortCommand::FlowControlSuspend => 0,
+            ComPortCommand::FlowControlResume => 0,
+            ComPortCommand::Unknown(_, data) => data.len(),
+            _ => 1,
+        }
+    }
+    /// Encode `ComPortControl` to `BufMut`
+    pub fn encode<T: BufMut>(&self, dst: &mut T) -> Result<(), TelnetError> {
+        use consts::option::comport::*;
+        let offset = if self.server { SERVER_OFFSET } else { 0 };
+        match &self.command {
+            ComPortCommand::Signature(signature) => {
+                dst.put_u8(SIGNATURE + offset);
+                dst.put(signature.as_bytes());
+            },
+            ComPortCommand::SetBaudRate(baud) => {
+                dst.put_u8(SET_BAUDRATE + offset);
+                dst.put_u32(*baud);
+            },
+            ComPortCommand::SetDataSize(size) => {
+                dst.put_u8(SET_DATASIZE + offset);
+                dst.put_u8(*size);
+            },
+            ComPortCommand::SetParity(parity) => {
+                dst.put_u8(SET_PARITY + offset);
+                dst.put_u8(u8::from(*parity));
+            },
+            ComPortCommand::SetStopSize(stopsize) => {
+                dst.put_u8(SET_STOPSIZE + offset);
+                dst.put_u8(u8::from(*stopsize));
+            },
+            ComPortCommand::SetControl(control) => {
+                dst.put_u8(SET_CONTROL + offset);
+                dst.put_u8(u8::from(*control));
+            },
+            ComPortCommand::NotifyLineState(state) => {
+                dst.put_u8(NOTIFY_LINESTATE + offset);
+                dst.put_u8(state.0);
+            },
+            ComPortCommand::NotifyModemState(state) => {
+                dst.put_u8(NOTIFY_MODEMSTATE + offset);
+                dst.put_u8(state.0);
+            },
+            ComPortCommand::FlowControlSuspend => {
+                dst.put_u8(FLOWCONTROL_SUSPEND + offset);
+            },
+            ComPortCommand::FlowControlResume => {
+                dst.put_u8(FLOWCONTROL_RESUME + offset);
+            },
+            ComPortCommand::SetLineStateMask(mask) => {
+                dst.put_u8(SET_LINESTATE_MASK + offset);
+                dst.put_u8(mask.0);
+            },
+            ComPortCommand::SetModemStateMask(mask) => {
+                dst.put_u8(SET_MODEMSTATE_MASK + offset);
+                dst.put_u8(mask.0);
+            },
+            ComPortCommand::PurgeData(purge) => {
+                dst.put_u8(PURGE_DATA + offset);
+                dst.put_u8(u8::from(*purge));
+            },
+            ComPortCommand::Unknown(command, data) => {
+                let code = Some(*command)
+                    .filter(|command| *command < SERVER_OFFSET)
+                    .and_then(|command| command.checked_add(offset))
+                    .ok_or_else(|| {
+                        TelnetError::EncodingError(format!(
+                            "Invalid Com Port Control command {}",
+                            command
+                        ))
+                    })?;
+                dst.put_u8(code);
+                dst.put(data.as_slice());
+            },
+        }
+        Ok(())
+    }
+    /// Decode `ComPortControl` from `Buf`
+    pub fn decode<T: Buf>(src: &mut T) -> Result<Self, TelnetError> {
+        use consts::option::comport::*;
+        if !src.has_remaining() {
+            return Err(TelnetError::DecodingError(
+                "Com Port Control subnegotiation is empty".to_string(),
+            ));
+        }
+        let code = src.get_u8();
+        let server = code >= SERVER_OFFSET;
+        let command = if server { code - SERVER_OFFSET } else { code };
+        let command = match command {
+            SIGNATURE => {
+                let signature = String::from_utf8(src.to_bytes().to_vec())
+                    .map_err(|error| {
+                        TelnetError::DecodingError(error.to_string())
+                    })?;
+                ComPortCommand::Signature(signature)
+            },
+            SET_BAUDRATE => {
+                if src.remaining() < 4 {
+                    return Err(TelnetError::DecodingError(
+                        "Truncated SET-BAUDRATE".to_string(),
+                    ));
+                }
+                ComPortCommand::SetBaudRate(src.get_u32())
+            },
+            FLOWCONTROL_SUSPEND => ComPortCommand::FlowControlSuspend,
+            FLOWCONTROL_RESUME => ComPortCommand::FlowControlResume,
+            SET_DATASIZE | SET_PARITY | SET_STOPSIZE | SET_CONTROL
+            | NOTIFY_LINESTATE | NOTIFY_MODEMSTATE | SET_LINESTATE_MASK
+            | SET_MODEMSTATE_MASK | PURGE_DATA => {
+                if !src.has_remaining() {
+                    return Err(TelnetError::DecodingError(format!(
+                        "Com Port Control command {} missing value",
+                        command
+                    )));
+                }
+                let value = src.get_u8();
+                match command {
+                    SET_DATASIZE => ComPortCommand::SetDataSize(value),
+                    SET_PARITY => ComPortCommand::SetParity(value.into()),
+                    SET_STOPSIZE => ComPortCommand::SetStopSize(value.into()),
+                    SET_CONTROL => ComPortCommand::SetControl(value.into()),
+                    NOTIFY_LINESTATE => {
+                        ComPortCommand::NotifyLineState(LineState(value))
+                    },
+                    NOTIFY_MODEMSTATE => {
+                        ComPortCommand::NotifyModemState(ModemState(value))
+                    },
+                    SET_LINESTATE_MASK => {
+                        ComPortCommand::SetLineStateMask(LineState(value))
+                    },
+                    SET_MODEMSTATE_MASK => {
+                        ComPortCommand::SetModemStateMask(ModemState(value))
+                    },
+                    _ => ComPortCommand::PurgeData(value.into()),
+                }
+            },
+            command => {
+                ComPortCommand::Unknown(command, src.to_bytes().to_vec())
+            },
+        };
+        Ok(ComPortControl { server, command })
+    }
+}
+
+///
+/// Com Port Control Command. A value of `0` or a `Request` variant asks the
+/// access server for the current setting without changing it.
+///
+#[derive(Clone, Debug, PartialEq)]
+pub enum ComPortCommand {
+    /// Identify the access server. Empty to request the peer's signature.
+    Signature(String),
+    /// Set the baud rate.
+    SetBaudRate(u32),
+    /// Set the number of data bits, 5 through 8.
+    SetDataSize(u8),
+    /// Set the parity.
+    SetParity(Parity),
+    /// Set the number of stop bits.
+    SetStopSize(StopSize),
+    /// Set flow control, break, DTR and RTS.
+    SetControl(Control),
+    /// Line state changed.
+    NotifyLineState(LineState),
+    /// Modem state changed.
+    NotifyModemState(ModemState),
+    /// Sender is unable to accept more data.
+    FlowControlSuspend,
+    /// Sender is able to accept data again.
+    FlowControlResume,
+    /// Line state changes to report.
+    SetLineStateMask(LineState),
+    /// Modem state changes to report.
+    SetModemStateMask(ModemState),
+    /// Discard buffered data.
+    PurgeData(Purge),
+    /// Unknown Command
+    Unknown(u8, Vec<u8>),
+}
+
+///
+/// Serial Parity
+///
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Parity {
+    Request,
+    None,
+    Odd,
+    Even,
+    Mark,
+    Space,
+    Unknown(u8),
+}
+
+impl From<u8> for Parity {
+    fn from(byte: u8) -> Self {
+        use consts::option::comport::parity;
+        match byte {
+            parity::REQUEST => Parity::Request,
+            parity::NONE => Parity::None,
+            parity::ODD => Parity::Odd,
+            parity::EVEN => Parity::Even,
+            parity::MARK => Parity::Mark,
+            parity::SPACE => Parity::Space,
+            byte => Parity::Unknown(byte),
+        }
+    }
+}
+
+impl From<Parity> for u8 {
+    fn from(value: Parity) -> Self {
+        use consts::option::comport::parity;
+        match value {
+            Parity::Request => parity::REQUEST,
+            Parity::None => parity::NONE,
+            Parity::Odd => parity::ODD,
+            Parity::Even => parity::EVEN,
+            Parity::Mark => parity::MARK,
+            Parity::Space => parity::SPACE,
+            Parity::Unknown(byte) => byte,
+        }
+    }
+}
+
+///
+/// Serial Stop Bits
+///
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum StopSize {
+    Request,
+    One,
+    Two,
+    OneAndHalf,
+    Unknown(u8),
+}
+
+impl From<u8> for StopSize {
+    fn from(byte: u8) -> Self {
+        use consts::option::comport::stopsize;
+        match byte {
+            stopsize::REQUEST => StopSize::Request,
+            stopsize::ONE => StopSize::One,
+            stopsize::TWO => StopSize::Two,
+            stopsize::ONE_AND_HALF => StopSize::OneAndHalf,
+            byte => StopSize::Unknown(byte),
+        }
+    }
+}
+
+impl From<StopSize> for u8 {
+    fn from(value: StopSize) -> Self {
+        use consts::option::comport::stopsize;
+        match value {
+            StopSize::Request => stopsize::REQUEST,
+            StopSize::One => stopsize::ONE,
+            StopSize::Two => stopsize::TWO,
+            StopSize::OneAndHalf => stopsize::ONE_AND_HALF,
+            StopSize::Unknown(byte) => byte,
+        }
+    }
+}
+
+///
+/// Serial Control Lines and Flow Control
+///
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Control {
+    RequestFlowOut,
+    FlowOutNone,
+    FlowOutXonXoff,
+    FlowOutHardware,
+    RequestBreak,
+    BreakOn,
+    BreakOff,
+    RequestDTR,
+    DTROn,
+    DTROff,
+    RequestRTS,
+    RTSOn,
+    RTSOff,
+    RequestFlowIn,
+    FlowInNone,
+    FlowInXonXoff,
+    FlowInHardware,
+    FlowDCD,
+    FlowDTR,
+    FlowDSR,
+    Unknown(u8),
+}
+
+impl From<u8> for Control {
+    fn from(byte: u8) -> Self {
+        use consts::option::comport::control;
+        match byte {
+            control::REQUEST_FLOW_OUT => Control::RequestFlowOut,
+            control::FLOW_OUT_NONE => Control::FlowOutNone,
+            control::FLOW_OUT_XON_XOFF => Control::FlowOutXonXoff,
+            control::FLOW_OUT_HARDWARE => Control::FlowOutHardware,
+            control::REQUEST_BREAK => Control::RequestBreak,
+            control::BREAK_ON => Control::BreakOn,
+            control::BREAK_OFF => Control::BreakOff,
+            control::REQUEST_DTR => Control::RequestDTR,
+            control::DTR_ON => Control::DTROn,
+            control::DTR_OFF => Control::DTROff,
+            control::REQUEST_RTS => Control::RequestRTS,
+            control::RTS_ON => Control::RTSOn,
+            control::RTS_OFF => Control::RTSOff,
+            control::REQUEST_FLOW_IN => Control::RequestFlowIn,
+            control::FLOW_IN_NONE => Control::FlowInNone,
+            control::FLOW_IN_XON_XOFF => Control::FlowInXonXoff,
+            control::FLOW_IN_HARDWARE => Control::FlowInHardware,
+            control::FLOW_DCD => Control::FlowDCD,
+            control::FLOW_DTR => Control::FlowDTR,
+            control::FLOW_DSR => Control::FlowDSR,
+            byte => Control::Unknown(byte),
+        }
+    }
+}
+
+impl From<Control> for u8 {
+    fn from(value: Control) -> Self {
+        use consts::option::comport::control;
+        match value {
+            Control::RequestFlowOut => control::REQUEST_FLOW_OUT,
+            Control::FlowOutNone => control::FLOW_OUT_NONE,
+            Control::FlowOutXonXoff => control::FLOW_OUT_XON_XOFF,
+            Control::FlowOutHardware => control::FLOW_OUT_HARDWARE,
+            Control::RequestBreak => control::REQUEST_BREAK,
+            Control::BreakOn => control::BREAK_ON,
+            Control::BreakOff => control::BREAK_OFF,
+            Control::RequestDTR => control::REQUEST_DTR,
+            Control::DTROn => control::DTR_ON,
+            Control::DTROff => control::DTR_OFF,
+            Control::RequestRTS => control::REQUEST_RTS,
+            Control::RTSOn => control::RTS_ON,
+            Control::RTSOff => control::RTS_OFF,
+            Control::RequestFlowIn => control::REQUEST_FLOW_IN,
+            Control::FlowInNone => control::FLOW_IN_NONE,
+            Control::FlowInXonXoff => control::FLOW_IN_XON_XOFF,
+            Control::FlowInHardware => control::FLOW_IN_HARDWARE,
+            Control::FlowDCD => control::FLOW_DCD,
+            Control::FlowDTR => control::FLOW_DTR,
+            Control::FlowDSR => control::FLOW_DSR,
+            Control::Unknown(byte) => byte,
+        }
+    }
+}
+
+///
+/// Serial Line State bit field
+///
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct LineState(pub u8);
+
+impl LineState {
+    /// True if every bit in `mask` is set.
+    pub fn contains(self, mask: u8) -> bool {
+        self.0 & mask == mask
+    }
+}
+
+///
+/// Serial Modem State bit field
+///
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct ModemState(pub u8);
+
+impl ModemState {
+    /// True if every bit in `mask` is set.
+    pub fn contains(self, mask: u8) -> bool {
+        self.0 & mask == mask
+    }
+}
+
+///
+/// Buffers to discard
+///
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Purge {
+    Receive,
+    Transmit,
+    Both,
+    Unknown(u8),
+}
+
+impl From<u8> for Purge {
+    fn from(byte: u8) -> Self {
+        use consts::option::comport::purge;
+        match byte {
+            purge::RECEIVE => Purge::Receive,
+            purge::TRANSMIT => Purge::Transmit,
+            purge::BOTH => Purge::Both,
+            byte => Purge::Unknown(byte),
+        }
+    }
+}
+
+impl From<Purge> for u8 {
+    fn from(value: Purge) -> Self {
+        use consts::option::comport::purge;
+        match value {
+            Purge::Receive => purge::RECEIVE,
+            Purge::Transmit => purge::TRANSMIT,
+            Purge::Both => purge::BOTH,
+            Purge::Unknown(byte) => byte,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ComPortCommand, ComPortControl, Control, LineState, Parity};
+    use bytes::BytesMut;
+
+    #[test]
+    fn test_round_trip() {
+        let arguments = vec![
+            ComPortControl::client(ComPortCommand::SetBaudRate(115_200)),
+            ComPortControl::server(ComPortCommand::SetParity(Parity::Even)),
+            ComPortControl::client(ComPortCommand::SetControl(Control::DTROn)),
+            ComPortControl::server(ComPortCommand::NotifyLineState(LineState(
+                0x60,
+            ))),
+            ComPortControl::client(ComPortCommand::FlowControlSuspend),
+            ComPortControl::server(ComPortCommand::Signature("lab".into())),
+        ];
+        for argument in arguments {
+            let mut encoded = BytesMut::with_capacity(argument.len());
+            argument.encode(&mut encoded).unwrap();
+            assert_eq!(argument.len(), encoded.len());
+            let decoded = ComPortControl::decode(&mut &encoded[..]).unwrap();
+            assert_eq!(argument, decoded);
+        }
+    }
+
+    #[test]
+    fn test_decode_baud_rate() {
+        let decoded =
+            ComPortControl::decode(&mut &[101u8, 0, 0, 0x25, 0x80][..]);
+        assert_eq!(
+            ComPortControl::server(ComPortCommand::SetBaudRate(9600)),
+            decoded.unwrap()
+        );
+        assert!(ComPortControl::decode(&mut &[1u8, 0, 0][..]).is_err());
+    }
+
+    #[test]
+    fn test_encode_unknown_out_of_range() {
+        let mut encoded = BytesMut::new();
+        let argument =
+            ComPortControl::server(ComPortCommand::Unknown(200, Vec::new()));
+        assert!(argument.encode(&mut encoded).is_err());
+        let argument =
+            ComPortControl::client(ComPortCommand::Unknown(120, Vec::new()));
+        assert!(argument.encode(&mut encoded).is_err());
+        let argument =
+            ComPortControl::server(ComPortCommand::Unknown(20, vec![1]));
+        argument.encode(&mut encoded).unwrap();
+        assert_eq!(&[120u8, 1][..], &encoded[..]);
+    }
+}
@@ -0,0 +1,334 @@
+//
+// Copyright 2019 Hans W. Uhlig. All Rights Reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+use super::{consts, TelnetError};
+use bytes::{Buf, BufMut};
+
+///
+/// Data Entry Terminal Subnegotiation [RFC1043](http://www.iana.org/go/rfc1043)
+///
+/// Cursor positions are given as column `x` and line `y`. Facility bytes are
+/// the bit fields defined by the RFC.
+///
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum DataEntry {
+    /// Editing facilities supported.
+    EditFacilities(u8),
+    /// Erasure facilities supported.
+    EraseFacilities(u8),
+    /// Transmission facilities supported.
+    TransmitFacilities(u8),
+    /// Formatting facilities supported.
+    FormatFacilities(u8, u8),
+    /// Move the cursor to a column and line.
+    MoveCursor { x: u8, y: u8 },
+    /// Move the cursor to the start of a line.
+    SkipToLine(u8),
+    /// Move the cursor to a column on the current line.
+    SkipToChar(u8),
+    /// Move the cursor up one line.
+    Up,
+    /// Move the cursor down one line.
+    Down,
+    /// Move the cursor left one column.
+    Left,
+    /// Move the cursor right one column.
+    Right,
+    /// Move the cursor to the top left corner.
+    Home,
+    /// Insert a line at the cursor.
+    LineInsert,
+    /// Delete the line at the cursor.
+    LineDelete,
+    /// Insert a character at the cursor.
+    CharInsert,
+    /// Delete the character at the cursor.
+    CharDelete,
+    /// Ask for the cursor position.
+    ReadCursor,
+    /// Report the cursor position.
+    CursorPosition { x: u8, y: u8 },
+    /// Move the cursor to the previous field.
+    ReverseTab,
+    /// Transmit the whole screen.
+    TransmitScreen,
+    /// Transmit the unprotected fields.
+    TransmitUnprotected,
+    /// Transmit the current line.
+    TransmitLine,
+    /// Transmit the current field.
+    TransmitField,
+    /// Transmit the screen from the cursor on.
+    TransmitRestScreen,
+    /// Transmit the line from the cursor on.
+    TransmitRestLine,
+    /// Transmit the field from the cursor on.
+    TransmitRestField,
+    /// Transmit the modified fields.
+    TransmitModified,
+    /// Transmitted data follows, starting at a column and line.
+    DataTransmit { x: u8, y: u8 },
+    /// Erase the whole screen.
+    EraseScreen,
+    /// Erase the current line.
+    EraseLine,
+    /// Erase the current field.
+    EraseField,
+    /// Erase the screen from the cursor on.
+    EraseRestScreen,
+    /// Erase the line from the cursor on.
+    EraseRestLine,
+    /// Erase the field from the cursor on.
+    EraseRestField,
+    /// Erase the unprotected fields.
+    EraseUnprotected,
+    /// Field data with its two byte format map.
+    FormatData { format: u16, data: Vec<u8> },
+    /// Repeat `character` `count` times.
+    Repeat { count: u8, character: u8 },
+    /// Negotiate suppression of field protection with a DO, DONT, WILL or
+    /// WONT byte.
+    SuppressProtection(u8),
+    /// Separates fields of transmitted data.
+    FieldSeparator,
+    /// Function key pressed.
+    Function(u8),
+    /// Error `code` in a received `command`.
+    Error { command: u8, code: u8 },
+}
+
+impl DataEntry {
+    /// Command code and argument bytes.
+    fn parts(&self) -> (u8, Vec<u8>) {
+        use consts::option::det::*;
+        match self {
+            DataEntry::EditFacilities(facilities) => {
+                (EDIT_FACILITIES, vec![*facilities])
+            },
+            DataEntry::EraseFacilities(facilities) => {
+                (ERASE_FACILITIES, vec![*facilities])
+            },
+            DataEntry::TransmitFacilities(facilities) => {
+                (TRANSMIT_FACILITIES, vec![*facilities])
+            },
+            DataEntry::FormatFacilities(first, second) => {
+                (FORMAT_FACILITIES, vec![*first, *second])
+            },
+            DataEntry::MoveCursor { x, y } => (MOVE_CURSOR, vec![*x, *y]),
+            DataEntry::SkipToLine(line) => (SKIP_TO_LINE, vec![*line]),
+            DataEntry::SkipToChar(column) => (SKIP_TO_CHAR, vec![*column]),
+            DataEntry::Up => (UP, Vec::new()),
+            DataEntry::Down => (DOWN, Vec::new()),
+            DataEntry::Left => (LEFT, Vec::new()),
+            DataEntry::Right => (RIGHT, Vec::new()),
+            DataEntry::Home => (HOME, Vec::new()),
+            DataEntry::LineInsert => (LINE_INSERT, Vec::new()),
+            DataEntry::LineDelete => (LINE_DELETE, Vec::new()),
+            DataEntry::CharInsert => (CHAR_INSERT, Vec::new()),
+            DataEntry::CharDelete => (CHAR_DELETE, Vec::new()),
+            DataEntry::ReadCursor => (READ_CURSOR, Vec::new()),
+            DataEntry::CursorPosition { x, y } => {
+                (CURSOR_POSITION, vec![*x, *y])
+            },
+            DataEntry::ReverseTab => (REVERSE_TAB, Vec::new()),
+            DataEntry::TransmitScreen => (TRANSMIT_SCREEN, Vec::new()),
+            DataEntry::TransmitUnprotected => {
+                (TRANSMIT_UNPROTECTED, Vec::new())
+            },
+            DataEntry::TransmitLine => (TRANSMIT_LINE, Vec::new()),
+            DataEntry::TransmitField => (TRANSMIT_FIELD, Vec::new()),
+            DataEntry::TransmitRestScreen => (TRANSMIT_REST_SCREEN, Vec::new()),
+            DataEntry::TransmitRestLine => (TRANSMIT_REST_LINE, Vec::new()),
+            DataEntry::TransmitRestField => (TRANSMIT_REST_FIELD, Vec::new()),
+            DataEntry::TransmitModified => (TRANSMIT_MODIFIED, Vec::new()),
+            DataEntry::DataTransmit { x, y } => (DATA_TRANSMIT, vec![*x, *y]),
+            DataEntry::EraseScreen => (ERASE_SCREEN, Vec::new()),
+            DataEntry::EraseLine => (ERASE_LINE, Vec::new()),
+            DataEntry::EraseField => (ERASE_FIELD, Vec::new()),
+            DataEntry::EraseRestScreen => (ERASE_REST_SCREEN, Vec::new()),
+            DataEntry::EraseRestLine => (ERASE_REST_LINE, Vec::new()),
+            DataEntry::EraseRestField => (ERASE_REST_FIELD, Vec::new()),
+            DataEntry::EraseUnprotected => (ERASE_UNPROTECTED, Vec::new()),
+            DataEntry::FormatData { format, data } => {
+                let mut arguments = Vec::with_capacity(3 + data.len());
+                arguments.extend_from_slice(&format.to_be_bytes());
+                arguments.push(data.len() as u8);
+                arguments.extend_from_slice(data);
+                (FORMAT_DATA, arguments)
+            },
+            DataEntry::Repeat { count, character } => {
+                (REPEAT, vec![*count, *character])
+            },
+            DataEntry::SuppressProtection(negotiation) => {
+                (SUPPRESS_PROTECTION, vec![*negotiation])
+            },
+            DataEntry::FieldSeparator => (FIELD_SEPARATOR, Vec::new()),
+            DataEntry::Function(key) => (FN, vec![*key]),
+            DataEntry::Error { command, code } => {
+                (ERROR, vec![*command, *code])
+            },
+        }
+    }
+    /// Get Encoded Length of `DataEntry`
+    #[allow(clippy::len_without_is_empty)]
+    pub fn len(&self) -> usize {
+        match self {
+            DataEntry::FormatData { data, .. } => 4 + data.len(),
+            entry => 1 + entry.parts().1.len(),
+        }
+    }
+    /// Encode `DataEntry` to `BufMut`
+    pub fn encode<T: BufMut>(&self, dst: &mut T) -> Result<(), TelnetError> {
+        if let DataEntry::FormatData { data, .. } = self {
+            if data.len() > usize::from(u8::MAX) {
+                return Err(TelnetError::EncodingError(format!(
+                    "DET FORMAT-DATA is {} bytes, over 255",
+                    data.len()
+                )));
+            }
+        }
+        let (command, arguments) = self.parts();
+        dst.put_u8(command);
+        dst.put_slice(&arguments);
+        Ok(())
+    }
+    /// Decode `DataEntry` from `Buf`
+    pub fn decode<T: Buf>(src: &mut T) -> Result<Self, TelnetError> {
+        use consts::option::det::*;
+        if !src.has_remaining() {
+            return Err(TelnetError::DecodingError(
+                "DET subnegotiation is empty".to_string(),
+            ));
+        }
+        let command = src.get_u8();
+        let arguments = src.to_bytes();
+        let expected = match command {
+            EDIT_FACILITIES | ERASE_FACILITIES | TRANSMIT_FACILITIES
+            | SKIP_TO_LINE | SKIP_TO_CHAR | SUPPRESS_PROTECTION | FN => 1,
+            FORMAT_FACILITIES | MOVE_CURSOR | CURSOR_POSITION
+            | DATA_TRANSMIT | REPEAT | ERROR => 2,
+            FORMAT_DATA if arguments.len() >= 3 => 3 + arguments[2] as usize,
+            FORMAT_DATA => 3,
+            UP..=READ_CURSOR
+            | REVERSE_TAB..=TRANSMIT_MODIFIED
+            | ERASE_SCREEN..=ERASE_UNPROTECTED
+            | FIELD_SEPARATOR => 0,
+            command => {
+                return Err(TelnetError::DecodingError(format!(
+                    "Unknown DET command {:#X}",
+                    command
+                )))
+            },
+        };
+        if arguments.len() != expected {
+            return Err(TelnetError::DecodingError(format!(
+                "DET command {:#X} expects {} argument bytes, got {}",
+                command,
+                expected,
+                arguments.len()
+            )));
+        }
+        let a = |index: usize| arguments[index];
+        Ok(match command {
+            EDIT_FACILITIES => DataEntry::EditFacilities(a(0)),
+            ERASE_FACILITIES => DataEntry::EraseFacilities(a(0)),
+            TRANSMIT_FACILITIES => DataEntry::TransmitFacilities(a(0)),
+            FORMAT_FACILITIES => DataEntry::FormatFacilities(a(0), a(1)),
+            MOVE_CURSOR => DataEntry::MoveCursor { x: a(0), y: a(1) },
+            SKIP_TO_LINE => DataEntry::SkipToLine(a(0)),
+            SKIP_TO_CHAR => DataEntry::SkipToChar(a(0)),
+            UP => DataEntry::Up,
+            DOWN => DataEntry::Down,
+            LEFT => DataEntry::Left,
+            RIGHT => DataEntry::Right,
+            HOME => DataEntry::Home,
+            LINE_INSERT => DataEntry::LineInsert,
+            LINE_DELETE => DataEntry::LineDelete,
+            CHAR_INSERT => DataEntry::CharInsert,
+            CHAR_DELETE => DataEntry::CharDelete,
+            READ_CURSOR => DataEntry::ReadCursor,
+            CURSOR_POSITION => DataEntry::CursorPosition { x: a(0), y: a(1) },
+            REVERSE_TAB => DataEntry::ReverseTab,
+            TRANSMIT_SCREEN => DataEntry::TransmitScreen,
+            TRANSMIT_UNPROTECTED => DataEntry::TransmitUnprotected,
+            TRANSMIT_LINE => DataEntry::TransmitLine,
+            TRANSMIT_FIELD => DataEntry::TransmitField,
+            TRANSMIT_REST_SCREEN => DataEntry::TransmitRestScreen,
+            TRANSMIT_REST_LINE => DataEntry::TransmitRestLine,
+            TRANSMIT_REST_FIELD => DataEntry::TransmitRestField,
+            TRANSMIT_MODIFIED => DataEntry::TransmitModified,
+            DATA_TRANSMIT => DataEntry::DataTransmit { x: a(0), y: a(1) },
+            ERASE_SCREEN => DataEntry::EraseScreen,
+            ERASE_LINE => DataEntry::EraseLine,
+            ERASE_FIELD => DataEntry::EraseField,
+            ERASE_REST_SCREEN => DataEntry::EraseRestScreen,
+            ERASE_REST_LINE => DataEntry::EraseRestLine,
+            ERASE_REST_FIELD => DataEntry::EraseRestField,
+            ERASE_UNPROTECTED => DataEntry::EraseUnprotected,
+            FORMAT_DATA => DataEntry::FormatData {
+                format: u16::from_be_bytes([a(0), a(1)]),
+                data: arguments[3..].to_vec(),
+            },
+            REPEAT => DataEntry::Repeat {
+                count: a(0),
+                character: a(1),
+            },
+            SUPPRESS_PROTECTION => DataEntry::SuppressProtection(a(0)),
+            FIELD_SEPARATOR => DataEntry::FieldSeparator,
+            FN => DataEntry::Function(a(0)),
+            _ => DataEntry::Error {
+                command: a(0),
+                code: a(1),
+            },
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DataEntry;
+    use bytes::BytesMut;
+
+    #[test]
+    fn test_round_trip() {
+        let field = DataEntry::FormatData {
+            format: 0x8001,
+            data: b"NAME".to_vec(),
+        };
+        for (argument, expected) in &[
+            (DataEntry::MoveCursor { x: 10, y: 2 }, &[5u8, 10, 2][..]),
+            (DataEntry::EraseField, &[31u8][..]),
+            (DataEntry::TransmitScreen, &[20u8][..]),
+            (field, &[36u8, 0x80, 1, 4, b'N', b'A', b'M', b'E'][..]),
+            (DataEntry::Function(3), &[40u8, 3][..]),
+        ] {
+            let mut encoded = BytesMut::with_capacity(argument.len());
+            argument.encode(&mut encoded).unwrap();
+            assert_eq!(*expected, &encoded[..]);
+            assert_eq!(argument.len(), encoded.len());
+            assert_eq!(
+                *argument,
+                DataEntry::decode(&mut &encoded[..]).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn test_decode_invalid() {
+        assert!(DataEntry::decode(&mut &[5u8, 1][..]).is_err());
+        assert!(DataEntry::decode(&mut &[36u8, 0, 0, 3, b'a'][..]).is_err());
+        assert!(DataEntry::decode(&mut &[42u8][..]).is_err());
+    }
+}
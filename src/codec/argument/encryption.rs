@@ -0,0 +1,279 @@
+//
+// Copyright 2019 Hans W. Uhlig. All Rights Reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+
+use super::{consts, TelnetError};
+use bytes::{Buf, BufMut};
+
+///
+/// Encryption Subnegotiation [RFC2946](http://www.iana.org/go/rfc2946)
+///
+#[derive(Clone, Debug, PartialEq)]
+pub enum Encryption {
+    /// Initial cipher data from the encrypting side.
+    Is(EncryptionType, Vec<u8>),
+    /// Encryption types supported by the decrypting side.
+    Support(Vec<EncryptionType>),
+    /// Cipher data answering `Is` from the decrypting side.
+    Reply(EncryptionType, Vec<u8>),
+    /// All data following this subnegotiation is encrypted with key id.
+    Start(Vec<u8>),
+    /// All data following this subnegotiation is no longer encrypted.
+    End,
+    /// Ask the encrypting side to `Start` with key id.
+    RequestStart(Vec<u8>),
+    /// Ask the encrypting side to `End`.
+    RequestEnd,
+    /// Key id the encrypting side wants to use.
+    EncryptKeyId(Vec<u8>),
+    /// Key id acknowledged by the decrypting side.
+    DecryptKeyId(Vec<u8>),
+}
+
+impl Encryption {
+    /// Get Encoded Length of `Encryption`
+    #[allow(clippy::len_without_is_empty)]
+    pub fn len(&self) -> usize {
+        1 + match self {
+            Encryption::Is(_, data) => 1 + data.len(),
+            Encryption::Support(kinds) => kinds.len(),
+            Encryption::Reply(_, data) => 1 + data.len(),
+            Encryption::Start(keyid) => keyid.len(),
+            Encryption::End => 0,
+            Encryption::RequestStart(keyid) => keyid.len(),
+            Encryption::RequestEnd => 0,
+            Encryption::EncryptKeyId(keyid) => keyid.len(),
+            Encryption::DecryptKeyId(keyid) => keyid.len(),
+        }
+    }
+    /// Encode `Encryption` to `BufMut`
+    pub fn encode<T: BufMut>(&self, dst: &mut T) -> Result<(), TelnetError> {
+        use consts::option::encryption::*;
+        match self {
+            Encryption::Is(kind, data) => {
+                dst.put_u8(IS);
+                dst.put_u8(u8::from(*kind));
+                dst.put(data.as_slice());
+            },
+            Encryption::Support(kinds) => {
+                dst.put_u8(SUPPORT);
+                for kind in kinds {
+                    dst.put_u8(u8::from(*kind));
+                }
+            },
+            Encryption::Reply(kind, data) => {
+                dst.put_u8(REPLY);
+                dst.put_u8(u8::from(*kind));
+                dst.put(data.as_slice());
+            },
+            Encryption::Start(keyid) => {
+                dst.put_u8(START);
+                dst.put(keyid.as_slice());
+            },
+            Encryption::End => dst.put_u8(END),
+            Encryption::RequestStart(keyid) => {
+                dst.put_u8(REQUEST_START);
+                dst.put(keyid.as_slice());
+            },
+            Encryption::RequestEnd => dst.put_u8(REQUEST_END),
+            Encryption::EncryptKeyId(keyid) => {
+                dst.put_u8(ENC_KEYID);
+                dst.put(keyid.as_slice());
+            },
+            Encryption::DecryptKeyId(keyid) => {
+                dst.put_u8(DEC_KEYID);
+                dst.put(keyid.as_slice());
+            },
+        }
+        Ok(())
+    }
+    /// Decode `Encryption` from `Buf`
+    pub fn decode<T: Buf>(src: &mut T) -> Result<Self, TelnetError> {
+        use consts::option::encryption::*;
+        if !src.has_remaining() {
+            return Err(TelnetError::DecodingError(
+                "Encryption subnegotiation is empty".to_string(),
+            ));
+        }
+        let command = src.get_u8();
+        match command {
+            IS | REPLY => {
+                if !src.has_remaining() {
+                    return Err(TelnetError::DecodingError(
+                        "Encryption type missing".to_string(),
+                    ));
+                }
+                let kind = EncryptionType::from(src.get_u8());
+                let data = src.to_bytes().to_vec();
+                match command {
+                    IS => Ok(Encryption::Is(kind, data)),
+                    _ => Ok(Encryption::Reply(kind, data)),
+                }
+            },
+            SUPPORT => {
+                let kinds = src.to_bytes();
+                let kinds =
+                    kinds.iter().map(|kind| EncryptionType::from(*kind));
+                Ok(Encryption::Support(kinds.collect()))
+            },
+            START => Ok(Encryption::Start(src.to_bytes().to_vec())),
+            END => Ok(Encryption::End),
+            REQUEST_START => {
+                Ok(Encryption::RequestStart(src.to_bytes().to_vec()))
+            },
+            REQUEST_END => Ok(Encryption::RequestEnd),
+            ENC_KEYID => Ok(Encryption::EncryptKeyId(src.to_bytes().to_vec())),
+            DEC_KEYID => Ok(Encryption::DecryptKeyId(src.to_bytes().to_vec())),
+            command => Err(TelnetError::DecodingError(format!(
+                "Unknown Encryption command {:#X}",
+                command
+            ))),
+        }
+    }
+}
+
+///
+/// [Telnet Encryption Types](https://www.iana.org/assignments/telnet-options/telnet-options.xhtml#telnet-options-4)
+///
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum EncryptionType {
+    /// No encryption. Sent to refuse all supported types.
+    Null,
+    /// DES 64 bit Cipher Feedback [RFC2952](http://www.iana.org/go/rfc2952)
+    DESCFB64,
+    /// DES 64 bit Output Feedback [RFC2953](http://www.iana.org/go/rfc2953)
+    DESOFB64,
+    /// Triple DES 64 bit Cipher Feedback [RFC2947](http://www.iana.org/go/rfc2947)
+    DES3CFB64,
+    /// Triple DES 64 bit Output Feedback [RFC2948](http://www.iana.org/go/rfc2948)
+    DES3OFB64,
+    /// CAST5 40 bit key 64 bit Cipher Feedback [RFC2950](http://www.iana.org/go/rfc2950)
+    CAST540CFB64,
+    /// CAST5 40 bit key 64 bit Output Feedback [RFC2949](http://www.iana.org/go/rfc2949)
+    CAST540OFB64,
+    /// CAST128 64 bit Cipher Feedback [RFC2950](http://www.iana.org/go/rfc2950)
+    CAST128CFB64,
+    /// CAST128 64 bit Output Feedback [RFC2949](http://www.iana.org/go/rfc2949)
+    CAST128OFB64,
+    /// AES Counter with CBC-MAC
+    AESCCM,
+    /// Unknown or Unassigned
+    Unknown(u8),
+}
+
+impl From<u8> for EncryptionType {
+    fn from(byte: u8) -> Self {
+        use consts::option::encryption::kind;
+        match byte {
+            kind::NULL => EncryptionType::Null,
+            kind::DES_CFB64 => EncryptionType::DESCFB64,
+            kind::DES_OFB64 => EncryptionType::DESOFB64,
+            kind::DES3_CFB64 => EncryptionType::DES3CFB64,
+            kind::DES3_OFB64 => EncryptionType::DES3OFB64,
+            kind::CAST5_40_CFB64 => EncryptionType::CAST540CFB64,
+            kind::CAST5_40_OFB64 => EncryptionType::CAST540OFB64,
+            kind::CAST128_CFB64 => EncryptionType::CAST128CFB64,
+            kind::CAST128_OFB64 => EncryptionType::CAST128OFB64,
+            kind::AES_CCM => EncryptionType::AESCCM,
+            byte => EncryptionType::Unknown(byte),
+        }
+    }
+}
+
+impl From<EncryptionType> for u8 {
+    fn from(kind: EncryptionType) -> Self {
+        use consts::option::encryption::kind;
+        match kind {
+            EncryptionType::Null => kind::NULL,
+            EncryptionType::DESCFB64 => kind::DES_CFB64,
+            EncryptionType::DESOFB64 => kind::DES_OFB64,
+            EncryptionType::DES3CFB64 => kind::DES3_CFB64,
+            EncryptionType::DES3OFB64 => kind::DES3_OFB64,
+            EncryptionType::CAST540CFB64 => kind::CAST5_40_CFB64,
+            EncryptionType::CAST540OFB64 => kind::CAST5_40_OFB64,
+            EncryptionType::CAST128CFB64 => kind::CAST128_CFB64,
+            EncryptionType::CAST128OFB64 => kind::CAST128_OFB64,
+            EncryptionType::AESCCM => kind::AES_CCM,
+            EncryptionType::Unknown(byte) => byte,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Encryption, EncryptionType};
+    use bytes::BytesMut;
+
+    #[test]
+    fn test_encode() {
+        let arguments = vec![
+            (
+                Encryption::Is(EncryptionType::DESCFB64, vec![1, 0xAA]),
+                vec![0, 1, 1, 0xAA],
+            ),
+            (
+                Encryption::Support(vec![
+                    EncryptionType::DES3CFB64,
+                    EncryptionType::Unknown(42),
+                ]),
+                vec![1, 3, 42],
+            ),
+            (
+                Encryption::Reply(EncryptionType::CAST128OFB64, vec![2]),
+                vec![2, 11, 2],
+            ),
+            (Encryption::Start(vec![0]), vec![3, 0]),
+            (Encryption::End, vec![4]),
+            (Encryption::RequestStart(vec![]), vec![5]),
+            (Encryption::RequestEnd, vec![6]),
+            (Encryption::EncryptKeyId(vec![1, 2]), vec![7, 1, 2]),
+            (Encryption::DecryptKeyId(vec![3]), vec![8, 3]),
+        ];
+        for (argument, bytes) in arguments {
+            let mut encoded = BytesMut::with_capacity(argument.len());
+            argument.encode(&mut encoded).unwrap();
+            assert_eq!(&bytes[..], &encoded[..]);
+            assert_eq!(argument.len(), encoded.len());
+
+            let decoded = Encryption::decode(&mut &encoded[..]).unwrap();
+            assert_eq!(argument, decoded);
+        }
+    }
+
+    #[test]
+    fn test_decode_null_and_support() {
+        let decoded = Encryption::decode(&mut &[0u8, 0][..]);
+        assert_eq!(
+            Encryption::Is(EncryptionType::Null, Vec::new()),
+            decoded.unwrap()
+        );
+        let decoded = Encryption::decode(&mut &[1u8, 12, 1][..]);
+        assert_eq!(
+            Encryption::Support(vec![
+                EncryptionType::AESCCM,
+                EncryptionType::DESCFB64
+            ]),
+            decoded.unwrap()
+        );
+    }
+
+    #[test]
+    fn test_decode_invalid() {
+        assert!(Encryption::decode(&mut &[][..]).is_err());
+        assert!(Encryption::decode(&mut &[0u8][..]).is_err());
+        assert!(Encryption::decode(&mut &[2u8][..]).is_err());
+        assert!(Encryption::decode(&mut &[9u8, 1][..]).is_err());
+    }
+}
@@ -0,0 +1,149 @@
+//
+// Copyright 2019 Hans W. Uhlig. All Rights Reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+use super::{consts, TelnetError};
+use crate::codec::ExtendedOption;
+use bytes::{Buf, BufMut};
+
+///
+/// Extended Options List Subnegotiation [RFC861](http://www.iana.org/go/rfc861)
+///
+/// Carries negotiation and subnegotiation of options 256 through 511.
+///
+#[derive(Clone, Debug, PartialEq)]
+pub enum ExtendedOptions {
+    /// Request the peer to enable an extended option.
+    Do(ExtendedOption),
+    /// Request the peer to disable an extended option.
+    Dont(ExtendedOption),
+    /// Offer to enable an extended option.
+    Will(ExtendedOption),
+    /// Refuse to enable an extended option.
+    Wont(ExtendedOption),
+    /// Subnegotiation parameters of an extended option.
+    Subnegotiate(ExtendedOption, Vec<u8>),
+}
+
+impl ExtendedOptions {
+    /// Get Encoded Length of `ExtendedOptions`
+    #[allow(clippy::len_without_is_empty)]
+    pub fn len(&self) -> usize {
+        2 + match self {
+            ExtendedOptions::Subnegotiate(_, data) => data.len() + 1,
+            _ => 0,
+        }
+    }
+    /// Encode `ExtendedOptions` to `BufMut`
+    pub fn encode<T: BufMut>(&self, dst: &mut T) -> Result<(), TelnetError> {
+        use consts::option::exopl::*;
+        match self {
+            ExtendedOptions::Do(option) => {
+                dst.put_u8(DO);
+                dst.put_u8(option.code());
+            },
+            ExtendedOptions::Dont(option) => {
+                dst.put_u8(DONT);
+                dst.put_u8(option.code());
+            },
+            ExtendedOptions::Will(option) => {
+                dst.put_u8(WILL);
+                dst.put_u8(option.code());
+            },
+            ExtendedOptions::Wont(option) => {
+                dst.put_u8(WONT);
+                dst.put_u8(option.code());
+            },
+            ExtendedOptions::Subnegotiate(option, data) => {
+                dst.put_u8(SB);
+                dst.put_u8(option.code());
+                dst.put(data.as_slice());
+                dst.put_u8(SE);
+            },
+        }
+        Ok(())
+    }
+    /// Decode `ExtendedOptions` from `Buf`
+    pub fn decode<T: Buf>(src: &mut T) -> Result<Self, TelnetError> {
+        use consts::option::exopl::*;
+        if src.remaining() < 2 {
+            return Err(TelnetError::DecodingError(
+                "Truncated EXOPL subnegotiation".to_string(),
+            ));
+        }
+        let command = src.get_u8();
+        let option = ExtendedOption::new(src.get_u8());
+        match command {
+            DO | DONT | WILL | WONT if src.has_remaining() => {
+                Err(TelnetError::DecodingError(format!(
+                    "Trailing data after EXOPL negotiation of {}",
+                    option.number()
+                )))
+            },
+            DO => Ok(ExtendedOptions::Do(option)),
+            DONT => Ok(ExtendedOptions::Dont(option)),
+            WILL => Ok(ExtendedOptions::Will(option)),
+            WONT => Ok(ExtendedOptions::Wont(option)),
+            SB => {
+                let mut data = src.to_bytes().to_vec();
+                if data.pop() != Some(SE) {
+                    return Err(TelnetError::DecodingError(format!(
+                        "Unterminated EXOPL subnegotiation of {}",
+                        option.number()
+                    )));
+                }
+                Ok(ExtendedOptions::Subnegotiate(option, data))
+            },
+            command => Err(TelnetError::DecodingError(format!(
+                "Unknown EXOPL command {:#X}",
+                command
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ExtendedOption, ExtendedOptions};
+    use bytes::BytesMut;
+
+    #[test]
+    fn test_round_trip() {
+        let option = ExtendedOption::from_number(300).unwrap();
+        for argument in &[
+            ExtendedOptions::Do(option),
+            ExtendedOptions::Wont(option),
+            ExtendedOptions::Subnegotiate(option, vec![1, 2, 3]),
+        ] {
+            let mut encoded = BytesMut::with_capacity(argument.len());
+            argument.encode(&mut encoded).unwrap();
+            assert_eq!(argument.len(), encoded.len());
+            let decoded = ExtendedOptions::decode(&mut &encoded[..]).unwrap();
+            assert_eq!(*argument, decoded);
+        }
+        let mut encoded = BytesMut::new();
+        ExtendedOptions::Subnegotiate(option, vec![9])
+            .encode(&mut encoded)
+            .unwrap();
+        assert_eq!(&[250u8, 44, 9, 240][..], &encoded[..]);
+    }
+
+    #[test]
+    fn test_decode_invalid() {
+        assert!(ExtendedOptions::decode(&mut &[253u8][..]).is_err());
+        assert!(ExtendedOptions::decode(&mut &[253u8, 1, 2][..]).is_err());
+        assert!(ExtendedOptions::decode(&mut &[250u8, 1, 2][..]).is_err());
+        assert!(ExtendedOptions::decode(&mut &[1u8, 1][..]).is_err());
+    }
+}
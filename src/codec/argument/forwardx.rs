@@ -0,0 +1,177 @@
+//
+// Copyright 2019 Hans W. Uhlig. All Rights Reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+use super::{consts, TelnetError};
+use bytes::{Buf, BufMut};
+
+///
+/// X Window System Forwarding Subnegotiation
+/// [draft-altman-telnet-forwardx](https://tools.ietf.org/html/draft-altman-telnet-forwardx-03)
+///
+/// X connections are multiplexed over the session as numbered channels.
+///
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ForwardX {
+    /// Client asks for X connections on display `screen` to be forwarded.
+    Forward(u8),
+    /// Server accepted a new X connection as channel.
+    Open(u16),
+    /// Channel was closed by the sender.
+    Close(u16),
+    /// Data for a channel.
+    Data(u16, Vec<u8>),
+    /// Bitmask of optional features supported by the sender.
+    Options(Vec<u8>),
+    /// Data for an optional feature.
+    OptionData(Vec<u8>),
+    /// Stop sending data on a channel.
+    Xoff(u16),
+    /// Resume sending data on a channel.
+    Xon(u16),
+}
+
+impl ForwardX {
+    /// Get Encoded Length of `ForwardX`
+    #[allow(clippy::len_without_is_empty)]
+    pub fn len(&self) -> usize {
+        match self {
+            ForwardX::Forward(_) => 2,
+            ForwardX::Open(_)
+            | ForwardX::Close(_)
+            | ForwardX::Xoff(_)
+            | ForwardX::Xon(_) => 3,
+            ForwardX::Data(_, data) => 3 + data.len(),
+            ForwardX::Options(data) | ForwardX::OptionData(data) => {
+                1 + data.len()
+            },
+        }
+    }
+    /// Encode `ForwardX` to `BufMut`
+    pub fn encode<T: BufMut>(&self, dst: &mut T) -> Result<(), TelnetError> {
+        use consts::option::forwardx::*;
+        match self {
+            ForwardX::Forward(screen) => {
+                dst.put_u8(SCREEN);
+                dst.put_u8(*screen);
+            },
+            ForwardX::Open(channel) => {
+                dst.put_u8(OPEN);
+                dst.put_u16(*channel);
+            },
+            ForwardX::Close(channel) => {
+                dst.put_u8(CLOSE);
+                dst.put_u16(*channel);
+            },
+            ForwardX::Data(channel, data) => {
+                dst.put_u8(DATA);
+                dst.put_u16(*channel);
+                dst.put_slice(data);
+            },
+            ForwardX::Options(data) => {
+                dst.put_u8(OPTIONS);
+                dst.put_slice(data);
+            },
+            ForwardX::OptionData(data) => {
+                dst.put_u8(OPTION_DATA);
+                dst.put_slice(data);
+            },
+            ForwardX::Xoff(channel) => {
+                dst.put_u8(XOFF);
+                dst.put_u16(*channel);
+            },
+            ForwardX::Xon(channel) => {
+                dst.put_u8(XON);
+                dst.put_u16(*channel);
+            },
+        }
+        Ok(())
+    }
+    /// Decode `ForwardX` from `Buf`
+    pub fn decode<T: Buf>(src: &mut T) -> Result<Self, TelnetError> {
+        use consts::option::forwardx::*;
+        if !src.has_remaining() {
+            return Err(TelnetError::DecodingError(
+                "FORWARD_X subnegotiation is empty".to_string(),
+            ));
+        }
+        let command = src.get_u8();
+        match command {
+            SCREEN if src.remaining() == 1 => {
+                Ok(ForwardX::Forward(src.get_u8()))
+            },
+            OPTIONS => Ok(ForwardX::Options(src.to_bytes().to_vec())),
+            OPTION_DATA => Ok(ForwardX::OptionData(src.to_bytes().to_vec())),
+            OPEN | CLOSE | DATA | XOFF | XON if src.remaining() >= 2 => {
+                let channel = src.get_u16();
+                match command {
+                    DATA => {
+                        Ok(ForwardX::Data(channel, src.to_bytes().to_vec()))
+                    },
+                    _ if src.has_remaining() => {
+                        Err(TelnetError::DecodingError(format!(
+                            "Trailing data after FORWARD_X command {:#X}",
+                            command
+                        )))
+                    },
+                    OPEN => Ok(ForwardX::Open(channel)),
+                    CLOSE => Ok(ForwardX::Close(channel)),
+                    XOFF => Ok(ForwardX::Xoff(channel)),
+                    _ => Ok(ForwardX::Xon(channel)),
+                }
+            },
+            SCREEN | OPEN | CLOSE | DATA | XOFF | XON => {
+                Err(TelnetError::DecodingError(format!(
+                    "FORWARD_X command {:#X} has wrong length",
+                    command
+                )))
+            },
+            command => Err(TelnetError::DecodingError(format!(
+                "Unknown FORWARD_X command {:#X}",
+                command
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ForwardX;
+    use bytes::BytesMut;
+
+    #[test]
+    fn test_round_trip() {
+        for (argument, expected) in &[
+            (ForwardX::Forward(0), &[0u8, 0][..]),
+            (ForwardX::Open(0x0102), &[1u8, 1, 2][..]),
+            (
+                ForwardX::Data(3, vec![b'x', 255]),
+                &[3u8, 0, 3, b'x', 255][..],
+            ),
+            (ForwardX::Xon(7), &[7u8, 0, 7][..]),
+        ] {
+            let mut encoded = BytesMut::with_capacity(argument.len());
+            argument.encode(&mut encoded).unwrap();
+            assert_eq!(*expected, &encoded[..]);
+            assert_eq!(*argument, ForwardX::decode(&mut &encoded[..]).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_decode_invalid() {
+        assert!(ForwardX::decode(&mut &[1u8, 0][..]).is_err());
+        assert!(ForwardX::decode(&mut &[2u8, 0, 1, 2][..]).is_err());
+        assert!(ForwardX::decode(&mut &[9u8][..]).is_err());
+    }
+}
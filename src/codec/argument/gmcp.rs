@@ -0,0 +1,95 @@
+//
+// Copyright 2019 Hans W. Uhlig. All Rights Reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+
+use super::TelnetError;
+use bytes::{Buf, BufMut};
+
+///
+/// [Generic Mud Communication Protocol](https://www.gammon.com.au/gmcp)
+/// message.
+///
+/// A dotted package and message name such as `Char.Vitals`, optionally
+/// followed by a space and JSON data. The data is left as text.
+///
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct GenericMudMessage {
+    /// Package and message name
+    pub package: String,
+    /// JSON data, if any
+    pub data: Option<String>,
+}
+
+impl GenericMudMessage {
+    /// Get Encoded Length of `GenericMudMessage`
+    #[allow(clippy::len_without_is_empty)]
+    pub fn len(&self) -> usize {
+        self.package.len() + self.data.as_ref().map_or(0, |data| 1 + data.len())
+    }
+    /// Encode `GenericMudMessage` to `BufMut`
+    pub fn encode<T: BufMut>(&self, dst: &mut T) -> Result<(), TelnetError> {
+        if self.package.is_empty() || self.package.contains(' ') {
+            return Err(TelnetError::EncodingError(format!(
+                "Invalid GMCP package {:?}",
+                self.package
+            )));
+        }
+        dst.put(self.package.as_bytes());
+        if let Some(data) = &self.data {
+            dst.put_u8(b' ');
+            dst.put(data.as_bytes());
+        }
+        Ok(())
+    }
+    /// Decode `GenericMudMessage` from `Buf`
+    pub fn decode<T: Buf>(src: &mut T) -> Result<Self, TelnetError> {
+        let message = String::from_utf8(src.to_bytes().to_vec())
+            .map_err(|error| TelnetError::DecodingError(error.to_string()))?;
+        let mut parts = message.splitn(2, ' ');
+        let package = parts.next().unwrap_or_default().to_string();
+        if package.is_empty() {
+            return Err(TelnetError::DecodingError(
+                "GMCP message has no package".to_string(),
+            ));
+        }
+        let data = parts.next().map(|data| data.trim().to_string());
+        Ok(GenericMudMessage { package, data })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::GenericMudMessage;
+    use bytes::BytesMut;
+
+    #[test]
+    fn test_round_trip() {
+        let argument = GenericMudMessage {
+            package: "Char.Vitals".to_string(),
+            data: Some("{\"hp\":10}".to_string()),
+        };
+        let mut encoded = BytesMut::with_capacity(argument.len());
+        argument.encode(&mut encoded).unwrap();
+        assert_eq!(&b"Char.Vitals {\"hp\":10}"[..], &encoded[..]);
+        assert_eq!(argument.len(), encoded.len());
+
+        let decoded = GenericMudMessage::decode(&mut &encoded[..]).unwrap();
+        assert_eq!(argument, decoded);
+
+        let decoded = GenericMudMessage::decode(&mut &b"Core.Ping"[..]);
+        assert_eq!(None, decoded.unwrap().data);
+        assert!(GenericMudMessage::decode(&mut &b" {}"[..]).is_err());
+    }
+}
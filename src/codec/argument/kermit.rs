@@ -0,0 +1,143 @@
+//
+// Copyright 2019 Hans W. Uhlig. All Rights Reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+use super::{consts, TelnetError};
+use bytes::{Buf, BufMut};
+
+///
+/// Kermit Subnegotiation [RFC2840](http://www.iana.org/go/rfc2840)
+///
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Kermit {
+    /// Sender has entered Kermit server mode.
+    StartServer,
+    /// Sender has left Kermit server mode.
+    StopServer,
+    /// Ask the receiver to enter Kermit server mode.
+    RequestStartServer,
+    /// Ask the receiver to leave Kermit server mode.
+    RequestStopServer,
+    /// Start of packet character the sender uses.
+    StartOfPacket(u8),
+    /// Answer to `RequestStartServer`, true if server mode was entered.
+    ResponseStartServer(bool),
+    /// Answer to `RequestStopServer`, true if server mode was left.
+    ResponseStopServer(bool),
+}
+
+impl Kermit {
+    /// Get Encoded Length of `Kermit`
+    #[allow(clippy::len_without_is_empty)]
+    pub fn len(&self) -> usize {
+        match self {
+            Kermit::StartOfPacket(_)
+            | Kermit::ResponseStartServer(_)
+            | Kermit::ResponseStopServer(_) => 2,
+            _ => 1,
+        }
+    }
+    /// Encode `Kermit` to `BufMut`
+    pub fn encode<T: BufMut>(&self, dst: &mut T) -> Result<(), TelnetError> {
+        use consts::option::kermit::*;
+        match self {
+            Kermit::StartServer => dst.put_u8(START_SERVER),
+            Kermit::StopServer => dst.put_u8(STOP_SERVER),
+            Kermit::RequestStartServer => dst.put_u8(REQ_START_SERVER),
+            Kermit::RequestStopServer => dst.put_u8(REQ_STOP_SERVER),
+            Kermit::StartOfPacket(sop) => {
+                dst.put_u8(SOP);
+                dst.put_u8(*sop);
+            },
+            Kermit::ResponseStartServer(ok) => {
+                dst.put_u8(RESP_START_SERVER);
+                dst.put_u8(u8::from(*ok));
+            },
+            Kermit::ResponseStopServer(ok) => {
+                dst.put_u8(RESP_STOP_SERVER);
+                dst.put_u8(u8::from(*ok));
+            },
+        }
+        Ok(())
+    }
+    /// Decode `Kermit` from `Buf`
+    pub fn decode<T: Buf>(src: &mut T) -> Result<Self, TelnetError> {
+        use consts::option::kermit::*;
+        if !src.has_remaining() {
+            return Err(TelnetError::DecodingError(
+                "Kermit subnegotiation is empty".to_string(),
+            ));
+        }
+        let command = src.get_u8();
+        let argument = match (command, src.remaining()) {
+            (SOP, 1) | (RESP_START_SERVER, 1) | (RESP_STOP_SERVER, 1) => {
+                src.get_u8()
+            },
+            (SOP, _) | (RESP_START_SERVER, _) | (RESP_STOP_SERVER, _) => {
+                return Err(TelnetError::DecodingError(format!(
+                    "Kermit command {:#X} takes exactly one byte",
+                    command
+                )))
+            },
+            (_, 0) => 0,
+            (_, _) => {
+                return Err(TelnetError::DecodingError(format!(
+                    "Trailing data after Kermit command {:#X}",
+                    command
+                )))
+            },
+        };
+        match command {
+            START_SERVER => Ok(Kermit::StartServer),
+            STOP_SERVER => Ok(Kermit::StopServer),
+            REQ_START_SERVER => Ok(Kermit::RequestStartServer),
+            REQ_STOP_SERVER => Ok(Kermit::RequestStopServer),
+            SOP => Ok(Kermit::StartOfPacket(argument)),
+            RESP_START_SERVER => Ok(Kermit::ResponseStartServer(argument != 0)),
+            RESP_STOP_SERVER => Ok(Kermit::ResponseStopServer(argument != 0)),
+            command => Err(TelnetError::DecodingError(format!(
+                "Unknown Kermit command {:#X}",
+                command
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Kermit;
+    use bytes::BytesMut;
+
+    #[test]
+    fn test_round_trip() {
+        for (argument, expected) in &[
+            (Kermit::StartServer, &[0u8][..]),
+            (Kermit::RequestStopServer, &[3u8][..]),
+            (Kermit::StartOfPacket(1), &[4u8, 1][..]),
+            (Kermit::ResponseStartServer(true), &[8u8, 1][..]),
+        ] {
+            let mut encoded = BytesMut::with_capacity(argument.len());
+            argument.encode(&mut encoded).unwrap();
+            assert_eq!(*expected, &encoded[..]);
+            assert_eq!(*argument, Kermit::decode(&mut &encoded[..]).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_decode_invalid() {
+        assert!(Kermit::decode(&mut &[4u8][..]).is_err());
+        assert!(Kermit::decode(&mut &[0u8, 1][..]).is_err());
+        assert!(Kermit::decode(&mut &[7u8][..]).is_err());
+    }
+}
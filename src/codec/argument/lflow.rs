@@ -0,0 +1,101 @@
+//
+// Copyright 2019 Hans W. Uhlig. All Rights Reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+
+use super::{consts, TelnetError};
+use bytes::{Buf, BufMut};
+
+///
+/// Remote Flow Control Subnegotiation [RFC1372](http://www.iana.org/go/rfc1372)
+///
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum FlowControl {
+    /// XON and XOFF are passed through as data.
+    Off,
+    /// XOFF suspends output until it is restarted.
+    On,
+    /// Any character restarts suspended output.
+    RestartAny,
+    /// Only XON restarts suspended output.
+    RestartXon,
+}
+
+impl FlowControl {
+    /// Get Encoded Length of `FlowControl`
+    #[allow(clippy::len_without_is_empty)]
+    pub fn len(&self) -> usize {
+        1
+    }
+    /// Encode `FlowControl` to `BufMut`
+    pub fn encode<T: BufMut>(&self, dst: &mut T) -> Result<(), TelnetError> {
+        use consts::option::lflow::*;
+        dst.put_u8(match self {
+            FlowControl::Off => OFF,
+            FlowControl::On => ON,
+            FlowControl::RestartAny => RESTART_ANY,
+            FlowControl::RestartXon => RESTART_XON,
+        });
+        Ok(())
+    }
+    /// Decode `FlowControl` from `Buf`
+    pub fn decode<T: Buf>(src: &mut T) -> Result<Self, TelnetError> {
+        use consts::option::lflow::*;
+        if src.remaining() != 1 {
+            return Err(TelnetError::DecodingError(format!(
+                "LFLOW subnegotiation must be 1 byte, got {}",
+                src.remaining()
+            )));
+        }
+        match src.get_u8() {
+            OFF => Ok(FlowControl::Off),
+            ON => Ok(FlowControl::On),
+            RESTART_ANY => Ok(FlowControl::RestartAny),
+            RESTART_XON => Ok(FlowControl::RestartXon),
+            command => Err(TelnetError::DecodingError(format!(
+                "Unknown LFLOW command {:#X}",
+                command
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FlowControl;
+    use bytes::BytesMut;
+
+    #[test]
+    fn test_round_trip() {
+        for (argument, byte) in &[
+            (FlowControl::Off, 0u8),
+            (FlowControl::On, 1),
+            (FlowControl::RestartAny, 2),
+            (FlowControl::RestartXon, 3),
+        ] {
+            let mut encoded = BytesMut::with_capacity(argument.len());
+            argument.encode(&mut encoded).unwrap();
+            assert_eq!(&[*byte][..], &encoded[..]);
+            let decoded = FlowControl::decode(&mut &encoded[..]).unwrap();
+            assert_eq!(*argument, decoded);
+        }
+    }
+
+    #[test]
+    fn test_decode_invalid() {
+        assert!(FlowControl::decode(&mut &[4u8][..]).is_err());
+        assert!(FlowControl::decode(&mut &[1u8, 1][..]).is_err());
+        assert!(FlowControl::decode(&mut &[][..]).is_err());
+    }
+}
@@ -19,34 +19,36 @@ mod parser;
 
 use super::consts;
 use std::collections::HashMap;
-use crate::codec::TelnetError;
 
 pub use self::encoder::{encode_msdp, encoded_msdp_length};
+pub use self::parser::decode_msdp;
 
 /// `MudServerData` contains data about the Mud.
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
 pub struct MudServerData(HashMap<MudServerDataVariable, MudServerDataValue>);
 
 impl From<Vec<(MudServerDataVariable, MudServerDataValue)>> for MudServerData {
-    fn from(table: Vec<(MudServerDataVariable, MudServerDataValue)>) -> MudServerData {
+    fn from(
+        table: Vec<(MudServerDataVariable, MudServerDataValue)>,
+    ) -> MudServerData {
         let mut map = HashMap::default();
-        table.into_iter().for_each(|(k, v)| { map.insert(k, v); });
+        table.into_iter().for_each(|(k, v)| {
+            map.insert(k, v);
+        });
         MudServerData(map)
     }
 }
 
-impl From<HashMap<MudServerDataVariable, MudServerDataValue>> for MudServerData {
-    fn from(table: HashMap<MudServerDataVariable, MudServerDataValue>) -> MudServerData {
+impl From<HashMap<MudServerDataVariable, MudServerDataValue>>
+    for MudServerData
+{
+    fn from(
+        table: HashMap<MudServerDataVariable, MudServerDataValue>,
+    ) -> MudServerData {
         MudServerData(table)
     }
 }
 
-impl Default for MudServerData {
-    fn default() -> MudServerData {
-        MudServerData(HashMap::new())
-    }
-}
-
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub enum MudServerDataVariable {
     Bytes(Vec<u8>),
@@ -66,7 +68,7 @@ impl From<&[u8]> for MudServerDataVariable {
 ///
 /// `MudServerDataValue`
 ///
-#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum MudServerDataValue {
     Bytes(Vec<u8>),
     String(String),
@@ -96,35 +98,84 @@ impl From<Vec<MudServerDataValue>> for MudServerDataValue {
     }
 }
 
-impl From<Vec<(MudServerDataVariable, MudServerDataValue)>> for MudServerDataValue {
-    fn from(table: Vec<(MudServerDataVariable, MudServerDataValue)>) -> MudServerDataValue {
+impl From<Vec<(MudServerDataVariable, MudServerDataValue)>>
+    for MudServerDataValue
+{
+    fn from(
+        table: Vec<(MudServerDataVariable, MudServerDataValue)>,
+    ) -> MudServerDataValue {
         let mut map = HashMap::default();
-        table.into_iter().for_each(|(k, v)| { map.insert(k, v); });
+        table.into_iter().for_each(|(k, v)| {
+            map.insert(k, v);
+        });
         MudServerDataValue::Table(map)
     }
 }
 
-impl From<HashMap<MudServerDataVariable, MudServerDataValue>> for MudServerDataValue {
-    fn from(table: HashMap<MudServerDataVariable, MudServerDataValue>) -> MudServerDataValue {
+impl From<HashMap<MudServerDataVariable, MudServerDataValue>>
+    for MudServerDataValue
+{
+    fn from(
+        table: HashMap<MudServerDataVariable, MudServerDataValue>,
+    ) -> MudServerDataValue {
         MudServerDataValue::Table(table)
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{consts, encoded_msdp_length, MudServerData, MudServerDataValue};
+    use super::{
+        consts, decode_msdp, encode_msdp, encoded_msdp_length, MudServerData,
+        MudServerDataValue, MudServerDataVariable,
+    };
     use bytes::BytesMut;
     use std::collections::HashMap;
 
     #[test]
     fn test_encode() {
         let mut map = HashMap::new();
-        map.insert("LIST".to_string(), MudServerDataValue::from("COMMANDS"));
-
+        map.insert(
+            MudServerDataVariable::from(&b"LIST"[..]),
+            MudServerDataValue::from("COMMANDS"),
+        );
+        let data = MudServerData::from(map);
 
         // MSDP_VAR "LIST" MSDP_VAL "COMMANDS"
-        let expected = 14;
-        let result = encoded_msdp_length(&MudServerData::from(map));
-        assert_eq!(expected, result, "encoded size didn't match");
+        let mut expected = vec![consts::option::msdp::VAR];
+        expected.extend_from_slice(b"LIST");
+        expected.push(consts::option::msdp::VAL);
+        expected.extend_from_slice(b"COMMANDS");
+        assert_eq!(14, encoded_msdp_length(&data));
+        let mut buffer = BytesMut::new();
+        encode_msdp(&data, &mut buffer);
+        assert_eq!(expected, buffer.to_vec());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_decode() {
+        use consts::option::msdp::*;
+        let mut input = vec![VAR];
+        input.extend_from_slice(b"ROOM");
+        input.extend_from_slice(&[VAL, TABLE_OPEN, VAR]);
+        input.extend_from_slice(b"EXITS");
+        input.extend_from_slice(&[VAL, ARRAY_OPEN, VAL, b'n', VAL, b's']);
+        input.extend_from_slice(&[ARRAY_CLOSE, TABLE_CLOSE]);
+        let data = decode_msdp(&input).unwrap();
+
+        let exits = MudServerDataValue::Array(vec![
+            MudServerDataValue::from("n"),
+            MudServerDataValue::from("s"),
+        ]);
+        let room = MudServerDataValue::from(vec![(
+            MudServerDataVariable::from(&b"EXITS"[..]),
+            exits,
+        )]);
+        let expected = MudServerData::from(vec![(
+            MudServerDataVariable::from(&b"ROOM"[..]),
+            room,
+        )]);
+        assert_eq!(expected, data);
+        assert_eq!(input.len(), encoded_msdp_length(&data));
+        assert!(decode_msdp(&[VAR, b'X']).is_err());
+    }
+}
@@ -14,83 +14,112 @@
 // limitations under the License.
 //
 
-use bytes::Buf;
-use nom::{IResult};
-use super::{consts, MudServerData};
+use super::{consts, MudServerData, MudServerDataValue, MudServerDataVariable};
 use crate::codec::TelnetError;
+use std::collections::HashMap;
 
-pub fn decode_msdp<T: Buf>(input: &[u8]) -> Result<MudServerData, TelnetError> {
-    parser::msdp
+///
+/// Decode an MSDP subnegotiation payload, a sequence of `VAR` name `VAL`
+/// value pairs. Values are plain strings, `ARRAY_OPEN`/`ARRAY_CLOSE`
+/// delimited arrays, or `TABLE_OPEN`/`TABLE_CLOSE` delimited tables.
+///
+pub fn decode_msdp(input: &[u8]) -> Result<MudServerData, TelnetError> {
+    let mut parser = Parser { input, position: 0 };
+    let table = parser.entries(None)?;
+    Ok(MudServerData(table))
 }
 
-mod parser {
-    use super::consts;
-    use crate::codec::argument::msdp::{MudServerDataValue, MudServerData};
-    use nom::combinator::{not, map};
-    use nom::sequence::{delimited, preceded, pair};
-    use nom::bytes::complete::{tag, take_until, take_while};
-    use nom::multi::many0;
-    use nom::branch::alt;
-    use nom::IResult;
-    use crate::codec::TelnetError;
-    use nom::lib::std::collections::HashMap;
-
-    pub fn msdp(i: &[u8]) -> IResult<&[u8], MudServerData> {
-        map(many0(table_entry)(i), MudServerData)
-    }
-
-    pub fn variable(i: &[u8]) -> nom::IResult<&[u8], &str> {
-        preceded(
-            tag(consts::option::msdp::VAR),
-            take_until(not(tag(control_byte))),
-        )(i)
-    }
+struct Parser<'a> {
+    input: &'a [u8],
+    position: usize,
+}
 
-    pub fn value(i: &[u8]) -> nom::IResult<&[u8], MudServerDataValue> {
-        preceded(
-            tag(consts::option::msdp::VAL),
-            alt((
-                map(array, MudServerDataValue::Array),
-                map(table, MudServerDataValue::Table),
-                map(string, MudServerDataValue::String),
-            ))
-        )(i)
+impl<'a> Parser<'a> {
+    /// Next byte without consuming it
+    fn peek(&self) -> Option<u8> {
+        self.input.get(self.position).copied()
     }
-
-    pub fn array(i: &[u8]) -> IResult<&[u8], Vec<MudServerDataValue>> {
-        delimited(
-            tag(consts::option::msdp::ARRAY_OPEN),
-            many0(value),
-            tag(consts::option::msdp::ARRAY_CLOSE),
-        )(i)
+    /// Consume `byte`, failing if anything else is next
+    fn expect(&mut self, byte: u8) -> Result<(), TelnetError> {
+        match self.peek() {
+            Some(next) if next == byte => {
+                self.position += 1;
+                Ok(())
+            },
+            next => Err(TelnetError::DecodingError(format!(
+                "Expected MSDP byte {:#X}, found {:?}",
+                byte, next
+            ))),
+        }
     }
-
-    pub fn string(i: &[u8]) -> nom::IResult<&[u8], String> {
-        take_while(not(control_byte))
+    /// Bytes up to the next control byte
+    fn text(&mut self) -> &'a [u8] {
+        let start = self.position;
+        while let Some(byte) = self.peek() {
+            if control_byte(byte) {
+                break;
+            }
+            self.position += 1;
+        }
+        &self.input[start..self.position]
     }
-
-    pub fn table(i: &[u8]) -> nom::IResult<&[u8], Vec<(String,MudServerDataValue)>> {
-        delimited(
-            tag(consts::option::msdp::TABLE_OPEN),
-            many0(table_entry),
-            tag(consts::option::msdp::TABLE_CLOSE),
-        )
+    /// `VAR` name `VAL` value pairs until `close`, or the end of input
+    fn entries(
+        &mut self,
+        close: Option<u8>,
+    ) -> Result<HashMap<MudServerDataVariable, MudServerDataValue>, TelnetError>
+    {
+        let mut table = HashMap::new();
+        loop {
+            match self.peek() {
+                None if close.is_none() => return Ok(table),
+                Some(byte) if Some(byte) == close => {
+                    self.position += 1;
+                    return Ok(table);
+                },
+                _ => {},
+            }
+            self.expect(consts::option::msdp::VAR)?;
+            let name = MudServerDataVariable::from(self.text());
+            self.expect(consts::option::msdp::VAL)?;
+            let value = self.value()?;
+            table.insert(name, value);
+        }
     }
-
-    pub fn table_entry(i: &[u8]) -> nom::IResult<&[u8], (String, MudServerDataValue)> {
-        pair(variable, value)
+    /// A value following `VAL`
+    fn value(&mut self) -> Result<MudServerDataValue, TelnetError> {
+        match self.peek() {
+            Some(consts::option::msdp::TABLE_OPEN) => {
+                self.position += 1;
+                let close = Some(consts::option::msdp::TABLE_CLOSE);
+                Ok(MudServerDataValue::Table(self.entries(close)?))
+            },
+            Some(consts::option::msdp::ARRAY_OPEN) => {
+                self.position += 1;
+                let mut array = Vec::new();
+                while self.peek() == Some(consts::option::msdp::VAL) {
+                    self.position += 1;
+                    array.push(self.value()?);
+                }
+                self.expect(consts::option::msdp::ARRAY_CLOSE)?;
+                Ok(MudServerDataValue::Array(array))
+            },
+            _ => Ok(MudServerDataValue::from(self.text())),
+        }
     }
+}
 
-    pub fn control_byte(byte: u8) -> bool {
-        (
-            consts::NUL,
-            consts::IAC,
-            consts::option::msdp::VAL,
-            consts::option::msdp::VAR,
-            consts::option::msdp::TABLE_OPEN,
-            consts::option::msdp::TABLE_CLOSE,
-            consts::option::msdp::ARRAY_OPEN,
-            consts::option::msdp::ARRAY_CLOSE,
-        ).contains(byte)
-    }
-}
\ No newline at end of file
+/// True for bytes with a meaning of their own in MSDP
+fn control_byte(byte: u8) -> bool {
+    [
+        consts::NUL,
+        consts::IAC,
+        consts::option::msdp::VAL,
+        consts::option::msdp::VAR,
+        consts::option::msdp::TABLE_OPEN,
+        consts::option::msdp::TABLE_CLOSE,
+        consts::option::msdp::ARRAY_OPEN,
+        consts::option::msdp::ARRAY_CLOSE,
+    ]
+    .contains(&byte)
+}
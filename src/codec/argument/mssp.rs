@@ -31,7 +31,7 @@ impl Default for MudServerStatus {
 impl MudServerStatus {
     /// Create a new MudServerStatus
     pub fn new(status: HashMap<String, Vec<String>>) -> MudServerStatus {
-        MudServerStatus(HashMap::new())
+        MudServerStatus(status)
     }
     /// Get Encoded Length of `MudServerStatus`
     pub fn len(&self) -> usize {
@@ -80,7 +80,7 @@ impl MudServerStatus {
         Ok(())
     }
     /// Decode `MudServerStatus` from `Buf`
-    pub fn decode<T: Buf>(src: &mut T) -> Result<Self, TelnetError> {
+    pub fn decode<T: Buf>(_src: &mut T) -> Result<Self, TelnetError> {
         Ok(MudServerStatus::default())
     }
 }
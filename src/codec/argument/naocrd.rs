@@ -20,6 +20,7 @@ use bytes::{Buf, BufMut};
 ///
 /// Negotiate About Output Carriage-Return Disposition Data Sender (NAOCRD)
 ///
+#[allow(clippy::upper_case_acronyms)]
 #[derive(Clone, Debug, PartialEq)]
 pub enum NAOCRD {
     Sender(u8),
@@ -28,7 +29,7 @@ pub enum NAOCRD {
 }
 
 impl NAOCRD {
-    ///
+    /// Create a new NAOCRD for the data sender.
     pub fn new_sender(value: u8) -> NAOCRD {
         NAOCRD::Sender(value)
     }
@@ -61,7 +62,7 @@ impl NAOCRD {
         Ok(())
     }
     /// Decode `NAOCRD` from `Buf`
-    pub fn decode<T: Buf>(src: &mut T) -> Result<Self, TelnetError> {
+    pub fn decode<T: Buf>(_src: &mut T) -> Result<Self, TelnetError> {
         Ok(NAOCRD::default())
     }
 }
@@ -22,6 +22,7 @@ use bytes::{Buf, BufMut};
 /// [RFC653](http://www.iana.org/go/rfc653)
 /// TODO: Implement This
 ///
+#[allow(clippy::upper_case_acronyms)]
 #[derive(Clone, Debug, PartialEq)]
 pub struct NAOHTS(Vec<u8>);
 
@@ -40,7 +41,7 @@ impl NAOHTS {
         Ok(())
     }
     /// Decode `NAOCRD` from `Buf`
-    pub fn decode<T: Buf>(src: &mut T) -> Result<Self, TelnetError> {
+    pub fn decode<T: Buf>(_src: &mut T) -> Result<Self, TelnetError> {
         Ok(NAOHTS::default())
     }
 }
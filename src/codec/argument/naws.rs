@@ -0,0 +1,82 @@
+//
+// Copyright 2019 Hans W. Uhlig. All Rights Reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+
+use super::TelnetError;
+use bytes::{Buf, BufMut};
+
+///
+/// Negotiate About Window Size Subnegotiation
+/// [RFC1073](http://www.iana.org/go/rfc1073)
+///
+/// Sent by the client whenever its window changes. Zero means the size in
+/// that dimension is unknown.
+///
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct WindowSize {
+    /// Width in characters
+    pub width: u16,
+    /// Height in characters
+    pub height: u16,
+}
+
+impl WindowSize {
+    /// Get Encoded Length of `WindowSize`
+    #[allow(clippy::len_without_is_empty)]
+    pub fn len(&self) -> usize {
+        4
+    }
+    /// Encode `WindowSize` to `BufMut`
+    pub fn encode<T: BufMut>(&self, dst: &mut T) -> Result<(), TelnetError> {
+        dst.put_u16(self.width);
+        dst.put_u16(self.height);
+        Ok(())
+    }
+    /// Decode `WindowSize` from `Buf`
+    pub fn decode<T: Buf>(src: &mut T) -> Result<Self, TelnetError> {
+        if src.remaining() != 4 {
+            return Err(TelnetError::DecodingError(format!(
+                "Window Size must be 4 bytes, got {}",
+                src.remaining()
+            )));
+        }
+        Ok(WindowSize {
+            width: src.get_u16(),
+            height: src.get_u16(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::WindowSize;
+    use bytes::BytesMut;
+
+    #[test]
+    fn test_round_trip() {
+        let argument = WindowSize {
+            width: 80,
+            height: 300,
+        };
+        let mut encoded = BytesMut::with_capacity(argument.len());
+        argument.encode(&mut encoded).unwrap();
+        assert_eq!(&[0u8, 80, 1, 44][..], &encoded[..]);
+        assert_eq!(argument.len(), encoded.len());
+
+        let decoded = WindowSize::decode(&mut &encoded[..]).unwrap();
+        assert_eq!(argument, decoded);
+        assert!(WindowSize::decode(&mut &[0u8, 80, 0][..]).is_err());
+    }
+}
@@ -0,0 +1,127 @@
+//
+// Copyright 2019 Hans W. Uhlig. All Rights Reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+use super::{consts, TelnetError};
+use bytes::{Buf, BufMut};
+
+///
+/// Output Marking Subnegotiation [RFC933](http://www.iana.org/go/rfc933)
+///
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum OutputMarking {
+    /// Banner accepted and displayed.
+    Acknowledge,
+    /// Banner refused.
+    Refuse,
+    /// Banner to mark output with. `control` says where the terminal
+    /// should display it.
+    Banner {
+        /// Positioning control
+        control: u8,
+        /// Marking text
+        text: String,
+    },
+}
+
+impl OutputMarking {
+    /// Get Encoded Length of `OutputMarking`
+    #[allow(clippy::len_without_is_empty)]
+    pub fn len(&self) -> usize {
+        1 + match self {
+            OutputMarking::Banner { text, .. } => text.len(),
+            _ => 0,
+        }
+    }
+    /// Encode `OutputMarking` to `BufMut`
+    pub fn encode<T: BufMut>(&self, dst: &mut T) -> Result<(), TelnetError> {
+        use consts::option::outmrk::*;
+        match self {
+            OutputMarking::Acknowledge => dst.put_u8(ACK),
+            OutputMarking::Refuse => dst.put_u8(NAK),
+            OutputMarking::Banner { control, .. }
+                if *control == ACK || *control == NAK =>
+            {
+                return Err(TelnetError::EncodingError(format!(
+                    "OUTMRK banner control {:#X} is reserved",
+                    control
+                )));
+            },
+            OutputMarking::Banner { control, text } => {
+                dst.put_u8(*control);
+                dst.put(text.as_bytes());
+            },
+        }
+        Ok(())
+    }
+    /// Decode `OutputMarking` from `Buf`
+    pub fn decode<T: Buf>(src: &mut T) -> Result<Self, TelnetError> {
+        use consts::option::outmrk::*;
+        if !src.has_remaining() {
+            return Err(TelnetError::DecodingError(
+                "OUTMRK subnegotiation is empty".to_string(),
+            ));
+        }
+        match src.get_u8() {
+            ACK if !src.has_remaining() => Ok(OutputMarking::Acknowledge),
+            NAK if !src.has_remaining() => Ok(OutputMarking::Refuse),
+            ACK | NAK => Err(TelnetError::DecodingError(
+                "OUTMRK response carries text".to_string(),
+            )),
+            control => {
+                let text = String::from_utf8(src.to_bytes().to_vec()).map_err(
+                    |error| TelnetError::DecodingError(error.to_string()),
+                )?;
+                Ok(OutputMarking::Banner { control, text })
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::OutputMarking;
+    use bytes::BytesMut;
+
+    #[test]
+    fn test_round_trip() {
+        let banner = OutputMarking::Banner {
+            control: b'-',
+            text: "SECRET".to_string(),
+        };
+        for (argument, expected) in &[
+            (banner, &b"-SECRET"[..]),
+            (OutputMarking::Acknowledge, &[6u8][..]),
+            (OutputMarking::Refuse, &[21u8][..]),
+        ] {
+            let mut encoded = BytesMut::with_capacity(argument.len());
+            argument.encode(&mut encoded).unwrap();
+            assert_eq!(*expected, &encoded[..]);
+            assert_eq!(
+                *argument,
+                OutputMarking::decode(&mut &encoded[..]).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert!(OutputMarking::decode(&mut &[6u8, b'x'][..]).is_err());
+        let banner = OutputMarking::Banner {
+            control: 6,
+            text: String::new(),
+        };
+        assert!(banner.encode(&mut BytesMut::new()).is_err());
+    }
+}
@@ -0,0 +1,209 @@
+//
+// Copyright 2019 Hans W. Uhlig. All Rights Reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+use super::{consts, TelnetError};
+use bytes::{Buf, BufMut};
+
+///
+/// Remote Controlled Transmission and Echoing Subnegotiation
+/// [RFC726](http://www.iana.org/go/rfc726)
+///
+/// Sent by the host to tell the terminal how to echo the text it buffers and
+/// which characters end a line of input.
+///
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum RCTE {
+    /// Repeat the previous command.
+    Repeat,
+    /// Echo settings for the next line of input, and optionally new break
+    /// and transmit classes. A command with every field unset is sent as
+    /// `Repeat`.
+    Command {
+        /// Don't echo the break character.
+        skip_break: bool,
+        /// Don't echo text up to the break character.
+        skip_text: bool,
+        /// New break classes
+        break_classes: Option<RCTEClasses>,
+        /// New transmit classes
+        transmit_classes: Option<RCTEClasses>,
+    },
+}
+
+///
+/// Set of RCTE character classes
+///
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct RCTEClasses(pub u16);
+
+impl RCTEClasses {
+    /// True if every class in `mask` is set.
+    pub fn contains(self, mask: u16) -> bool {
+        self.0 & mask == mask
+    }
+    /// True if `byte` falls in any class of the set.
+    pub fn matches(self, byte: u8) -> bool {
+        self.0 & RCTEClasses::class(byte) != 0
+    }
+    /// Class of `byte`, or 0 for bytes outside every class.
+    pub fn class(byte: u8) -> u16 {
+        use consts::option::rcte::class::*;
+        match byte {
+            b'A'..=b'Z' => UPPER_CASE,
+            b'a'..=b'z' => LOWER_CASE,
+            b'0'..=b'9' => NUMBERS,
+            0x08..=0x0D => FORMAT_EFFECTORS,
+            0x00..=0x1F | 0x7F => CONTROL,
+            b'.' | b',' | b';' | b':' | b'?' | b'!' => PUNCTUATION,
+            b'{' | b'[' | b'(' | b'<' | b'>' | b')' | b']' | b'}' => GROUPING,
+            b' ' => SPACE,
+            0x21..=0x7E => MISCELLANEOUS,
+            _ => 0,
+        }
+    }
+}
+
+impl RCTE {
+    /// Get Encoded Length of `RCTE`
+    #[allow(clippy::len_without_is_empty)]
+    pub fn len(&self) -> usize {
+        match self {
+            RCTE::Repeat => 1,
+            RCTE::Command {
+                break_classes,
+                transmit_classes,
+                ..
+            } => {
+                1 + break_classes.map_or(0, |_| 2)
+                    + transmit_classes.map_or(0, |_| 2)
+            },
+        }
+    }
+    /// Encode `RCTE` to `BufMut`
+    pub fn encode<T: BufMut>(&self, dst: &mut T) -> Result<(), TelnetError> {
+        use consts::option::rcte::*;
+        match self {
+            RCTE::Repeat => dst.put_u8(REPEAT),
+            RCTE::Command {
+                skip_break,
+                skip_text,
+                break_classes,
+                transmit_classes,
+            } => {
+                let mut command = REPEAT;
+                if *skip_break {
+                    command |= SKIP_BREAK;
+                }
+                if *skip_text {
+                    command |= SKIP_TEXT;
+                }
+                if break_classes.is_some() {
+                    command |= BREAK_CLASSES;
+                }
+                if transmit_classes.is_some() {
+                    command |= TRANSMIT_CLASSES;
+                }
+                dst.put_u8(command);
+                if let Some(classes) = break_classes {
+                    dst.put_u16(classes.0);
+                }
+                if let Some(classes) = transmit_classes {
+                    dst.put_u16(classes.0);
+                }
+            },
+        }
+        Ok(())
+    }
+    /// Decode `RCTE` from `Buf`
+    pub fn decode<T: Buf>(src: &mut T) -> Result<Self, TelnetError> {
+        use consts::option::rcte::*;
+        if !src.has_remaining() {
+            return Err(TelnetError::DecodingError(
+                "RCTE subnegotiation is empty".to_string(),
+            ));
+        }
+        let command = src.get_u8();
+        let expected = match command {
+            REPEAT => 0,
+            command => {
+                (command & BREAK_CLASSES).count_ones() * 2
+                    + (command & TRANSMIT_CLASSES).count_ones() * 2
+            },
+        };
+        if src.remaining() != expected as usize {
+            return Err(TelnetError::DecodingError(format!(
+                "RCTE command {:#X} expects {} class bytes, got {}",
+                command,
+                expected,
+                src.remaining()
+            )));
+        }
+        if command == REPEAT {
+            return Ok(RCTE::Repeat);
+        }
+        let break_classes = if command & BREAK_CLASSES != 0 {
+            Some(RCTEClasses(src.get_u16()))
+        } else {
+            None
+        };
+        let transmit_classes = if command & TRANSMIT_CLASSES != 0 {
+            Some(RCTEClasses(src.get_u16()))
+        } else {
+            None
+        };
+        Ok(RCTE::Command {
+            skip_break: command & SKIP_BREAK != 0,
+            skip_text: command & SKIP_TEXT != 0,
+            break_classes,
+            transmit_classes,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RCTEClasses, RCTE};
+    use crate::codec::consts::option::rcte::class;
+    use bytes::BytesMut;
+
+    #[test]
+    fn test_round_trip() {
+        let command = RCTE::Command {
+            skip_break: true,
+            skip_text: false,
+            break_classes: Some(RCTEClasses(class::FORMAT_EFFECTORS)),
+            transmit_classes: Some(RCTEClasses(class::CONTROL)),
+        };
+        for (argument, expected) in &[
+            (RCTE::Repeat, &[0u8][..]),
+            (command, &[13u8, 0, 8, 0, 16][..]),
+        ] {
+            let mut encoded = BytesMut::with_capacity(argument.len());
+            argument.encode(&mut encoded).unwrap();
+            assert_eq!(*expected, &encoded[..]);
+            assert_eq!(*argument, RCTE::decode(&mut &encoded[..]).unwrap());
+        }
+        assert!(RCTE::decode(&mut &[4u8, 0][..]).is_err());
+    }
+
+    #[test]
+    fn test_classes() {
+        let classes = RCTEClasses(class::FORMAT_EFFECTORS | class::GROUPING);
+        assert!(classes.matches(b'\r'));
+        assert!(classes.matches(b'('));
+        assert!(!classes.matches(b'a'));
+        assert!(!classes.matches(0x1B));
+    }
+}
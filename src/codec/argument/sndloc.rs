@@ -0,0 +1,60 @@
+//
+// Copyright 2019 Hans W. Uhlig. All Rights Reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+use super::TelnetError;
+use bytes::{Buf, BufMut};
+
+///
+/// Send Location Subnegotiation [RFC779](http://www.iana.org/go/rfc779)
+///
+/// Free form text describing where the user's terminal is.
+///
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SendLocation(pub String);
+
+impl SendLocation {
+    /// Get Encoded Length of `SendLocation`
+    #[allow(clippy::len_without_is_empty)]
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+    /// Encode `SendLocation` to `BufMut`
+    pub fn encode<T: BufMut>(&self, dst: &mut T) -> Result<(), TelnetError> {
+        dst.put(self.0.as_bytes());
+        Ok(())
+    }
+    /// Decode `SendLocation` from `Buf`
+    pub fn decode<T: Buf>(src: &mut T) -> Result<Self, TelnetError> {
+        String::from_utf8(src.to_bytes().to_vec())
+            .map(SendLocation)
+            .map_err(|error| TelnetError::DecodingError(error.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SendLocation;
+    use bytes::BytesMut;
+
+    #[test]
+    fn test_round_trip() {
+        let argument = SendLocation("Building 3, Room 101".to_string());
+        let mut encoded = BytesMut::with_capacity(argument.len());
+        argument.encode(&mut encoded).unwrap();
+        assert_eq!(&b"Building 3, Room 101"[..], &encoded[..]);
+        assert_eq!(argument, SendLocation::decode(&mut &encoded[..]).unwrap());
+        assert!(SendLocation::decode(&mut &[0xFFu8][..]).is_err());
+    }
+}
@@ -20,7 +20,7 @@ use bytes::{Buf, BufMut};
 use std::collections::HashMap;
 
 ///
-///
+/// Option status report (RFC 859).
 ///
 #[derive(Clone, Debug, PartialEq)]
 pub struct OptionStatus(HashMap<TelnetOption, (bool, bool)>);
@@ -50,7 +50,7 @@ impl OptionStatus {
         Ok(())
     }
     /// Decode `TelnetOptionStatus` from `Buf`
-    pub fn decode<T: Buf>(src: &mut T) -> Result<Self, TelnetError> {
+    pub fn decode<T: Buf>(_src: &mut T) -> Result<Self, TelnetError> {
         // TODO: Implement this
         Ok(OptionStatus::default())
     }
@@ -0,0 +1,487 @@
+//
+// Copyright 2019 Hans W. Uhlig. All Rights Reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+use super::{consts, TelnetError};
+use bytes::{Buf, BufMut};
+
+///
+/// SUPDUP Output Subnegotiation [RFC749](http://www.iana.org/go/rfc749)
+///
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum SUPDUPOutput {
+    /// User side describes its display.
+    Terminal(SUPDUPParameters),
+    /// Server side display operations, and the cursor position once they
+    /// have been applied.
+    Output {
+        /// Display operations, in order
+        operations: Vec<SUPDUPDisplay>,
+        /// Horizontal cursor position after the operations
+        hpos: u8,
+        /// Vertical cursor position after the operations
+        vpos: u8,
+    },
+}
+
+impl SUPDUPOutput {
+    /// Get Encoded Length of `SUPDUPOutput`
+    #[allow(clippy::len_without_is_empty)]
+    pub fn len(&self) -> usize {
+        1 + match self {
+            SUPDUPOutput::Terminal(parameters) => parameters.len(),
+            SUPDUPOutput::Output { operations, .. } => {
+                3 + operations.iter().map(|op| op.len()).sum::<usize>()
+            },
+        }
+    }
+    /// Encode `SUPDUPOutput` to `BufMut`
+    pub fn encode<T: BufMut>(&self, dst: &mut T) -> Result<(), TelnetError> {
+        use consts::option::supdup_output::*;
+        match self {
+            SUPDUPOutput::Terminal(parameters) => {
+                dst.put_u8(TERMINAL);
+                parameters.encode(dst)
+            },
+            SUPDUPOutput::Output {
+                operations,
+                hpos,
+                vpos,
+            } => {
+                let count: usize = operations.iter().map(|op| op.len()).sum();
+                if count > usize::from(u8::MAX) {
+                    return Err(TelnetError::EncodingError(format!(
+                        "SUPDUP output of {} bytes does not fit one \
+                         subnegotiation",
+                        count
+                    )));
+                }
+                dst.put_u8(OUTPUT);
+                dst.put_u8(count as u8);
+                for operation in operations {
+                    operation.encode(dst);
+                }
+                dst.put_u8(*hpos);
+                dst.put_u8(*vpos);
+                Ok(())
+            },
+        }
+    }
+    /// Decode `SUPDUPOutput` from `Buf`
+    pub fn decode<T: Buf>(src: &mut T) -> Result<Self, TelnetError> {
+        use consts::option::supdup_output::*;
+        if !src.has_remaining() {
+            return Err(TelnetError::DecodingError(
+                "SUPDUP Output subnegotiation is empty".to_string(),
+            ));
+        }
+        match src.get_u8() {
+            TERMINAL => {
+                Ok(SUPDUPOutput::Terminal(SUPDUPParameters::decode(src)?))
+            },
+            OUTPUT => {
+                let rest = src.to_bytes();
+                let count = match rest.first() {
+                    Some(&count) => usize::from(count),
+                    None => 0,
+                };
+                if rest.len() != count + 3 {
+                    return Err(TelnetError::DecodingError(format!(
+                        "SUPDUP output count {} does not match {} bytes",
+                        count,
+                        rest.len()
+                    )));
+                }
+                let operations = SUPDUPDisplay::decode_all(&rest[1..=count])?;
+                Ok(SUPDUPOutput::Output {
+                    operations,
+                    hpos: rest[count + 1],
+                    vpos: rest[count + 2],
+                })
+            },
+            command => Err(TelnetError::DecodingError(format!(
+                "Unknown SUPDUP Output command {:#X}",
+                command
+            ))),
+        }
+    }
+}
+
+///
+/// SUPDUP initial terminal parameters [RFC734](http://www.iana.org/go/rfc734)
+///
+/// Sent as a count word followed by 36 bit words, each split into six
+/// bytes of six bits, most significant first.
+///
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SUPDUPParameters {
+    /// TCTYP, always `consts::option::supdup::TCTYP_SOFTWARE`
+    pub terminal_type: u64,
+    /// TTYOPT terminal capability bits
+    pub options: u64,
+    /// TCMXV screen height in lines
+    pub height: u64,
+    /// TCMXH screen width in columns, minus one
+    pub width: u64,
+    /// TTYROL lines to scroll when output reaches the bottom
+    pub scroll: u64,
+    /// TTYSMT terminal smarts bits
+    pub smarts: u64,
+}
+
+impl SUPDUPParameters {
+    const WORDS: usize = 6;
+    const WORD_MASK: u64 = (1 << 36) - 1;
+
+    /// Create `SUPDUPParameters` for a display of `width` by `height`.
+    pub fn new(width: u64, height: u64) -> SUPDUPParameters {
+        SUPDUPParameters {
+            terminal_type: consts::option::supdup::TCTYP_SOFTWARE,
+            options: 0,
+            height,
+            width: width.saturating_sub(1),
+            scroll: 1,
+            smarts: 0,
+        }
+    }
+    /// Get Encoded Length of `SUPDUPParameters`
+    #[allow(clippy::len_without_is_empty)]
+    pub fn len(&self) -> usize {
+        6 * (1 + Self::WORDS)
+    }
+    /// Encode `SUPDUPParameters` to `BufMut`
+    pub fn encode<T: BufMut>(&self, dst: &mut T) -> Result<(), TelnetError> {
+        // Count word is -count,,0 in 36 bit two's complement
+        let count = ((1u64 << 18) - Self::WORDS as u64) << 18;
+        let words = [
+            count,
+            self.terminal_type,
+            self.options,
+            self.height,
+            self.width,
+            self.scroll,
+            self.smarts,
+        ];
+        for word in words.iter() {
+            let word = word & Self::WORD_MASK;
+            for shift in (0..6).rev() {
+                dst.put_u8(((word >> (shift * 6)) & 0o77) as u8);
+            }
+        }
+        Ok(())
+    }
+    /// Decode `SUPDUPParameters` from `Buf`. Missing trailing words are 0,
+    /// extra words are ignored.
+    pub fn decode<T: Buf>(src: &mut T) -> Result<Self, TelnetError> {
+        let mut words = Vec::with_capacity(src.remaining() / 6);
+        while src.remaining() >= 6 {
+            let mut word = 0u64;
+            for _ in 0..6 {
+                word = (word << 6) | u64::from(src.get_u8() & 0o77);
+            }
+            words.push(word);
+        }
+        if src.has_remaining() || words.is_empty() {
+            return Err(TelnetError::DecodingError(
+                "SUPDUP parameters are not a whole number of words".to_string(),
+            ));
+        }
+        let count = ((1u64 << 18) - (words[0] >> 18)) as usize;
+        if count != words.len() - 1 {
+            return Err(TelnetError::DecodingError(format!(
+                "SUPDUP parameter count {} does not match {} words",
+                count,
+                words.len() - 1
+            )));
+        }
+        let word = |index: usize| words.get(index).copied().unwrap_or(0);
+        Ok(SUPDUPParameters {
+            terminal_type: word(1),
+            options: word(2),
+            height: word(3),
+            width: word(4),
+            scroll: word(5),
+            smarts: word(6),
+        })
+    }
+}
+
+///
+/// Single SUPDUP display operation.
+///
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SUPDUPDisplay {
+    /// Print a character at the cursor and advance.
+    Char(u8),
+    /// Move the cursor from the old position to the new one.
+    Move {
+        /// Old vertical position
+        from_vpos: u8,
+        /// Old horizontal position
+        from_hpos: u8,
+        /// New vertical position
+        vpos: u8,
+        /// New horizontal position
+        hpos: u8,
+    },
+    /// Move the cursor to a position.
+    MoveTo {
+        /// New vertical position
+        vpos: u8,
+        /// New horizontal position
+        hpos: u8,
+    },
+    /// Clear to end of screen.
+    ClearEndOfScreen,
+    /// Clear to end of line.
+    ClearEndOfLine,
+    /// Clear the character under the cursor.
+    ClearCharacter,
+    /// Carriage return, line feed and clear line.
+    NewLine,
+    /// No operation.
+    NoOperation,
+    /// Output was reset, the cursor position is unknown.
+    OutputReset,
+    /// Print the quoted byte as a character.
+    Quote(u8),
+    /// Move the cursor forward one column.
+    Forward,
+    /// Clear the screen and home the cursor.
+    ClearScreen,
+    /// Ring the bell.
+    Bell,
+    /// Insert blank lines at the cursor.
+    InsertLines(u8),
+    /// Delete lines at the cursor.
+    DeleteLines(u8),
+    /// Insert blank characters at the cursor.
+    InsertCharacters(u8),
+    /// Delete characters at the cursor.
+    DeleteCharacters(u8),
+    /// Black on white.
+    BlackOnWhite,
+    /// Reset terminal modes.
+    Reset,
+    /// Enter graphics mode. Graphics codes are passed as `Char`.
+    Graphics,
+    /// Scroll a region of lines up.
+    ScrollUp {
+        /// Lines in the region, starting at the cursor
+        lines: u8,
+        /// Lines to scroll
+        count: u8,
+    },
+    /// Scroll a region of lines down.
+    ScrollDown {
+        /// Lines in the region, starting at the cursor
+        lines: u8,
+        /// Lines to scroll
+        count: u8,
+    },
+    /// Unassigned display code
+    Unknown(u8),
+}
+
+impl SUPDUPDisplay {
+    /// Get Encoded Length of `SUPDUPDisplay`
+    #[allow(clippy::len_without_is_empty)]
+    pub fn len(&self) -> usize {
+        1 + match self {
+            SUPDUPDisplay::Move { .. } => 4,
+            SUPDUPDisplay::MoveTo { .. } => 2,
+            SUPDUPDisplay::Quote(_)
+            | SUPDUPDisplay::InsertLines(_)
+            | SUPDUPDisplay::DeleteLines(_)
+            | SUPDUPDisplay::InsertCharacters(_)
+            | SUPDUPDisplay::DeleteCharacters(_) => 1,
+            SUPDUPDisplay::ScrollUp { .. }
+            | SUPDUPDisplay::ScrollDown { .. } => 2,
+            _ => 0,
+        }
+    }
+    /// Encode `SUPDUPDisplay` to `BufMut`
+    pub fn encode<T: BufMut>(&self, dst: &mut T) {
+        use consts::option::supdup::*;
+        match *self {
+            SUPDUPDisplay::Char(byte) => dst.put_u8(byte),
+            SUPDUPDisplay::Move {
+                from_vpos,
+                from_hpos,
+                vpos,
+                hpos,
+            } => dst.put_slice(&[TDMOV, from_vpos, from_hpos, vpos, hpos]),
+            SUPDUPDisplay::MoveTo { vpos, hpos } => {
+                dst.put_slice(&[TDMV0, vpos, hpos])
+            },
+            SUPDUPDisplay::ClearEndOfScreen => dst.put_u8(TDEOF),
+            SUPDUPDisplay::ClearEndOfLine => dst.put_u8(TDEOL),
+            SUPDUPDisplay::ClearCharacter => dst.put_u8(TDDLF),
+            SUPDUPDisplay::NewLine => dst.put_u8(TDCRL),
+            SUPDUPDisplay::NoOperation => dst.put_u8(TDNOP),
+            SUPDUPDisplay::OutputReset => dst.put_u8(TDORS),
+            SUPDUPDisplay::Quote(byte) => dst.put_slice(&[TDQOT, byte]),
+            SUPDUPDisplay::Forward => dst.put_u8(TDFS),
+            SUPDUPDisplay::ClearScreen => dst.put_u8(TDCLR),
+            SUPDUPDisplay::Bell => dst.put_u8(TDBEL),
+            SUPDUPDisplay::InsertLines(count) => dst.put_slice(&[TDILP, count]),
+            SUPDUPDisplay::DeleteLines(count) => dst.put_slice(&[TDDLP, count]),
+            SUPDUPDisplay::InsertCharacters(count) => {
+                dst.put_slice(&[TDICP, count])
+            },
+            SUPDUPDisplay::DeleteCharacters(count) => {
+                dst.put_slice(&[TDDCP, count])
+            },
+            SUPDUPDisplay::BlackOnWhite => dst.put_u8(TDBOW),
+            SUPDUPDisplay::Reset => dst.put_u8(TDRST),
+            SUPDUPDisplay::Graphics => dst.put_u8(TDGRF),
+            SUPDUPDisplay::ScrollUp { lines, count } => {
+                dst.put_slice(&[TDRSU, lines, count])
+            },
+            SUPDUPDisplay::ScrollDown { lines, count } => {
+                dst.put_slice(&[TDRSD, lines, count])
+            },
+            SUPDUPDisplay::Unknown(byte) => dst.put_u8(byte),
+        }
+    }
+    /// Decode every display operation in `src`.
+    pub fn decode_all(src: &[u8]) -> Result<Vec<Self>, TelnetError> {
+        let mut decoder = SUPDUPDisplayDecoder::new();
+        let operations: Vec<_> =
+            src.iter().filter_map(|&byte| decoder.push(byte)).collect();
+        if decoder.pending() {
+            return Err(TelnetError::DecodingError(
+                "Truncated SUPDUP display operation".to_string(),
+            ));
+        }
+        Ok(operations)
+    }
+}
+
+///
+/// Incremental decoder for SUPDUP display operations in a data stream.
+///
+#[derive(Clone, Debug, Default)]
+pub struct SUPDUPDisplayDecoder {
+    buffer: Vec<u8>,
+}
+
+impl SUPDUPDisplayDecoder {
+    /// Create a new `SUPDUPDisplayDecoder`
+    pub fn new() -> SUPDUPDisplayDecoder {
+        SUPDUPDisplayDecoder::default()
+    }
+    /// True while an operation is waiting for its arguments.
+    pub fn pending(&self) -> bool {
+        !self.buffer.is_empty()
+    }
+    /// Feed one byte, returning an operation once it is complete.
+    pub fn push(&mut self, byte: u8) -> Option<SUPDUPDisplay> {
+        use consts::option::supdup::*;
+        self.buffer.push(byte);
+        let arguments = match self.buffer[0] {
+            TDMOV => 4,
+            TDMV0 | TDRSU | TDRSD => 2,
+            TDQOT | TDILP | TDDLP | TDICP | TDDCP => 1,
+            _ => 0,
+        };
+        if self.buffer.len() <= arguments {
+            return None;
+        }
+        let operation = match self.buffer[..] {
+            [TDMOV, from_vpos, from_hpos, vpos, hpos] => SUPDUPDisplay::Move {
+                from_vpos,
+                from_hpos,
+                vpos,
+                hpos,
+            },
+            [TDMV0, vpos, hpos] => SUPDUPDisplay::MoveTo { vpos, hpos },
+            [TDRSU, lines, count] => SUPDUPDisplay::ScrollUp { lines, count },
+            [TDRSD, lines, count] => SUPDUPDisplay::ScrollDown { lines, count },
+            [TDQOT, byte] => SUPDUPDisplay::Quote(byte),
+            [TDILP, count] => SUPDUPDisplay::InsertLines(count),
+            [TDDLP, count] => SUPDUPDisplay::DeleteLines(count),
+            [TDICP, count] => SUPDUPDisplay::InsertCharacters(count),
+            [TDDCP, count] => SUPDUPDisplay::DeleteCharacters(count),
+            [TDEOF] => SUPDUPDisplay::ClearEndOfScreen,
+            [TDEOL] => SUPDUPDisplay::ClearEndOfLine,
+            [TDDLF] => SUPDUPDisplay::ClearCharacter,
+            [TDCRL] => SUPDUPDisplay::NewLine,
+            [TDNOP] => SUPDUPDisplay::NoOperation,
+            [TDORS] => SUPDUPDisplay::OutputReset,
+            [TDFS] => SUPDUPDisplay::Forward,
+            [TDCLR] => SUPDUPDisplay::ClearScreen,
+            [TDBEL] => SUPDUPDisplay::Bell,
+            [TDBOW] => SUPDUPDisplay::BlackOnWhite,
+            [TDRST] => SUPDUPDisplay::Reset,
+            [TDGRF] => SUPDUPDisplay::Graphics,
+            [byte] if byte < 0o200 => SUPDUPDisplay::Char(byte),
+            [byte, ..] => SUPDUPDisplay::Unknown(byte),
+            [] => unreachable!(),
+        };
+        self.buffer.clear();
+        Some(operation)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{SUPDUPDisplay, SUPDUPOutput, SUPDUPParameters};
+    use bytes::BytesMut;
+
+    #[test]
+    fn test_output_round_trip() {
+        let argument = SUPDUPOutput::Output {
+            operations: vec![
+                SUPDUPDisplay::MoveTo { vpos: 3, hpos: 10 },
+                SUPDUPDisplay::Char(b'h'),
+                SUPDUPDisplay::Char(b'i'),
+                SUPDUPDisplay::ClearEndOfLine,
+                SUPDUPDisplay::Quote(0o210),
+            ],
+            hpos: 13,
+            vpos: 3,
+        };
+        let mut encoded = BytesMut::with_capacity(argument.len());
+        argument.encode(&mut encoded).unwrap();
+        assert_eq!(
+            &[2u8, 8, 0o217, 3, 10, b'h', b'i', 0o203, 0o215, 0o210, 13, 3][..],
+            &encoded[..]
+        );
+        assert_eq!(argument.len(), encoded.len());
+        assert_eq!(argument, SUPDUPOutput::decode(&mut &encoded[..]).unwrap());
+    }
+
+    #[test]
+    fn test_parameters_round_trip() {
+        let argument = SUPDUPOutput::Terminal(SUPDUPParameters::new(80, 24));
+        let mut encoded = BytesMut::with_capacity(argument.len());
+        argument.encode(&mut encoded).unwrap();
+        // -6,,0 followed by TCTYP 7
+        assert_eq!(
+            &[1u8, 0o77, 0o77, 0o72, 0, 0, 0, 0, 0, 0, 0, 0, 0o07][..],
+            &encoded[..13]
+        );
+        assert_eq!(argument.len(), encoded.len());
+        assert_eq!(argument, SUPDUPOutput::decode(&mut &encoded[..]).unwrap());
+    }
+
+    #[test]
+    fn test_decode_invalid() {
+        assert!(SUPDUPOutput::decode(&mut &[2u8, 2, 0o217, 1, 0][..]).is_err());
+        assert!(SUPDUPOutput::decode(&mut &[2u8, 1, 0o217, 0, 0][..]).is_err());
+        assert!(SUPDUPOutput::decode(&mut &[1u8, 0, 0, 0][..]).is_err());
+        assert!(SUPDUPOutput::decode(&mut &[3u8][..]).is_err());
+    }
+}
@@ -0,0 +1,381 @@
+//
+// Copyright 2019 Hans W. Uhlig. All Rights Reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+use super::{consts, TelnetError};
+use bytes::{Buf, BufMut};
+
+///
+/// TN3270E Subnegotiation [RFC2355](http://www.iana.org/go/rfc2355)
+///
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum TN3270E {
+    /// Server asks the client for its device type.
+    SendDeviceType,
+    /// Client requests a device type, optionally for a specific resource.
+    DeviceTypeRequest(String, Option<TN3270EResource>),
+    /// Server accepts the device type and names the connected device.
+    DeviceTypeIs(String, String),
+    /// Server rejects the requested device type.
+    DeviceTypeReject(TN3270EReason),
+    /// Propose a list of functions.
+    FunctionsRequest(Vec<TN3270EFunction>),
+    /// Agree to a list of functions.
+    FunctionsIs(Vec<TN3270EFunction>),
+}
+
+impl TN3270E {
+    /// Get Encoded Length of `TN3270E`
+    #[allow(clippy::len_without_is_empty)]
+    pub fn len(&self) -> usize {
+        match self {
+            TN3270E::SendDeviceType => 2,
+            TN3270E::DeviceTypeRequest(device_type, resource) => {
+                2 + device_type.len()
+                    + resource.as_ref().map_or(0, |resource| resource.len())
+            },
+            TN3270E::DeviceTypeIs(device_type, device_name) => {
+                3 + device_type.len() + device_name.len()
+            },
+            TN3270E::DeviceTypeReject(_) => 4,
+            TN3270E::FunctionsRequest(functions) => 2 + functions.len(),
+            TN3270E::FunctionsIs(functions) => 2 + functions.len(),
+        }
+    }
+    /// Encode `TN3270E` to `BufMut`
+    pub fn encode<T: BufMut>(&self, dst: &mut T) -> Result<(), TelnetError> {
+        use consts::option::tn3270e::*;
+        match self {
+            TN3270E::SendDeviceType => {
+                dst.put_u8(SEND);
+                dst.put_u8(DEVICE_TYPE);
+            },
+            TN3270E::DeviceTypeRequest(device_type, resource) => {
+                dst.put_u8(DEVICE_TYPE);
+                dst.put_u8(REQUEST);
+                dst.put(device_type.as_bytes());
+                if let Some(resource) = resource {
+                    resource.encode(dst);
+                }
+            },
+            TN3270E::DeviceTypeIs(device_type, device_name) => {
+                dst.put_u8(DEVICE_TYPE);
+                dst.put_u8(IS);
+                dst.put(device_type.as_bytes());
+                dst.put_u8(CONNECT);
+                dst.put(device_name.as_bytes());
+            },
+            TN3270E::DeviceTypeReject(reason) => {
+                dst.put_u8(DEVICE_TYPE);
+                dst.put_u8(REJECT);
+                dst.put_u8(REASON);
+                dst.put_u8(u8::from(*reason));
+            },
+            TN3270E::FunctionsRequest(functions) => {
+                dst.put_u8(FUNCTIONS);
+                dst.put_u8(REQUEST);
+                for function in functions {
+                    dst.put_u8(u8::from(*function));
+                }
+            },
+            TN3270E::FunctionsIs(functions) => {
+                dst.put_u8(FUNCTIONS);
+                dst.put_u8(IS);
+                for function in functions {
+                    dst.put_u8(u8::from(*function));
+                }
+            },
+        }
+        Ok(())
+    }
+    /// Decode `TN3270E` from `Buf`
+    pub fn decode<T: Buf>(src: &mut T) -> Result<Self, TelnetError> {
+        use consts::option::tn3270e::*;
+        if src.remaining() < 2 {
+            return Err(TelnetError::DecodingError(
+                "Truncated TN3270E subnegotiation".to_string(),
+            ));
+        }
+        let command = (src.get_u8(), src.get_u8());
+        let rest = src.to_bytes();
+        match command {
+            (SEND, DEVICE_TYPE) => Ok(TN3270E::SendDeviceType),
+            (DEVICE_TYPE, REQUEST) => {
+                let split = rest
+                    .iter()
+                    .position(|&byte| byte == CONNECT || byte == ASSOCIATE)
+                    .unwrap_or_else(|| rest.len());
+                let device_type = ascii(&rest[..split])?;
+                let resource = match rest.get(split) {
+                    Some(&CONNECT) => Some(TN3270EResource::Connect(ascii(
+                        &rest[split + 1..],
+                    )?)),
+                    Some(_) => Some(TN3270EResource::Associate(ascii(
+                        &rest[split + 1..],
+                    )?)),
+                    None => None,
+                };
+                Ok(TN3270E::DeviceTypeRequest(device_type, resource))
+            },
+            (DEVICE_TYPE, IS) => {
+                let split = match rest.iter().position(|&byte| byte == CONNECT)
+                {
+                    Some(split) => split,
+                    None => {
+                        return Err(TelnetError::DecodingError(
+                            "TN3270E DEVICE-TYPE IS without CONNECT"
+                                .to_string(),
+                        ))
+                    },
+                };
+                Ok(TN3270E::DeviceTypeIs(
+                    ascii(&rest[..split])?,
+                    ascii(&rest[split + 1..])?,
+                ))
+            },
+            (DEVICE_TYPE, REJECT) => match &rest[..] {
+                [REASON, reason] => {
+                    Ok(TN3270E::DeviceTypeReject(TN3270EReason::from(*reason)))
+                },
+                _ => Err(TelnetError::DecodingError(
+                    "TN3270E DEVICE-TYPE REJECT without REASON".to_string(),
+                )),
+            },
+            (FUNCTIONS, REQUEST) => Ok(TN3270E::FunctionsRequest(
+                rest.iter()
+                    .map(|&byte| TN3270EFunction::from(byte))
+                    .collect(),
+            )),
+            (FUNCTIONS, IS) => Ok(TN3270E::FunctionsIs(
+                rest.iter()
+                    .map(|&byte| TN3270EFunction::from(byte))
+                    .collect(),
+            )),
+            (first, second) => Err(TelnetError::DecodingError(format!(
+                "Unknown TN3270E command {:#X} {:#X}",
+                first, second
+            ))),
+        }
+    }
+}
+
+fn ascii(data: &[u8]) -> Result<String, TelnetError> {
+    if !data.is_ascii() {
+        return Err(TelnetError::DecodingError(
+            "TN3270E names must be ASCII".to_string(),
+        ));
+    }
+    Ok(String::from_utf8_lossy(data).into_owned())
+}
+
+///
+/// Resource requested together with a device type.
+///
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum TN3270EResource {
+    /// Connect to the named terminal or printer resource.
+    Connect(String),
+    /// Connect a printer to the device associated with the named terminal.
+    Associate(String),
+}
+
+impl TN3270EResource {
+    fn len(&self) -> usize {
+        match self {
+            TN3270EResource::Connect(name) => 1 + name.len(),
+            TN3270EResource::Associate(name) => 1 + name.len(),
+        }
+    }
+    fn encode<T: BufMut>(&self, dst: &mut T) {
+        use consts::option::tn3270e::*;
+        match self {
+            TN3270EResource::Connect(name) => {
+                dst.put_u8(CONNECT);
+                dst.put(name.as_bytes());
+            },
+            TN3270EResource::Associate(name) => {
+                dst.put_u8(ASSOCIATE);
+                dst.put(name.as_bytes());
+            },
+        }
+    }
+}
+
+///
+/// Reason a device type request was rejected.
+///
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum TN3270EReason {
+    /// Associated terminal is not connected.
+    ConnectionPartner,
+    /// Requested device is already in use.
+    DeviceInUse,
+    /// Associated device name is not valid.
+    InvalidAssociate,
+    /// Requested device name is not valid.
+    InvalidName,
+    /// Requested device type is not valid.
+    InvalidDeviceType,
+    /// Device type and device name do not match.
+    TypeNameError,
+    /// Any other error.
+    UnknownError,
+    /// Request is not supported.
+    UnsupportedRequest,
+    /// Unassigned reason code
+    Unknown(u8),
+}
+
+impl From<u8> for TN3270EReason {
+    fn from(byte: u8) -> Self {
+        use consts::option::tn3270e::reason::*;
+        match byte {
+            CONN_PARTNER => TN3270EReason::ConnectionPartner,
+            DEVICE_IN_USE => TN3270EReason::DeviceInUse,
+            INV_ASSOCIATE => TN3270EReason::InvalidAssociate,
+            INV_NAME => TN3270EReason::InvalidName,
+            INV_DEVICE_TYPE => TN3270EReason::InvalidDeviceType,
+            TYPE_NAME_ERROR => TN3270EReason::TypeNameError,
+            UNKNOWN_ERROR => TN3270EReason::UnknownError,
+            UNSUPPORTED_REQ => TN3270EReason::UnsupportedRequest,
+            byte => TN3270EReason::Unknown(byte),
+        }
+    }
+}
+
+impl From<TN3270EReason> for u8 {
+    fn from(reason: TN3270EReason) -> Self {
+        use consts::option::tn3270e::reason::*;
+        match reason {
+            TN3270EReason::ConnectionPartner => CONN_PARTNER,
+            TN3270EReason::DeviceInUse => DEVICE_IN_USE,
+            TN3270EReason::InvalidAssociate => INV_ASSOCIATE,
+            TN3270EReason::InvalidName => INV_NAME,
+            TN3270EReason::InvalidDeviceType => INV_DEVICE_TYPE,
+            TN3270EReason::TypeNameError => TYPE_NAME_ERROR,
+            TN3270EReason::UnknownError => UNKNOWN_ERROR,
+            TN3270EReason::UnsupportedRequest => UNSUPPORTED_REQ,
+            TN3270EReason::Unknown(byte) => byte,
+        }
+    }
+}
+
+///
+/// Optional TN3270E function.
+///
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum TN3270EFunction {
+    /// Host sends BIND and UNBIND images.
+    BindImage,
+    /// Host sends data stream control (SCS and 3270 data types).
+    DataStreamControl,
+    /// Positive and negative responses are exchanged.
+    Responses,
+    /// Printer sessions receive SCS control codes.
+    SCSControlCodes,
+    /// SYSREQ key handling.
+    SysReq,
+    /// Unassigned function
+    Unknown(u8),
+}
+
+impl From<u8> for TN3270EFunction {
+    fn from(byte: u8) -> Self {
+        use consts::option::tn3270e::function::*;
+        match byte {
+            BIND_IMAGE => TN3270EFunction::BindImage,
+            DATA_STREAM_CTL => TN3270EFunction::DataStreamControl,
+            RESPONSES => TN3270EFunction::Responses,
+            SCS_CTL_CODES => TN3270EFunction::SCSControlCodes,
+            SYSREQ => TN3270EFunction::SysReq,
+            byte => TN3270EFunction::Unknown(byte),
+        }
+    }
+}
+
+impl From<TN3270EFunction> for u8 {
+    fn from(function: TN3270EFunction) -> Self {
+        use consts::option::tn3270e::function::*;
+        match function {
+            TN3270EFunction::BindImage => BIND_IMAGE,
+            TN3270EFunction::DataStreamControl => DATA_STREAM_CTL,
+            TN3270EFunction::Responses => RESPONSES,
+            TN3270EFunction::SCSControlCodes => SCS_CTL_CODES,
+            TN3270EFunction::SysReq => SYSREQ,
+            TN3270EFunction::Unknown(byte) => byte,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{TN3270EFunction, TN3270EReason, TN3270EResource, TN3270E};
+    use bytes::BytesMut;
+
+    fn round_trip(argument: TN3270E, expected: &[u8]) {
+        let mut encoded = BytesMut::with_capacity(argument.len());
+        argument.encode(&mut encoded).unwrap();
+        assert_eq!(expected, &encoded[..]);
+        assert_eq!(argument.len(), encoded.len());
+        assert_eq!(argument, TN3270E::decode(&mut &encoded[..]).unwrap());
+    }
+
+    #[test]
+    fn test_device_type() {
+        round_trip(TN3270E::SendDeviceType, &[8, 2]);
+        round_trip(
+            TN3270E::DeviceTypeRequest(
+                "IBM-3278-2-E".to_string(),
+                Some(TN3270EResource::Connect("LU1".to_string())),
+            ),
+            b"\x02\x07IBM-3278-2-E\x01LU1",
+        );
+        round_trip(
+            TN3270E::DeviceTypeRequest("IBM-3287-1".to_string(), None),
+            b"\x02\x07IBM-3287-1",
+        );
+        round_trip(
+            TN3270E::DeviceTypeIs(
+                "IBM-3278-2-E".to_string(),
+                "LU1".to_string(),
+            ),
+            b"\x02\x04IBM-3278-2-E\x01LU1",
+        );
+        round_trip(
+            TN3270E::DeviceTypeReject(TN3270EReason::DeviceInUse),
+            &[2, 6, 5, 1],
+        );
+    }
+
+    #[test]
+    fn test_functions() {
+        round_trip(
+            TN3270E::FunctionsRequest(vec![
+                TN3270EFunction::BindImage,
+                TN3270EFunction::Responses,
+                TN3270EFunction::SysReq,
+            ]),
+            &[3, 7, 0, 2, 4],
+        );
+        round_trip(TN3270E::FunctionsIs(vec![]), &[3, 4]);
+    }
+
+    #[test]
+    fn test_decode_invalid() {
+        assert!(TN3270E::decode(&mut &[2u8][..]).is_err());
+        assert!(TN3270E::decode(&mut &b"\x02\x04IBM"[..]).is_err());
+        assert!(TN3270E::decode(&mut &[2u8, 6, 1][..]).is_err());
+        assert!(TN3270E::decode(&mut &[9u8, 9][..]).is_err());
+    }
+}
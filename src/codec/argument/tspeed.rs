@@ -0,0 +1,128 @@
+//
+// Copyright 2019 Hans W. Uhlig. All Rights Reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+use super::{consts, TelnetError};
+use bytes::{Buf, BufMut};
+
+///
+/// Terminal Speed Subnegotiation [RFC1079](http://www.iana.org/go/rfc1079)
+///
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum TerminalSpeed {
+    /// Transmit and receive speed of the client terminal in bits per second.
+    Is {
+        /// Transmit speed
+        transmit: u32,
+        /// Receive speed
+        receive: u32,
+    },
+    /// Ask the client for its terminal speed.
+    Send,
+}
+
+impl TerminalSpeed {
+    /// Get Encoded Length of `TerminalSpeed`
+    #[allow(clippy::len_without_is_empty)]
+    pub fn len(&self) -> usize {
+        1 + match self {
+            TerminalSpeed::Is { transmit, receive } => {
+                format!("{},{}", transmit, receive).len()
+            },
+            TerminalSpeed::Send => 0,
+        }
+    }
+    /// Encode `TerminalSpeed` to `BufMut`
+    pub fn encode<T: BufMut>(&self, dst: &mut T) -> Result<(), TelnetError> {
+        match self {
+            TerminalSpeed::Is { transmit, receive } => {
+                dst.put_u8(consts::option::tspeed::IS);
+                dst.put(format!("{},{}", transmit, receive).as_bytes());
+            },
+            TerminalSpeed::Send => dst.put_u8(consts::option::tspeed::SEND),
+        }
+        Ok(())
+    }
+    /// Decode `TerminalSpeed` from `Buf`
+    pub fn decode<T: Buf>(src: &mut T) -> Result<Self, TelnetError> {
+        if !src.has_remaining() {
+            return Err(TelnetError::DecodingError(
+                "Terminal Speed subnegotiation is empty".to_string(),
+            ));
+        }
+        match src.get_u8() {
+            consts::option::tspeed::IS => {
+                let speeds = src.to_bytes();
+                let speeds = std::str::from_utf8(&speeds).map_err(|error| {
+                    TelnetError::DecodingError(error.to_string())
+                })?;
+                let mut parts = speeds.splitn(2, ',').map(|part| {
+                    part.trim().parse::<u32>().map_err(|error| {
+                        TelnetError::DecodingError(format!(
+                            "Invalid Terminal Speed {:?}: {}",
+                            speeds, error
+                        ))
+                    })
+                });
+                match (parts.next(), parts.next()) {
+                    (Some(transmit), Some(receive)) => Ok(TerminalSpeed::Is {
+                        transmit: transmit?,
+                        receive: receive?,
+                    }),
+                    _ => Err(TelnetError::DecodingError(format!(
+                        "Invalid Terminal Speed {:?}",
+                        speeds
+                    ))),
+                }
+            },
+            consts::option::tspeed::SEND => Ok(TerminalSpeed::Send),
+            command => Err(TelnetError::DecodingError(format!(
+                "Unknown Terminal Speed command {:#X}",
+                command
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TerminalSpeed;
+    use bytes::BytesMut;
+
+    #[test]
+    fn test_is_round_trip() {
+        let argument = TerminalSpeed::Is {
+            transmit: 38400,
+            receive: 9600,
+        };
+        let mut encoded = BytesMut::with_capacity(argument.len());
+        argument.encode(&mut encoded).unwrap();
+        assert_eq!(&b"\x0038400,9600"[..], &encoded[..]);
+        assert_eq!(argument.len(), encoded.len());
+
+        let decoded = TerminalSpeed::decode(&mut &encoded[..]).unwrap();
+        assert_eq!(argument, decoded);
+    }
+
+    #[test]
+    fn test_decode_invalid() {
+        assert_eq!(
+            TerminalSpeed::Send,
+            TerminalSpeed::decode(&mut &[1u8][..]).unwrap()
+        );
+        assert!(TerminalSpeed::decode(&mut &b"\x0038400"[..]).is_err());
+        assert!(TerminalSpeed::decode(&mut &b"\x00fast,slow"[..]).is_err());
+        assert!(TerminalSpeed::decode(&mut &[][..]).is_err());
+    }
+}
@@ -0,0 +1,116 @@
+//
+// Copyright 2019 Hans W. Uhlig. All Rights Reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+use super::{consts, TelnetError};
+use bytes::{Buf, BufMut};
+use std::net::Ipv4Addr;
+
+///
+/// Terminal Location Number Subnegotiation [RFC946](http://www.iana.org/go/rfc946)
+///
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum TerminalLocation {
+    /// Format 0: 64 bit number made of the gateway's internet address and
+    /// the terminal number on that gateway.
+    Internet {
+        /// Internet address of the gateway the terminal is attached to.
+        gateway: Ipv4Addr,
+        /// Terminal number on the gateway.
+        terminal: u32,
+    },
+    /// Unassigned format and its raw location number.
+    Unknown(u8, Vec<u8>),
+}
+
+impl TerminalLocation {
+    /// Get Encoded Length of `TerminalLocation`
+    #[allow(clippy::len_without_is_empty)]
+    pub fn len(&self) -> usize {
+        1 + match self {
+            TerminalLocation::Internet { .. } => 8,
+            TerminalLocation::Unknown(_, data) => data.len(),
+        }
+    }
+    /// Encode `TerminalLocation` to `BufMut`
+    pub fn encode<T: BufMut>(&self, dst: &mut T) -> Result<(), TelnetError> {
+        match self {
+            TerminalLocation::Internet { gateway, terminal } => {
+                dst.put_u8(consts::option::ttyloc::FORMAT_64BIT);
+                dst.put_u32(u32::from(*gateway));
+                dst.put_u32(*terminal);
+            },
+            TerminalLocation::Unknown(format, data) => {
+                dst.put_u8(*format);
+                dst.put(data.as_slice());
+            },
+        }
+        Ok(())
+    }
+    /// Decode `TerminalLocation` from `Buf`
+    pub fn decode<T: Buf>(src: &mut T) -> Result<Self, TelnetError> {
+        if !src.has_remaining() {
+            return Err(TelnetError::DecodingError(
+                "TTYLOC subnegotiation is empty".to_string(),
+            ));
+        }
+        match src.get_u8() {
+            consts::option::ttyloc::FORMAT_64BIT => {
+                if src.remaining() != 8 {
+                    return Err(TelnetError::DecodingError(format!(
+                        "TTYLOC number must be 8 bytes, got {}",
+                        src.remaining()
+                    )));
+                }
+                let gateway = Ipv4Addr::from(src.get_u32());
+                let terminal = src.get_u32();
+                Ok(TerminalLocation::Internet { gateway, terminal })
+            },
+            format => {
+                Ok(TerminalLocation::Unknown(format, src.to_bytes().to_vec()))
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TerminalLocation;
+    use bytes::BytesMut;
+    use std::net::Ipv4Addr;
+
+    #[test]
+    fn test_round_trip() {
+        let argument = TerminalLocation::Internet {
+            gateway: Ipv4Addr::new(10, 0, 255, 1),
+            terminal: 7,
+        };
+        let mut encoded = BytesMut::with_capacity(argument.len());
+        argument.encode(&mut encoded).unwrap();
+        assert_eq!(&[0u8, 10, 0, 255, 1, 0, 0, 0, 7][..], &encoded[..]);
+        assert_eq!(argument.len(), encoded.len());
+
+        let decoded = TerminalLocation::decode(&mut &encoded[..]).unwrap();
+        assert_eq!(argument, decoded);
+    }
+
+    #[test]
+    fn test_decode_invalid() {
+        assert!(TerminalLocation::decode(&mut &[0u8, 1, 2][..]).is_err());
+        assert_eq!(
+            TerminalLocation::Unknown(9, vec![1, 2]),
+            TerminalLocation::decode(&mut &[9u8, 1, 2][..]).unwrap()
+        );
+    }
+}
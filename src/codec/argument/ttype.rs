@@ -0,0 +1,95 @@
+//
+// Copyright 2019 Hans W. Uhlig. All Rights Reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+
+use super::{consts, TelnetError};
+use bytes::{Buf, BufMut};
+
+///
+/// Terminal Type Subnegotiation [RFC1091](http://www.iana.org/go/rfc1091)
+///
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum TerminalType {
+    /// Terminal type of the client, such as `XTERM-256COLOR`.
+    Is(String),
+    /// Ask the client for its next terminal type.
+    Send,
+}
+
+impl TerminalType {
+    /// Get Encoded Length of `TerminalType`
+    #[allow(clippy::len_without_is_empty)]
+    pub fn len(&self) -> usize {
+        1 + match self {
+            TerminalType::Is(name) => name.len(),
+            TerminalType::Send => 0,
+        }
+    }
+    /// Encode `TerminalType` to `BufMut`
+    pub fn encode<T: BufMut>(&self, dst: &mut T) -> Result<(), TelnetError> {
+        match self {
+            TerminalType::Is(name) => {
+                dst.put_u8(consts::option::ttype::IS);
+                dst.put(name.as_bytes());
+            },
+            TerminalType::Send => dst.put_u8(consts::option::ttype::SEND),
+        }
+        Ok(())
+    }
+    /// Decode `TerminalType` from `Buf`
+    pub fn decode<T: Buf>(src: &mut T) -> Result<Self, TelnetError> {
+        if !src.has_remaining() {
+            return Err(TelnetError::DecodingError(
+                "Terminal Type subnegotiation is empty".to_string(),
+            ));
+        }
+        match src.get_u8() {
+            consts::option::ttype::IS => {
+                let name = String::from_utf8(src.to_bytes().to_vec()).map_err(
+                    |error| TelnetError::DecodingError(error.to_string()),
+                )?;
+                Ok(TerminalType::Is(name))
+            },
+            consts::option::ttype::SEND => Ok(TerminalType::Send),
+            command => Err(TelnetError::DecodingError(format!(
+                "Unknown Terminal Type command {:#X}",
+                command
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TerminalType;
+    use bytes::BytesMut;
+
+    #[test]
+    fn test_round_trip() {
+        let argument = TerminalType::Is("XTERM-256COLOR".to_string());
+        let mut encoded = BytesMut::with_capacity(argument.len());
+        argument.encode(&mut encoded).unwrap();
+        assert_eq!(&b"\x00XTERM-256COLOR"[..], &encoded[..]);
+        assert_eq!(argument.len(), encoded.len());
+
+        let decoded = TerminalType::decode(&mut &encoded[..]).unwrap();
+        assert_eq!(argument, decoded);
+        assert_eq!(
+            TerminalType::Send,
+            TerminalType::decode(&mut &[1u8][..]).unwrap()
+        );
+        assert!(TerminalType::decode(&mut &[2u8][..]).is_err());
+    }
+}
@@ -0,0 +1,67 @@
+//
+// Copyright 2019 Hans W. Uhlig. All Rights Reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+use super::TelnetError;
+use bytes::{Buf, BufMut};
+
+///
+/// TACACS User Identification Subnegotiation [RFC927](http://www.iana.org/go/rfc927)
+///
+/// 32 bit identifier the TACACS server assigned to the user at login.
+///
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct TerminalUserId(pub u32);
+
+impl TerminalUserId {
+    /// Get Encoded Length of `TerminalUserId`
+    #[allow(clippy::len_without_is_empty)]
+    pub fn len(&self) -> usize {
+        4
+    }
+    /// Encode `TerminalUserId` to `BufMut`
+    pub fn encode<T: BufMut>(&self, dst: &mut T) -> Result<(), TelnetError> {
+        dst.put_u32(self.0);
+        Ok(())
+    }
+    /// Decode `TerminalUserId` from `Buf`
+    pub fn decode<T: Buf>(src: &mut T) -> Result<Self, TelnetError> {
+        if src.remaining() != 4 {
+            return Err(TelnetError::DecodingError(format!(
+                "TUID must be 4 bytes, got {}",
+                src.remaining()
+            )));
+        }
+        Ok(TerminalUserId(src.get_u32()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TerminalUserId;
+    use bytes::BytesMut;
+
+    #[test]
+    fn test_round_trip() {
+        let argument = TerminalUserId(0x0102_0304);
+        let mut encoded = BytesMut::with_capacity(argument.len());
+        argument.encode(&mut encoded).unwrap();
+        assert_eq!(&[1u8, 2, 3, 4][..], &encoded[..]);
+        assert_eq!(
+            argument,
+            TerminalUserId::decode(&mut &encoded[..]).unwrap()
+        );
+        assert!(TerminalUserId::decode(&mut &[1u8, 2, 3][..]).is_err());
+    }
+}
@@ -0,0 +1,271 @@
+//
+// Copyright 2019 Hans W. Uhlig. All Rights Reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+use super::{consts, TelnetError};
+use bytes::{Buf, BufMut};
+
+///
+/// X.3-PAD Subnegotiation [RFC1053](http://www.iana.org/go/rfc1053)
+///
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum X3PAD {
+    /// Set PAD parameters.
+    Set(Vec<X3Setting>),
+    /// Parameter values in effect after a `Set`.
+    ResponseSet(Vec<X3Setting>),
+    /// Current PAD parameter values.
+    Is(Vec<X3Setting>),
+    /// Acknowledge reported parameter values.
+    ResponseIs(Vec<X3Setting>),
+    /// Ask for the listed parameters, or all of them if empty.
+    Send(Vec<X3Parameter>),
+}
+
+///
+/// X.3 parameter and its value
+///
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct X3Setting {
+    /// Parameter reference
+    pub parameter: X3Parameter,
+    /// Parameter value
+    pub value: u8,
+}
+
+impl X3Setting {
+    /// Create a new `X3Setting`.
+    pub fn new(parameter: X3Parameter, value: u8) -> X3Setting {
+        X3Setting { parameter, value }
+    }
+}
+
+///
+/// Standard X.3 PAD parameters
+///
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum X3Parameter {
+    /// Escape from data transfer state
+    PadRecall,
+    /// Local echo
+    Echo,
+    /// Data forwarding characters
+    Forwarding,
+    /// Idle timer delay, in twentieths of a second
+    IdleTimer,
+    /// Flow control of the terminal by the PAD
+    AncillaryDeviceControl,
+    /// PAD service signals
+    ServiceSignals,
+    /// Action on receiving a break signal
+    Break,
+    /// Discard output
+    DiscardOutput,
+    /// Padding after carriage return
+    CRPadding,
+    /// Line folding
+    LineFolding,
+    /// Terminal line speed, read only
+    BinarySpeed,
+    /// Flow control of the PAD by the terminal
+    FlowControl,
+    /// Line feed insertion after carriage return
+    LFInsertion,
+    /// Padding after line feed
+    LFPadding,
+    /// Local editing
+    Editing,
+    /// Character delete character
+    CharacterDelete,
+    /// Line delete character
+    LineDelete,
+    /// Line display character
+    LineDisplay,
+    /// Editing PAD service signals
+    EditingSignals,
+    /// Characters not echoed
+    EchoMask,
+    /// Parity treatment
+    Parity,
+    /// Page wait
+    PageWait,
+    /// Unknown or national parameter
+    Unknown(u8),
+}
+
+impl From<u8> for X3Parameter {
+    fn from(parameter: u8) -> Self {
+        use consts::option::x3pad::param::*;
+        match parameter {
+            PAD_RECALL => X3Parameter::PadRecall,
+            ECHO => X3Parameter::Echo,
+            FORWARDING => X3Parameter::Forwarding,
+            IDLE_TIMER => X3Parameter::IdleTimer,
+            ANCILLARY_DEVICE_CONTROL => X3Parameter::AncillaryDeviceControl,
+            SERVICE_SIGNALS => X3Parameter::ServiceSignals,
+            BREAK => X3Parameter::Break,
+            DISCARD_OUTPUT => X3Parameter::DiscardOutput,
+            CR_PADDING => X3Parameter::CRPadding,
+            LINE_FOLDING => X3Parameter::LineFolding,
+            BINARY_SPEED => X3Parameter::BinarySpeed,
+            FLOW_CONTROL => X3Parameter::FlowControl,
+            LF_INSERTION => X3Parameter::LFInsertion,
+            LF_PADDING => X3Parameter::LFPadding,
+            EDITING => X3Parameter::Editing,
+            CHARACTER_DELETE => X3Parameter::CharacterDelete,
+            LINE_DELETE => X3Parameter::LineDelete,
+            LINE_DISPLAY => X3Parameter::LineDisplay,
+            EDITING_SIGNALS => X3Parameter::EditingSignals,
+            ECHO_MASK => X3Parameter::EchoMask,
+            PARITY => X3Parameter::Parity,
+            PAGE_WAIT => X3Parameter::PageWait,
+            parameter => X3Parameter::Unknown(parameter),
+        }
+    }
+}
+
+impl From<X3Parameter> for u8 {
+    fn from(parameter: X3Parameter) -> Self {
+        use consts::option::x3pad::param::*;
+        match parameter {
+            X3Parameter::PadRecall => PAD_RECALL,
+            X3Parameter::Echo => ECHO,
+            X3Parameter::Forwarding => FORWARDING,
+            X3Parameter::IdleTimer => IDLE_TIMER,
+            X3Parameter::AncillaryDeviceControl => ANCILLARY_DEVICE_CONTROL,
+            X3Parameter::ServiceSignals => SERVICE_SIGNALS,
+            X3Parameter::Break => BREAK,
+            X3Parameter::DiscardOutput => DISCARD_OUTPUT,
+            X3Parameter::CRPadding => CR_PADDING,
+            X3Parameter::LineFolding => LINE_FOLDING,
+            X3Parameter::BinarySpeed => BINARY_SPEED,
+            X3Parameter::FlowControl => FLOW_CONTROL,
+            X3Parameter::LFInsertion => LF_INSERTION,
+            X3Parameter::LFPadding => LF_PADDING,
+            X3Parameter::Editing => EDITING,
+            X3Parameter::CharacterDelete => CHARACTER_DELETE,
+            X3Parameter::LineDelete => LINE_DELETE,
+            X3Parameter::LineDisplay => LINE_DISPLAY,
+            X3Parameter::EditingSignals => EDITING_SIGNALS,
+            X3Parameter::EchoMask => ECHO_MASK,
+            X3Parameter::Parity => PARITY,
+            X3Parameter::PageWait => PAGE_WAIT,
+            X3Parameter::Unknown(parameter) => parameter,
+        }
+    }
+}
+
+impl X3PAD {
+    /// Get Encoded Length of `X3PAD`
+    #[allow(clippy::len_without_is_empty)]
+    pub fn len(&self) -> usize {
+        match self {
+            X3PAD::Set(settings)
+            | X3PAD::ResponseSet(settings)
+            | X3PAD::Is(settings)
+            | X3PAD::ResponseIs(settings) => 1 + settings.len() * 2,
+            X3PAD::Send(parameters) => 1 + parameters.len(),
+        }
+    }
+    /// Encode `X3PAD` to `BufMut`
+    pub fn encode<T: BufMut>(&self, dst: &mut T) -> Result<(), TelnetError> {
+        use consts::option::x3pad::*;
+        let (command, settings) = match self {
+            X3PAD::Set(settings) => (SET, settings),
+            X3PAD::ResponseSet(settings) => (RESPONSE_SET, settings),
+            X3PAD::Is(settings) => (IS, settings),
+            X3PAD::ResponseIs(settings) => (RESPONSE_IS, settings),
+            X3PAD::Send(parameters) => {
+                dst.put_u8(SEND);
+                for parameter in parameters {
+                    dst.put_u8(u8::from(*parameter));
+                }
+                return Ok(());
+            },
+        };
+        dst.put_u8(command);
+        for setting in settings {
+            dst.put_u8(u8::from(setting.parameter));
+            dst.put_u8(setting.value);
+        }
+        Ok(())
+    }
+    /// Decode `X3PAD` from `Buf`
+    pub fn decode<T: Buf>(src: &mut T) -> Result<Self, TelnetError> {
+        use consts::option::x3pad::*;
+        if !src.has_remaining() {
+            return Err(TelnetError::DecodingError(
+                "X.3-PAD subnegotiation is empty".to_string(),
+            ));
+        }
+        let command = src.get_u8();
+        if command == SEND {
+            let parameters = src.to_bytes();
+            let parameters = parameters.iter().map(|p| X3Parameter::from(*p));
+            return Ok(X3PAD::Send(parameters.collect()));
+        }
+        if src.remaining() & 1 != 0 {
+            return Err(TelnetError::DecodingError(format!(
+                "X.3-PAD command {:#X} has an unpaired parameter",
+                command
+            )));
+        }
+        let mut settings = Vec::with_capacity(src.remaining() / 2);
+        while src.has_remaining() {
+            let parameter = X3Parameter::from(src.get_u8());
+            settings.push(X3Setting::new(parameter, src.get_u8()));
+        }
+        match command {
+            SET => Ok(X3PAD::Set(settings)),
+            RESPONSE_SET => Ok(X3PAD::ResponseSet(settings)),
+            IS => Ok(X3PAD::Is(settings)),
+            RESPONSE_IS => Ok(X3PAD::ResponseIs(settings)),
+            command => Err(TelnetError::DecodingError(format!(
+                "Unknown X.3-PAD command {:#X}",
+                command
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{X3Parameter, X3Setting, X3PAD};
+    use bytes::BytesMut;
+
+    #[test]
+    fn test_round_trip() {
+        let set = X3PAD::Set(vec![
+            X3Setting::new(X3Parameter::Echo, 0),
+            X3Setting::new(X3Parameter::IdleTimer, 20),
+        ]);
+        let send = X3PAD::Send(vec![X3Parameter::Forwarding]);
+        for (argument, expected) in &[
+            (set, &[0u8, 2, 0, 4, 20][..]),
+            (send, &[4u8, 3][..]),
+            (X3PAD::Send(Vec::new()), &[4u8][..]),
+        ] {
+            let mut encoded = BytesMut::with_capacity(argument.len());
+            argument.encode(&mut encoded).unwrap();
+            assert_eq!(*expected, &encoded[..]);
+            assert_eq!(*argument, X3PAD::decode(&mut &encoded[..]).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_decode_invalid() {
+        assert!(X3PAD::decode(&mut &[0u8, 2][..]).is_err());
+        assert!(X3PAD::decode(&mut &[9u8, 2, 1][..]).is_err());
+    }
+}
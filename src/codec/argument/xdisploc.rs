@@ -0,0 +1,96 @@
+//
+// Copyright 2019 Hans W. Uhlig. All Rights Reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+use super::{consts, TelnetError};
+use bytes::{Buf, BufMut};
+
+///
+/// X Display Location Subnegotiation [RFC1096](http://www.iana.org/go/rfc1096)
+///
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum XDisplayLocation {
+    /// X display of the client in `host:display[.screen]` form.
+    Is(String),
+    /// Ask the client for its X display location.
+    Send,
+}
+
+impl XDisplayLocation {
+    /// Get Encoded Length of `XDisplayLocation`
+    #[allow(clippy::len_without_is_empty)]
+    pub fn len(&self) -> usize {
+        1 + match self {
+            XDisplayLocation::Is(display) => display.len(),
+            XDisplayLocation::Send => 0,
+        }
+    }
+    /// Encode `XDisplayLocation` to `BufMut`
+    pub fn encode<T: BufMut>(&self, dst: &mut T) -> Result<(), TelnetError> {
+        match self {
+            XDisplayLocation::Is(display) => {
+                dst.put_u8(consts::option::xdisploc::IS);
+                dst.put(display.as_bytes());
+            },
+            XDisplayLocation::Send => {
+                dst.put_u8(consts::option::xdisploc::SEND)
+            },
+        }
+        Ok(())
+    }
+    /// Decode `XDisplayLocation` from `Buf`
+    pub fn decode<T: Buf>(src: &mut T) -> Result<Self, TelnetError> {
+        if !src.has_remaining() {
+            return Err(TelnetError::DecodingError(
+                "X Display Location subnegotiation is empty".to_string(),
+            ));
+        }
+        match src.get_u8() {
+            consts::option::xdisploc::IS => {
+                let display = String::from_utf8(src.to_bytes().to_vec())
+                    .map_err(|error| {
+                        TelnetError::DecodingError(error.to_string())
+                    })?;
+                Ok(XDisplayLocation::Is(display))
+            },
+            consts::option::xdisploc::SEND => Ok(XDisplayLocation::Send),
+            command => Err(TelnetError::DecodingError(format!(
+                "Unknown X Display Location command {:#X}",
+                command
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::XDisplayLocation;
+    use bytes::BytesMut;
+
+    #[test]
+    fn test_round_trip() {
+        let argument = XDisplayLocation::Is("host.example:0.0".to_string());
+        let mut encoded = BytesMut::with_capacity(argument.len());
+        argument.encode(&mut encoded).unwrap();
+        assert_eq!(&b"\x00host.example:0.0"[..], &encoded[..]);
+        assert_eq!(argument.len(), encoded.len());
+
+        let decoded = XDisplayLocation::decode(&mut &encoded[..]).unwrap();
+        assert_eq!(argument, decoded);
+        assert_eq!(
+            XDisplayLocation::Send,
+            XDisplayLocation::decode(&mut &[1u8][..]).unwrap()
+        );
+    }
+}
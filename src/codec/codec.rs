@@ -14,46 +14,38 @@
 // limitations under the License.
 //
 
-use super::{consts, TelnetArgument, TelnetError, TelnetFrame, TelnetOption};
-use bytes::{Buf, BufMut, BytesMut};
+use super::{
+    StreamTransformer, TelnetDecoder, TelnetEncoder, TelnetError, TelnetFrame,
+};
+use bytes::{Buf, BytesMut};
 use futures::task::{Context, Poll};
-use futures::{AsyncRead, AsyncWrite, Sink, Stream};
+use futures::{ready, AsyncRead, AsyncWrite, Sink, Stream};
+use std::io;
 use std::pin::Pin;
-use tracing::{event, Level};
+
+/// Bytes read from the transport at a time.
+const READ_SIZE: usize = 4096;
+
+/// Encoded bytes waiting to be sent before `poll_ready` flushes them first.
+const WRITE_HIGH_WATER: usize = 8192;
 
 ///
+/// Telnet framing over an async byte stream.
 ///
+/// Incoming bytes are decoded into `TelnetFrame`s with a `TelnetDecoder`,
+/// and frames sent through the `Sink` are encoded with a `TelnetEncoder`
+/// into a buffer written out on flush.
 ///
 pub struct TelnetCodec<S>
 where
     S: AsyncRead + AsyncWrite,
 {
     stream: S,
-    encoder_buffer: BytesMut,
-    decoder_buffer: BytesMut,
-    decoder_state: DecoderState,
-}
-
-#[derive(Clone, Copy, Debug)]
-enum DecoderState {
-    /// Normal Data
-    NormalData,
-    /// Received IAC, Next byte is Command
-    InterpretAsCommand,
-    /// Received Do Command, Next Byte is arguments
-    NegotiateDo,
-    /// Received Dont Command, Next Byte is arguments
-    NegotiateDont,
-    /// Received Will Command, Next Byte is arguments
-    NegotiateWill,
-    /// Received Wont Command, Next Byte is arguments
-    NegotiateWont,
-    /// Received Subnegotiate Command, Next Byte is arguments
-    Subnegotiate,
-    /// Received Subnegotiate Option, Next Bytes are arguments
-    SubnegotiateArgument(TelnetOption),
-    /// Received IAC during Subnegotiation, Next Byte is command
-    SubnegotiateArgumentIAC(TelnetOption),
+    decoder: TelnetDecoder,
+    encoder: TelnetEncoder,
+    read_buffer: BytesMut,
+    write_buffer: BytesMut,
+    eof: bool,
 }
 
 impl<S> TelnetCodec<S>
@@ -62,273 +54,117 @@ where
 {
     /// Create new Telnet Codec
     pub fn new(stream: S) -> Self {
-        let encoder_buffer = BytesMut::with_capacity(4096);
-        let decoder_buffer = BytesMut::with_capacity(4096);
-        let decoder_state = DecoderState::NormalData;
         TelnetCodec {
             stream,
-            encoder_buffer,
-            decoder_buffer,
-            decoder_state,
+            decoder: TelnetDecoder::default(),
+            encoder: TelnetEncoder::default(),
+            read_buffer: BytesMut::with_capacity(READ_SIZE),
+            write_buffer: BytesMut::with_capacity(READ_SIZE),
+            eof: false,
         }
     }
-    ///
-    /// Decode incoming data frame by frame
-    ///
-    pub fn decode(
+    /// Underlying stream
+    pub fn get_ref(&self) -> &S {
+        &self.stream
+    }
+    /// Mutable underlying stream. Bytes read or written directly bypass the
+    /// codec buffers.
+    pub fn get_mut(&mut self) -> &mut S {
+        &mut self.stream
+    }
+    /// Consume the codec, returning the underlying stream. Buffered bytes
+    /// are lost.
+    pub fn into_inner(self) -> S {
+        self.stream
+    }
+    /// Install or remove the transformer applied to incoming bytes before
+    /// they are decoded.
+    pub fn set_decoder_transformer(
         &mut self,
-        src: &mut BytesMut,
-    ) -> Result<Option<TelnetFrame>, TelnetError> {
-        while src.remaining() > 0 {
-            let byte = src.get_u8();
-            match (self.decoder_state, byte) {
-                (DecoderState::NormalData, consts::IAC) => {
-                    self.decoder_state = DecoderState::InterpretAsCommand;
-                }
-                (DecoderState::NormalData, _) => {
-                    self.decoder_state = DecoderState::NormalData;
-                    return Ok(Some(TelnetFrame::Data(byte)));
-                }
-                (DecoderState::InterpretAsCommand, consts::NOP) => {
-                    self.decoder_state = DecoderState::NormalData;
-                    return Ok(Some(TelnetFrame::NoOperation));
-                }
-                (DecoderState::InterpretAsCommand, consts::DM) => {
-                    self.decoder_state = DecoderState::NormalData;
-                    return Ok(Some(TelnetFrame::DataMark));
-                }
-                (DecoderState::InterpretAsCommand, consts::BRK) => {
-                    self.decoder_state = DecoderState::NormalData;
-                    return Ok(Some(TelnetFrame::Break));
-                }
-                (DecoderState::InterpretAsCommand, consts::IP) => {
-                    self.decoder_state = DecoderState::NormalData;
-                    return Ok(Some(TelnetFrame::InterruptProcess));
-                }
-                (DecoderState::InterpretAsCommand, consts::AO) => {
-                    self.decoder_state = DecoderState::NormalData;
-                    return Ok(Some(TelnetFrame::AbortOutput));
-                }
-                (DecoderState::InterpretAsCommand, consts::AYT) => {
-                    self.decoder_state = DecoderState::NormalData;
-                    return Ok(Some(TelnetFrame::AreYouThere));
-                }
-                (DecoderState::InterpretAsCommand, consts::EC) => {
-                    self.decoder_state = DecoderState::NormalData;
-                    return Ok(Some(TelnetFrame::EraseCharacter));
-                }
-                (DecoderState::InterpretAsCommand, consts::EL) => {
-                    self.decoder_state = DecoderState::NormalData;
-                    return Ok(Some(TelnetFrame::EraseLine));
-                }
-                (DecoderState::InterpretAsCommand, consts::GA) => {
-                    self.decoder_state = DecoderState::NormalData;
-                    return Ok(Some(TelnetFrame::GoAhead));
-                }
-                (DecoderState::InterpretAsCommand, consts::IAC) => {
-                    self.decoder_state = DecoderState::NormalData;
-                    return Ok(Some(TelnetFrame::Data(consts::IAC)));
-                }
-                (DecoderState::InterpretAsCommand, consts::DO) => {
-                    self.decoder_state = DecoderState::NegotiateDo;
-                }
-                (DecoderState::InterpretAsCommand, consts::DONT) => {
-                    self.decoder_state = DecoderState::NegotiateDont;
-                }
-                (DecoderState::InterpretAsCommand, consts::WILL) => {
-                    self.decoder_state = DecoderState::NegotiateWill;
-                }
-                (DecoderState::InterpretAsCommand, consts::WONT) => {
-                    self.decoder_state = DecoderState::NegotiateWont;
-                }
-                (DecoderState::InterpretAsCommand, consts::SB) => {
-                    self.decoder_state = DecoderState::Subnegotiate;
-                }
-                (DecoderState::InterpretAsCommand, _) => {
-                    // Return to NormalData State, and return a No Operation
-                    event!(Level::WARN, "Received Unknown Command {:#X}", byte);
-                    self.decoder_state = DecoderState::NormalData;
-                    return Ok(Some(TelnetFrame::NoOperation));
-                }
-                (DecoderState::NegotiateDo, _) => {
-                    self.decoder_state = DecoderState::NormalData;
-                    return Ok(Some(TelnetFrame::Do(TelnetOption::from(byte))));
-                }
-                (DecoderState::NegotiateDont, _) => {
-                    self.decoder_state = DecoderState::NormalData;
-                    return Ok(Some(TelnetFrame::Dont(TelnetOption::from(
-                        byte,
-                    ))));
-                }
-                (DecoderState::NegotiateWill, _) => {
-                    self.decoder_state = DecoderState::NormalData;
-                    return Ok(Some(TelnetFrame::Will(TelnetOption::from(
-                        byte,
-                    ))));
-                }
-                (DecoderState::NegotiateWont, _) => {
-                    self.decoder_state = DecoderState::NormalData;
-                    return Ok(Some(TelnetFrame::Wont(TelnetOption::from(
-                        byte,
-                    ))));
-                }
-                (DecoderState::Subnegotiate, _) => {
-                    self.decoder_state = DecoderState::SubnegotiateArgument(
-                        TelnetOption::from(byte),
-                    );
-                }
-                (DecoderState::SubnegotiateArgument(option), consts::IAC) => {
-                    self.decoder_state =
-                        DecoderState::SubnegotiateArgumentIAC(option);
-                }
-                (DecoderState::SubnegotiateArgument(_option), _) => {
-                    self.decoder_buffer.put_u8(byte);
-                }
-                (
-                    DecoderState::SubnegotiateArgumentIAC(option),
-                    consts::IAC,
-                ) => {
-                    self.decoder_state =
-                        DecoderState::SubnegotiateArgument(option);
-                    self.decoder_buffer.put_u8(consts::IAC);
-                }
-                (DecoderState::SubnegotiateArgumentIAC(option), consts::SE) => {
-                    self.decoder_state = DecoderState::NormalData;
-                    let buffer = Vec::from(self.decoder_buffer.as_ref());
-                    self.decoder_buffer.clear();
-                    let argument = match option {
-                        TelnetOption::MSDP => decode_msdp(buffer),
-                        _ => TelnetArgument::Unknown(buffer),
-                    };
-                    return Ok(Some(TelnetFrame::Subnegotiate(
-                        option, argument,
-                    )));
-                }
-                (DecoderState::SubnegotiateArgumentIAC(_), _) => {
-                    // TODO: Evaluate if better to return back to SubnegotiateArgumentIAC state and keep buffer
-                    self.decoder_state = DecoderState::NormalData;
-                    self.decoder_buffer.clear();
-                    event!(Level::WARN, "Received Unknown or invalid Command during Subnegotiation {:#X}. Aborting", byte);
-                    return Ok(Some(TelnetFrame::NoOperation));
-                }
-            }
-        }
-        Ok(None)
+        transformer: Option<Box<dyn StreamTransformer>>,
+    ) {
+        self.decoder.set_transformer(transformer);
+    }
+    /// Install or remove the transformer applied to outgoing bytes after
+    /// they are encoded.
+    pub fn set_encoder_transformer(
+        &mut self,
+        transformer: Option<Box<dyn StreamTransformer>>,
+    ) {
+        self.encoder.set_transformer(transformer);
     }
+    /// Encode `item` into the outgoing buffer, ahead of any transformer
+    /// change that follows.
+    pub fn queue(&mut self, item: TelnetFrame) -> Result<(), TelnetError> {
+        self.encoder.encode(item, &mut self.write_buffer)
+    }
+    /// Encoded bytes not yet written to the stream.
+    pub fn pending_write(&self) -> usize {
+        self.write_buffer.len()
+    }
+}
 
-    pub fn encode(
+impl<S> TelnetCodec<S>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    /// Write out the outgoing buffer without flushing the stream.
+    fn poll_write_buffer(
         &mut self,
-        item: TelnetFrame,
-        dst: &mut BytesMut,
-    ) -> Result<(), TelnetError> {
-        match item {
-            TelnetFrame::Data(ch) => {
-                dst.reserve(2);
-                if ch == consts::IAC {
-                    dst.put_u8(consts::IAC);
-                }
-                dst.put_u8(ch);
-            }
-            TelnetFrame::NoOperation => {
-                dst.reserve(2);
-                dst.put_u8(consts::IAC);
-                dst.put_u8(consts::NOP);
-            }
-            TelnetFrame::DataMark => {
-                dst.reserve(2);
-                dst.put_u8(consts::IAC);
-                dst.put_u8(consts::DM);
-            }
-            TelnetFrame::Break => {
-                dst.reserve(2);
-                dst.put_u8(consts::IAC);
-                dst.put_u8(consts::BRK);
-            }
-            TelnetFrame::InterruptProcess => {
-                dst.reserve(2);
-                dst.put_u8(consts::IAC);
-                dst.put_u8(consts::IP);
-            }
-            TelnetFrame::AbortOutput => {
-                dst.reserve(2);
-                dst.put_u8(consts::IAC);
-                dst.put_u8(consts::AO);
-            }
-            TelnetFrame::AreYouThere => {
-                dst.reserve(2);
-                dst.put_u8(consts::IAC);
-                dst.put_u8(consts::AYT);
-            }
-            TelnetFrame::EraseCharacter => {
-                dst.reserve(2);
-                dst.put_u8(consts::IAC);
-                dst.put_u8(consts::EC);
-            }
-            TelnetFrame::EraseLine => {
-                dst.reserve(2);
-                dst.put_u8(consts::IAC);
-                dst.put_u8(consts::EL);
-            }
-            TelnetFrame::GoAhead => {
-                dst.reserve(2);
-                dst.put_u8(consts::IAC);
-                dst.put_u8(consts::GA);
-            }
-            TelnetFrame::Do(option) => {
-                dst.reserve(3);
-                dst.put_u8(consts::IAC);
-                dst.put_u8(consts::DO);
-                dst.put_u8(option.into());
-            }
-            TelnetFrame::Dont(option) => {
-                dst.reserve(3);
-                dst.put_u8(consts::IAC);
-                dst.put_u8(consts::DONT);
-                dst.put_u8(option.into());
-            }
-            TelnetFrame::Will(option) => {
-                dst.reserve(3);
-                dst.put_u8(consts::IAC);
-                dst.put_u8(consts::WILL);
-                dst.put_u8(option.into());
-            }
-            TelnetFrame::Wont(option) => {
-                dst.reserve(3);
-                dst.put_u8(consts::IAC);
-                dst.put_u8(consts::WONT);
-                dst.put_u8(option.into());
-            }
-            TelnetFrame::Subnegotiate(option, argument) => {
-                dst.reserve(5 + argument.len());
-                dst.put_u8(consts::IAC);
-                dst.put_u8(consts::SB);
-                dst.put_u8(option.into());
-                dst.put_slice(&arguments);
-                dst.put_u8(consts::IAC);
-                dst.put_u8(consts::SE);
-            }
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<(), TelnetError>> {
+        while !self.write_buffer.is_empty() {
+            let written =
+                ready!(Pin::new(&mut self.stream)
+                    .poll_write(cx, &self.write_buffer))?;
+            if written == 0 {
+                let error = io::Error::from(io::ErrorKind::WriteZero);
+                return Poll::Ready(Err(error.into()));
+            }
+            self.write_buffer.advance(written);
         }
-        Ok(())
+        Poll::Ready(Ok(()))
     }
 }
 
 impl<S> Stream for TelnetCodec<S>
 where
-    S: AsyncRead + AsyncWrite,
+    S: AsyncRead + AsyncWrite + Unpin,
 {
-    type Item = TelnetFrame;
+    type Item = Result<TelnetFrame, TelnetError>;
 
     fn poll_next(
         self: Pin<&mut Self>,
         cx: &mut Context<'_>,
     ) -> Poll<Option<Self::Item>> {
-        Poll::Pending
+        let this = self.get_mut();
+        loop {
+            match this.decoder.decode(&mut this.read_buffer) {
+                Ok(Some(frame)) => return Poll::Ready(Some(Ok(frame))),
+                Ok(None) => {},
+                Err(error) => return Poll::Ready(Some(Err(error))),
+            }
+            if this.eof {
+                return Poll::Ready(None);
+            }
+            let mut chunk = [0u8; READ_SIZE];
+            let read = match ready!(
+                Pin::new(&mut this.stream).poll_read(cx, &mut chunk)
+            ) {
+                Ok(read) => read,
+                Err(error) => return Poll::Ready(Some(Err(error.into()))),
+            };
+            if read == 0 {
+                this.eof = true;
+            }
+            this.read_buffer.extend_from_slice(&chunk[..read]);
+        }
     }
 }
 
 impl<S> Sink<TelnetFrame> for TelnetCodec<S>
 where
-    S: AsyncRead + AsyncWrite,
+    S: AsyncRead + AsyncWrite + Unpin,
 {
     type Error = TelnetError;
 
@@ -336,31 +172,65 @@ where
         self: Pin<&mut Self>,
         cx: &mut Context<'_>,
     ) -> Poll<Result<(), Self::Error>> {
-        Poll::Pending
+        let this = self.get_mut();
+        if this.write_buffer.len() >= WRITE_HIGH_WATER {
+            ready!(this.poll_write_buffer(cx))?;
+        }
+        Poll::Ready(Ok(()))
     }
 
     fn start_send(
         self: Pin<&mut Self>,
         item: TelnetFrame,
     ) -> Result<(), Self::Error> {
-        Ok(())
+        self.get_mut().queue(item)
     }
 
     fn poll_flush(
         self: Pin<&mut Self>,
         cx: &mut Context<'_>,
     ) -> Poll<Result<(), Self::Error>> {
-        Poll::Pending
+        let this = self.get_mut();
+        ready!(this.poll_write_buffer(cx))?;
+        ready!(Pin::new(&mut this.stream).poll_flush(cx))?;
+        Poll::Ready(Ok(()))
     }
 
     fn poll_close(
         self: Pin<&mut Self>,
         cx: &mut Context<'_>,
     ) -> Poll<Result<(), Self::Error>> {
-        Poll::Pending
+        let this = self.get_mut();
+        ready!(this.poll_write_buffer(cx))?;
+        ready!(Pin::new(&mut this.stream).poll_close(cx))?;
+        Poll::Ready(Ok(()))
     }
 }
 
-fn decode_msdp(buffer: Vec<u8>) -> TelnetArgument {
-    TelnetArgument::Unknown(buffer)
+#[cfg(test)]
+mod tests {
+    use super::TelnetCodec;
+    use crate::codec::{consts, TelnetFrame, TelnetOption};
+    use futures::executor::block_on;
+    use futures::io::Cursor;
+    use futures::{SinkExt, StreamExt};
+
+    #[test]
+    fn test_read_write() {
+        let input = vec![b'h', consts::IAC, consts::DO, consts::option::ECHO];
+        let mut codec = TelnetCodec::new(Cursor::new(input));
+        block_on(async {
+            let frame = codec.next().await.unwrap().unwrap();
+            assert_eq!(TelnetFrame::Data(b'h'), frame);
+            let frame = codec.next().await.unwrap().unwrap();
+            assert_eq!(TelnetFrame::Do(TelnetOption::Echo), frame);
+            assert!(codec.next().await.is_none());
+
+            let frame = TelnetFrame::Will(TelnetOption::Echo);
+            codec.send(frame).await.unwrap();
+        });
+        let written = codec.into_inner().into_inner();
+        let expected = [consts::IAC, consts::WILL, consts::option::ECHO];
+        assert_eq!(&expected[..], &written[4..]);
+    }
 }
@@ -20,6 +20,10 @@ pub const NUL: u8 = 0;
 pub const LF: u8 = 10;
 /// Carriage Return
 pub const CR: u8 = 13;
+/// Device Control 1, Transmit On
+pub const XON: u8 = 17;
+/// Device Control 3, Transmit Off
+pub const XOFF: u8 = 19;
 /// Negative Acknowledge
 pub const NAK: u8 = 21;
 /// End of File (EOF already used)
@@ -189,12 +193,12 @@ pub mod option {
     /// Charset Subnegotiation Tokens
     pub mod charset {
         pub const REQUEST: u8 = 1;
-        pub const ACCEPTED: u8 = 02;
-        pub const REJECTED: u8 = 03;
-        pub const TTABLE_IS: u8 = 04;
-        pub const TTABLE_REJECTED: u8 = 05;
-        pub const TTABLE_ACK: u8 = 06;
-        pub const TTABLE_NAK: u8 = 07;
+        pub const ACCEPTED: u8 = 2;
+        pub const REJECTED: u8 = 3;
+        pub const TTABLE_IS: u8 = 4;
+        pub const TTABLE_REJECTED: u8 = 5;
+        pub const TTABLE_ACK: u8 = 6;
+        pub const TTABLE_NAK: u8 = 7;
     }
 
     pub mod naocrd {
@@ -230,6 +234,645 @@ pub mod option {
         pub const SEND: u8 = 1;
     }
 
+    /// Authentication Subnegotiation Tokens [RFC2941](http://www.iana.org/go/rfc2941)
+    pub mod authentication {
+        /// Subnegotiation IS command.
+        pub const IS: u8 = 0;
+        /// Subnegotiation SEND command.
+        pub const SEND: u8 = 1;
+        /// Subnegotiation REPLY command.
+        pub const REPLY: u8 = 2;
+        /// Subnegotiation NAME command.
+        pub const NAME: u8 = 3;
+
+        /// Authentication Types
+        pub mod kind {
+            pub const NULL: u8 = 0;
+            pub const KERBEROS_V4: u8 = 1;
+            pub const KERBEROS_V5: u8 = 2;
+            pub const SPX: u8 = 3;
+            pub const MINK: u8 = 4;
+            pub const SRP: u8 = 5;
+            pub const RSA: u8 = 6;
+            pub const SSL: u8 = 7;
+            pub const LOKI: u8 = 10;
+            pub const SSA: u8 = 11;
+            pub const KEA_SJ: u8 = 12;
+            pub const KEA_SJ_INTEG: u8 = 13;
+            pub const DSS: u8 = 14;
+            pub const NTLM: u8 = 15;
+        }
+
+        /// Authentication Type Modifiers
+        pub mod modifier {
+            pub const WHO_MASK: u8 = 1;
+            pub const CLIENT_TO_SERVER: u8 = 0;
+            pub const SERVER_TO_CLIENT: u8 = 1;
+            pub const HOW_MASK: u8 = 2;
+            pub const ONE_WAY: u8 = 0;
+            pub const MUTUAL: u8 = 2;
+            pub const ENCRYPT_MASK: u8 = 20;
+            pub const ENCRYPT_OFF: u8 = 0;
+            pub const ENCRYPT_USING_TELOPT: u8 = 4;
+            pub const ENCRYPT_AFTER_EXCHANGE: u8 = 16;
+            pub const ENCRYPT_RESERVED: u8 = 20;
+            pub const INI_CRED_FWD_MASK: u8 = 8;
+            pub const INI_CRED_FWD_OFF: u8 = 0;
+            pub const INI_CRED_FWD_ON: u8 = 8;
+        }
+    }
+
+    /// Encryption Subnegotiation Tokens [RFC2946](http://www.iana.org/go/rfc2946)
+    pub mod encryption {
+        /// Subnegotiation IS command.
+        pub const IS: u8 = 0;
+        /// Subnegotiation SUPPORT command.
+        pub const SUPPORT: u8 = 1;
+        /// Subnegotiation REPLY command.
+        pub const REPLY: u8 = 2;
+        /// Subnegotiation START command.
+        pub const START: u8 = 3;
+        /// Subnegotiation END command.
+        pub const END: u8 = 4;
+        /// Subnegotiation REQUEST-START command.
+        pub const REQUEST_START: u8 = 5;
+        /// Subnegotiation REQUEST-END command.
+        pub const REQUEST_END: u8 = 6;
+        /// Subnegotiation ENC_KEYID command.
+        pub const ENC_KEYID: u8 = 7;
+        /// Subnegotiation DEC_KEYID command.
+        pub const DEC_KEYID: u8 = 8;
+
+        /// Encryption Types
+        pub mod kind {
+            pub const NULL: u8 = 0;
+            pub const DES_CFB64: u8 = 1;
+            pub const DES_OFB64: u8 = 2;
+            pub const DES3_CFB64: u8 = 3;
+            pub const DES3_OFB64: u8 = 4;
+            pub const CAST5_40_CFB64: u8 = 8;
+            pub const CAST5_40_OFB64: u8 = 9;
+            pub const CAST128_CFB64: u8 = 10;
+            pub const CAST128_OFB64: u8 = 11;
+            pub const AES_CCM: u8 = 12;
+        }
+    }
+
+    /// Com Port Control Subnegotiation Tokens [RFC2217](http://www.iana.org/go/rfc2217)
+    pub mod comport {
+        /// Offset added to a command code when sent by the access server.
+        pub const SERVER_OFFSET: u8 = 100;
+        /// Subnegotiation SIGNATURE command.
+        pub const SIGNATURE: u8 = 0;
+        /// Subnegotiation SET-BAUDRATE command.
+        pub const SET_BAUDRATE: u8 = 1;
+        /// Subnegotiation SET-DATASIZE command.
+        pub const SET_DATASIZE: u8 = 2;
+        /// Subnegotiation SET-PARITY command.
+        pub const SET_PARITY: u8 = 3;
+        /// Subnegotiation SET-STOPSIZE command.
+        pub const SET_STOPSIZE: u8 = 4;
+        /// Subnegotiation SET-CONTROL command.
+        pub const SET_CONTROL: u8 = 5;
+        /// Subnegotiation NOTIFY-LINESTATE command.
+        pub const NOTIFY_LINESTATE: u8 = 6;
+        /// Subnegotiation NOTIFY-MODEMSTATE command.
+        pub const NOTIFY_MODEMSTATE: u8 = 7;
+        /// Subnegotiation FLOWCONTROL-SUSPEND command.
+        pub const FLOWCONTROL_SUSPEND: u8 = 8;
+        /// Subnegotiation FLOWCONTROL-RESUME command.
+        pub const FLOWCONTROL_RESUME: u8 = 9;
+        /// Subnegotiation SET-LINESTATE-MASK command.
+        pub const SET_LINESTATE_MASK: u8 = 10;
+        /// Subnegotiation SET-MODEMSTATE-MASK command.
+        pub const SET_MODEMSTATE_MASK: u8 = 11;
+        /// Subnegotiation PURGE-DATA command.
+        pub const PURGE_DATA: u8 = 12;
+
+        /// SET-PARITY values
+        pub mod parity {
+            pub const REQUEST: u8 = 0;
+            pub const NONE: u8 = 1;
+            pub const ODD: u8 = 2;
+            pub const EVEN: u8 = 3;
+            pub const MARK: u8 = 4;
+            pub const SPACE: u8 = 5;
+        }
+
+        /// SET-STOPSIZE values
+        pub mod stopsize {
+            pub const REQUEST: u8 = 0;
+            pub const ONE: u8 = 1;
+            pub const TWO: u8 = 2;
+            pub const ONE_AND_HALF: u8 = 3;
+        }
+
+        /// SET-CONTROL values
+        pub mod control {
+            pub const REQUEST_FLOW_OUT: u8 = 0;
+            pub const FLOW_OUT_NONE: u8 = 1;
+            pub const FLOW_OUT_XON_XOFF: u8 = 2;
+            pub const FLOW_OUT_HARDWARE: u8 = 3;
+            pub const REQUEST_BREAK: u8 = 4;
+            pub const BREAK_ON: u8 = 5;
+            pub const BREAK_OFF: u8 = 6;
+            pub const REQUEST_DTR: u8 = 7;
+            pub const DTR_ON: u8 = 8;
+            pub const DTR_OFF: u8 = 9;
+            pub const REQUEST_RTS: u8 = 10;
+            pub const RTS_ON: u8 = 11;
+            pub const RTS_OFF: u8 = 12;
+            pub const REQUEST_FLOW_IN: u8 = 13;
+            pub const FLOW_IN_NONE: u8 = 14;
+            pub const FLOW_IN_XON_XOFF: u8 = 15;
+            pub const FLOW_IN_HARDWARE: u8 = 16;
+            pub const FLOW_DCD: u8 = 17;
+            pub const FLOW_DTR: u8 = 18;
+            pub const FLOW_DSR: u8 = 19;
+        }
+
+        /// NOTIFY-LINESTATE bits
+        pub mod linestate {
+            pub const TIMEOUT: u8 = 128;
+            pub const SHIFT_REGISTER_EMPTY: u8 = 64;
+            pub const HOLDING_REGISTER_EMPTY: u8 = 32;
+            pub const BREAK_DETECT: u8 = 16;
+            pub const FRAMING_ERROR: u8 = 8;
+            pub const PARITY_ERROR: u8 = 4;
+            pub const OVERRUN_ERROR: u8 = 2;
+            pub const DATA_READY: u8 = 1;
+        }
+
+        /// NOTIFY-MODEMSTATE bits
+        pub mod modemstate {
+            pub const CARRIER_DETECT: u8 = 128;
+            pub const RING_INDICATOR: u8 = 64;
+            pub const DATA_SET_READY: u8 = 32;
+            pub const CLEAR_TO_SEND: u8 = 16;
+            pub const DELTA_CARRIER_DETECT: u8 = 8;
+            pub const TRAILING_EDGE_RING: u8 = 4;
+            pub const DELTA_DATA_SET_READY: u8 = 2;
+            pub const DELTA_CLEAR_TO_SEND: u8 = 1;
+        }
+
+        /// PURGE-DATA values
+        pub mod purge {
+            pub const RECEIVE: u8 = 1;
+            pub const TRANSMIT: u8 = 2;
+            pub const BOTH: u8 = 3;
+        }
+    }
+
+    /// SUPDUP Display Protocol Codes [RFC734](http://www.iana.org/go/rfc734)
+    pub mod supdup {
+        /// Terminal type every SUPDUP client reports.
+        pub const TCTYP_SOFTWARE: u64 = 0o7;
+        /// Move cursor, followed by old vpos, old hpos, new vpos, new hpos.
+        pub const TDMOV: u8 = 0o200;
+        /// Clear to end of screen.
+        pub const TDEOF: u8 = 0o202;
+        /// Clear to end of line.
+        pub const TDEOL: u8 = 0o203;
+        /// Clear the character under the cursor.
+        pub const TDDLF: u8 = 0o204;
+        /// Carriage return, line feed and clear line.
+        pub const TDCRL: u8 = 0o207;
+        /// No operation.
+        pub const TDNOP: u8 = 0o210;
+        /// Output reset.
+        pub const TDORS: u8 = 0o214;
+        /// Quote the next byte as a printing character.
+        pub const TDQOT: u8 = 0o215;
+        /// Move cursor forward one column.
+        pub const TDFS: u8 = 0o216;
+        /// Move cursor, followed by new vpos, new hpos.
+        pub const TDMV0: u8 = 0o217;
+        /// Clear screen and home cursor.
+        pub const TDCLR: u8 = 0o220;
+        /// Ring the bell.
+        pub const TDBEL: u8 = 0o221;
+        /// Insert lines, followed by count.
+        pub const TDILP: u8 = 0o223;
+        /// Delete lines, followed by count.
+        pub const TDDLP: u8 = 0o224;
+        /// Insert characters, followed by count.
+        pub const TDICP: u8 = 0o225;
+        /// Delete characters, followed by count.
+        pub const TDDCP: u8 = 0o226;
+        /// Black on white.
+        pub const TDBOW: u8 = 0o227;
+        /// Reset terminal modes.
+        pub const TDRST: u8 = 0o230;
+        /// Enter graphics mode.
+        pub const TDGRF: u8 = 0o231;
+        /// Scroll region up, followed by line count and scroll count.
+        pub const TDRSU: u8 = 0o232;
+        /// Scroll region down, followed by line count and scroll count.
+        pub const TDRSD: u8 = 0o233;
+    }
+
+    /// SUPDUP Output Subnegotiation Tokens [RFC749](http://www.iana.org/go/rfc749)
+    pub mod supdup_output {
+        /// Terminal parameters from the user side.
+        pub const TERMINAL: u8 = 1;
+        /// Display codes from the server side.
+        pub const OUTPUT: u8 = 2;
+    }
+
+    /// Terminal Location Number Subnegotiation Tokens [RFC946](http://www.iana.org/go/rfc946)
+    pub mod ttyloc {
+        /// 64 bit gateway address and terminal number.
+        pub const FORMAT_64BIT: u8 = 0;
+    }
+
+    /// Terminal Type Subnegotiation Tokens [RFC1091](http://www.iana.org/go/rfc1091)
+    pub mod ttype {
+        /// Subnegotiation IS command.
+        pub const IS: u8 = 0;
+        /// Subnegotiation SEND command.
+        pub const SEND: u8 = 1;
+    }
+
+    /// Terminal Speed Subnegotiation Tokens [RFC1079](http://www.iana.org/go/rfc1079)
+    pub mod tspeed {
+        /// Subnegotiation IS command.
+        pub const IS: u8 = 0;
+        /// Subnegotiation SEND command.
+        pub const SEND: u8 = 1;
+    }
+
+    /// X Display Location Subnegotiation Tokens [RFC1096](http://www.iana.org/go/rfc1096)
+    pub mod xdisploc {
+        /// Subnegotiation IS command.
+        pub const IS: u8 = 0;
+        /// Subnegotiation SEND command.
+        pub const SEND: u8 = 1;
+    }
+
+    /// Output Marking Subnegotiation Tokens [RFC933](http://www.iana.org/go/rfc933)
+    ///
+    /// Any other leading byte is the positioning control of a banner.
+    pub mod outmrk {
+        /// Banner accepted.
+        pub const ACK: u8 = 6;
+        /// Banner refused.
+        pub const NAK: u8 = 21;
+    }
+
+    /// Extended Options List Subnegotiation Tokens [RFC861](http://www.iana.org/go/rfc861)
+    pub mod exopl {
+        /// Extended option negotiation DO.
+        pub const DO: u8 = super::super::DO;
+        /// Extended option negotiation DONT.
+        pub const DONT: u8 = super::super::DONT;
+        /// Extended option negotiation WILL.
+        pub const WILL: u8 = super::super::WILL;
+        /// Extended option negotiation WONT.
+        pub const WONT: u8 = super::super::WONT;
+        /// Extended option subnegotiation begin.
+        pub const SB: u8 = super::super::SB;
+        /// Extended option subnegotiation end.
+        pub const SE: u8 = super::super::SE;
+    }
+
+    /// TN3270E Subnegotiation Tokens [RFC2355](http://www.iana.org/go/rfc2355)
+    pub mod tn3270e {
+        pub const ASSOCIATE: u8 = 0;
+        pub const CONNECT: u8 = 1;
+        pub const DEVICE_TYPE: u8 = 2;
+        pub const FUNCTIONS: u8 = 3;
+        pub const IS: u8 = 4;
+        pub const REASON: u8 = 5;
+        pub const REJECT: u8 = 6;
+        pub const REQUEST: u8 = 7;
+        pub const SEND: u8 = 8;
+
+        /// DEVICE-TYPE REJECT reason codes
+        pub mod reason {
+            pub const CONN_PARTNER: u8 = 0;
+            pub const DEVICE_IN_USE: u8 = 1;
+            pub const INV_ASSOCIATE: u8 = 2;
+            pub const INV_NAME: u8 = 3;
+            pub const INV_DEVICE_TYPE: u8 = 4;
+            pub const TYPE_NAME_ERROR: u8 = 5;
+            pub const UNKNOWN_ERROR: u8 = 6;
+            pub const UNSUPPORTED_REQ: u8 = 7;
+        }
+
+        /// FUNCTIONS list entries
+        pub mod function {
+            pub const BIND_IMAGE: u8 = 0;
+            pub const DATA_STREAM_CTL: u8 = 1;
+            pub const RESPONSES: u8 = 2;
+            pub const SCS_CTL_CODES: u8 = 3;
+            pub const SYSREQ: u8 = 4;
+        }
+
+        /// Data header DATA-TYPE values
+        pub mod data_type {
+            pub const DATA_3270: u8 = 0;
+            pub const SCS_DATA: u8 = 1;
+            pub const RESPONSE: u8 = 2;
+            pub const BIND_IMAGE: u8 = 3;
+            pub const UNBIND: u8 = 4;
+            pub const NVT_DATA: u8 = 5;
+            pub const REQUEST: u8 = 6;
+            pub const SSCP_LU_DATA: u8 = 7;
+            pub const PRINT_EOJ: u8 = 8;
+        }
+
+        /// Data header REQUEST-FLAG values
+        pub mod request_flag {
+            pub const ERR_COND_CLEARED: u8 = 0;
+        }
+
+        /// Data header RESPONSE-FLAG values
+        pub mod response_flag {
+            pub const NO_RESPONSE: u8 = 0;
+            pub const ERROR_RESPONSE: u8 = 1;
+            pub const ALWAYS_RESPONSE: u8 = 2;
+            pub const POSITIVE_RESPONSE: u8 = 0;
+            pub const NEGATIVE_RESPONSE: u8 = 1;
+        }
+    }
+
+    /// Remote Controlled Transmission and Echoing Subnegotiation Tokens
+    /// [RFC726](http://www.iana.org/go/rfc726)
+    pub mod rcte {
+        /// Command byte asking to repeat the previous command.
+        pub const REPEAT: u8 = 0;
+        /// Don't echo the break character.
+        pub const SKIP_BREAK: u8 = 1;
+        /// Don't echo text up to the break character.
+        pub const SKIP_TEXT: u8 = 2;
+        /// Two bytes of new break classes follow.
+        pub const BREAK_CLASSES: u8 = 4;
+        /// Two bytes of new transmit classes follow.
+        pub const TRANSMIT_CLASSES: u8 = 8;
+
+        /// Character classes used for break and transmit class sets.
+        pub mod class {
+            /// Upper-case letters.
+            pub const UPPER_CASE: u16 = 0x0001;
+            /// Lower-case letters.
+            pub const LOWER_CASE: u16 = 0x0002;
+            /// Digits.
+            pub const NUMBERS: u16 = 0x0004;
+            /// Format effectors: BS, HT, LF, VT, FF and CR.
+            pub const FORMAT_EFFECTORS: u16 = 0x0008;
+            /// Other control characters, including ESC and DEL.
+            pub const CONTROL: u16 = 0x0010;
+            /// Punctuation: `. , ; : ? !`
+            pub const PUNCTUATION: u16 = 0x0020;
+            /// Grouping: `{ [ ( < > ) ] }`
+            pub const GROUPING: u16 = 0x0040;
+            /// Miscellaneous printing characters.
+            pub const MISCELLANEOUS: u16 = 0x0080;
+            /// Space.
+            pub const SPACE: u16 = 0x0100;
+        }
+    }
+
+    /// X.3-PAD Subnegotiation Tokens [RFC1053](http://www.iana.org/go/rfc1053)
+    pub mod x3pad {
+        /// Set PAD parameters.
+        pub const SET: u8 = 0;
+        /// Parameter values in effect after a SET.
+        pub const RESPONSE_SET: u8 = 1;
+        /// Current PAD parameter values.
+        pub const IS: u8 = 2;
+        /// Acknowledge reported parameter values.
+        pub const RESPONSE_IS: u8 = 3;
+        /// Ask for current PAD parameter values.
+        pub const SEND: u8 = 4;
+
+        /// Standard X.3 parameter references
+        pub mod param {
+            /// Escape from data transfer state
+            pub const PAD_RECALL: u8 = 1;
+            /// Local echo
+            pub const ECHO: u8 = 2;
+            /// Data forwarding characters
+            pub const FORWARDING: u8 = 3;
+            /// Idle timer delay, in twentieths of a second
+            pub const IDLE_TIMER: u8 = 4;
+            /// Flow control of the terminal by the PAD
+            pub const ANCILLARY_DEVICE_CONTROL: u8 = 5;
+            /// PAD service signals
+            pub const SERVICE_SIGNALS: u8 = 6;
+            /// Action on receiving a break signal
+            pub const BREAK: u8 = 7;
+            /// Discard output
+            pub const DISCARD_OUTPUT: u8 = 8;
+            /// Padding after carriage return
+            pub const CR_PADDING: u8 = 9;
+            /// Line folding
+            pub const LINE_FOLDING: u8 = 10;
+            /// Terminal line speed, read only
+            pub const BINARY_SPEED: u8 = 11;
+            /// Flow control of the PAD by the terminal
+            pub const FLOW_CONTROL: u8 = 12;
+            /// Line feed insertion after carriage return
+            pub const LF_INSERTION: u8 = 13;
+            /// Padding after line feed
+            pub const LF_PADDING: u8 = 14;
+            /// Local editing
+            pub const EDITING: u8 = 15;
+            /// Character delete character
+            pub const CHARACTER_DELETE: u8 = 16;
+            /// Line delete character
+            pub const LINE_DELETE: u8 = 17;
+            /// Line display character
+            pub const LINE_DISPLAY: u8 = 18;
+            /// Editing PAD service signals
+            pub const EDITING_SIGNALS: u8 = 19;
+            /// Characters not echoed
+            pub const ECHO_MASK: u8 = 20;
+            /// Parity treatment
+            pub const PARITY: u8 = 21;
+            /// Page wait
+            pub const PAGE_WAIT: u8 = 22;
+        }
+
+        /// Data forwarding character classes for the FORWARDING parameter
+        pub mod forward {
+            /// Letters and digits
+            pub const ALPHANUMERIC: u8 = 1;
+            /// Carriage return
+            pub const CR: u8 = 2;
+            /// ESC, BEL, ENQ and ACK
+            pub const ESCAPE: u8 = 4;
+            /// DEL, CAN and DC2
+            pub const EDITING: u8 = 8;
+            /// ETX and EOT
+            pub const TERMINATORS: u8 = 16;
+            /// HT, LF, VT and FF
+            pub const FORMAT_EFFECTORS: u8 = 32;
+            /// Every other control character
+            pub const CONTROL: u8 = 64;
+        }
+    }
+
+    /// Data Entry Terminal Subnegotiation Tokens [RFC1043](http://www.iana.org/go/rfc1043)
+    pub mod det {
+        /// Editing facilities supported.
+        pub const EDIT_FACILITIES: u8 = 1;
+        /// Erasure facilities supported.
+        pub const ERASE_FACILITIES: u8 = 2;
+        /// Transmission facilities supported.
+        pub const TRANSMIT_FACILITIES: u8 = 3;
+        /// Formatting facilities supported.
+        pub const FORMAT_FACILITIES: u8 = 4;
+        /// Move the cursor to a column and line.
+        pub const MOVE_CURSOR: u8 = 5;
+        /// Move the cursor to the start of a line.
+        pub const SKIP_TO_LINE: u8 = 6;
+        /// Move the cursor to a column on the current line.
+        pub const SKIP_TO_CHAR: u8 = 7;
+        /// Move the cursor up one line.
+        pub const UP: u8 = 8;
+        /// Move the cursor down one line.
+        pub const DOWN: u8 = 9;
+        /// Move the cursor left one column.
+        pub const LEFT: u8 = 10;
+        /// Move the cursor right one column.
+        pub const RIGHT: u8 = 11;
+        /// Move the cursor to the top left corner.
+        pub const HOME: u8 = 12;
+        /// Insert a line at the cursor.
+        pub const LINE_INSERT: u8 = 13;
+        /// Delete the line at the cursor.
+        pub const LINE_DELETE: u8 = 14;
+        /// Insert a character at the cursor.
+        pub const CHAR_INSERT: u8 = 15;
+        /// Delete the character at the cursor.
+        pub const CHAR_DELETE: u8 = 16;
+        /// Ask for the cursor position.
+        pub const READ_CURSOR: u8 = 17;
+        /// Report the cursor position.
+        pub const CURSOR_POSITION: u8 = 18;
+        /// Move the cursor to the previous field.
+        pub const REVERSE_TAB: u8 = 19;
+        /// Transmit the whole screen.
+        pub const TRANSMIT_SCREEN: u8 = 20;
+        /// Transmit the unprotected fields.
+        pub const TRANSMIT_UNPROTECTED: u8 = 21;
+        /// Transmit the current line.
+        pub const TRANSMIT_LINE: u8 = 22;
+        /// Transmit the current field.
+        pub const TRANSMIT_FIELD: u8 = 23;
+        /// Transmit the screen from the cursor on.
+        pub const TRANSMIT_REST_SCREEN: u8 = 24;
+        /// Transmit the line from the cursor on.
+        pub const TRANSMIT_REST_LINE: u8 = 25;
+        /// Transmit the field from the cursor on.
+        pub const TRANSMIT_REST_FIELD: u8 = 26;
+        /// Transmit the modified fields.
+        pub const TRANSMIT_MODIFIED: u8 = 27;
+        /// Transmitted data follows, starting at a column and line.
+        pub const DATA_TRANSMIT: u8 = 28;
+        /// Erase the whole screen.
+        pub const ERASE_SCREEN: u8 = 29;
+        /// Erase the current line.
+        pub const ERASE_LINE: u8 = 30;
+        /// Erase the current field.
+        pub const ERASE_FIELD: u8 = 31;
+        /// Erase the screen from the cursor on.
+        pub const ERASE_REST_SCREEN: u8 = 32;
+        /// Erase the line from the cursor on.
+        pub const ERASE_REST_LINE: u8 = 33;
+        /// Erase the field from the cursor on.
+        pub const ERASE_REST_FIELD: u8 = 34;
+        /// Erase the unprotected fields.
+        pub const ERASE_UNPROTECTED: u8 = 35;
+        /// Formatted field data.
+        pub const FORMAT_DATA: u8 = 36;
+        /// Repeat a character.
+        pub const REPEAT: u8 = 37;
+        /// Negotiate suppression of field protection.
+        pub const SUPPRESS_PROTECTION: u8 = 38;
+        /// Separates fields of transmitted data.
+        pub const FIELD_SEPARATOR: u8 = 39;
+        /// Function key pressed.
+        pub const FN: u8 = 40;
+        /// Error in a received command.
+        pub const ERROR: u8 = 41;
+    }
+
+    /// Byte Macro Subnegotiation Tokens [RFC735](http://www.iana.org/go/rfc735)
+    pub mod bm {
+        /// Define a macro byte and its replacement string.
+        pub const DEFINE: u8 = 1;
+        /// Accept a macro definition.
+        pub const ACCEPT: u8 = 2;
+        /// Refuse a macro definition.
+        pub const REFUSE: u8 = 3;
+        /// Next occurrence of the macro byte is taken literally.
+        pub const LITERAL: u8 = 4;
+        /// Cancel a macro definition.
+        pub const CANCEL: u8 = 5;
+
+        /// Refusal for any other reason.
+        pub const OTHER_REASON: u8 = 0;
+        /// Macro byte may not be used.
+        pub const BAD_CHOICE: u8 = 1;
+        /// Replacement string is too long.
+        pub const TOO_LONG: u8 = 2;
+        /// Count does not match the replacement string length.
+        pub const WRONG_LENGTH: u8 = 3;
+    }
+
+    /// X Window System Forwarding Subnegotiation Tokens
+    /// [draft-altman-telnet-forwardx](https://tools.ietf.org/html/draft-altman-telnet-forwardx-03)
+    pub mod forwardx {
+        /// Ask the server to forward X connections for a display screen.
+        pub const SCREEN: u8 = 0;
+        /// A new X connection arrived on a channel.
+        pub const OPEN: u8 = 1;
+        /// A channel was closed.
+        pub const CLOSE: u8 = 2;
+        /// Data for a channel.
+        pub const DATA: u8 = 3;
+        /// Optional features supported by the sender.
+        pub const OPTIONS: u8 = 4;
+        /// Data for an optional feature.
+        pub const OPTION_DATA: u8 = 5;
+        /// Stop sending data on a channel.
+        pub const XOFF: u8 = 6;
+        /// Resume sending data on a channel.
+        pub const XON: u8 = 7;
+    }
+
+    /// Kermit Subnegotiation Tokens [RFC2840](http://www.iana.org/go/rfc2840)
+    pub mod kermit {
+        /// Sender has entered Kermit server mode.
+        pub const START_SERVER: u8 = 0;
+        /// Sender has left Kermit server mode.
+        pub const STOP_SERVER: u8 = 1;
+        /// Ask the receiver to enter Kermit server mode.
+        pub const REQ_START_SERVER: u8 = 2;
+        /// Ask the receiver to leave Kermit server mode.
+        pub const REQ_STOP_SERVER: u8 = 3;
+        /// Start of packet character the sender uses.
+        pub const SOP: u8 = 4;
+        /// Answer to REQ-START-SERVER.
+        pub const RESP_START_SERVER: u8 = 8;
+        /// Answer to REQ-STOP-SERVER.
+        pub const RESP_STOP_SERVER: u8 = 9;
+    }
+
+    /// Remote Flow Control Subnegotiation Tokens [RFC1372](http://www.iana.org/go/rfc1372)
+    pub mod lflow {
+        /// Disable remote flow control.
+        pub const OFF: u8 = 0;
+        /// Enable remote flow control.
+        pub const ON: u8 = 1;
+        /// Any character restarts output.
+        pub const RESTART_ANY: u8 = 2;
+        /// Only XON restarts output.
+        pub const RESTART_XON: u8 = 3;
+    }
+
     ///
     /// Options this library supports.
     /// Set to True when implementation exists.
@@ -243,7 +886,7 @@ pub mod option {
         (false, false), //   4 - NAMS
         (false, false), //   5 - STATUS
         (false, false), //   6 - TM
-        (false, false), //   7 - RCTE
+        (true, true),   //   7 - RCTE
         (false, false), //   8 - NAOL
         (false, false), //   9 - NAOP
         (false, false), //  10 - NAOCRD
@@ -254,38 +897,38 @@ pub mod option {
         (false, false), //  15 - NAOVTD
         (false, false), //  16 - NAOLFD
         (false, false), //  17 - XASCII
-        (false, false), //  18 - LOGOUT
-        (false, false), //  19 - BM
-        (false, false), //  20 - DET
-        (false, false), //  21 - SUPDUP
-        (false, false), //  22 - SUPDUP_OUTPUT
-        (false, false), //  23 - SNDLOC
-        (false, false), //  24 - TTYPE
+        (true, true),   //  18 - LOGOUT
+        (true, true),   //  19 - BM
+        (true, true),   //  20 - DET
+        (true, true),   //  21 - SUPDUP
+        (true, true),   //  22 - SUPDUP_OUTPUT
+        (true, true),   //  23 - SNDLOC
+        (true, true),   //  24 - TTYPE
         (false, false), //  25 - EOR
-        (false, false), //  26 - TUID
-        (false, false), //  27 - OUTMRK
-        (false, false), //  28 - TTYLOC
+        (true, true),   //  26 - TUID
+        (true, true),   //  27 - OUTMRK
+        (true, true),   //  28 - TTYLOC
         (false, false), //  29 - OPT3270REGIME
-        (false, false), //  30 - X3PAD
-        (false, false), //  31 - NAWS
-        (false, false), //  32 - TSPEED
-        (false, false), //  33 - LFLOW
+        (true, true),   //  30 - X3PAD
+        (true, true),   //  31 - NAWS
+        (true, true),   //  32 - TSPEED
+        (true, true),   //  33 - LFLOW
         (false, false), //  34 - LINEMODE
-        (false, false), //  35 - XDISPLOC
+        (true, true),   //  35 - XDISPLOC
         (false, false), //  36 - OLD_ENVIRONMENT
-        (false, false), //  37 - AUTHENTICATION
-        (false, false), //  38 - ENCRYPTION
+        (true, true),   //  37 - AUTHENTICATION
+        (true, true),   //  38 - ENCRYPTION
         (false, false), //  39 - NEW_ENVIRONMENT
-        (false, false), //  40 - TN3270E
+        (true, true),   //  40 - TN3270E
         (false, false), //  41 - XAUTH
         (false, false), //  42 - CHARSET
         (false, false), //  43 - TRSP
-        (false, false), //  44 - CPCO
+        (true, true),   //  44 - CPCO
         (false, false), //  45 - TSLE
         (false, false), //  46 - START_TLS
-        (false, false), //  47 - KERMIT
+        (true, true),   //  47 - KERMIT
         (false, false), //  48 - SENDURL
-        (false, false), //  49 - FORWARDX
+        (true, true),   //  49 - FORWARDX
         (false, false), //  50 -
         (false, false), //  51 -
         (false, false), //  52 -
@@ -437,7 +1080,7 @@ pub mod option {
         (false, false), // 198 -
         (false, false), // 199 -
         (false, false), // 200 -
-        (false, false), // 201 - GMCP
+        (true, true),   // 201 - GMCP
         (false, false), // 202 -
         (false, false), // 203 -
         (false, false), // 204 -
@@ -491,6 +1134,6 @@ pub mod option {
         (false, false), // 252 -
         (false, false), // 253 -
         (false, false), // 254 -
-        (false, false), // 255 - EXOPL
+        (true, true),   // 255 - EXOPL
     ];
 }
@@ -14,16 +14,19 @@
 // limitations under the License.
 //
 
-use super::{consts, TelnetArgument, TelnetError, TelnetFrame, TelnetOption};
+use super::{consts, TelnetError, TelnetFrame, TelnetOption};
+use crate::codec::argument::decode_argument;
+use crate::codec::StreamTransformer;
 use bytes::{Buf, BufMut, BytesMut};
 use tracing::{event, Level};
 
 ///
-///
+/// Decodes `TelnetFrame`s from their wire form.
 ///
 pub struct TelnetDecoder {
     buffer: BytesMut,
     state: DecoderState,
+    transformer: Option<Box<dyn StreamTransformer>>,
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -60,8 +63,17 @@ impl TelnetDecoder {
         TelnetDecoder {
             buffer: BytesMut::with_capacity(buffer_size),
             state: DecoderState::NormalData,
+            transformer: None,
         }
     }
+    /// Install or remove the transformer applied to incoming bytes before
+    /// they are decoded. Bytes already decoded are not affected.
+    pub fn set_transformer(
+        &mut self,
+        transformer: Option<Box<dyn StreamTransformer>>,
+    ) {
+        self.transformer = transformer;
+    }
     /// Reset Decoder and discard buffer
     pub fn reset(&mut self) {
         self.state = DecoderState::NormalData;
@@ -73,7 +85,11 @@ impl TelnetDecoder {
         src: &mut BytesMut,
     ) -> Result<Option<TelnetFrame>, TelnetError> {
         while src.remaining() > 0 {
-            let byte = src.get_u8();
+            let mut byte = [src.get_u8()];
+            if let Some(transformer) = &mut self.transformer {
+                transformer.transform(&mut byte);
+            }
+            let byte = byte[0];
             match (self.state, byte) {
                 (DecoderState::NormalData, consts::IAC) => {
                     self.state = DecoderState::InterpretAsCommand;
@@ -118,6 +134,10 @@ impl TelnetDecoder {
                     self.state = DecoderState::NormalData;
                     return Ok(Some(TelnetFrame::GoAhead));
                 }
+                (DecoderState::InterpretAsCommand, consts::EOR) => {
+                    self.state = DecoderState::NormalData;
+                    return Ok(Some(TelnetFrame::EndOfRecord));
+                }
                 (DecoderState::InterpretAsCommand, consts::IAC) => {
                     self.state = DecoderState::NormalData;
                     return Ok(Some(TelnetFrame::Data(consts::IAC)));
@@ -186,9 +206,7 @@ impl TelnetDecoder {
                 (DecoderState::SubnegotiateArgumentIAC(option), consts::SE) => {
                     self.state = DecoderState::NormalData;
                     let buffer = Vec::from(self.buffer.as_ref());
-                    let argument =match option {
-                        _=> TelnetArgument::Unknown(buffer)
-                    };
+                    let argument = decode_argument(option, buffer);
                     self.buffer.clear();
                     return Ok(Some(TelnetFrame::Subnegotiate(
                         option, argument,
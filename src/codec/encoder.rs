@@ -17,24 +17,31 @@
 use super::{consts, TelnetError, TelnetFrame};
 use bytes::{BufMut, BytesMut};
 use crate::codec::argument::{encoded_argument_length, encode_argument};
+use crate::codec::StreamTransformer;
 
 ///
+/// Encodes `TelnetFrame`s into their wire form.
 ///
-///
-pub struct TelnetEncoder;
-
-impl Default for TelnetEncoder {
-    fn default() -> TelnetEncoder {
-        TelnetEncoder {}
-    }
+#[derive(Default)]
+pub struct TelnetEncoder {
+    transformer: Option<Box<dyn StreamTransformer>>,
 }
 
 impl TelnetEncoder {
+    /// Install or remove the transformer applied to outgoing bytes after
+    /// they are encoded. Bytes already encoded are not affected.
+    pub fn set_transformer(
+        &mut self,
+        transformer: Option<Box<dyn StreamTransformer>>,
+    ) {
+        self.transformer = transformer;
+    }
     pub fn encode(
         &mut self,
         item: TelnetFrame,
         dst: &mut BytesMut,
     ) -> Result<(), TelnetError> {
+        let start = dst.len();
         match item {
             TelnetFrame::Data(ch) => {
                 dst.reserve(2);
@@ -88,6 +95,11 @@ impl TelnetEncoder {
                 dst.put_u8(consts::IAC);
                 dst.put_u8(consts::GA);
             }
+            TelnetFrame::EndOfRecord => {
+                dst.reserve(2);
+                dst.put_u8(consts::IAC);
+                dst.put_u8(consts::EOR);
+            }
             TelnetFrame::Do(option) => {
                 dst.reserve(3);
                 dst.put_u8(consts::IAC);
@@ -113,15 +125,27 @@ impl TelnetEncoder {
                 dst.put_u8(option.into());
             }
             TelnetFrame::Subnegotiate(option, argument) => {
-                dst.reserve(5 + encoded_argument_length(&argument));
+                let mut payload =
+                    BytesMut::with_capacity(encoded_argument_length(&argument));
+                encode_argument(&argument, &mut payload)?;
+                dst.reserve(5 + payload.len() * 2);
                 dst.put_u8(consts::IAC);
                 dst.put_u8(consts::SB);
                 dst.put_u8(option.into());
-                encode_argument(&argument, dst);
+                for byte in payload {
+                    // IAC inside a Subnegotiation payload must be doubled
+                    if byte == consts::IAC {
+                        dst.put_u8(consts::IAC);
+                    }
+                    dst.put_u8(byte);
+                }
                 dst.put_u8(consts::IAC);
                 dst.put_u8(consts::SE);
             }
         }
+        if let Some(transformer) = &mut self.transformer {
+            transformer.transform(&mut dst[start..]);
+        }
         Ok(())
     }
 }
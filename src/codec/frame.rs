@@ -39,6 +39,8 @@ pub enum TelnetFrame {
     EraseLine,
     /// End of input for half-duplex connections.
     GoAhead,
+    /// End of a record [RFC885](http://www.iana.org/go/rfc885)
+    EndOfRecord,
     /// Request to start using specified arguments.
     Do(TelnetOption),
     /// Demand to stop using specified arguments.
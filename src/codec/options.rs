@@ -109,11 +109,11 @@ pub enum TelnetOption {
     XAUTH,
     /// Charset [RFC2066](http://www.iana.org/go/rfc2066)
     Charset,
-    /// Telnet Remote Serial Port (RSP)	[Robert_Barnes](https://www.iana.org/assignments/telnet-options/telnet-options.xhtml#Robert_Barnes)
+    /// Telnet Remote Serial Port (RSP) [Robert_Barnes](https://www.iana.org/assignments/telnet-options/telnet-options.xhtml#Robert_Barnes)
     TRSP,
-    /// Com Port Control Option	[RFC2217](http://www.iana.org/go/rfc2217)
+    /// Com Port Control Option [RFC2217](http://www.iana.org/go/rfc2217)
     CPCO,
-    /// Telnet Suppress Local Echo	[Wirt_Atmar](https://www.iana.org/assignments/telnet-options/telnet-options.xhtml#Wirt_Atmar)
+    /// Telnet Suppress Local Echo [Wirt_Atmar](https://www.iana.org/assignments/telnet-options/telnet-options.xhtml#Wirt_Atmar)
     TSLE,
     /// Telnet Start TLS [Michael_Boe](https://www.iana.org/assignments/telnet-options/telnet-options.xhtml#Michael_Boe)
     StartTLS,
@@ -282,3 +282,45 @@ impl From<TelnetOption> for u8 {
         }
     }
 }
+
+///
+/// Extended Telnet Option negotiated through EXOPL [RFC861](http://www.iana.org/go/rfc861)
+///
+/// The option code byte identifies options 256 through 511.
+///
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct ExtendedOption(u8);
+
+impl ExtendedOption {
+    /// Create an `ExtendedOption` from its code byte.
+    pub fn new(code: u8) -> ExtendedOption {
+        ExtendedOption(code)
+    }
+    /// Create an `ExtendedOption` from its option number, if in range.
+    pub fn from_number(number: u16) -> Option<ExtendedOption> {
+        match number {
+            256..=511 => Some(ExtendedOption((number - 256) as u8)),
+            _ => None,
+        }
+    }
+    /// Code byte sent on the wire.
+    pub fn code(self) -> u8 {
+        self.0
+    }
+    /// Full option number, 256 through 511.
+    pub fn number(self) -> u16 {
+        256 + u16::from(self.0)
+    }
+}
+
+impl From<u8> for ExtendedOption {
+    fn from(code: u8) -> ExtendedOption {
+        ExtendedOption(code)
+    }
+}
+
+impl From<ExtendedOption> for u8 {
+    fn from(option: ExtendedOption) -> u8 {
+        option.0
+    }
+}
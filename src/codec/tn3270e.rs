@@ -0,0 +1,298 @@
+//
+// Copyright 2019 Hans W. Uhlig. All Rights Reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+use super::{consts, TelnetError, TelnetFrame};
+use futures::task::{Context, Poll};
+use futures::{ready, Stream};
+use std::pin::Pin;
+use tracing::{event, Level};
+
+///
+/// Type of data carried by a TN3270E record.
+///
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum TN3270EDataType {
+    /// 3270 data stream
+    Data3270,
+    /// SNA Character Stream for printers
+    SCSData,
+    /// Response to a previous record
+    Response,
+    /// BIND image
+    BindImage,
+    /// UNBIND
+    Unbind,
+    /// NVT data
+    NVTData,
+    /// Request
+    Request,
+    /// SSCP-LU data stream
+    SSCPLUData,
+    /// End of print job
+    PrintEOJ,
+    /// Unassigned data type
+    Unknown(u8),
+}
+
+impl From<u8> for TN3270EDataType {
+    fn from(byte: u8) -> Self {
+        use consts::option::tn3270e::data_type::*;
+        match byte {
+            DATA_3270 => TN3270EDataType::Data3270,
+            SCS_DATA => TN3270EDataType::SCSData,
+            RESPONSE => TN3270EDataType::Response,
+            BIND_IMAGE => TN3270EDataType::BindImage,
+            UNBIND => TN3270EDataType::Unbind,
+            NVT_DATA => TN3270EDataType::NVTData,
+            REQUEST => TN3270EDataType::Request,
+            SSCP_LU_DATA => TN3270EDataType::SSCPLUData,
+            PRINT_EOJ => TN3270EDataType::PrintEOJ,
+            byte => TN3270EDataType::Unknown(byte),
+        }
+    }
+}
+
+impl From<TN3270EDataType> for u8 {
+    fn from(data_type: TN3270EDataType) -> Self {
+        use consts::option::tn3270e::data_type::*;
+        match data_type {
+            TN3270EDataType::Data3270 => DATA_3270,
+            TN3270EDataType::SCSData => SCS_DATA,
+            TN3270EDataType::Response => RESPONSE,
+            TN3270EDataType::BindImage => BIND_IMAGE,
+            TN3270EDataType::Unbind => UNBIND,
+            TN3270EDataType::NVTData => NVT_DATA,
+            TN3270EDataType::Request => REQUEST,
+            TN3270EDataType::SSCPLUData => SSCP_LU_DATA,
+            TN3270EDataType::PrintEOJ => PRINT_EOJ,
+            TN3270EDataType::Unknown(byte) => byte,
+        }
+    }
+}
+
+///
+/// 5 byte header in front of every TN3270E record [RFC2355](http://www.iana.org/go/rfc2355)
+///
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct TN3270EHeader {
+    /// Type of the data following the header
+    pub data_type: TN3270EDataType,
+    /// `consts::option::tn3270e::request_flag` value
+    pub request_flag: u8,
+    /// `consts::option::tn3270e::response_flag` value
+    pub response_flag: u8,
+    /// Sequence number, used to match responses to requests
+    pub sequence: u16,
+}
+
+impl TN3270EHeader {
+    /// Encoded Length of `TN3270EHeader`
+    pub const LEN: usize = 5;
+
+    /// Create a header for `data_type` with no response requested.
+    pub fn new(data_type: TN3270EDataType, sequence: u16) -> TN3270EHeader {
+        TN3270EHeader {
+            data_type,
+            request_flag: 0,
+            response_flag: consts::option::tn3270e::response_flag::NO_RESPONSE,
+            sequence,
+        }
+    }
+    /// Encode `TN3270EHeader` to bytes
+    pub fn encode(&self) -> [u8; 5] {
+        let sequence = self.sequence.to_be_bytes();
+        [
+            u8::from(self.data_type),
+            self.request_flag,
+            self.response_flag,
+            sequence[0],
+            sequence[1],
+        ]
+    }
+    /// Decode `TN3270EHeader` from the start of `src`
+    pub fn decode(src: &[u8]) -> Result<Self, TelnetError> {
+        match src {
+            [data_type, request_flag, response_flag, high, low, ..] => {
+                Ok(TN3270EHeader {
+                    data_type: TN3270EDataType::from(*data_type),
+                    request_flag: *request_flag,
+                    response_flag: *response_flag,
+                    sequence: u16::from_be_bytes([*high, *low]),
+                })
+            },
+            _ => Err(TelnetError::DecodingError(format!(
+                "TN3270E record of {} bytes is shorter than its header",
+                src.len()
+            ))),
+        }
+    }
+}
+
+///
+/// Single EOR delimited TN3270E record.
+///
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TN3270ERecord {
+    /// Record Header
+    pub header: TN3270EHeader,
+    /// Record Data, without Telnet escaping
+    pub data: Vec<u8>,
+}
+
+impl TN3270ERecord {
+    /// Create a new `TN3270ERecord`
+    pub fn new(header: TN3270EHeader, data: Vec<u8>) -> TN3270ERecord {
+        TN3270ERecord { header, data }
+    }
+    /// Decode a `TN3270ERecord` from the bytes between two EORs.
+    pub fn decode(src: &[u8]) -> Result<Self, TelnetError> {
+        let header = TN3270EHeader::decode(src)?;
+        let data = src[TN3270EHeader::LEN..].to_vec();
+        Ok(TN3270ERecord { header, data })
+    }
+    /// Frames sending this record, terminated by `EndOfRecord`. IAC bytes
+    /// are doubled by the encoder.
+    pub fn frames(&self) -> Vec<TelnetFrame> {
+        self.header
+            .encode()
+            .iter()
+            .chain(self.data.iter())
+            .map(|&byte| TelnetFrame::Data(byte))
+            .chain(Some(TelnetFrame::EndOfRecord))
+            .collect()
+    }
+}
+
+///
+/// Collects `Data` frames into `TN3270ERecord`s at every `EndOfRecord`.
+///
+#[derive(Clone, Debug, Default)]
+pub struct TN3270EAssembler {
+    buffer: Vec<u8>,
+}
+
+impl TN3270EAssembler {
+    /// Create a new, empty `TN3270EAssembler`
+    pub fn new() -> TN3270EAssembler {
+        TN3270EAssembler::default()
+    }
+    /// Append a received data byte to the current record.
+    pub fn push(&mut self, byte: u8) {
+        self.buffer.push(byte);
+    }
+    /// Complete the current record at `EndOfRecord`.
+    pub fn finish(&mut self) -> Result<TN3270ERecord, TelnetError> {
+        let record = TN3270ERecord::decode(&self.buffer);
+        self.buffer.clear();
+        record
+    }
+}
+
+///
+/// Record-oriented view of a frame stream negotiated into TN3270E mode.
+///
+/// Frames other than `Data` and `EndOfRecord` are logged and skipped, so
+/// negotiation must be handled before the stream is wrapped.
+///
+pub struct TN3270ERecords<S> {
+    frames: S,
+    assembler: TN3270EAssembler,
+}
+
+impl<S> TN3270ERecords<S>
+where
+    S: Stream<Item = TelnetFrame> + Unpin,
+{
+    /// Wrap a stream of decoded frames.
+    pub fn new(frames: S) -> TN3270ERecords<S> {
+        TN3270ERecords {
+            frames,
+            assembler: TN3270EAssembler::new(),
+        }
+    }
+    /// Recover the wrapped stream, dropping any partial record.
+    pub fn into_inner(self) -> S {
+        self.frames
+    }
+}
+
+impl<S> Stream for TN3270ERecords<S>
+where
+    S: Stream<Item = TelnetFrame> + Unpin,
+{
+    type Item = Result<TN3270ERecord, TelnetError>;
+
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        loop {
+            match ready!(Pin::new(&mut self.frames).poll_next(cx)) {
+                Some(TelnetFrame::Data(byte)) => self.assembler.push(byte),
+                Some(TelnetFrame::EndOfRecord) => {
+                    return Poll::Ready(Some(self.assembler.finish()));
+                },
+                Some(frame) => {
+                    event!(
+                        Level::DEBUG,
+                        "Skipping {:?} in TN3270E stream",
+                        frame
+                    )
+                },
+                None => return Poll::Ready(None),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        TN3270EDataType, TN3270EHeader, TN3270ERecord, TN3270ERecords,
+    };
+    use crate::codec::{consts, TelnetDecoder, TelnetEncoder, TelnetFrame};
+    use bytes::BytesMut;
+    use futures::executor::block_on;
+    use futures::stream::{self, StreamExt};
+
+    #[test]
+    fn test_record_round_trip() {
+        let record = TN3270ERecord::new(
+            TN3270EHeader::new(TN3270EDataType::Data3270, 0x0102),
+            vec![0xF5, consts::IAC, 0x40],
+        );
+        let mut encoder = TelnetEncoder::default();
+        let mut buffer = BytesMut::new();
+        for frame in record.frames() {
+            encoder.encode(frame, &mut buffer).unwrap();
+        }
+        assert_eq!(
+            &[0, 0, 0, 1, 2, 0xF5, 0xFF, 0xFF, 0x40, 0xFF, 0xEF][..],
+            &buffer[..]
+        );
+
+        let mut decoder = TelnetDecoder::default();
+        let mut frames = vec![TelnetFrame::NoOperation];
+        while let Some(frame) = decoder.decode(&mut buffer).unwrap() {
+            frames.push(frame);
+        }
+        frames.extend(vec![TelnetFrame::Data(0), TelnetFrame::EndOfRecord]);
+        let records: Vec<_> =
+            block_on(TN3270ERecords::new(stream::iter(frames)).collect());
+        assert_eq!(2, records.len());
+        assert_eq!(&record, records[0].as_ref().unwrap());
+        assert!(records[1].is_err());
+    }
+}
@@ -0,0 +1,28 @@
+//
+// Copyright 2019 Hans W. Uhlig. All Rights Reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+
+///
+/// Transformation applied to the raw byte stream underneath the codec.
+///
+/// A transformer installed on a `TelnetDecoder` sees every byte before it is
+/// interpreted, one installed on a `TelnetEncoder` sees every byte after it
+/// has been encoded. Transformers are stateful and must be applied to each
+/// byte exactly once, in order, which makes them a fit for stream ciphers.
+///
+pub trait StreamTransformer: Send {
+    /// Transform `data` in place.
+    fn transform(&mut self, data: &mut [u8]);
+}
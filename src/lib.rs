@@ -16,4 +16,4 @@
 
 pub mod codec;
 pub mod utility;
-//pub mod terminal;
+pub mod terminal;
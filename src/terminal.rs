@@ -14,23 +14,78 @@
 // limitations under the License.
 //
 
+mod authentication;
+mod binary;
+mod bytemacro;
+mod comport;
+mod delay;
+mod det;
+mod echo;
+mod editor;
+mod encryption;
 mod error;
+mod flow;
+mod forwardx;
+mod guard;
+mod handler;
+mod info;
 mod input;
+mod kermit;
+mod linemode;
+mod logout;
 mod manager;
 mod output;
+mod policy;
+mod prompt;
+mod rcte;
+#[allow(clippy::module_inception)]
 mod terminal;
+mod tn3270e;
+mod x3pad;
 
+pub use self::authentication::{
+    AuthenticationMechanism, AuthenticationRole, AuthenticationStatus,
+    AuthenticationStep, Authenticator, SharedSecret,
+};
+pub use self::binary::{BinaryMode, DataReader};
+pub use self::bytemacro::MacroTable;
+#[cfg(target_os = "linux")]
+pub use self::comport::TtyPort;
+pub use self::comport::{
+    ComPortClient, ComPortServer, SerialPort, SerialSettings,
+};
+pub use self::delay::Delay;
+pub use self::det::DataEntryForm;
+pub use self::echo::Echo;
+pub use self::editor::{Completer, EditorOutput, LineEditor};
+pub use self::encryption::{
+    EncryptionCipher, EncryptionEvent, EncryptionStep, Encryptor,
+    SharedKeyStream,
+};
 pub use self::error::TerminalError;
+pub use self::flow::FlowController;
+#[cfg(unix)]
+pub use self::forwardx::UnixSocketBackend;
+pub use self::forwardx::{
+    ForwardXBackend, ForwardXChannel, ForwardXMux, ForwardXStream,
+};
+pub use self::guard::{
+    GuardVerdict, NegotiationGuard, NegotiationStorm, StormResponse,
+};
+pub use self::handler::{TerminalDriver, TerminalHandler};
+pub use self::info::TerminalInfo;
 pub use self::input::TerminalInput;
-pub use self::output::TerminalOutput;
+pub use self::kermit::{
+    KermitFile, KermitPacket, KermitReceiver, KermitSender, KermitStatus,
+    KermitTransfer, KERMIT_SOH,
+};
+pub use self::linemode::{KludgeLineMode, LineModeOutput};
+pub use self::logout::{Logout, LogoutStatus};
+pub use self::manager::{NegotiationResult, OptionId};
+pub use self::output::{TerminalEndpoint, TerminalOutput};
+pub use self::policy::{NegotiationPolicy, OptionRule};
+pub use self::prompt::PromptMarker;
+pub use self::rcte::{RemoteEcho, RemoteEchoOutput};
 pub use self::terminal::NetworkVirtualTerminal;
-
-pub mod option {
-    pub use super::arguments::msdp;
-    pub use super::arguments::mssp;
-    pub use super::arguments::naocrd;
-    pub use super::arguments::naohts;
-    pub use super::arguments::status;
-    pub use super::arguments::TelnetArgument;
-    pub use super::options::TerminalOption;
-}
+pub use self::tn3270e::{TN3270EClient, TN3270EStatus};
+pub use self::x3pad::X3Profile;
@@ -0,0 +1,519 @@
+//
+// Copyright 2019 Hans W. Uhlig. All Rights Reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+
+use crate::codec::{
+    Authentication, AuthenticationModifiers, AuthenticationPair,
+    AuthenticationType, TelnetArgument, TelnetFrame, TelnetOption,
+};
+use crate::terminal::{TerminalEndpoint, TerminalError};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::io;
+
+///
+/// Result of a single step of an Authentication exchange.
+///
+#[derive(Clone, Debug, PartialEq)]
+pub enum AuthenticationStep {
+    /// Send data to the peer and wait for its answer.
+    Continue(Vec<u8>),
+    /// Exchange succeeded. Optionally send final data to the peer.
+    Accept(Option<Vec<u8>>, String),
+    /// Exchange failed. Optionally send final data to the peer.
+    Reject(Option<Vec<u8>>),
+}
+
+///
+/// Pluggable Authentication Mechanism.
+///
+/// A mechanism implements one authentication type pair for both sides of the
+/// exchange. The client calls `start` after the server sent `SEND`, and then
+/// `client` for every `REPLY`. The server calls `server` for every `IS`.
+///
+pub trait AuthenticationMechanism: Send {
+    /// Authentication type pair this mechanism implements.
+    fn pair(&self) -> AuthenticationPair;
+    /// Client: Produce the data for the initial `IS`.
+    fn start(&mut self) -> Result<Vec<u8>, TerminalError>;
+    /// Server: Handle data received in an `IS`.
+    fn server(
+        &mut self,
+        data: &[u8],
+    ) -> Result<AuthenticationStep, TerminalError>;
+    /// Client: Handle data received in a `REPLY`.
+    fn client(
+        &mut self,
+        data: &[u8],
+    ) -> Result<AuthenticationStep, TerminalError>;
+}
+
+///
+/// Which side of the Authentication exchange we are on.
+///
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum AuthenticationRole {
+    /// We sent `DO AUTHENTICATION` and verify the peer.
+    Server,
+    /// We sent `WILL AUTHENTICATION` and prove our identity.
+    Client,
+}
+
+impl AuthenticationRole {
+    /// Side AUTHENTICATION is enabled on for this role.
+    pub fn endpoint(self) -> TerminalEndpoint {
+        match self {
+            AuthenticationRole::Server => TerminalEndpoint::Remote,
+            AuthenticationRole::Client => TerminalEndpoint::Local,
+        }
+    }
+}
+
+///
+/// Current state of the Authentication exchange.
+///
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum AuthenticationStatus {
+    /// No exchange has been started.
+    Idle,
+    /// Exchange in progress.
+    Pending,
+    /// Exchange succeeded for the contained principal.
+    Authenticated(String),
+    /// Exchange failed or no common mechanism was found.
+    Failed,
+}
+
+///
+/// Session side Authentication state machine [RFC2941](http://www.iana.org/go/rfc2941)
+///
+pub struct Authenticator {
+    role: AuthenticationRole,
+    mechanisms: Vec<Box<dyn AuthenticationMechanism>>,
+    active: Option<usize>,
+    status: AuthenticationStatus,
+    name: Option<String>,
+}
+
+impl Authenticator {
+    /// Create a new `Authenticator`
+    pub fn new(role: AuthenticationRole) -> Authenticator {
+        Authenticator {
+            role,
+            mechanisms: Vec::new(),
+            active: None,
+            status: AuthenticationStatus::Idle,
+            name: None,
+        }
+    }
+    /// Register a mechanism. Earlier mechanisms are preferred.
+    pub fn with_mechanism<M>(mut self, mechanism: M) -> Authenticator
+    where
+        M: AuthenticationMechanism + 'static,
+    {
+        self.mechanisms.push(Box::new(mechanism));
+        self
+    }
+    /// Client: Account name to send with `NAME` before authenticating.
+    pub fn with_name(mut self, name: &str) -> Authenticator {
+        self.name = Some(name.to_string());
+        self
+    }
+    /// Our side of the exchange
+    pub fn role(&self) -> AuthenticationRole {
+        self.role
+    }
+    /// Current state of the exchange
+    pub fn status(&self) -> &AuthenticationStatus {
+        &self.status
+    }
+    /// Principal authenticated by the exchange, if it succeeded.
+    pub fn principal(&self) -> Option<&str> {
+        match &self.status {
+            AuthenticationStatus::Authenticated(principal) => Some(principal),
+            _ => None,
+        }
+    }
+    /// Account name the client asked for with `NAME`, or our own as client.
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+    /// Server: Build the `SEND` frame offering every registered mechanism.
+    pub fn request(&mut self) -> Result<TelnetFrame, TerminalError> {
+        if self.role != AuthenticationRole::Server {
+            return Err(TerminalError::NegotiationError);
+        }
+        self.active = None;
+        self.status = AuthenticationStatus::Pending;
+        let pairs = self.mechanisms.iter().map(|m| m.pair()).collect();
+        Ok(frame(Authentication::Send(pairs)))
+    }
+    /// Handle a received Authentication subnegotiation and produce the
+    /// frames to send in response.
+    pub fn handle(
+        &mut self,
+        argument: Authentication,
+    ) -> Result<Vec<TelnetFrame>, TerminalError> {
+        match (self.role, argument) {
+            (AuthenticationRole::Client, Authentication::Send(pairs)) => {
+                self.handle_send(pairs)
+            },
+            (AuthenticationRole::Client, Authentication::Reply(pair, data)) => {
+                self.handle_step(pair, &data)
+            },
+            (AuthenticationRole::Server, Authentication::Name(name)) => {
+                self.name = Some(name);
+                Ok(Vec::new())
+            },
+            (AuthenticationRole::Server, Authentication::Is(pair, data)) => {
+                if pair.kind == AuthenticationType::Null {
+                    self.status = AuthenticationStatus::Failed;
+                    return Ok(Vec::new());
+                }
+                if self.active.is_none() {
+                    self.active = self.find(pair);
+                }
+                self.handle_step(pair, &data)
+            },
+            (_, _) => Err(TerminalError::NegotiationError),
+        }
+    }
+    fn handle_send(
+        &mut self,
+        pairs: Vec<AuthenticationPair>,
+    ) -> Result<Vec<TelnetFrame>, TerminalError> {
+        let mut frames = Vec::new();
+        let selected = pairs
+            .into_iter()
+            .filter_map(|pair| self.find(pair).map(|index| (pair, index)))
+            .next();
+        match selected {
+            Some((pair, index)) => {
+                if let Some(name) = &self.name {
+                    frames.push(frame(Authentication::Name(name.clone())));
+                }
+                let data = self.mechanisms[index].start()?;
+                frames.push(frame(Authentication::Is(pair, data)));
+                self.active = Some(index);
+                self.status = AuthenticationStatus::Pending;
+            },
+            None => {
+                let null = AuthenticationPair::new(
+                    AuthenticationType::Null,
+                    AuthenticationModifiers::NONE,
+                );
+                frames.push(frame(Authentication::Is(null, Vec::new())));
+                self.active = None;
+                self.status = AuthenticationStatus::Failed;
+            },
+        }
+        Ok(frames)
+    }
+    fn handle_step(
+        &mut self,
+        pair: AuthenticationPair,
+        data: &[u8],
+    ) -> Result<Vec<TelnetFrame>, TerminalError> {
+        let index = match self.active {
+            Some(index) if self.mechanisms[index].pair() == pair => index,
+            _ => {
+                self.status = AuthenticationStatus::Failed;
+                return Err(TerminalError::NegotiationError);
+            },
+        };
+        let step = match self.role {
+            AuthenticationRole::Server => {
+                self.mechanisms[index].server(data)?
+            },
+            AuthenticationRole::Client => {
+                self.mechanisms[index].client(data)?
+            },
+        };
+        let (data, status) = match step {
+            AuthenticationStep::Continue(data) => {
+                (Some(data), AuthenticationStatus::Pending)
+            },
+            AuthenticationStep::Accept(data, principal) => {
+                (data, AuthenticationStatus::Authenticated(principal))
+            },
+            AuthenticationStep::Reject(data) => {
+                (data, AuthenticationStatus::Failed)
+            },
+        };
+        self.status = status;
+        Ok(data
+            .map(|data| match self.role {
+                AuthenticationRole::Server => Authentication::Reply(pair, data),
+                AuthenticationRole::Client => Authentication::Is(pair, data),
+            })
+            .map(frame)
+            .into_iter()
+            .collect())
+    }
+    fn find(&self, pair: AuthenticationPair) -> Option<usize> {
+        self.mechanisms.iter().position(|m| m.pair() == pair)
+    }
+}
+
+fn frame(authentication: Authentication) -> TelnetFrame {
+    TelnetFrame::Subnegotiate(
+        TelnetOption::Authentication,
+        TelnetArgument::Authentication(authentication),
+    )
+}
+
+/// `SharedSecret` sub-commands
+mod shared_secret {
+    pub const AUTH: u8 = 0;
+    pub const CHALLENGE: u8 = 1;
+    pub const RESPONSE: u8 = 2;
+    pub const ACCEPT: u8 = 3;
+    pub const REJECT: u8 = 4;
+    pub const CHALLENGE_LENGTH: usize = 16;
+}
+
+///
+/// Challenge/Response Mechanism based on a secret known to both sides.
+///
+/// The client names its principal, the server answers with a random
+/// challenge and the client proves knowledge of the secret by returning the
+/// HMAC-SHA256 of the challenge and principal keyed with it. Challenges come
+/// from the operating system's random number generator.
+///
+pub struct SharedSecret {
+    pair: AuthenticationPair,
+    principal: String,
+    secret: Vec<u8>,
+    challenge: Option<[u8; shared_secret::CHALLENGE_LENGTH]>,
+}
+
+impl SharedSecret {
+    /// Create a new `SharedSecret` mechanism advertised as `kind`. On the
+    /// client `principal` is the identity claimed, on the server it is the
+    /// only identity accepted. `kind` must be a type agreed privately with
+    /// the peer, never one IANA assigned to another mechanism.
+    pub fn new(
+        kind: AuthenticationType,
+        principal: &str,
+        secret: &[u8],
+    ) -> SharedSecret {
+        SharedSecret {
+            pair: AuthenticationPair::new(kind, AuthenticationModifiers::NONE),
+            principal: principal.to_string(),
+            secret: secret.to_vec(),
+            challenge: None,
+        }
+    }
+    fn digest(&self, challenge: &[u8]) -> Hmac<Sha256> {
+        // HMAC accepts keys of any length
+        let mut mac = Hmac::<Sha256>::new_from_slice(&self.secret)
+            .expect("HMAC key of any length");
+        mac.update(challenge);
+        mac.update(self.principal.as_bytes());
+        mac
+    }
+}
+
+impl AuthenticationMechanism for SharedSecret {
+    fn pair(&self) -> AuthenticationPair {
+        self.pair
+    }
+    fn start(&mut self) -> Result<Vec<u8>, TerminalError> {
+        let mut data = vec![shared_secret::AUTH];
+        data.extend_from_slice(self.principal.as_bytes());
+        Ok(data)
+    }
+    fn server(
+        &mut self,
+        data: &[u8],
+    ) -> Result<AuthenticationStep, TerminalError> {
+        let reject =
+            AuthenticationStep::Reject(Some(vec![shared_secret::REJECT]));
+        match (data.split_first(), self.challenge.take()) {
+            (Some((&shared_secret::AUTH, principal)), None) => {
+                if principal != self.principal.as_bytes() {
+                    return Ok(reject);
+                }
+                let mut challenge = [0; shared_secret::CHALLENGE_LENGTH];
+                getrandom::getrandom(&mut challenge)
+                    .map_err(io::Error::from)?;
+                self.challenge = Some(challenge);
+                let mut reply = vec![shared_secret::CHALLENGE];
+                reply.extend_from_slice(&challenge);
+                Ok(AuthenticationStep::Continue(reply))
+            },
+            (Some((&shared_secret::RESPONSE, digest)), Some(challenge)) => {
+                if self.digest(&challenge).verify_slice(digest).is_ok() {
+                    Ok(AuthenticationStep::Accept(
+                        Some(vec![shared_secret::ACCEPT]),
+                        self.principal.clone(),
+                    ))
+                } else {
+                    Ok(reject)
+                }
+            },
+            (_, _) => Ok(reject),
+        }
+    }
+    fn client(
+        &mut self,
+        data: &[u8],
+    ) -> Result<AuthenticationStep, TerminalError> {
+        match data.split_first() {
+            Some((&shared_secret::CHALLENGE, challenge))
+                if challenge.len() == shared_secret::CHALLENGE_LENGTH =>
+            {
+                let digest = self.digest(challenge).finalize().into_bytes();
+                let mut response = vec![shared_secret::RESPONSE];
+                response.extend_from_slice(&digest);
+                Ok(AuthenticationStep::Continue(response))
+            },
+            Some((&shared_secret::ACCEPT, _)) => {
+                Ok(AuthenticationStep::Accept(None, self.principal.clone()))
+            },
+            _ => Ok(AuthenticationStep::Reject(None)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        shared_secret, AuthenticationMechanism, AuthenticationRole,
+        AuthenticationStatus, AuthenticationStep, Authenticator, SharedSecret,
+    };
+    use crate::codec::{
+        Authentication, AuthenticationType, TelnetArgument, TelnetFrame,
+    };
+
+    /// Unassigned authentication type, so no registered mechanism is
+    /// claimed.
+    const PRIVATE: u8 = 200;
+
+    fn server(secret: &[u8]) -> Authenticator {
+        Authenticator::new(AuthenticationRole::Server).with_mechanism(
+            SharedSecret::new(
+                AuthenticationType::Unknown(PRIVATE),
+                "alice",
+                secret,
+            ),
+        )
+    }
+
+    fn client(principal: &str, secret: &[u8]) -> Authenticator {
+        Authenticator::new(AuthenticationRole::Client)
+            .with_name(principal)
+            .with_mechanism(SharedSecret::new(
+                AuthenticationType::Unknown(PRIVATE),
+                principal,
+                secret,
+            ))
+    }
+
+    fn arguments(frames: Vec<TelnetFrame>) -> Vec<Authentication> {
+        frames
+            .into_iter()
+            .map(|frame| match frame {
+                TelnetFrame::Subnegotiate(
+                    _,
+                    TelnetArgument::Authentication(argument),
+                ) => argument,
+                frame => panic!("Unexpected frame {:?}", frame),
+            })
+            .collect()
+    }
+
+    /// Pass frames back and forth until both sides go quiet.
+    fn handshake(server: &mut Authenticator, client: &mut Authenticator) {
+        let mut to_client = arguments(vec![server.request().unwrap()]);
+        while !to_client.is_empty() {
+            let mut to_server = Vec::new();
+            for argument in to_client {
+                to_server.extend(arguments(client.handle(argument).unwrap()));
+            }
+            to_client = Vec::new();
+            for argument in to_server {
+                to_client.extend(arguments(server.handle(argument).unwrap()));
+            }
+        }
+    }
+
+    #[test]
+    fn test_handshake() {
+        let mut server = server(b"secret");
+        let mut client = client("alice", b"secret");
+        handshake(&mut server, &mut client);
+        assert_eq!(Some("alice"), server.principal());
+        assert_eq!(Some("alice"), server.name());
+        assert_eq!(Some("alice"), client.principal());
+    }
+
+    #[test]
+    fn test_wrong_secret() {
+        let mut server = server(b"secret");
+        let mut client = client("alice", b"guess");
+        handshake(&mut server, &mut client);
+        assert_eq!(&AuthenticationStatus::Failed, server.status());
+        assert_eq!(&AuthenticationStatus::Failed, client.status());
+    }
+
+    #[test]
+    fn test_wrong_principal() {
+        let mut server = server(b"secret");
+        let mut client = client("mallory", b"secret");
+        handshake(&mut server, &mut client);
+        assert_eq!(&AuthenticationStatus::Failed, server.status());
+        assert_eq!(None, server.principal());
+    }
+
+    #[test]
+    fn test_no_common_mechanism() {
+        let mut server = server(b"secret");
+        let mut client = Authenticator::new(AuthenticationRole::Client)
+            .with_mechanism(SharedSecret::new(
+                AuthenticationType::Unknown(PRIVATE + 1),
+                "alice",
+                b"secret",
+            ));
+        handshake(&mut server, &mut client);
+        assert_eq!(&AuthenticationStatus::Failed, server.status());
+        assert_eq!(&AuthenticationStatus::Failed, client.status());
+    }
+
+    #[test]
+    fn test_challenge_not_replayable() {
+        let kind = AuthenticationType::Unknown(PRIVATE);
+        let mut server = SharedSecret::new(kind, "alice", b"secret");
+        let mut client = SharedSecret::new(kind, "alice", b"secret");
+        let start = client.start().unwrap();
+        let first = match server.server(&start).unwrap() {
+            AuthenticationStep::Continue(challenge) => challenge,
+            step => panic!("Unexpected step {:?}", step),
+        };
+        assert_eq!(1 + shared_secret::CHALLENGE_LENGTH, first.len());
+        let response = match client.client(&first).unwrap() {
+            AuthenticationStep::Continue(response) => response,
+            step => panic!("Unexpected step {:?}", step),
+        };
+        assert_eq!(33, response.len());
+
+        let second = server.server(&start).unwrap();
+        assert_ne!(AuthenticationStep::Continue(first), second);
+        assert_eq!(
+            AuthenticationStep::Reject(Some(vec![shared_secret::REJECT])),
+            server.server(&response).unwrap()
+        );
+    }
+}
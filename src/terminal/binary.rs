@@ -0,0 +1,252 @@
+//
+// Copyright 2019 Hans W. Uhlig. All Rights Reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+use crate::terminal::TerminalOutput;
+
+///
+/// Transmit Binary Option policy [RFC856](http://www.iana.org/go/rfc856)
+///
+/// Binary transmission is negotiated separately for each direction. While
+/// it is off, data follows the NVT rules: 7 bit ASCII with end of line sent
+/// as CR LF and a bare carriage return as CR NUL. The negotiated state is
+/// kept by the terminal with every other option.
+///
+#[derive(Clone, Debug)]
+pub struct BinaryMode {
+    accept: bool,
+}
+
+impl BinaryMode {
+    /// Create a new `BinaryMode`. Peer requests for binary transmission
+    /// are agreed to.
+    pub fn new() -> BinaryMode {
+        BinaryMode { accept: true }
+    }
+    /// Agree to peer requests for binary transmission.
+    pub fn with_accept(mut self, accept: bool) -> BinaryMode {
+        self.accept = accept;
+        self
+    }
+    /// True if peer requests for binary transmission are agreed to.
+    pub fn accepts(&self) -> bool {
+        self.accept
+    }
+    /// Apply the NVT rules to outgoing text unless our output is `binary`.
+    /// Line feeds become CR LF, bare carriage returns CR NUL, and
+    /// characters outside 7 bit ASCII are replaced with `?`.
+    pub fn encode_text(text: &str, binary: bool) -> Vec<u8> {
+        if binary {
+            return text.as_bytes().to_vec();
+        }
+        let mut encoded = Vec::with_capacity(text.len() + 2);
+        let mut chars = text.chars().peekable();
+        while let Some(ch) = chars.next() {
+            match ch {
+                '\r' if chars.peek() == Some(&'\n') => {
+                    chars.next();
+                    encoded.extend_from_slice(b"\r\n");
+                },
+                '\r' => encoded.extend_from_slice(b"\r\0"),
+                '\n' => encoded.extend_from_slice(b"\r\n"),
+                ch if ch.is_ascii() => encoded.push(ch as u8),
+                _ => encoded.push(b'?'),
+            }
+        }
+        encoded
+    }
+}
+
+impl Default for BinaryMode {
+    fn default() -> Self {
+        BinaryMode::new()
+    }
+}
+
+///
+/// Received data assembled according to the peer's transmission mode.
+///
+/// In NVT mode bytes are cut to 7 bits, and each line is returned as
+/// `AsciiData` when it ends with CR LF or a bare LF, both read as `\n`, or
+/// with CR NUL, read as `\r`. In binary mode bytes are kept as they are and
+/// returned as `BinaryData` once `capacity` bytes are buffered. A partial
+/// line or chunk is returned with `partial` once no more input is ready.
+/// Data buffered when the mode changes is returned under the mode it was
+/// received in.
+///
+#[derive(Clone, Debug)]
+pub struct DataReader {
+    binary: bool,
+    carriage_return: bool,
+    buffer: Vec<u8>,
+    capacity: usize,
+}
+
+impl DataReader {
+    /// Create a new `DataReader` in NVT mode, returning binary data in
+    /// chunks of at most `capacity` bytes.
+    pub fn new(capacity: usize) -> DataReader {
+        DataReader {
+            binary: false,
+            carriage_return: false,
+            buffer: Vec::with_capacity(capacity),
+            capacity,
+        }
+    }
+    /// True if received data is treated as binary.
+    pub fn binary(&self) -> bool {
+        self.binary
+    }
+    /// Switch mode, returning the data buffered under the previous mode.
+    pub fn set_binary(&mut self, binary: bool) -> Option<TerminalOutput> {
+        if self.binary == binary {
+            return None;
+        }
+        let output = self.flush();
+        self.binary = binary;
+        output
+    }
+    /// Add a received byte, returning a completed line or chunk.
+    pub fn push(&mut self, byte: u8) -> Option<TerminalOutput> {
+        if self.binary {
+            self.buffer.push(byte);
+            if self.buffer.len() >= self.capacity {
+                return self.flush();
+            }
+            return None;
+        }
+        let byte = byte & 0x7F;
+        if std::mem::take(&mut self.carriage_return) {
+            match byte {
+                b'\n' => {
+                    self.buffer.push(b'\n');
+                    return self.flush();
+                },
+                0 => {
+                    self.buffer.push(b'\r');
+                    return self.flush();
+                },
+                _ => self.buffer.push(b'\r'),
+            }
+        }
+        match byte {
+            b'\r' => self.carriage_return = true,
+            b'\n' => {
+                self.buffer.push(b'\n');
+                return self.flush();
+            },
+            0 => {},
+            byte => self.buffer.push(byte),
+        }
+        if self.buffer.len() >= self.capacity {
+            return self.flush();
+        }
+        None
+    }
+    /// Return the data buffered while waiting for more input, holding back
+    /// a trailing CR until the next byte shows how the line ends.
+    pub fn partial(&mut self) -> Option<TerminalOutput> {
+        let carriage_return = std::mem::take(&mut self.carriage_return);
+        let output = self.flush();
+        self.carriage_return = carriage_return;
+        output
+    }
+    /// Return whatever is buffered, such as a prompt without a line end.
+    pub fn flush(&mut self) -> Option<TerminalOutput> {
+        if std::mem::take(&mut self.carriage_return) {
+            self.buffer.push(b'\r');
+        }
+        if self.buffer.is_empty() {
+            return None;
+        }
+        let data = std::mem::take(&mut self.buffer);
+        if self.binary {
+            return Some(TerminalOutput::BinaryData(data));
+        }
+        let text = data.into_iter().map(char::from).collect();
+        Some(TerminalOutput::AsciiData(text))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BinaryMode, DataReader};
+    use crate::terminal::TerminalOutput;
+
+    fn read(reader: &mut DataReader, data: &[u8]) -> Vec<String> {
+        let mut output = Vec::new();
+        for byte in data {
+            match reader.push(*byte) {
+                Some(TerminalOutput::AsciiData(line)) => output.push(line),
+                Some(TerminalOutput::BinaryData(data)) => {
+                    output.push(format!("{:?}", data))
+                },
+                _ => {},
+            }
+        }
+        output
+    }
+
+    #[test]
+    fn test_nvt_lines() {
+        let mut reader = DataReader::new(64);
+        let lines = read(&mut reader, b"one\r\ntwo\nthree\r\0four\xC1");
+        assert_eq!(lines, vec!["one\n", "two\n", "three\r"]);
+        match reader.flush() {
+            Some(TerminalOutput::AsciiData(line)) => {
+                assert_eq!(line, "fourA")
+            },
+            output => panic!("unexpected {:?}", output),
+        }
+    }
+
+    #[test]
+    fn test_switch_mid_stream() {
+        let mut reader = DataReader::new(4);
+        assert!(read(&mut reader, b"ab\r").is_empty());
+        match reader.set_binary(true) {
+            Some(TerminalOutput::AsciiData(line)) => assert_eq!(line, "ab\r"),
+            output => panic!("unexpected {:?}", output),
+        }
+        let chunks = read(&mut reader, b"\r\n\xFF\x00\x01");
+        assert_eq!(chunks, vec!["[13, 10, 255, 0]"]);
+        match reader.set_binary(false) {
+            Some(TerminalOutput::BinaryData(data)) => assert_eq!(data, [1]),
+            output => panic!("unexpected {:?}", output),
+        }
+    }
+
+    #[test]
+    fn test_partial() {
+        let mut reader = DataReader::new(64);
+        assert!(read(&mut reader, b"login: \r").is_empty());
+        match reader.partial() {
+            Some(TerminalOutput::AsciiData(text)) => {
+                assert_eq!(text, "login: ")
+            },
+            output => panic!("unexpected {:?}", output),
+        }
+        assert!(reader.partial().is_none());
+        assert_eq!(read(&mut reader, b"\n"), vec!["\n"]);
+    }
+
+    #[test]
+    fn test_encode_text() {
+        assert!(!BinaryMode::new().with_accept(false).accepts());
+        let text = "a\nb\r\u{e9}";
+        let encoded = BinaryMode::encode_text(text, false);
+        assert_eq!(b"a\r\nb\r\0?".to_vec(), encoded);
+        assert_eq!(text.as_bytes(), &BinaryMode::encode_text(text, true)[..]);
+    }
+}
@@ -0,0 +1,192 @@
+//
+// Copyright 2019 Hans W. Uhlig. All Rights Reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+use crate::codec::{
+    consts, ByteMacro, ByteMacroReason, TelnetArgument, TelnetFrame,
+    TelnetOption,
+};
+use crate::terminal::{TerminalEndpoint, TerminalError};
+use std::collections::{HashMap, HashSet};
+
+///
+/// Byte Macro table for one session [RFC735](http://www.iana.org/go/rfc735)
+///
+/// Macros defined by the peer are expanded in received data. Macros we
+/// defined are only used once the peer accepts them, and literal occurrences
+/// of their bytes are announced with LITERAL.
+///
+#[derive(Clone, Debug)]
+pub struct MacroTable {
+    incoming: HashMap<u8, Vec<u8>>,
+    literal: HashSet<u8>,
+    proposed: HashMap<u8, Vec<u8>>,
+    outgoing: HashMap<u8, Vec<u8>>,
+    definitions: bool,
+    compress: bool,
+}
+
+impl MacroTable {
+    /// Create a new `MacroTable`. Peer definitions are refused, and
+    /// outgoing data is not compressed.
+    pub fn new() -> MacroTable {
+        MacroTable {
+            incoming: HashMap::new(),
+            literal: HashSet::new(),
+            proposed: HashMap::new(),
+            outgoing: HashMap::new(),
+            definitions: false,
+            compress: false,
+        }
+    }
+    /// Accept or refuse macros defined by the peer.
+    pub fn with_definitions(mut self, accept: bool) -> MacroTable {
+        self.definitions = accept;
+        self
+    }
+    /// Replace replacement strings in outgoing data with their accepted
+    /// macro bytes.
+    pub fn with_compression(mut self, compress: bool) -> MacroTable {
+        self.compress = compress;
+        self
+    }
+    /// True if the peer may enable BM on `side`: its own side when we
+    /// accept its definitions, ours when we compress outgoing data.
+    pub fn accepts(&self, side: TerminalEndpoint) -> bool {
+        match side {
+            TerminalEndpoint::Local => self.compress,
+            TerminalEndpoint::Remote => self.definitions,
+        }
+    }
+    /// Replacement for `byte` in received data.
+    pub fn incoming(&self, byte: u8) -> Option<&[u8]> {
+        self.incoming.get(&byte).map(Vec::as_slice)
+    }
+    /// Replacement for `byte` in sent data, once accepted by the peer.
+    pub fn outgoing(&self, byte: u8) -> Option<&[u8]> {
+        self.outgoing.get(&byte).map(Vec::as_slice)
+    }
+    /// Propose that `byte` stands for `replacement` in our data stream.
+    pub fn define(
+        &mut self,
+        byte: u8,
+        replacement: &[u8],
+    ) -> Result<TelnetFrame, TerminalError> {
+        if byte == consts::IAC
+            || replacement.is_empty()
+            || replacement.len() > usize::from(u8::MAX)
+        {
+            return Err(TerminalError::ConfigurationError);
+        }
+        self.proposed.insert(byte, replacement.to_vec());
+        Ok(frame(ByteMacro::Define(byte, replacement.to_vec())))
+    }
+    /// Stop using `byte` as a macro in our data stream.
+    pub fn cancel(&mut self, byte: u8) -> Option<TelnetFrame> {
+        self.proposed.remove(&byte);
+        self.outgoing
+            .remove(&byte)
+            .map(|_| frame(ByteMacro::Cancel(byte)))
+    }
+    /// Apply a received Byte Macro subnegotiation, returning the reply if
+    /// any.
+    pub fn handle(&mut self, argument: ByteMacro) -> Option<TelnetFrame> {
+        match argument {
+            ByteMacro::Define(byte, _) if !self.definitions => {
+                let reason = ByteMacroReason::OtherReason;
+                Some(frame(ByteMacro::Refuse(byte, reason)))
+            },
+            ByteMacro::Define(byte, _) if byte == consts::IAC => {
+                let reason = ByteMacroReason::BadChoice;
+                Some(frame(ByteMacro::Refuse(byte, reason)))
+            },
+            ByteMacro::Define(byte, replacement) => {
+                self.incoming.insert(byte, replacement);
+                Some(frame(ByteMacro::Accept(byte)))
+            },
+            ByteMacro::Accept(byte) => {
+                if let Some(replacement) = self.proposed.remove(&byte) {
+                    self.outgoing.insert(byte, replacement);
+                }
+                None
+            },
+            ByteMacro::Refuse(byte, _) => {
+                self.proposed.remove(&byte);
+                None
+            },
+            ByteMacro::Literal(byte) => {
+                self.literal.insert(byte);
+                None
+            },
+            ByteMacro::Cancel(byte) => {
+                self.incoming.remove(&byte);
+                self.literal.remove(&byte);
+                None
+            },
+        }
+    }
+    /// Expand a received data byte.
+    pub fn expand(&mut self, byte: u8) -> Vec<u8> {
+        if self.literal.remove(&byte) {
+            return vec![byte];
+        }
+        match self.incoming.get(&byte) {
+            Some(replacement) => replacement.clone(),
+            None => vec![byte],
+        }
+    }
+    /// Build the frames sending `data`, substituting accepted macros when
+    /// compressing.
+    pub fn encode(&self, data: &[u8]) -> Vec<TelnetFrame> {
+        let mut frames = Vec::with_capacity(data.len());
+        let mut index = 0;
+        while index < data.len() {
+            if let Some((byte, length)) = self.longest_match(&data[index..]) {
+                frames.push(TelnetFrame::Data(byte));
+                index += length;
+                continue;
+            }
+            let byte = data[index];
+            if self.outgoing.contains_key(&byte) {
+                frames.push(frame(ByteMacro::Literal(byte)));
+            }
+            frames.push(TelnetFrame::Data(byte));
+            index += 1;
+        }
+        frames
+    }
+    fn longest_match(&self, data: &[u8]) -> Option<(u8, usize)> {
+        if !self.compress {
+            return None;
+        }
+        self.outgoing
+            .iter()
+            .filter(|(_, replacement)| data.starts_with(replacement))
+            .map(|(byte, replacement)| (*byte, replacement.len()))
+            .max_by_key(|(_, length)| *length)
+    }
+}
+
+impl Default for MacroTable {
+    fn default() -> Self {
+        MacroTable::new()
+    }
+}
+
+fn frame(argument: ByteMacro) -> TelnetFrame {
+    TelnetFrame::Subnegotiate(
+        TelnetOption::ByteMacro,
+        TelnetArgument::ByteMacro(argument),
+    )
+}
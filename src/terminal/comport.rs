@@ -0,0 +1,490 @@
+//
+// Copyright 2019 Hans W. Uhlig. All Rights Reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+
+#[cfg(target_os = "linux")]
+mod tty;
+
+#[cfg(target_os = "linux")]
+pub use self::tty::TtyPort;
+
+use crate::codec::{
+    ComPortCommand, ComPortControl, Control, LineState, ModemState, Parity,
+    Purge, StopSize, TelnetArgument, TelnetFrame, TelnetOption,
+};
+use crate::terminal::TerminalError;
+use std::collections::VecDeque;
+use std::io;
+use std::time::Duration;
+use tracing::{event, Level};
+
+///
+/// Serial Port controlled by a Com Port Control access server.
+///
+pub trait SerialPort: Send {
+    /// Current baud rate.
+    fn baud_rate(&self) -> io::Result<u32>;
+    /// Change the baud rate.
+    fn set_baud_rate(&mut self, baud: u32) -> io::Result<()>;
+    /// Current number of data bits.
+    fn data_size(&self) -> io::Result<u8>;
+    /// Change the number of data bits.
+    fn set_data_size(&mut self, size: u8) -> io::Result<()>;
+    /// Current parity.
+    fn parity(&self) -> io::Result<Parity>;
+    /// Change the parity.
+    fn set_parity(&mut self, parity: Parity) -> io::Result<()>;
+    /// Current number of stop bits.
+    fn stop_size(&self) -> io::Result<StopSize>;
+    /// Change the number of stop bits.
+    fn set_stop_size(&mut self, stopsize: StopSize) -> io::Result<()>;
+    /// Apply a control setting, or answer a `Request` variant, returning the
+    /// resulting state.
+    fn control(&mut self, control: Control) -> io::Result<Control>;
+    /// Current line state.
+    fn line_state(&self) -> io::Result<LineState>;
+    /// Current modem state.
+    fn modem_state(&self) -> io::Result<ModemState>;
+    /// Discard buffered data.
+    fn purge(&mut self, purge: Purge) -> io::Result<()>;
+    /// Read data received on the serial line. Fails with `WouldBlock` when
+    /// nothing is waiting.
+    fn read_data(&mut self, buf: &mut [u8]) -> io::Result<usize>;
+    /// Transmit data on the serial line, returning how much was accepted.
+    fn write_data(&mut self, data: &[u8]) -> io::Result<usize>;
+}
+
+impl<P: SerialPort + ?Sized> SerialPort for Box<P> {
+    fn baud_rate(&self) -> io::Result<u32> {
+        (**self).baud_rate()
+    }
+    fn set_baud_rate(&mut self, baud: u32) -> io::Result<()> {
+        (**self).set_baud_rate(baud)
+    }
+    fn data_size(&self) -> io::Result<u8> {
+        (**self).data_size()
+    }
+    fn set_data_size(&mut self, size: u8) -> io::Result<()> {
+        (**self).set_data_size(size)
+    }
+    fn parity(&self) -> io::Result<Parity> {
+        (**self).parity()
+    }
+    fn set_parity(&mut self, parity: Parity) -> io::Result<()> {
+        (**self).set_parity(parity)
+    }
+    fn stop_size(&self) -> io::Result<StopSize> {
+        (**self).stop_size()
+    }
+    fn set_stop_size(&mut self, stopsize: StopSize) -> io::Result<()> {
+        (**self).set_stop_size(stopsize)
+    }
+    fn control(&mut self, control: Control) -> io::Result<Control> {
+        (**self).control(control)
+    }
+    fn line_state(&self) -> io::Result<LineState> {
+        (**self).line_state()
+    }
+    fn modem_state(&self) -> io::Result<ModemState> {
+        (**self).modem_state()
+    }
+    fn purge(&mut self, purge: Purge) -> io::Result<()> {
+        (**self).purge(purge)
+    }
+    fn read_data(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        (**self).read_data(buf)
+    }
+    fn write_data(&mut self, data: &[u8]) -> io::Result<usize> {
+        (**self).write_data(data)
+    }
+}
+
+///
+/// Access Server side of Com Port Control. Applies client commands to a
+/// `SerialPort` and reports line and modem state changes.
+///
+pub struct ComPortServer<P: SerialPort> {
+    port: P,
+    signature: String,
+    line_mask: LineState,
+    modem_mask: ModemState,
+    line_state: LineState,
+    modem_state: ModemState,
+    suspended: bool,
+    unsent: VecDeque<u8>,
+    poll_interval: Duration,
+}
+
+impl<P: SerialPort> ComPortServer<P> {
+    /// Create a new `ComPortServer` controlling `port`.
+    pub fn new(port: P, signature: &str) -> ComPortServer<P> {
+        ComPortServer {
+            port,
+            signature: signature.to_string(),
+            // RFC2217 default masks
+            line_mask: LineState(0),
+            modem_mask: ModemState(255),
+            line_state: LineState(0),
+            modem_state: ModemState(0),
+            suspended: false,
+            unsent: VecDeque::new(),
+            poll_interval: Duration::from_millis(20),
+        }
+    }
+    /// Check the port for data and state changes every `interval` while the
+    /// terminal is polled. The default is 20 milliseconds.
+    pub fn with_poll_interval(mut self, interval: Duration) -> Self {
+        self.poll_interval = interval;
+        self
+    }
+    /// How often the port is checked for data and state changes.
+    pub fn poll_interval(&self) -> Duration {
+        self.poll_interval
+    }
+    /// Controlled port
+    pub fn port(&self) -> &P {
+        &self.port
+    }
+    /// Controlled port
+    pub fn port_mut(&mut self) -> &mut P {
+        &mut self.port
+    }
+    /// True while the client asked us to stop sending serial data.
+    pub fn suspended(&self) -> bool {
+        self.suspended
+    }
+    /// Apply a client command and produce the replies to send. Server
+    /// commands sent by the client and commands the port fails to carry out
+    /// are logged and not answered.
+    pub fn handle(&mut self, control: ComPortControl) -> Vec<TelnetFrame> {
+        if control.server {
+            event!(
+                Level::WARN,
+                "Com Port server command from client ignored: {:?}",
+                control.command
+            );
+            return Vec::new();
+        }
+        match self.reply(control.command) {
+            Ok(Some(reply)) => vec![frame(ComPortControl::server(reply))],
+            Ok(None) => Vec::new(),
+            Err(error) => {
+                event!(Level::WARN, "Com Port command failed: {}", error);
+                Vec::new()
+            },
+        }
+    }
+    /// Apply a client command, returning the reply to send if any.
+    fn reply(
+        &mut self,
+        command: ComPortCommand,
+    ) -> io::Result<Option<ComPortCommand>> {
+        let reply = match command {
+            ComPortCommand::Signature(signature) => {
+                if signature.is_empty() {
+                    ComPortCommand::Signature(self.signature.clone())
+                } else {
+                    event!(
+                        Level::INFO,
+                        "Com Port client signature {}",
+                        signature
+                    );
+                    return Ok(None);
+                }
+            },
+            ComPortCommand::SetBaudRate(baud) => {
+                if baud != 0 {
+                    self.apply(|port| port.set_baud_rate(baud));
+                }
+                ComPortCommand::SetBaudRate(self.port.baud_rate()?)
+            },
+            ComPortCommand::SetDataSize(size) => {
+                if size != 0 {
+                    self.apply(|port| port.set_data_size(size));
+                }
+                ComPortCommand::SetDataSize(self.port.data_size()?)
+            },
+            ComPortCommand::SetParity(parity) => {
+                if parity != Parity::Request {
+                    self.apply(|port| port.set_parity(parity));
+                }
+                ComPortCommand::SetParity(self.port.parity()?)
+            },
+            ComPortCommand::SetStopSize(stopsize) => {
+                if stopsize != StopSize::Request {
+                    self.apply(|port| port.set_stop_size(stopsize));
+                }
+                ComPortCommand::SetStopSize(self.port.stop_size()?)
+            },
+            ComPortCommand::SetControl(control) => {
+                ComPortCommand::SetControl(self.port.control(control)?)
+            },
+            ComPortCommand::SetLineStateMask(mask) => {
+                self.line_mask = mask;
+                ComPortCommand::SetLineStateMask(mask)
+            },
+            ComPortCommand::SetModemStateMask(mask) => {
+                self.modem_mask = mask;
+                ComPortCommand::SetModemStateMask(mask)
+            },
+            ComPortCommand::PurgeData(purge) => {
+                self.port.purge(purge)?;
+                ComPortCommand::PurgeData(purge)
+            },
+            ComPortCommand::FlowControlSuspend => {
+                self.suspended = true;
+                return Ok(None);
+            },
+            ComPortCommand::FlowControlResume => {
+                self.suspended = false;
+                return Ok(None);
+            },
+            ComPortCommand::NotifyLineState(_)
+            | ComPortCommand::NotifyModemState(_)
+            | ComPortCommand::Unknown(_, _) => return Ok(None),
+        };
+        Ok(Some(reply))
+    }
+    /// Poll the port and produce notifications for state changes within the
+    /// client's masks.
+    pub fn poll_state(&mut self) -> Result<Vec<TelnetFrame>, TerminalError> {
+        let mut frames = Vec::new();
+        let line_state = self.port.line_state()?;
+        if (line_state.0 ^ self.line_state.0) & self.line_mask.0 != 0 {
+            frames.push(frame(ComPortControl::server(
+                ComPortCommand::NotifyLineState(LineState(
+                    line_state.0 & self.line_mask.0,
+                )),
+            )));
+        }
+        self.line_state = line_state;
+        let modem_state = self.port.modem_state()?;
+        if (modem_state.0 ^ self.modem_state.0) & self.modem_mask.0 != 0 {
+            frames.push(frame(ComPortControl::server(
+                ComPortCommand::NotifyModemState(ModemState(
+                    modem_state.0 & self.modem_mask.0,
+                )),
+            )));
+        }
+        self.modem_state = modem_state;
+        Ok(frames)
+    }
+    /// Read serial data to send to the client. Nothing is read while the
+    /// client has suspended the flow, the data waits in the port instead.
+    pub fn read_serial(&mut self) -> Result<Vec<TelnetFrame>, TerminalError> {
+        self.flush_serial()?;
+        if self.suspended {
+            return Ok(Vec::new());
+        }
+        let mut buf = [0; 512];
+        match self.port.read_data(&mut buf) {
+            Ok(length) => Ok(buf[..length]
+                .iter()
+                .copied()
+                .map(TelnetFrame::Data)
+                .collect()),
+            Err(error) if error.kind() == io::ErrorKind::WouldBlock => {
+                Ok(Vec::new())
+            },
+            Err(error) => Err(error.into()),
+        }
+    }
+    /// Transmit data received from the client. Whatever the port does not
+    /// accept yet is kept and retried by the next `read_serial`.
+    pub fn write_serial(&mut self, data: &[u8]) -> Result<(), TerminalError> {
+        self.unsent.extend(data);
+        self.flush_serial()
+    }
+    fn flush_serial(&mut self) -> Result<(), TerminalError> {
+        while !self.unsent.is_empty() {
+            let (data, _) = self.unsent.as_slices();
+            match self.port.write_data(data) {
+                Ok(0) => break,
+                Ok(length) => {
+                    self.unsent.drain(..length);
+                },
+                Err(error) if error.kind() == io::ErrorKind::WouldBlock => {
+                    break
+                },
+                Err(error) => return Err(error.into()),
+            }
+        }
+        Ok(())
+    }
+    /// Apply a setting. Unsupported settings are logged and the current
+    /// value is reported back instead, as RFC2217 expects.
+    fn apply<F>(&mut self, setter: F)
+    where
+        F: FnOnce(&mut P) -> io::Result<()>,
+    {
+        if let Err(error) = setter(&mut self.port) {
+            event!(Level::WARN, "Com Port setting rejected: {}", error);
+        }
+    }
+}
+
+///
+/// Serial Line Parameters as reported by the access server.
+///
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SerialSettings {
+    pub baud_rate: Option<u32>,
+    pub data_size: Option<u8>,
+    pub parity: Option<Parity>,
+    pub stop_size: Option<StopSize>,
+    pub flow_out: Option<Control>,
+    pub flow_in: Option<Control>,
+    pub dtr: Option<bool>,
+    pub rts: Option<bool>,
+    pub break_state: Option<bool>,
+}
+
+///
+/// Client side of Com Port Control. Builds commands and tracks the settings
+/// confirmed by the access server.
+///
+#[derive(Clone, Debug, Default)]
+pub struct ComPortClient {
+    settings: SerialSettings,
+    signature: Option<String>,
+    line_state: LineState,
+    modem_state: ModemState,
+    suspended: bool,
+}
+
+impl ComPortClient {
+    /// Create a new `ComPortClient`
+    pub fn new() -> ComPortClient {
+        ComPortClient::default()
+    }
+    /// Settings confirmed by the access server
+    pub fn settings(&self) -> &SerialSettings {
+        &self.settings
+    }
+    /// Access server signature, if received.
+    pub fn signature(&self) -> Option<&str> {
+        self.signature.as_deref()
+    }
+    /// Last reported line state
+    pub fn line_state(&self) -> LineState {
+        self.line_state
+    }
+    /// Last reported modem state
+    pub fn modem_state(&self) -> ModemState {
+        self.modem_state
+    }
+    /// True while the access server asked us to stop sending data.
+    pub fn suspended(&self) -> bool {
+        self.suspended
+    }
+    /// Request the access server's signature.
+    pub fn request_signature(&self) -> ComPortControl {
+        ComPortControl::client(ComPortCommand::Signature(String::new()))
+    }
+    /// Set the baud rate, `0` requests the current value.
+    pub fn set_baud_rate(&self, baud: u32) -> ComPortControl {
+        ComPortControl::client(ComPortCommand::SetBaudRate(baud))
+    }
+    /// Set the number of data bits, `0` requests the current value.
+    pub fn set_data_size(&self, size: u8) -> ComPortControl {
+        ComPortControl::client(ComPortCommand::SetDataSize(size))
+    }
+    /// Set the parity.
+    pub fn set_parity(&self, parity: Parity) -> ComPortControl {
+        ComPortControl::client(ComPortCommand::SetParity(parity))
+    }
+    /// Set the number of stop bits.
+    pub fn set_stop_size(&self, stopsize: StopSize) -> ComPortControl {
+        ComPortControl::client(ComPortCommand::SetStopSize(stopsize))
+    }
+    /// Set flow control, break, DTR or RTS.
+    pub fn set_control(&self, control: Control) -> ComPortControl {
+        ComPortControl::client(ComPortCommand::SetControl(control))
+    }
+    /// Choose which line state changes are reported.
+    pub fn set_line_state_mask(&self, mask: LineState) -> ComPortControl {
+        ComPortControl::client(ComPortCommand::SetLineStateMask(mask))
+    }
+    /// Choose which modem state changes are reported.
+    pub fn set_modem_state_mask(&self, mask: ModemState) -> ComPortControl {
+        ComPortControl::client(ComPortCommand::SetModemStateMask(mask))
+    }
+    /// Ask the access server to stop or resume sending serial data.
+    pub fn flow_control(&self, suspend: bool) -> ComPortControl {
+        match suspend {
+            true => ComPortControl::client(ComPortCommand::FlowControlSuspend),
+            false => ComPortControl::client(ComPortCommand::FlowControlResume),
+        }
+    }
+    /// Discard data buffered by the access server.
+    pub fn purge(&self, purge: Purge) -> ComPortControl {
+        ComPortControl::client(ComPortCommand::PurgeData(purge))
+    }
+    /// Handle a reply or notification from the access server.
+    pub fn handle(&mut self, control: ComPortControl) {
+        if !control.server {
+            return;
+        }
+        match control.command {
+            ComPortCommand::Signature(signature) => {
+                self.signature = Some(signature)
+            },
+            ComPortCommand::SetBaudRate(baud) => {
+                self.settings.baud_rate = Some(baud)
+            },
+            ComPortCommand::SetDataSize(size) => {
+                self.settings.data_size = Some(size)
+            },
+            ComPortCommand::SetParity(parity) => {
+                self.settings.parity = Some(parity)
+            },
+            ComPortCommand::SetStopSize(stopsize) => {
+                self.settings.stop_size = Some(stopsize)
+            },
+            ComPortCommand::SetControl(control) => match control {
+                Control::FlowOutNone
+                | Control::FlowOutXonXoff
+                | Control::FlowOutHardware => {
+                    self.settings.flow_out = Some(control)
+                },
+                Control::FlowInNone
+                | Control::FlowInXonXoff
+                | Control::FlowInHardware
+                | Control::FlowDCD
+                | Control::FlowDTR
+                | Control::FlowDSR => self.settings.flow_in = Some(control),
+                Control::BreakOn => self.settings.break_state = Some(true),
+                Control::BreakOff => self.settings.break_state = Some(false),
+                Control::DTROn => self.settings.dtr = Some(true),
+                Control::DTROff => self.settings.dtr = Some(false),
+                Control::RTSOn => self.settings.rts = Some(true),
+                Control::RTSOff => self.settings.rts = Some(false),
+                _ => {},
+            },
+            ComPortCommand::NotifyLineState(state) => self.line_state = state,
+            ComPortCommand::NotifyModemState(state) => self.modem_state = state,
+            ComPortCommand::FlowControlSuspend => self.suspended = true,
+            ComPortCommand::FlowControlResume => self.suspended = false,
+            _ => {},
+        }
+    }
+}
+
+fn frame(control: ComPortControl) -> TelnetFrame {
+    TelnetFrame::Subnegotiate(
+        TelnetOption::CPCO,
+        TelnetArgument::ComPortControl(control),
+    )
+}
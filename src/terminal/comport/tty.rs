@@ -0,0 +1,551 @@
+//
+// Copyright 2019 Hans W. Uhlig. All Rights Reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+
+use super::SerialPort;
+use crate::codec::consts::option::comport::{linestate, modemstate};
+use crate::codec::{Control, LineState, ModemState, Parity, Purge, StopSize};
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Write};
+use std::os::unix::fs::OpenOptionsExt;
+use std::os::unix::io::AsRawFd;
+use std::path::Path;
+
+/// Baud rates supported by termios
+static BAUD_RATES: &[(u32, libc::speed_t)] = &[
+    (50, libc::B50),
+    (75, libc::B75),
+    (110, libc::B110),
+    (134, libc::B134),
+    (150, libc::B150),
+    (200, libc::B200),
+    (300, libc::B300),
+    (600, libc::B600),
+    (1200, libc::B1200),
+    (1800, libc::B1800),
+    (2400, libc::B2400),
+    (4800, libc::B4800),
+    (9600, libc::B9600),
+    (19200, libc::B19200),
+    (38400, libc::B38400),
+    (57600, libc::B57600),
+    (115_200, libc::B115200),
+    (230_400, libc::B230400),
+    (460_800, libc::B460800),
+    (500_000, libc::B500000),
+    (576_000, libc::B576000),
+    (921_600, libc::B921600),
+    (1_000_000, libc::B1000000),
+    (1_152_000, libc::B1152000),
+    (1_500_000, libc::B1500000),
+    (2_000_000, libc::B2000000),
+    (2_500_000, libc::B2500000),
+    (3_000_000, libc::B3000000),
+    (3_500_000, libc::B3500000),
+    (4_000_000, libc::B4000000),
+];
+
+///
+/// `SerialPort` backed by a Linux tty device.
+///
+/// Settings are applied with termios. Modem control lines use the `TIOCM`
+/// ioctls, which pseudo terminals do not implement; on those the modem state
+/// reads as all lines clear and DTR/RTS changes are reported as unchanged.
+/// Pseudo terminals also force 8 data bits without parity, which is what gets
+/// reported back to the client after a change.
+///
+pub struct TtyPort {
+    file: File,
+    dtr: bool,
+    rts: bool,
+    break_state: bool,
+}
+
+impl TtyPort {
+    /// Open the tty at `path` without making it our controlling terminal.
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<TtyPort> {
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .custom_flags(libc::O_NOCTTY | libc::O_NONBLOCK)
+            .open(path)?;
+        TtyPort::from_file(file)
+    }
+    /// Wrap an already opened tty.
+    pub fn from_file(file: File) -> io::Result<TtyPort> {
+        let port = TtyPort {
+            file,
+            dtr: true,
+            rts: true,
+            break_state: false,
+        };
+        // Fail early if this is not a tty
+        port.termios()?;
+        Ok(port)
+    }
+    /// Underlying device
+    pub fn file(&self) -> &File {
+        &self.file
+    }
+    fn termios(&self) -> io::Result<libc::termios> {
+        // SAFETY: termios is plain old data, so all zeroes is a valid value.
+        let mut termios = unsafe { std::mem::zeroed::<libc::termios>() };
+        // SAFETY: the descriptor is owned by `self.file` and stays open for
+        // the call; `termios` is a valid, writable termios.
+        cvt(unsafe { libc::tcgetattr(self.file.as_raw_fd(), &mut termios) })?;
+        Ok(termios)
+    }
+    fn update<F>(&mut self, change: F) -> io::Result<()>
+    where
+        F: FnOnce(&mut libc::termios) -> io::Result<()>,
+    {
+        let mut termios = self.termios()?;
+        change(&mut termios)?;
+        let fd = self.file.as_raw_fd();
+        // SAFETY: `fd` is owned by `self.file` and `termios` was filled in by
+        // tcgetattr, so it points to a valid termios.
+        cvt(unsafe { libc::tcsetattr(fd, libc::TCSANOW, &termios) })?;
+        Ok(())
+    }
+    fn modem_bits(&self) -> io::Result<libc::c_int> {
+        let mut bits: libc::c_int = 0;
+        let fd = self.file.as_raw_fd();
+        // SAFETY: TIOCMGET writes a single c_int through the pointer, which
+        // refers to `bits` on our stack.
+        cvt(unsafe { libc::ioctl(fd, libc::TIOCMGET, &mut bits) })?;
+        Ok(bits)
+    }
+    fn set_modem_bit(&mut self, bit: libc::c_int, on: bool) -> io::Result<()> {
+        let request = if on { libc::TIOCMBIS } else { libc::TIOCMBIC };
+        let fd = self.file.as_raw_fd();
+        // SAFETY: TIOCMBIS and TIOCMBIC only read a single c_int through the
+        // pointer, which refers to `bit`.
+        cvt(unsafe { libc::ioctl(fd, request, &bit) })?;
+        Ok(())
+    }
+    fn queued(&self, request: libc::Ioctl) -> io::Result<libc::c_int> {
+        let mut count: libc::c_int = 0;
+        let fd = self.file.as_raw_fd();
+        // SAFETY: the queue size requests write a single c_int through the
+        // pointer, which refers to `count` on our stack.
+        cvt(unsafe { libc::ioctl(fd, request, &mut count) })?;
+        Ok(count)
+    }
+}
+
+impl SerialPort for TtyPort {
+    fn baud_rate(&self) -> io::Result<u32> {
+        // SAFETY: the termios was filled in by tcgetattr.
+        let speed = unsafe { libc::cfgetospeed(&self.termios()?) };
+        BAUD_RATES
+            .iter()
+            .find(|(_, constant)| *constant == speed)
+            .map(|(baud, _)| *baud)
+            .ok_or_else(|| invalid("Unknown termios speed"))
+    }
+    fn set_baud_rate(&mut self, baud: u32) -> io::Result<()> {
+        let speed = BAUD_RATES
+            .iter()
+            .find(|(rate, _)| *rate == baud)
+            .map(|(_, constant)| *constant)
+            .ok_or_else(|| invalid("Unsupported baud rate"))?;
+        self.update(|termios| {
+            // SAFETY: `termios` is a valid, exclusively borrowed termios.
+            cvt(unsafe { libc::cfsetispeed(termios, speed) })?;
+            // SAFETY: as above.
+            cvt(unsafe { libc::cfsetospeed(termios, speed) })?;
+            Ok(())
+        })
+    }
+    fn data_size(&self) -> io::Result<u8> {
+        match self.termios()?.c_cflag & libc::CSIZE {
+            libc::CS5 => Ok(5),
+            libc::CS6 => Ok(6),
+            libc::CS7 => Ok(7),
+            _ => Ok(8),
+        }
+    }
+    fn set_data_size(&mut self, size: u8) -> io::Result<()> {
+        let bits = match size {
+            5 => libc::CS5,
+            6 => libc::CS6,
+            7 => libc::CS7,
+            8 => libc::CS8,
+            _ => return Err(invalid("Unsupported data size")),
+        };
+        self.update(|termios| {
+            termios.c_cflag = (termios.c_cflag & !libc::CSIZE) | bits;
+            Ok(())
+        })
+    }
+    fn parity(&self) -> io::Result<Parity> {
+        let cflag = self.termios()?.c_cflag;
+        let parity = match (
+            cflag & libc::PARENB != 0,
+            cflag & libc::PARODD != 0,
+            cflag & libc::CMSPAR != 0,
+        ) {
+            (false, _, _) => Parity::None,
+            (true, false, false) => Parity::Even,
+            (true, true, false) => Parity::Odd,
+            (true, true, true) => Parity::Mark,
+            (true, false, true) => Parity::Space,
+        };
+        Ok(parity)
+    }
+    fn set_parity(&mut self, parity: Parity) -> io::Result<()> {
+        let bits = match parity {
+            Parity::None => 0,
+            Parity::Even => libc::PARENB,
+            Parity::Odd => libc::PARENB | libc::PARODD,
+            Parity::Mark => libc::PARENB | libc::PARODD | libc::CMSPAR,
+            Parity::Space => libc::PARENB | libc::CMSPAR,
+            _ => return Err(invalid("Unsupported parity")),
+        };
+        self.update(|termios| {
+            let mask = libc::PARENB | libc::PARODD | libc::CMSPAR;
+            termios.c_cflag = (termios.c_cflag & !mask) | bits;
+            Ok(())
+        })
+    }
+    fn stop_size(&self) -> io::Result<StopSize> {
+        match self.termios()?.c_cflag & libc::CSTOPB {
+            0 => Ok(StopSize::One),
+            _ => Ok(StopSize::Two),
+        }
+    }
+    fn set_stop_size(&mut self, stopsize: StopSize) -> io::Result<()> {
+        let bits = match stopsize {
+            StopSize::One => 0,
+            StopSize::Two => libc::CSTOPB,
+            _ => return Err(invalid("Unsupported stop size")),
+        };
+        self.update(|termios| {
+            termios.c_cflag = (termios.c_cflag & !libc::CSTOPB) | bits;
+            Ok(())
+        })
+    }
+    fn control(&mut self, control: Control) -> io::Result<Control> {
+        let fd = self.file.as_raw_fd();
+        match control {
+            Control::FlowOutNone
+            | Control::FlowOutXonXoff
+            | Control::FlowOutHardware => {
+                self.update(|termios| {
+                    termios.c_iflag &= !libc::IXON;
+                    termios.c_cflag &= !libc::CRTSCTS;
+                    match control {
+                        Control::FlowOutXonXoff => {
+                            termios.c_iflag |= libc::IXON
+                        },
+                        Control::FlowOutHardware => {
+                            termios.c_cflag |= libc::CRTSCTS
+                        },
+                        _ => {},
+                    }
+                    Ok(())
+                })?;
+                self.control(Control::RequestFlowOut)
+            },
+            Control::RequestFlowOut => {
+                let termios = self.termios()?;
+                if termios.c_cflag & libc::CRTSCTS != 0 {
+                    Ok(Control::FlowOutHardware)
+                } else if termios.c_iflag & libc::IXON != 0 {
+                    Ok(Control::FlowOutXonXoff)
+                } else {
+                    Ok(Control::FlowOutNone)
+                }
+            },
+            Control::FlowInNone
+            | Control::FlowInXonXoff
+            | Control::FlowInHardware => {
+                self.update(|termios| {
+                    termios.c_iflag &= !libc::IXOFF;
+                    termios.c_cflag &= !libc::CRTSCTS;
+                    match control {
+                        Control::FlowInXonXoff => {
+                            termios.c_iflag |= libc::IXOFF
+                        },
+                        Control::FlowInHardware => {
+                            termios.c_cflag |= libc::CRTSCTS
+                        },
+                        _ => {},
+                    }
+                    Ok(())
+                })?;
+                self.control(Control::RequestFlowIn)
+            },
+            Control::RequestFlowIn => {
+                let termios = self.termios()?;
+                if termios.c_cflag & libc::CRTSCTS != 0 {
+                    Ok(Control::FlowInHardware)
+                } else if termios.c_iflag & libc::IXOFF != 0 {
+                    Ok(Control::FlowInXonXoff)
+                } else {
+                    Ok(Control::FlowInNone)
+                }
+            },
+            Control::BreakOn | Control::BreakOff => {
+                let request = match control {
+                    Control::BreakOn => libc::TIOCSBRK,
+                    _ => libc::TIOCCBRK,
+                };
+                // SAFETY: TIOCSBRK and TIOCCBRK take no argument and `fd` is
+                // owned by `self.file`.
+                cvt(unsafe { libc::ioctl(fd, request) })?;
+                self.break_state = control == Control::BreakOn;
+                self.control(Control::RequestBreak)
+            },
+            Control::RequestBreak => match self.break_state {
+                true => Ok(Control::BreakOn),
+                false => Ok(Control::BreakOff),
+            },
+            Control::DTROn | Control::DTROff => {
+                let on = control == Control::DTROn;
+                if self.set_modem_bit(libc::TIOCM_DTR, on).is_ok() {
+                    self.dtr = on;
+                }
+                self.control(Control::RequestDTR)
+            },
+            Control::RequestDTR => match self.dtr {
+                true => Ok(Control::DTROn),
+                false => Ok(Control::DTROff),
+            },
+            Control::RTSOn | Control::RTSOff => {
+                let on = control == Control::RTSOn;
+                if self.set_modem_bit(libc::TIOCM_RTS, on).is_ok() {
+                    self.rts = on;
+                }
+                self.control(Control::RequestRTS)
+            },
+            Control::RequestRTS => match self.rts {
+                true => Ok(Control::RTSOn),
+                false => Ok(Control::RTSOff),
+            },
+            _ => Err(invalid("Unsupported control setting")),
+        }
+    }
+    fn line_state(&self) -> io::Result<LineState> {
+        let mut state = 0;
+        if self.queued(libc::FIONREAD)? > 0 {
+            state |= linestate::DATA_READY;
+        }
+        if self.queued(libc::TIOCOUTQ)? == 0 {
+            state |= linestate::HOLDING_REGISTER_EMPTY;
+            state |= linestate::SHIFT_REGISTER_EMPTY;
+        }
+        Ok(LineState(state))
+    }
+    fn modem_state(&self) -> io::Result<ModemState> {
+        let bits = match self.modem_bits() {
+            Ok(bits) => bits,
+            Err(_) => return Ok(ModemState::default()),
+        };
+        let mut state = 0;
+        if bits & libc::TIOCM_CD != 0 {
+            state |= modemstate::CARRIER_DETECT;
+        }
+        if bits & libc::TIOCM_RI != 0 {
+            state |= modemstate::RING_INDICATOR;
+        }
+        if bits & libc::TIOCM_DSR != 0 {
+            state |= modemstate::DATA_SET_READY;
+        }
+        if bits & libc::TIOCM_CTS != 0 {
+            state |= modemstate::CLEAR_TO_SEND;
+        }
+        Ok(ModemState(state))
+    }
+    fn purge(&mut self, purge: Purge) -> io::Result<()> {
+        let queue = match purge {
+            Purge::Receive => libc::TCIFLUSH,
+            Purge::Transmit => libc::TCOFLUSH,
+            Purge::Both => libc::TCIOFLUSH,
+            Purge::Unknown(_) => return Err(invalid("Unknown purge")),
+        };
+        // SAFETY: the descriptor is owned by `self.file` and stays open.
+        cvt(unsafe { libc::tcflush(self.file.as_raw_fd(), queue) })?;
+        Ok(())
+    }
+    fn read_data(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.file.read(buf)
+    }
+    fn write_data(&mut self, data: &[u8]) -> io::Result<usize> {
+        self.file.write(data)
+    }
+}
+
+fn cvt(result: libc::c_int) -> io::Result<libc::c_int> {
+    match result {
+        -1 => Err(io::Error::last_os_error()),
+        result => Ok(result),
+    }
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{cvt, TtyPort};
+    use crate::codec::{
+        consts, ComPortCommand, ComPortControl, Parity, Purge, StopSize,
+        TelnetFrame, TelnetOption,
+    };
+    use crate::terminal::{
+        ComPortServer, NetworkVirtualTerminal, SerialPort, TerminalEndpoint,
+    };
+    use futures::executor::block_on;
+    use futures::io::Cursor;
+    use futures::StreamExt;
+    use std::ffi::CStr;
+    use std::fs::File;
+    use std::io::{Read, Write};
+    use std::os::unix::io::{AsRawFd, FromRawFd};
+    use std::thread::sleep;
+    use std::time::Duration;
+
+    /// Open a pseudo terminal, returning its master and a port on the slave.
+    fn pty_pair() -> (File, TtyPort) {
+        let flags = libc::O_RDWR | libc::O_NOCTTY;
+        // SAFETY: posix_openpt has no memory safety requirements.
+        let master = cvt(unsafe { libc::posix_openpt(flags) }).unwrap();
+        // SAFETY: the descriptor was just opened and nothing else owns it.
+        let master = unsafe { File::from_raw_fd(master) };
+        let fd = master.as_raw_fd();
+        // SAFETY: `fd` is an open pty master owned by `master`.
+        cvt(unsafe { libc::grantpt(fd) }).unwrap();
+        // SAFETY: as above.
+        cvt(unsafe { libc::unlockpt(fd) }).unwrap();
+        let mut name = [0 as libc::c_char; 128];
+        // SAFETY: ptsname_r writes at most `name.len()` bytes into `name`.
+        let result =
+            unsafe { libc::ptsname_r(fd, name.as_mut_ptr(), name.len()) };
+        assert_eq!(0, result);
+        // SAFETY: ptsname_r succeeded, so `name` holds a NUL-terminated path.
+        let name = unsafe { CStr::from_ptr(name.as_ptr()) };
+        let port = TtyPort::open(name.to_str().unwrap()).unwrap();
+        (master, port)
+    }
+
+    /// Read serial data, giving the pty a moment to pass it along.
+    fn read_serial(server: &mut ComPortServer<TtyPort>) -> Vec<u8> {
+        for _ in 0..100 {
+            let frames = server.read_serial().unwrap();
+            if !frames.is_empty() {
+                return frames
+                    .into_iter()
+                    .map(|frame| match frame {
+                        TelnetFrame::Data(byte) => byte,
+                        frame => panic!("Unexpected frame {:?}", frame),
+                    })
+                    .collect();
+            }
+            sleep(Duration::from_millis(10));
+        }
+        Vec::new()
+    }
+
+    #[test]
+    fn test_settings() {
+        let (_master, mut port) = pty_pair();
+        port.set_baud_rate(9600).unwrap();
+        assert_eq!(9600, port.baud_rate().unwrap());
+        assert!(port.set_baud_rate(12345).is_err());
+        port.set_stop_size(StopSize::Two).unwrap();
+        assert_eq!(StopSize::Two, port.stop_size().unwrap());
+        port.set_parity(Parity::None).unwrap();
+        assert_eq!(Parity::None, port.parity().unwrap());
+        assert_eq!(8, port.data_size().unwrap());
+    }
+
+    #[test]
+    fn test_relay() {
+        let (mut master, port) = pty_pair();
+        let mut server = ComPortServer::new(port, "test");
+        let replies = server
+            .handle(ComPortControl::client(ComPortCommand::SetBaudRate(0)));
+        assert_eq!(1, replies.len());
+
+        server.write_serial(b"hello").unwrap();
+        let mut buf = [0; 5];
+        master.read_exact(&mut buf).unwrap();
+        assert_eq!(b"hello", &buf);
+
+        server
+            .handle(ComPortControl::client(ComPortCommand::FlowControlSuspend));
+        master.write_all(b"ping\n").unwrap();
+        sleep(Duration::from_millis(50));
+        assert!(server.read_serial().unwrap().is_empty());
+
+        server
+            .handle(ComPortControl::client(ComPortCommand::FlowControlResume));
+        assert_eq!(b"ping\n".to_vec(), read_serial(&mut server));
+    }
+
+    #[test]
+    fn test_rejected_commands() {
+        let (_master, port) = pty_pair();
+        let mut server = ComPortServer::new(port, "test");
+        server.port_mut().set_baud_rate(19200).unwrap();
+        let failing = ComPortCommand::PurgeData(Purge::Unknown(9));
+        assert!(server.handle(ComPortControl::client(failing)).is_empty());
+        let misdirected = ComPortCommand::SetBaudRate(9600);
+        assert!(server
+            .handle(ComPortControl::server(misdirected))
+            .is_empty());
+        assert_eq!(19200, server.port().baud_rate().unwrap());
+    }
+
+    #[test]
+    fn test_server_accepts_option() {
+        let (_master, port) = pty_pair();
+        let port: Box<dyn SerialPort> = Box::new(port);
+        let input = vec![consts::IAC, consts::WILL, consts::option::CPCO];
+        let mut terminal = NetworkVirtualTerminal::new(Cursor::new(input))
+            .with_com_port_server(ComPortServer::new(port, "test"));
+        block_on(terminal.by_ref().collect::<Vec<_>>());
+        let remote = TerminalEndpoint::Remote;
+        assert!(terminal.option_enabled(remote, TelnetOption::CPCO));
+    }
+
+    #[test]
+    fn test_terminal_relays_serial_data() {
+        let (mut master, port) = pty_pair();
+        master.write_all(b"ping\n").unwrap();
+        sleep(Duration::from_millis(50));
+        let port: Box<dyn SerialPort> = Box::new(port);
+        let mut input = vec![consts::IAC, consts::WILL, consts::option::CPCO];
+        input.extend(b"hi");
+        let mut terminal = NetworkVirtualTerminal::new(Cursor::new(input))
+            .with_com_port_server(ComPortServer::new(port, "test"));
+        let length = terminal.get_ref().get_ref().len();
+        block_on(terminal.by_ref().collect::<Vec<_>>());
+        let written = terminal.into_inner().into_inner().split_off(length);
+        let mut expected = vec![consts::IAC, consts::DO, consts::option::CPCO];
+        expected.extend(b"ping\n");
+        assert_eq!(expected, written);
+        // The pty echoes what the master wrote ahead of the relayed data
+        let mut received = Vec::new();
+        while !received.ends_with(b"hi") {
+            let mut buf = [0; 64];
+            let length = master.read(&mut buf).unwrap();
+            assert!(length > 0);
+            received.extend_from_slice(&buf[..length]);
+        }
+    }
+}
@@ -0,0 +1,86 @@
+//
+// Copyright 2019 Hans W. Uhlig. All Rights Reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+use futures::task::{Context, Poll};
+use futures::Future;
+use std::pin::Pin;
+use std::time::{Duration, Instant};
+
+///
+/// Future completing once `duration` has passed.
+///
+/// The crate does not depend on a runtime, so timers come from
+/// `futures-timer`, which works under any executor.
+///
+pub struct Delay {
+    deadline: Instant,
+    timer: futures_timer::Delay,
+}
+
+impl Delay {
+    /// Create a new `Delay` completing after `duration`.
+    pub fn new(duration: Duration) -> Delay {
+        Delay {
+            deadline: Instant::now() + duration,
+            timer: futures_timer::Delay::new(duration),
+        }
+    }
+    /// Instant the `Delay` completes.
+    pub fn deadline(&self) -> Instant {
+        self.deadline
+    }
+}
+
+impl Future for Delay {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        Pin::new(&mut self.timer).poll(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Delay;
+    use futures::executor::block_on;
+    use futures::future::{self, Either};
+    use futures::task::{noop_waker, Context, Poll};
+    use futures::Future;
+    use std::pin::Pin;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn test_completes_after_duration() {
+        let start = Instant::now();
+        let delay = Delay::new(Duration::from_millis(20));
+        assert!(delay.deadline() >= start + Duration::from_millis(20));
+        block_on(delay);
+        assert!(start.elapsed() >= Duration::from_millis(20));
+    }
+
+    #[test]
+    fn test_pending_until_deadline() {
+        let waker = noop_waker();
+        let mut cx = Context::from_waker(&waker);
+        let mut delay = Delay::new(Duration::from_secs(60));
+        assert_eq!(Poll::Pending, Pin::new(&mut delay).poll(&mut cx));
+
+        let short = Delay::new(Duration::from_millis(10));
+        match block_on(future::select(delay, short)) {
+            Either::Right(_) => {},
+            Either::Left(_) => panic!("long delay completed first"),
+        }
+    }
+}
@@ -0,0 +1,168 @@
+//
+// Copyright 2019 Hans W. Uhlig. All Rights Reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+use crate::codec::consts;
+use crate::codec::{DataEntry, TelnetArgument, TelnetFrame, TelnetOption};
+
+///
+/// Builder for Data Entry Terminal screens [RFC1043](http://www.iana.org/go/rfc1043)
+///
+/// Commands and text are queued in the order they are added, and turned into
+/// frames with `frames`. Text goes out as plain data between the DET
+/// subnegotiations, with IAC and CR escaped by the encoder as usual.
+///
+#[derive(Clone, Debug, Default)]
+pub struct DataEntryForm {
+    frames: Vec<TelnetFrame>,
+}
+
+impl DataEntryForm {
+    /// Create an empty `DataEntryForm`.
+    pub fn new() -> DataEntryForm {
+        DataEntryForm { frames: Vec::new() }
+    }
+    /// Queue an arbitrary DET command.
+    pub fn command(mut self, entry: DataEntry) -> DataEntryForm {
+        self.frames.push(TelnetFrame::Subnegotiate(
+            TelnetOption::DET,
+            TelnetArgument::DataEntry(entry),
+        ));
+        self
+    }
+    /// Queue text to display at the cursor.
+    pub fn text(mut self, text: &str) -> DataEntryForm {
+        self.frames.extend(text.bytes().map(TelnetFrame::Data));
+        self
+    }
+    /// Queue a formatted field. Data longer than 255 bytes is split into
+    /// consecutive fields with the same format.
+    pub fn field(mut self, format: u16, data: &str) -> DataEntryForm {
+        for chunk in data.as_bytes().chunks(usize::from(u8::MAX)) {
+            self = self.command(DataEntry::FormatData {
+                format,
+                data: chunk.to_vec(),
+            });
+        }
+        self
+    }
+    /// Queue a cursor move to column `x` and line `y`.
+    pub fn move_cursor(self, x: u8, y: u8) -> DataEntryForm {
+        self.command(DataEntry::MoveCursor { x, y })
+    }
+    /// Queue a cursor move to the start of line `y`.
+    pub fn skip_to_line(self, y: u8) -> DataEntryForm {
+        self.command(DataEntry::SkipToLine(y))
+    }
+    /// Queue a cursor move to column `x` of the current line.
+    pub fn skip_to_char(self, x: u8) -> DataEntryForm {
+        self.command(DataEntry::SkipToChar(x))
+    }
+    /// Queue a cursor move to the top left corner.
+    pub fn home(self) -> DataEntryForm {
+        self.command(DataEntry::Home)
+    }
+    /// Queue `count` copies of `character`.
+    pub fn repeat(self, count: u8, character: u8) -> DataEntryForm {
+        self.command(DataEntry::Repeat { count, character })
+    }
+    /// Queue erasure of the whole screen.
+    pub fn erase_screen(self) -> DataEntryForm {
+        self.command(DataEntry::EraseScreen)
+    }
+    /// Queue erasure of the line at the cursor.
+    pub fn erase_line(self) -> DataEntryForm {
+        self.command(DataEntry::EraseLine)
+    }
+    /// Queue erasure of the field at the cursor.
+    pub fn erase_field(self) -> DataEntryForm {
+        self.command(DataEntry::EraseField)
+    }
+    /// Queue erasure of every unprotected field.
+    pub fn erase_unprotected(self) -> DataEntryForm {
+        self.command(DataEntry::EraseUnprotected)
+    }
+    /// Queue a request for the cursor position.
+    pub fn read_cursor(self) -> DataEntryForm {
+        self.command(DataEntry::ReadCursor)
+    }
+    /// Queue a request for the whole screen.
+    pub fn transmit_screen(self) -> DataEntryForm {
+        self.command(DataEntry::TransmitScreen)
+    }
+    /// Queue a request for the unprotected fields.
+    pub fn transmit_unprotected(self) -> DataEntryForm {
+        self.command(DataEntry::TransmitUnprotected)
+    }
+    /// Queue a request for the modified fields.
+    pub fn transmit_modified(self) -> DataEntryForm {
+        self.command(DataEntry::TransmitModified)
+    }
+    /// Queue a request for the field at the cursor.
+    pub fn transmit_field(self) -> DataEntryForm {
+        self.command(DataEntry::TransmitField)
+    }
+    /// Number of queued frames.
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+    /// True if nothing is queued.
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+    /// Frames to send, in order.
+    pub fn frames(self) -> Vec<TelnetFrame> {
+        self.frames
+    }
+    /// Ask the terminal to suppress field protection, so protected fields
+    /// can be written to by the user.
+    pub fn suppress_protection(self) -> DataEntryForm {
+        self.command(DataEntry::SuppressProtection(consts::DO))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DataEntryForm;
+    use crate::codec::{DataEntry, TelnetArgument, TelnetFrame, TelnetOption};
+
+    #[test]
+    fn test_form_frames() {
+        let frames = DataEntryForm::new()
+            .erase_screen()
+            .move_cursor(2, 3)
+            .text("Hi")
+            .field(1, &"x".repeat(300))
+            .transmit_unprotected()
+            .frames();
+        let entry = |entry| {
+            TelnetFrame::Subnegotiate(
+                TelnetOption::DET,
+                TelnetArgument::DataEntry(entry),
+            )
+        };
+        assert_eq!(frames.len(), 7);
+        assert_eq!(frames[0], entry(DataEntry::EraseScreen));
+        assert_eq!(frames[1], entry(DataEntry::MoveCursor { x: 2, y: 3 }));
+        assert_eq!(frames[2], TelnetFrame::Data(b'H'));
+        assert_eq!(
+            frames[5],
+            entry(DataEntry::FormatData {
+                format: 1,
+                data: b"x".repeat(45),
+            })
+        );
+        assert_eq!(frames[6], entry(DataEntry::TransmitUnprotected));
+    }
+}
@@ -0,0 +1,147 @@
+//
+// Copyright 2019 Hans W. Uhlig. All Rights Reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+const BACKSPACE: u8 = 0x08;
+const DELETE: u8 = 0x7F;
+
+///
+/// Echo Option behaviour [RFC857](http://www.iana.org/go/rfc857)
+///
+/// The side that sent `WILL ECHO` echoes the characters it receives, and
+/// the other side stops echoing locally. Servers take over echo for
+/// character mode, and keep it while echoing nothing to hide passwords.
+/// The negotiated state is kept by the terminal with every other option.
+///
+#[derive(Clone, Debug)]
+pub struct Echo {
+    accept: bool,
+    hidden: Option<bool>,
+    column: usize,
+    carriage_return: bool,
+}
+
+impl Echo {
+    /// Create a new `Echo`. Peer requests to enable echo in either
+    /// direction are agreed to.
+    pub fn new() -> Echo {
+        Echo {
+            accept: true,
+            hidden: None,
+            column: 0,
+            carriage_return: false,
+        }
+    }
+    /// Agree to peer requests to enable echo in either direction.
+    pub fn with_accept(mut self, accept: bool) -> Echo {
+        self.accept = accept;
+        self
+    }
+    /// True if peer requests to enable echo are agreed to.
+    pub fn accepts(&self) -> bool {
+        self.accept
+    }
+    /// True while received characters are not echoed, such as during a
+    /// password prompt.
+    pub fn hidden(&self) -> bool {
+        self.hidden.is_some()
+    }
+    /// Server: Stop echoing received characters while keeping the echo
+    /// option, so the client does not echo them either. `echoing` tells
+    /// whether we held or asked for the option before, the caller asks for
+    /// it if not.
+    pub fn hide(&mut self, echoing: bool) {
+        if self.hidden.is_none() {
+            self.hidden = Some(echoing);
+        }
+    }
+    /// Server: Echo received characters again. Returns true if the echo
+    /// option was only taken for `hide` and should be given up.
+    pub fn reveal(&mut self) -> bool {
+        self.hidden.take() == Some(false)
+    }
+    /// Characters to send back for a received character. Nothing is echoed
+    /// unless we are `echoing`, holding the echo option, and input is not
+    /// hidden. Backspace
+    /// and delete erase the previous echoed character on the current line,
+    /// and a line end moves to the start of the next line.
+    pub fn input(&mut self, byte: u8, echoing: bool) -> Vec<u8> {
+        let after_cr = std::mem::take(&mut self.carriage_return);
+        if !echoing || self.hidden() {
+            self.carriage_return = byte == b'\r';
+            return Vec::new();
+        }
+        match byte {
+            b'\r' => {
+                self.carriage_return = true;
+                self.column = 0;
+                b"\r\n".to_vec()
+            },
+            b'\n' | 0 if after_cr => Vec::new(),
+            b'\n' => {
+                self.column = 0;
+                b"\r\n".to_vec()
+            },
+            BACKSPACE | DELETE if self.column > 0 => {
+                self.column -= 1;
+                vec![BACKSPACE, b' ', BACKSPACE]
+            },
+            BACKSPACE | DELETE => Vec::new(),
+            byte if byte < 0x20 => Vec::new(),
+            byte => {
+                self.column += 1;
+                vec![byte]
+            },
+        }
+    }
+}
+
+impl Default for Echo {
+    fn default() -> Self {
+        Echo::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Echo;
+
+    fn echoed(echo: &mut Echo, data: &[u8], echoing: bool) -> Vec<u8> {
+        data.iter()
+            .flat_map(|byte| echo.input(*byte, echoing))
+            .collect()
+    }
+
+    #[test]
+    fn test_server_echo() {
+        let mut echo = Echo::new();
+        assert!(echoed(&mut echo, b"ab", false).is_empty());
+        let typed = echoed(&mut echo, b"ab\x7F\x7F\x7Fc\r\n", true);
+        assert_eq!(b"ab\x08 \x08\x08 \x08c\r\n".to_vec(), typed);
+    }
+
+    #[test]
+    fn test_hidden_input() {
+        let mut echo = Echo::new();
+        echo.hide(false);
+        assert!(echo.hidden());
+        assert!(echoed(&mut echo, b"secret\r\n", true).is_empty());
+        assert!(echo.reveal());
+        assert!(!echo.hidden());
+
+        echo.hide(true);
+        assert!(!echo.reveal());
+        assert_eq!(b"ok".to_vec(), echoed(&mut echo, b"ok", true));
+    }
+}
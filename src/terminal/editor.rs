@@ -0,0 +1,459 @@
+//
+// Copyright 2019 Hans W. Uhlig. All Rights Reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+const CTRL_A: u8 = 0x01;
+const CTRL_D: u8 = 0x04;
+const CTRL_E: u8 = 0x05;
+const BACKSPACE: u8 = 0x08;
+const TAB: u8 = 0x09;
+const CTRL_K: u8 = 0x0B;
+const CTRL_L: u8 = 0x0C;
+const CTRL_U: u8 = 0x15;
+const CTRL_W: u8 = 0x17;
+const ESCAPE: u8 = 0x1B;
+const DELETE: u8 = 0x7F;
+
+///
+/// Tab completion callback. Given the line up to the cursor, returns the
+/// candidates to replace it with.
+///
+pub type Completer = Box<dyn FnMut(&str) -> Vec<String> + Send>;
+
+///
+/// Result of one keystroke in the `LineEditor`.
+///
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct EditorOutput {
+    /// Characters to send back to redraw the line.
+    pub echo: Vec<u8>,
+    /// Line completed by this keystroke.
+    pub line: Option<String>,
+}
+
+///
+/// Position in a VT100 escape sequence.
+///
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Escape {
+    /// Not in a sequence
+    None,
+    /// After ESC
+    Started,
+    /// After ESC [ and any numeric parameter
+    Control(u8),
+    /// After ESC O
+    Single,
+}
+
+///
+/// Server side line discipline for character at a time sessions.
+///
+/// Keystrokes are collected into a line which is edited in place and
+/// redrawn on the client with plain backspaces, so any terminal will do.
+/// VT100 arrow keys move the cursor and recall history, Ctrl-A and Ctrl-E
+/// go to the start and end, Ctrl-U, Ctrl-K and Ctrl-W delete before the
+/// cursor, after it and the previous word, and Tab asks the `Completer`.
+///
+pub struct LineEditor {
+    line: Vec<u8>,
+    cursor: usize,
+    prompt: Vec<u8>,
+    escape: Escape,
+    carriage_return: bool,
+    history: Vec<String>,
+    history_limit: usize,
+    recalled: Option<usize>,
+    draft: Vec<u8>,
+    completer: Option<Completer>,
+    hidden: bool,
+}
+
+impl LineEditor {
+    /// Create a new `LineEditor` keeping the last 100 lines of history.
+    pub fn new() -> LineEditor {
+        LineEditor {
+            line: Vec::new(),
+            cursor: 0,
+            prompt: Vec::new(),
+            escape: Escape::None,
+            carriage_return: false,
+            history: Vec::new(),
+            history_limit: 100,
+            recalled: None,
+            draft: Vec::new(),
+            completer: None,
+            hidden: false,
+        }
+    }
+    /// Keep the last `limit` lines of history.
+    pub fn with_history(mut self, limit: usize) -> LineEditor {
+        self.history_limit = limit;
+        self
+    }
+    /// Complete the line with `completer` when Tab is pressed.
+    pub fn with_completer(mut self, completer: Completer) -> LineEditor {
+        self.completer = Some(completer);
+        self
+    }
+    /// Prompt redrawn in front of the line after listing completions or
+    /// Ctrl-L. The application still sends the prompt itself.
+    pub fn set_prompt(&mut self, prompt: &str) {
+        self.prompt = prompt.as_bytes().to_vec();
+    }
+    /// Line being edited.
+    pub fn line(&self) -> String {
+        self.line.iter().copied().map(char::from).collect()
+    }
+    /// Cursor position in the line.
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+    /// Completed lines, oldest first.
+    pub fn history(&self) -> &[String] {
+        &self.history
+    }
+    /// Edit without echo, history or completion, such as while reading a
+    /// password.
+    pub fn set_hidden(&mut self, hidden: bool) {
+        self.hidden = hidden;
+    }
+    /// True while editing without echo.
+    pub fn hidden(&self) -> bool {
+        self.hidden
+    }
+    /// Handle a received keystroke.
+    pub fn input(&mut self, byte: u8) -> EditorOutput {
+        let mut output = self.edit(byte);
+        if self.hidden {
+            output.echo.clear();
+        }
+        output
+    }
+    /// Erase the character before the cursor, for Backspace and `IAC EC`.
+    pub fn erase_character(&mut self) -> Vec<u8> {
+        let echo = self.erase_previous();
+        self.visible(echo)
+    }
+    /// Erase the whole line, for `IAC EL`.
+    pub fn erase_line(&mut self) -> Vec<u8> {
+        let echo = self.replace(0, self.line.len(), b"");
+        self.visible(echo)
+    }
+    /// Take the line being edited without completing it or adding it to
+    /// the history.
+    pub fn take_line(&mut self) -> String {
+        let line = self.line();
+        self.line.clear();
+        self.cursor = 0;
+        line
+    }
+    /// `echo`, unless editing hidden.
+    fn visible(&self, echo: Vec<u8>) -> Vec<u8> {
+        match self.hidden {
+            true => Vec::new(),
+            false => echo,
+        }
+    }
+    /// Apply a keystroke, returning the echo as if visible.
+    fn edit(&mut self, byte: u8) -> EditorOutput {
+        let after_cr = std::mem::take(&mut self.carriage_return);
+        let byte = byte & 0x7F;
+        let mut output = EditorOutput::default();
+        match self.escape {
+            Escape::None => {},
+            Escape::Started => {
+                self.escape = match byte {
+                    b'[' => Escape::Control(0),
+                    b'O' => Escape::Single,
+                    _ => Escape::None,
+                };
+                return output;
+            },
+            Escape::Control(parameter) => {
+                self.escape = Escape::None;
+                output.echo = match byte {
+                    b'0'..=b'9' => {
+                        let digit = byte - b'0';
+                        let parameter =
+                            parameter.saturating_mul(10).saturating_add(digit);
+                        self.escape = Escape::Control(parameter);
+                        Vec::new()
+                    },
+                    b'~' => match parameter {
+                        1 | 7 => self.move_to(0),
+                        3 => self.delete(),
+                        4 | 8 => self.move_to(self.line.len()),
+                        _ => Vec::new(),
+                    },
+                    byte => self.cursor_key(byte),
+                };
+                return output;
+            },
+            Escape::Single => {
+                self.escape = Escape::None;
+                output.echo = self.cursor_key(byte);
+                return output;
+            },
+        }
+        output.echo = match byte {
+            b'\n' | 0 if after_cr => Vec::new(),
+            b'\r' | b'\n' => {
+                self.carriage_return = byte == b'\r';
+                output.line = Some(self.finish());
+                b"\r\n".to_vec()
+            },
+            ESCAPE => {
+                self.escape = Escape::Started;
+                Vec::new()
+            },
+            BACKSPACE | DELETE => self.erase_previous(),
+            CTRL_D => self.delete(),
+            CTRL_A => self.move_to(0),
+            CTRL_E => self.move_to(self.line.len()),
+            CTRL_U => self.replace(0, self.cursor, b""),
+            CTRL_K => self.replace(self.cursor, self.line.len(), b""),
+            CTRL_W => {
+                let before = &self.line[..self.cursor];
+                let end = before.iter().rposition(|b| *b != b' ');
+                let start = end.map_or(0, |end| {
+                    before[..end]
+                        .iter()
+                        .rposition(|b| *b == b' ')
+                        .map_or(0, |space| space + 1)
+                });
+                self.replace(start, self.cursor, b"")
+            },
+            CTRL_L => self.redraw(),
+            TAB if self.hidden => Vec::new(),
+            TAB => self.complete(),
+            byte if byte < 0x20 => Vec::new(),
+            byte => self.replace(self.cursor, self.cursor, &[byte]),
+        };
+        output
+    }
+    /// Erase the character before the cursor.
+    fn erase_previous(&mut self) -> Vec<u8> {
+        if self.cursor == 0 {
+            return Vec::new();
+        }
+        self.replace(self.cursor - 1, self.cursor, b"")
+    }
+    /// Handle the final byte of an arrow or Home and End key sequence.
+    fn cursor_key(&mut self, byte: u8) -> Vec<u8> {
+        match byte {
+            b'A' | b'B' if self.hidden => Vec::new(),
+            b'A' => self.recall(true),
+            b'B' => self.recall(false),
+            b'C' => self.move_to((self.cursor + 1).min(self.line.len())),
+            b'D' => self.move_to(self.cursor.saturating_sub(1)),
+            b'H' => self.move_to(0),
+            b'F' => self.move_to(self.line.len()),
+            _ => Vec::new(),
+        }
+    }
+    /// Delete the character under the cursor.
+    fn delete(&mut self) -> Vec<u8> {
+        if self.cursor == self.line.len() {
+            return Vec::new();
+        }
+        self.replace(self.cursor, self.cursor + 1, b"")
+    }
+    /// Move the cursor to `position`.
+    fn move_to(&mut self, position: usize) -> Vec<u8> {
+        let echo = if position < self.cursor {
+            vec![BACKSPACE; self.cursor - position]
+        } else {
+            self.line[self.cursor..position].to_vec()
+        };
+        self.cursor = position;
+        echo
+    }
+    /// Replace `start..end` of the line with `text`, leaving the cursor
+    /// after it, and redraw the rest of the line.
+    fn replace(&mut self, start: usize, end: usize, text: &[u8]) -> Vec<u8> {
+        let mut echo = self.move_to(start);
+        let removed = end - start;
+        self.line.splice(start..end, text.iter().copied());
+        echo.extend_from_slice(&self.line[start..]);
+        let padding = removed.saturating_sub(text.len());
+        echo.resize(echo.len() + padding, b' ');
+        let back = self.line.len() - start - text.len() + padding;
+        echo.resize(echo.len() + back, BACKSPACE);
+        self.cursor = start + text.len();
+        echo
+    }
+    /// Redraw the prompt and line on a fresh line.
+    fn redraw(&mut self) -> Vec<u8> {
+        let mut echo = b"\r\n".to_vec();
+        echo.extend_from_slice(&self.prompt);
+        echo.extend_from_slice(&self.line);
+        let back = self.line.len() - self.cursor;
+        echo.resize(echo.len() + back, BACKSPACE);
+        echo
+    }
+    /// Show the previous or next history line.
+    fn recall(&mut self, previous: bool) -> Vec<u8> {
+        let index = match (self.recalled, previous) {
+            (None, true) if !self.history.is_empty() => {
+                self.draft = self.line.clone();
+                Some(self.history.len() - 1)
+            },
+            (Some(index), true) => Some(index.saturating_sub(1)),
+            (Some(index), false) if index + 1 < self.history.len() => {
+                Some(index + 1)
+            },
+            (Some(_), false) => None,
+            (None, _) => return Vec::new(),
+        };
+        self.recalled = index;
+        let text = match index {
+            Some(index) => self.history[index].as_bytes().to_vec(),
+            None => std::mem::take(&mut self.draft),
+        };
+        self.replace(0, self.line.len(), &text)
+    }
+    /// Ask the completer to complete the line up to the cursor. A single
+    /// candidate replaces it, several are extended to their common prefix
+    /// and listed if that adds nothing.
+    fn complete(&mut self) -> Vec<u8> {
+        let before = self.line()[..self.cursor].to_string();
+        let candidates = match &mut self.completer {
+            Some(completer) => completer(&before),
+            None => return Vec::new(),
+        };
+        let first = match candidates.first() {
+            Some(first) => first.as_bytes(),
+            None => return Vec::new(),
+        };
+        let common = candidates.iter().fold(first.len(), |common, other| {
+            first
+                .iter()
+                .zip(other.as_bytes())
+                .take(common)
+                .take_while(|(a, b)| a == b)
+                .count()
+        });
+        let prefix = first[..common].to_vec();
+        if candidates.len() == 1 || prefix.len() > before.len() {
+            return self.replace(0, self.cursor, &prefix);
+        }
+        let mut echo = b"\r\n".to_vec();
+        echo.extend_from_slice(candidates.join("  ").as_bytes());
+        echo.extend(self.redraw());
+        echo
+    }
+    /// Take the completed line, adding it to the history.
+    fn finish(&mut self) -> String {
+        let line = self.line();
+        self.line.clear();
+        self.cursor = 0;
+        self.recalled = None;
+        self.draft.clear();
+        let repeated = self.history.last() == Some(&line);
+        let keep = !self.hidden && self.history_limit > 0;
+        if !line.is_empty() && !repeated && keep {
+            if self.history.len() == self.history_limit {
+                self.history.remove(0);
+            }
+            self.history.push(line.clone());
+        }
+        line
+    }
+}
+
+impl Default for LineEditor {
+    fn default() -> Self {
+        LineEditor::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LineEditor;
+
+    fn type_keys(editor: &mut LineEditor, keys: &[u8]) -> Vec<String> {
+        keys.iter()
+            .filter_map(|key| editor.input(*key).line)
+            .collect()
+    }
+
+    #[test]
+    fn test_editing() {
+        let mut editor = LineEditor::new();
+        type_keys(&mut editor, b"helo\x1b[D");
+        assert_eq!(b"lo\x08".to_vec(), editor.input(b'l').echo);
+        assert_eq!("hello", editor.line());
+        assert_eq!(4, editor.cursor());
+
+        type_keys(&mut editor, b"\x05 world\x17");
+        assert_eq!("hello ", editor.line());
+        assert_eq!(
+            b"\x08\x08\x08\x08\x08\x08      \x08\x08\x08\x08\x08\x08".to_vec(),
+            editor.erase_line()
+        );
+        let lines = type_keys(&mut editor, b"abc\x7f\x01x\r\n");
+        assert_eq!(lines, vec!["xab"]);
+    }
+
+    #[test]
+    fn test_history_and_completion() {
+        let mut editor = LineEditor::new().with_completer(Box::new(|line| {
+            ["look", "logout", "say"]
+                .iter()
+                .filter(|word| word.starts_with(line))
+                .map(|word| word.to_string())
+                .collect()
+        }));
+        type_keys(&mut editor, b"one\rtwo\r");
+        type_keys(&mut editor, b"dr\x1b[A\x1b[A");
+        assert_eq!("one", editor.line());
+        type_keys(&mut editor, b"\x1b[B\x1b[B");
+        assert_eq!("dr", editor.line());
+
+        type_keys(&mut editor, b"\x15s\t");
+        assert_eq!("say", editor.line());
+        type_keys(&mut editor, b"\x15l\t");
+        assert_eq!("lo", editor.line());
+        let echo = editor.input(b'\t').echo;
+        assert!(echo.starts_with(b"\r\nlook  logout\r\nlo"));
+    }
+
+    #[test]
+    fn test_hidden() {
+        let mut editor = LineEditor::new();
+        type_keys(&mut editor, b"one\r");
+        editor.set_hidden(true);
+        let mut lines = Vec::new();
+        for key in b"secx\x7f\x1b[At\r" {
+            let output = editor.input(*key);
+            assert!(output.echo.is_empty());
+            lines.extend(output.line);
+        }
+        assert_eq!(lines, vec!["sect"]);
+        assert_eq!(&["one"], editor.history());
+
+        editor.set_hidden(false);
+        type_keys(&mut editor, b"\x1b[A");
+        assert_eq!("one", editor.line());
+    }
+
+    #[test]
+    fn test_large_escape_parameter() {
+        let mut editor = LineEditor::new();
+        type_keys(&mut editor, b"ab\x1b[261~\x1b[999999~c");
+        assert_eq!("abc", editor.line());
+        assert_eq!(3, editor.cursor());
+    }
+}
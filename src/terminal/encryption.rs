@@ -0,0 +1,667 @@
+//
+// Copyright 2019 Hans W. Uhlig. All Rights Reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+
+use crate::codec::{
+    Encryption, EncryptionType, StreamTransformer, TelnetArgument, TelnetFrame,
+    TelnetOption,
+};
+use crate::terminal::{TerminalEndpoint, TerminalError};
+use chacha20::cipher::{KeyIvInit, StreamCipher};
+use chacha20::ChaCha20;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::io;
+
+///
+/// Result of a single step of a cipher's initialization exchange.
+///
+#[derive(Clone, Debug, PartialEq)]
+pub enum EncryptionStep {
+    /// Send data to the peer and wait for its answer.
+    Continue(Vec<u8>),
+    /// Cipher is initialized. Optionally send final data to the peer.
+    Ready(Option<Vec<u8>>),
+    /// Initialization failed. Optionally send final data to the peer.
+    Failed(Option<Vec<u8>>),
+}
+
+///
+/// Pluggable Encryption Cipher.
+///
+/// The encrypting side (`WILL ENCRYPT`) calls `start` once the peer sent
+/// `SUPPORT`, and then `reply` for every `REPLY`. The decrypting side
+/// (`DO ENCRYPT`) calls `is` for every `IS`. Once initialized, the cipher
+/// produces the stream transformers for either direction.
+///
+pub trait EncryptionCipher: Send {
+    /// Encryption type this cipher implements.
+    fn kind(&self) -> EncryptionType;
+    /// Encrypting side: Produce the data for the initial `IS`.
+    fn start(&mut self) -> Result<Vec<u8>, TerminalError>;
+    /// Decrypting side: Handle data received in an `IS`.
+    fn is(&mut self, data: &[u8]) -> Result<EncryptionStep, TerminalError>;
+    /// Encrypting side: Handle data received in a `REPLY`.
+    fn reply(&mut self, data: &[u8]) -> Result<EncryptionStep, TerminalError>;
+    /// Transformer encrypting our output with `keyid`. Called for every
+    /// `START` we send, each of which must begin a fresh keystream.
+    fn encryptor(
+        &mut self,
+        keyid: &[u8],
+    ) -> Result<Box<dyn StreamTransformer>, TerminalError>;
+    /// Transformer decrypting the peer's output with `keyid`. Called for
+    /// every `START` the peer sends.
+    fn decryptor(
+        &mut self,
+        keyid: &[u8],
+    ) -> Result<Box<dyn StreamTransformer>, TerminalError>;
+}
+
+///
+/// Action requested by the `Encryptor`, to be applied in order.
+///
+pub enum EncryptionEvent {
+    /// Send a frame to the peer.
+    Send(TelnetFrame),
+    /// Install the transformer after the encoder.
+    StartEncrypting(Box<dyn StreamTransformer>),
+    /// Remove the transformer after the encoder.
+    StopEncrypting,
+    /// Install the transformer before the decoder.
+    StartDecrypting(Box<dyn StreamTransformer>),
+    /// Remove the transformer before the decoder.
+    StopDecrypting,
+}
+
+///
+/// Session side Encryption state machine [RFC2946](http://www.iana.org/go/rfc2946)
+///
+/// Each direction is negotiated independently. Our output is governed by
+/// `WILL ENCRYPT`, the peer's output by `DO ENCRYPT`.
+///
+pub struct Encryptor {
+    ciphers: Vec<Box<dyn EncryptionCipher>>,
+    keyid: Vec<u8>,
+    proposed: Option<Vec<u8>>,
+    output: Option<usize>,
+    output_ready: bool,
+    encrypting: bool,
+    input: Option<usize>,
+    input_ready: bool,
+    decrypting: bool,
+}
+
+impl Encryptor {
+    /// Create a new `Encryptor` using the default key id `0`.
+    pub fn new() -> Encryptor {
+        Encryptor {
+            ciphers: Vec::new(),
+            keyid: vec![0],
+            proposed: None,
+            output: None,
+            output_ready: false,
+            encrypting: false,
+            input: None,
+            input_ready: false,
+            decrypting: false,
+        }
+    }
+    /// Register a cipher. Earlier ciphers are preferred.
+    pub fn with_cipher<C>(mut self, cipher: C) -> Encryptor
+    where
+        C: EncryptionCipher + 'static,
+    {
+        self.ciphers.push(Box::new(cipher));
+        self
+    }
+    /// True while our output is encrypted.
+    pub fn encrypting(&self) -> bool {
+        self.encrypting
+    }
+    /// True while the peer's output is decrypted.
+    pub fn decrypting(&self) -> bool {
+        self.decrypting
+    }
+    /// Decrypting side: Build the `SUPPORT` frame listing every cipher.
+    pub fn support(&mut self) -> TelnetFrame {
+        self.input = None;
+        self.input_ready = false;
+        let kinds = self.ciphers.iter().map(|c| c.kind()).collect();
+        frame(Encryption::Support(kinds))
+    }
+    /// Encrypting side: Start encrypting our output.
+    pub fn start(&mut self) -> Result<Vec<EncryptionEvent>, TerminalError> {
+        match self.output {
+            Some(index) if self.output_ready => {
+                let encryptor = self.ciphers[index].encryptor(&self.keyid)?;
+                self.encrypting = true;
+                Ok(vec![
+                    EncryptionEvent::Send(frame(Encryption::Start(
+                        self.keyid.clone(),
+                    ))),
+                    EncryptionEvent::StartEncrypting(encryptor),
+                ])
+            },
+            _ => Err(TerminalError::NegotiationError),
+        }
+    }
+    /// Encrypting side: Stop encrypting our output.
+    pub fn end(&mut self) -> Vec<EncryptionEvent> {
+        if !self.encrypting {
+            return Vec::new();
+        }
+        self.encrypting = false;
+        vec![
+            EncryptionEvent::Send(frame(Encryption::End)),
+            EncryptionEvent::StopEncrypting,
+        ]
+    }
+    /// Encrypting side: Propose `keyid` for our output. It is used once the
+    /// peer acknowledges it with a matching `DEC_KEYID`.
+    pub fn propose_keyid(&mut self, keyid: Vec<u8>) -> TelnetFrame {
+        self.proposed = Some(keyid.clone());
+        frame(Encryption::EncryptKeyId(keyid))
+    }
+    /// Decrypting side: Ask the peer to start encrypting.
+    pub fn request_start(&self) -> TelnetFrame {
+        frame(Encryption::RequestStart(self.keyid.clone()))
+    }
+    /// Decrypting side: Ask the peer to stop encrypting.
+    pub fn request_end(&self) -> TelnetFrame {
+        frame(Encryption::RequestEnd)
+    }
+    /// Handle a received Encryption subnegotiation. An `IS` for a cipher we
+    /// never offered, a `START` or `END` that does not match the state of
+    /// the peer's output, or a `DEC_KEYID` for a key id we never proposed,
+    /// is ignored. A `REPLY` for a cipher we did not start is an error.
+    pub fn handle(
+        &mut self,
+        argument: Encryption,
+    ) -> Result<Vec<EncryptionEvent>, TerminalError> {
+        match argument {
+            Encryption::Support(kinds) => {
+                self.output_ready = false;
+                self.output =
+                    kinds.into_iter().filter_map(|kind| self.find(kind)).next();
+                let is = match self.output {
+                    Some(index) => {
+                        let kind = self.ciphers[index].kind();
+                        Encryption::Is(kind, self.ciphers[index].start()?)
+                    },
+                    None => Encryption::Is(EncryptionType::Null, Vec::new()),
+                };
+                Ok(vec![EncryptionEvent::Send(frame(is))])
+            },
+            Encryption::Is(kind, data) => {
+                if kind == EncryptionType::Null {
+                    self.input = None;
+                    return Ok(Vec::new());
+                }
+                let index = match self.find(kind) {
+                    Some(index) => index,
+                    None => {
+                        self.input = None;
+                        return Ok(Vec::new());
+                    },
+                };
+                self.input = Some(index);
+                let (data, ready) = match self.ciphers[index].is(&data)? {
+                    EncryptionStep::Continue(data) => (Some(data), false),
+                    EncryptionStep::Ready(data) => (data, true),
+                    EncryptionStep::Failed(data) => (data, false),
+                };
+                self.input_ready = ready;
+                Ok(data
+                    .map(|data| Encryption::Reply(kind, data))
+                    .map(|reply| EncryptionEvent::Send(frame(reply)))
+                    .into_iter()
+                    .collect())
+            },
+            Encryption::Reply(kind, data) => {
+                let index = match self.output {
+                    Some(index) if self.ciphers[index].kind() == kind => index,
+                    _ => return Err(TerminalError::NegotiationError),
+                };
+                match self.ciphers[index].reply(&data)? {
+                    EncryptionStep::Continue(data) => {
+                        Ok(vec![EncryptionEvent::Send(frame(Encryption::Is(
+                            kind, data,
+                        )))])
+                    },
+                    EncryptionStep::Ready(data) => {
+                        self.output_ready = true;
+                        let mut events: Vec<EncryptionEvent> = data
+                            .map(|data| Encryption::Is(kind, data))
+                            .map(|is| EncryptionEvent::Send(frame(is)))
+                            .into_iter()
+                            .collect();
+                        events.extend(self.start()?);
+                        Ok(events)
+                    },
+                    EncryptionStep::Failed(data) => {
+                        self.output = None;
+                        Ok(data
+                            .map(|data| Encryption::Is(kind, data))
+                            .map(|is| EncryptionEvent::Send(frame(is)))
+                            .into_iter()
+                            .collect())
+                    },
+                }
+            },
+            Encryption::Start(keyid) => match self.input {
+                Some(index) if self.input_ready => {
+                    let decryptor = self.ciphers[index].decryptor(&keyid)?;
+                    self.decrypting = true;
+                    Ok(vec![EncryptionEvent::StartDecrypting(decryptor)])
+                },
+                _ => Ok(Vec::new()),
+            },
+            Encryption::End if self.decrypting => {
+                self.decrypting = false;
+                Ok(vec![EncryptionEvent::StopDecrypting])
+            },
+            Encryption::End => Ok(Vec::new()),
+            Encryption::RequestStart(_) => match self.encrypting {
+                true => Ok(Vec::new()),
+                false => self.start(),
+            },
+            Encryption::RequestEnd => Ok(self.end()),
+            Encryption::EncryptKeyId(keyid) => {
+                // Accept whichever key id the encrypting side proposes.
+                let reply = Encryption::DecryptKeyId(keyid);
+                Ok(vec![EncryptionEvent::Send(frame(reply))])
+            },
+            Encryption::DecryptKeyId(keyid) => {
+                // An empty key id means the peer does not know ours.
+                if !keyid.is_empty() && self.proposed.as_ref() == Some(&keyid) {
+                    self.keyid = keyid;
+                }
+                self.proposed = None;
+                Ok(Vec::new())
+            },
+        }
+    }
+    fn find(&self, kind: EncryptionType) -> Option<usize> {
+        self.ciphers.iter().position(|c| c.kind() == kind)
+    }
+}
+
+impl Default for Encryptor {
+    fn default() -> Encryptor {
+        Encryptor::new()
+    }
+}
+
+/// Endpoint whose `ENCRYPT` option governs `argument`. Commands sent by the
+/// decrypting peer concern our output, the rest concern the peer's output.
+pub(crate) fn encryption_side(argument: &Encryption) -> TerminalEndpoint {
+    match argument {
+        Encryption::Support(_)
+        | Encryption::Reply(_, _)
+        | Encryption::RequestStart(_)
+        | Encryption::RequestEnd
+        | Encryption::DecryptKeyId(_) => TerminalEndpoint::Local,
+        Encryption::Is(_, _)
+        | Encryption::Start(_)
+        | Encryption::End
+        | Encryption::EncryptKeyId(_) => TerminalEndpoint::Remote,
+    }
+}
+
+fn frame(encryption: Encryption) -> TelnetFrame {
+    TelnetFrame::Subnegotiate(
+        TelnetOption::Encryption,
+        TelnetArgument::Encryption(encryption),
+    )
+}
+
+/// `SharedKeyStream` sub-commands
+mod shared_key {
+    pub const IV: u8 = 1;
+    pub const IV_OK: u8 = 2;
+    pub const IV_BAD: u8 = 3;
+    pub const IV_LENGTH: usize = 16;
+}
+
+///
+/// ChaCha20 Stream Cipher keyed with a secret known to both sides.
+///
+/// The encrypting side sends a random initialization vector in `IS`, which
+/// the decrypting side acknowledges in `REPLY`, mirroring the CFB64 exchange.
+/// Each direction is keyed with the HMAC-SHA256 of its initialization vector
+/// and key id under the shared secret. Every `START` since the last exchange
+/// uses the next nonce, so restarting after `END` never reuses a keystream.
+///
+pub struct SharedKeyStream {
+    kind: EncryptionType,
+    key: Vec<u8>,
+    output_iv: Option<[u8; shared_key::IV_LENGTH]>,
+    output_starts: u64,
+    input_iv: Option<[u8; shared_key::IV_LENGTH]>,
+    input_starts: u64,
+}
+
+impl SharedKeyStream {
+    /// Create a new `SharedKeyStream` advertised as `kind`. `kind` must be
+    /// a type agreed privately with the peer, never one IANA assigned to
+    /// another cipher such as `DESCFB64`.
+    pub fn new(kind: EncryptionType, key: &[u8]) -> SharedKeyStream {
+        SharedKeyStream {
+            kind,
+            key: key.to_vec(),
+            output_iv: None,
+            output_starts: 0,
+            input_iv: None,
+            input_starts: 0,
+        }
+    }
+    fn keystream(
+        &self,
+        iv: Option<[u8; shared_key::IV_LENGTH]>,
+        keyid: &[u8],
+        starts: u64,
+    ) -> Result<Box<dyn StreamTransformer>, TerminalError> {
+        let iv = iv.ok_or(TerminalError::NegotiationError)?;
+        // HMAC accepts keys of any length
+        let mut mac = Hmac::<Sha256>::new_from_slice(&self.key)
+            .expect("HMAC key of any length");
+        mac.update(&iv);
+        mac.update(keyid);
+        let key = mac.finalize().into_bytes();
+        let mut nonce = [0; 12];
+        nonce[4..].copy_from_slice(&starts.to_be_bytes());
+        let cipher = ChaCha20::new(&key, &nonce.into());
+        Ok(Box::new(Keystream(cipher)))
+    }
+}
+
+impl EncryptionCipher for SharedKeyStream {
+    fn kind(&self) -> EncryptionType {
+        self.kind
+    }
+    fn start(&mut self) -> Result<Vec<u8>, TerminalError> {
+        let mut iv = [0; shared_key::IV_LENGTH];
+        getrandom::getrandom(&mut iv).map_err(io::Error::from)?;
+        self.output_iv = Some(iv);
+        self.output_starts = 0;
+        let mut data = vec![shared_key::IV];
+        data.extend_from_slice(&iv);
+        Ok(data)
+    }
+    fn is(&mut self, data: &[u8]) -> Result<EncryptionStep, TerminalError> {
+        match data.split_first() {
+            Some((&shared_key::IV, iv))
+                if iv.len() == shared_key::IV_LENGTH =>
+            {
+                let mut bytes = [0; shared_key::IV_LENGTH];
+                bytes.copy_from_slice(iv);
+                self.input_iv = Some(bytes);
+                self.input_starts = 0;
+                Ok(EncryptionStep::Ready(Some(vec![shared_key::IV_OK])))
+            },
+            _ => Ok(EncryptionStep::Failed(Some(vec![shared_key::IV_BAD]))),
+        }
+    }
+    fn reply(&mut self, data: &[u8]) -> Result<EncryptionStep, TerminalError> {
+        match data.first() {
+            Some(&shared_key::IV_OK) if self.output_iv.is_some() => {
+                Ok(EncryptionStep::Ready(None))
+            },
+            _ => Ok(EncryptionStep::Failed(None)),
+        }
+    }
+    fn encryptor(
+        &mut self,
+        keyid: &[u8],
+    ) -> Result<Box<dyn StreamTransformer>, TerminalError> {
+        let keystream =
+            self.keystream(self.output_iv, keyid, self.output_starts)?;
+        self.output_starts += 1;
+        Ok(keystream)
+    }
+    fn decryptor(
+        &mut self,
+        keyid: &[u8],
+    ) -> Result<Box<dyn StreamTransformer>, TerminalError> {
+        let keystream =
+            self.keystream(self.input_iv, keyid, self.input_starts)?;
+        self.input_starts += 1;
+        Ok(keystream)
+    }
+}
+
+/// ChaCha20 keystream, symmetric for both directions.
+struct Keystream(ChaCha20);
+
+impl StreamTransformer for Keystream {
+    fn transform(&mut self, data: &mut [u8]) {
+        self.0.apply_keystream(data);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{EncryptionEvent, Encryptor, SharedKeyStream};
+    use crate::codec::{
+        Encryption, EncryptionType, StreamTransformer, TelnetArgument,
+        TelnetFrame,
+    };
+
+    /// Unassigned encryption type, so no registered cipher is claimed.
+    const PRIVATE: u8 = 200;
+
+    /// `Encryptor` with the transformers it installed.
+    struct Endpoint {
+        encryptor: Encryptor,
+        encrypting: Option<Box<dyn StreamTransformer>>,
+        decrypting: Option<Box<dyn StreamTransformer>>,
+    }
+
+    impl Endpoint {
+        fn new(key: &[u8]) -> Endpoint {
+            Endpoint::with_cipher(EncryptionType::Unknown(PRIVATE), key)
+        }
+        fn with_cipher(kind: EncryptionType, key: &[u8]) -> Endpoint {
+            Endpoint {
+                encryptor: Encryptor::new()
+                    .with_cipher(SharedKeyStream::new(kind, key)),
+                encrypting: None,
+                decrypting: None,
+            }
+        }
+        /// Encrypt `length` zero bytes, exposing our keystream.
+        fn keystream(&mut self, length: usize) -> Vec<u8> {
+            let mut data = vec![0; length];
+            self.encrypting.as_mut().unwrap().transform(&mut data);
+            data
+        }
+    }
+
+    fn argument(frame: TelnetFrame) -> Encryption {
+        match frame {
+            TelnetFrame::Subnegotiate(
+                _,
+                TelnetArgument::Encryption(argument),
+            ) => argument,
+            frame => panic!("Unexpected frame {:?}", frame),
+        }
+    }
+
+    /// Apply `events` produced by `local`, delivering frames to `remote`.
+    fn apply(
+        local: &mut Endpoint,
+        remote: &mut Endpoint,
+        events: Vec<EncryptionEvent>,
+    ) {
+        for event in events {
+            match event {
+                EncryptionEvent::Send(frame) => {
+                    let events = remote.encryptor.handle(argument(frame));
+                    apply(remote, local, events.unwrap());
+                },
+                EncryptionEvent::StartEncrypting(transformer) => {
+                    local.encrypting = Some(transformer)
+                },
+                EncryptionEvent::StartDecrypting(transformer) => {
+                    local.decrypting = Some(transformer)
+                },
+                EncryptionEvent::StopEncrypting => local.encrypting = None,
+                EncryptionEvent::StopDecrypting => local.decrypting = None,
+            }
+        }
+    }
+
+    /// Let `receiver` offer its ciphers and `sender` start encrypting.
+    fn negotiate(receiver: &mut Endpoint, sender: &mut Endpoint) {
+        let support = receiver.encryptor.support();
+        apply(receiver, sender, vec![EncryptionEvent::Send(support)]);
+    }
+
+    #[test]
+    fn test_handshake() {
+        let mut client = Endpoint::new(b"secret");
+        let mut server = Endpoint::new(b"secret");
+        negotiate(&mut server, &mut client);
+        assert!(client.encryptor.encrypting());
+        assert!(server.encryptor.decrypting());
+
+        let encrypting = client.encrypting.as_mut().unwrap();
+        let decrypting = server.decrypting.as_mut().unwrap();
+        let mut data = b"attack at dawn".to_vec();
+        encrypting.transform(&mut data);
+        assert_ne!(b"attack at dawn".to_vec(), data);
+        let first = data.clone();
+        decrypting.transform(&mut data);
+        assert_eq!(b"attack at dawn".to_vec(), data);
+
+        // The keystream advances, repeated plaintext encrypts differently
+        let mut repeated = b"attack at dawn".to_vec();
+        encrypting.transform(&mut repeated);
+        assert_ne!(first, repeated);
+
+        let end = client.encryptor.end();
+        apply(&mut client, &mut server, end);
+        assert!(client.encrypting.is_none());
+        assert!(server.decrypting.is_none());
+        assert!(!server.encryptor.decrypting());
+    }
+
+    #[test]
+    fn test_directions_use_separate_keystreams() {
+        let mut client = Endpoint::new(b"secret");
+        let mut server = Endpoint::new(b"secret");
+        negotiate(&mut server, &mut client);
+        negotiate(&mut client, &mut server);
+        assert!(client.encrypting.is_some() && client.decrypting.is_some());
+        assert!(server.encrypting.is_some() && server.decrypting.is_some());
+
+        let mut data = b"ping".to_vec();
+        server.encrypting.as_mut().unwrap().transform(&mut data);
+        client.decrypting.as_mut().unwrap().transform(&mut data);
+        assert_eq!(b"ping".to_vec(), data);
+
+        let upstream = client.keystream(32);
+        let downstream = server.keystream(32);
+        assert_ne!(upstream, downstream);
+    }
+
+    #[test]
+    fn test_restart_uses_fresh_keystream() {
+        let mut client = Endpoint::new(b"secret");
+        let mut server = Endpoint::new(b"secret");
+        negotiate(&mut server, &mut client);
+        let first = client.keystream(32);
+
+        let end = argument(server.encryptor.request_end());
+        let events = client.encryptor.handle(end).unwrap();
+        apply(&mut client, &mut server, events);
+        assert!(client.encrypting.is_none());
+        let start = argument(server.encryptor.request_start());
+        let events = client.encryptor.handle(start).unwrap();
+        apply(&mut client, &mut server, events);
+        assert!(server.encryptor.decrypting());
+
+        let mut data = vec![0; 32];
+        client.encrypting.as_mut().unwrap().transform(&mut data);
+        assert_ne!(first, data);
+        server.decrypting.as_mut().unwrap().transform(&mut data);
+        assert_eq!(vec![0; 32], data);
+    }
+
+    #[test]
+    fn test_keyid_needs_proposal() {
+        let mut client = Endpoint::new(b"secret");
+        let unsolicited = Encryption::DecryptKeyId(vec![7]);
+        assert!(client.encryptor.handle(unsolicited).unwrap().is_empty());
+        assert_eq!(vec![0], client.encryptor.keyid);
+
+        let proposal = argument(client.encryptor.propose_keyid(vec![7]));
+        let mut server = Endpoint::new(b"secret");
+        let answer = match server.encryptor.handle(proposal).unwrap().pop() {
+            Some(EncryptionEvent::Send(frame)) => argument(frame),
+            _ => panic!("Expected DEC_KEYID"),
+        };
+        assert!(client.encryptor.handle(answer).unwrap().is_empty());
+        assert_eq!(vec![7], client.encryptor.keyid);
+    }
+
+    #[test]
+    fn test_reply_without_start() {
+        let mut client = Endpoint::new(b"secret");
+        let kind = EncryptionType::Unknown(PRIVATE);
+        let reply = Encryption::Reply(kind, vec![2]);
+        assert!(client.encryptor.handle(reply).is_err());
+        assert!(!client.encryptor.encrypting());
+    }
+
+    #[test]
+    fn test_wrong_key() {
+        let mut client = Endpoint::new(b"secret");
+        let mut server = Endpoint::new(b"guess");
+        negotiate(&mut server, &mut client);
+        let mut data = b"attack at dawn".to_vec();
+        client.encrypting.unwrap().transform(&mut data);
+        server.decrypting.unwrap().transform(&mut data);
+        assert_ne!(b"attack at dawn".to_vec(), data);
+    }
+
+    #[test]
+    fn test_no_common_cipher() {
+        let kind = EncryptionType::Unknown(PRIVATE + 1);
+        let mut client = Endpoint::with_cipher(kind, b"secret");
+        let mut server = Endpoint::new(b"secret");
+        let support = argument(server.encryptor.support());
+        let is = match client.encryptor.handle(support).unwrap().pop() {
+            Some(EncryptionEvent::Send(frame)) => argument(frame),
+            _ => panic!("Expected IS"),
+        };
+        assert_eq!(Encryption::Is(EncryptionType::Null, Vec::new()), is);
+        assert!(server.encryptor.handle(is).unwrap().is_empty());
+        assert!(!client.encryptor.encrypting());
+        assert!(!server.encryptor.decrypting());
+    }
+
+    #[test]
+    fn test_unexpected_commands_ignored() {
+        let mut server = Endpoint::new(b"secret");
+        let kind = EncryptionType::Unknown(PRIVATE + 1);
+        let is = Encryption::Is(kind, vec![1; 17]);
+        assert!(server.encryptor.handle(is).unwrap().is_empty());
+        let start = Encryption::Start(vec![0]);
+        assert!(server.encryptor.handle(start).unwrap().is_empty());
+        assert!(server.encryptor.handle(Encryption::End).unwrap().is_empty());
+        assert!(!server.encryptor.decrypting());
+    }
+}
@@ -14,12 +14,13 @@
 // limitations under the License.
 //
 
-use std::{error, fmt};
+use crate::codec::TelnetError;
+use std::{error, fmt, io};
 
 ///
 /// Telnet Network Virtual Terminal Error
 ///
-#[derive(Clone, Debug)]
+#[derive(Debug)]
 pub enum TerminalError {
     /// Telnet Codec Error
     TelnetError(TelnetError),
@@ -40,3 +41,15 @@ impl fmt::Display for TerminalError {
         write!(f, "TerminalError")
     }
 }
+
+impl From<io::Error> for TerminalError {
+    fn from(error: io::Error) -> TerminalError {
+        TerminalError::TelnetError(TelnetError::IOError(error))
+    }
+}
+
+impl From<TelnetError> for TerminalError {
+    fn from(error: TelnetError) -> TerminalError {
+        TerminalError::TelnetError(error)
+    }
+}
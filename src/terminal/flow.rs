@@ -0,0 +1,161 @@
+//
+// Copyright 2019 Hans W. Uhlig. All Rights Reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+
+use crate::codec::{
+    consts, FlowControl, TelnetArgument, TelnetFrame, TelnetOption,
+};
+
+///
+/// Remote Flow Control state [RFC1372](http://www.iana.org/go/rfc1372)
+///
+/// Tracks the mode negotiated with LFLOW subnegotiations in either direction
+/// and whether output is currently suspended by an XOFF from the peer.
+///
+#[derive(Clone, Debug)]
+pub struct FlowController {
+    enabled: bool,
+    restart_any: bool,
+    paused: bool,
+}
+
+impl FlowController {
+    /// Create a new `FlowController`. Flow control starts enabled, and only
+    /// XON restarts suspended output.
+    pub fn new() -> FlowController {
+        FlowController {
+            enabled: true,
+            restart_any: false,
+            paused: false,
+        }
+    }
+    /// True while XON and XOFF are interpreted instead of passed as data.
+    pub fn enabled(&self) -> bool {
+        self.enabled
+    }
+    /// True if any character restarts suspended output.
+    pub fn restart_any(&self) -> bool {
+        self.restart_any
+    }
+    /// True while output is suspended.
+    pub fn paused(&self) -> bool {
+        self.paused
+    }
+    /// Apply `mode` locally and build the frame telling the peer.
+    pub fn command(&mut self, mode: FlowControl) -> TelnetFrame {
+        self.handle(mode);
+        TelnetFrame::Subnegotiate(
+            TelnetOption::LFLOW,
+            TelnetArgument::FlowControl(mode),
+        )
+    }
+    /// Apply a received LFLOW subnegotiation.
+    pub fn handle(&mut self, mode: FlowControl) {
+        match mode {
+            FlowControl::Off => {
+                self.enabled = false;
+                self.paused = false;
+            },
+            FlowControl::On => self.enabled = true,
+            FlowControl::RestartAny => self.restart_any = true,
+            FlowControl::RestartXon => self.restart_any = false,
+        }
+    }
+    /// Inspect a received data byte, updating the suspended state. Returns
+    /// false if the byte was consumed as XON or XOFF.
+    pub fn input(&mut self, byte: u8) -> bool {
+        if !self.enabled {
+            return true;
+        }
+        match byte {
+            consts::XOFF => {
+                self.paused = true;
+                false
+            },
+            consts::XON => {
+                self.paused = false;
+                false
+            },
+            _ => {
+                if self.restart_any {
+                    self.paused = false;
+                }
+                true
+            },
+        }
+    }
+}
+
+impl Default for FlowController {
+    fn default() -> FlowController {
+        FlowController::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FlowController;
+    use crate::codec::{consts, FlowControl, TelnetArgument, TelnetFrame};
+
+    #[test]
+    fn test_xon_xoff() {
+        let mut flow = FlowController::new();
+        assert!(flow.enabled());
+        assert!(!flow.paused());
+        assert!(!flow.input(consts::XOFF));
+        assert!(flow.paused());
+        assert!(flow.input(b'a'));
+        assert!(flow.paused());
+        assert!(!flow.input(consts::XON));
+        assert!(!flow.paused());
+    }
+
+    #[test]
+    fn test_restart_any() {
+        let mut flow = FlowController::new();
+        flow.handle(FlowControl::RestartAny);
+        assert!(flow.restart_any());
+        flow.input(consts::XOFF);
+        assert!(flow.input(b'a'));
+        assert!(!flow.paused());
+
+        flow.handle(FlowControl::RestartXon);
+        flow.input(consts::XOFF);
+        flow.input(b'a');
+        assert!(flow.paused());
+    }
+
+    #[test]
+    fn test_off() {
+        let mut flow = FlowController::new();
+        flow.input(consts::XOFF);
+        let frame = flow.command(FlowControl::Off);
+        assert_eq!(
+            TelnetArgument::FlowControl(FlowControl::Off),
+            match frame {
+                TelnetFrame::Subnegotiate(_, argument) => argument,
+                frame => panic!("Unexpected frame {:?}", frame),
+            }
+        );
+        assert!(!flow.enabled());
+        assert!(!flow.paused());
+        assert!(flow.input(consts::XOFF));
+        assert!(!flow.paused());
+
+        flow.handle(FlowControl::On);
+        assert!(!flow.input(consts::XOFF));
+        assert!(flow.paused());
+    }
+}
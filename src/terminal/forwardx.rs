@@ -0,0 +1,573 @@
+//
+// Copyright 2019 Hans W. Uhlig. All Rights Reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+use crate::codec::{ForwardX, TelnetArgument, TelnetFrame, TelnetOption};
+use crate::terminal::TerminalError;
+use futures::task::{Context, Poll, Waker};
+use futures::{AsyncRead, AsyncWrite};
+use std::collections::{HashMap, VecDeque};
+use std::io;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+
+/// Largest payload placed in a single FORWARD_X DATA subnegotiation.
+const MAX_DATA: usize = 1024;
+
+///
+/// Connection to an X server, as returned by a `ForwardXBackend`.
+///
+pub trait ForwardXStream: AsyncRead + AsyncWrite + Send + Unpin {}
+
+impl<T: AsyncRead + AsyncWrite + Send + Unpin> ForwardXStream for T {}
+
+///
+/// Opens connections to the local X server for channels opened by the peer.
+///
+pub trait ForwardXBackend: Send {
+    /// Connect to display `screen` of the X server.
+    fn connect(&mut self, screen: u8) -> io::Result<Box<dyn ForwardXStream>>;
+}
+
+#[derive(Default)]
+struct Channel {
+    incoming: VecDeque<u8>,
+    closed: bool,
+    paused: bool,
+    reader: Option<Waker>,
+    writer: Option<Waker>,
+}
+
+#[derive(Default)]
+struct Shared {
+    channels: HashMap<u16, Channel>,
+    frames: VecDeque<TelnetFrame>,
+    waker: Option<Waker>,
+}
+
+impl Shared {
+    fn send(&mut self, argument: ForwardX) {
+        self.frames.push_back(frame(argument));
+        if let Some(waker) = self.waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+fn frame(argument: ForwardX) -> TelnetFrame {
+    TelnetFrame::Subnegotiate(
+        TelnetOption::ForwardX,
+        TelnetArgument::ForwardX(argument),
+    )
+}
+
+///
+/// One X connection multiplexed over the telnet session. Bytes written are
+/// sent to the peer as FORWARD_X DATA, and DATA received for this channel
+/// is returned by reads. Dropping the channel closes it.
+///
+pub struct ForwardXChannel {
+    id: u16,
+    shared: Arc<Mutex<Shared>>,
+}
+
+impl ForwardXChannel {
+    /// Channel number.
+    pub fn id(&self) -> u16 {
+        self.id
+    }
+}
+
+impl AsyncRead for ForwardXChannel {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let mut shared = self.shared.lock().expect("FORWARD_X state");
+        let channel = match shared.channels.get_mut(&self.id) {
+            Some(channel) => channel,
+            None => return Poll::Ready(Ok(0)),
+        };
+        if channel.incoming.is_empty() {
+            if channel.closed {
+                return Poll::Ready(Ok(0));
+            }
+            channel.reader = Some(cx.waker().clone());
+            return Poll::Pending;
+        }
+        let count = buf.len().min(channel.incoming.len());
+        for (byte, data) in buf.iter_mut().zip(channel.incoming.drain(..count))
+        {
+            *byte = data;
+        }
+        Poll::Ready(Ok(count))
+    }
+}
+
+impl AsyncWrite for ForwardXChannel {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let mut shared = self.shared.lock().expect("FORWARD_X state");
+        let channel = match shared.channels.get_mut(&self.id) {
+            Some(channel) if !channel.closed => channel,
+            _ => return Poll::Ready(Err(io::ErrorKind::BrokenPipe.into())),
+        };
+        if channel.paused {
+            channel.writer = Some(cx.waker().clone());
+            return Poll::Pending;
+        }
+        let count = buf.len().min(MAX_DATA);
+        shared.send(ForwardX::Data(self.id, buf[..count].to_vec()));
+        Poll::Ready(Ok(count))
+    }
+
+    fn poll_flush(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+    ) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+    ) -> Poll<io::Result<()>> {
+        let mut shared = self.shared.lock().expect("FORWARD_X state");
+        if let Some(channel) = shared.channels.get_mut(&self.id) {
+            if !channel.closed {
+                channel.closed = true;
+                shared.send(ForwardX::Close(self.id));
+            }
+        }
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl Drop for ForwardXChannel {
+    fn drop(&mut self) {
+        if let Ok(mut shared) = self.shared.lock() {
+            if let Some(channel) = shared.channels.remove(&self.id) {
+                if !channel.closed {
+                    shared.send(ForwardX::Close(self.id));
+                }
+            }
+        }
+    }
+}
+
+/// Channel opened by the peer, connected to the X server by the backend.
+struct Bridge {
+    channel: ForwardXChannel,
+    stream: Box<dyn ForwardXStream>,
+    to_stream: Vec<u8>,
+    to_channel: Vec<u8>,
+    done: bool,
+}
+
+impl Bridge {
+    /// Copy data both ways until neither side can make progress.
+    fn poll(&mut self, cx: &mut Context<'_>) {
+        let mut buffer = [0u8; MAX_DATA];
+        while !self.done {
+            let mut progress = false;
+            if self.to_stream.is_empty() {
+                let read =
+                    Pin::new(&mut self.channel).poll_read(cx, &mut buffer);
+                if let Some(count) = self.transferred(read) {
+                    self.to_stream.extend_from_slice(&buffer[..count]);
+                    progress = true;
+                }
+            }
+            if !self.to_stream.is_empty() {
+                let written =
+                    Pin::new(&mut self.stream).poll_write(cx, &self.to_stream);
+                if let Some(count) = self.transferred(written) {
+                    self.to_stream.drain(..count);
+                    progress = true;
+                }
+            }
+            if self.to_channel.is_empty() {
+                let read =
+                    Pin::new(&mut self.stream).poll_read(cx, &mut buffer);
+                if let Some(count) = self.transferred(read) {
+                    self.to_channel.extend_from_slice(&buffer[..count]);
+                    progress = true;
+                }
+            }
+            if !self.to_channel.is_empty() {
+                let written = Pin::new(&mut self.channel)
+                    .poll_write(cx, &self.to_channel);
+                if let Some(count) = self.transferred(written) {
+                    self.to_channel.drain(..count);
+                    progress = true;
+                }
+            }
+            if !progress {
+                break;
+            }
+        }
+        if self.done {
+            let _ = Pin::new(&mut self.channel).poll_close(cx);
+        }
+    }
+    /// Byte count of a completed transfer. End of stream and errors finish
+    /// the bridge.
+    fn transferred(
+        &mut self,
+        result: Poll<io::Result<usize>>,
+    ) -> Option<usize> {
+        match result {
+            Poll::Ready(Ok(0)) | Poll::Ready(Err(_)) => {
+                self.done = true;
+                None
+            },
+            Poll::Ready(Ok(count)) => Some(count),
+            Poll::Pending => None,
+        }
+    }
+}
+
+///
+/// Multiplexes X11 connections over a telnet session with the FORWARD_X
+/// option.
+///
+/// The server side opens a channel for each X client with `open`. The client
+/// side either connects channels to its X server through a
+/// `ForwardXBackend`, or takes them with `accept` and serves them itself.
+///
+pub struct ForwardXMux {
+    shared: Arc<Mutex<Shared>>,
+    backend: Option<Box<dyn ForwardXBackend>>,
+    bridges: Vec<Bridge>,
+    accepted: VecDeque<ForwardXChannel>,
+    screen: u8,
+    next: u16,
+}
+
+impl ForwardXMux {
+    /// Create a new `ForwardXMux`.
+    pub fn new() -> ForwardXMux {
+        ForwardXMux {
+            shared: Arc::new(Mutex::new(Shared::default())),
+            backend: None,
+            bridges: Vec::new(),
+            accepted: VecDeque::new(),
+            screen: 0,
+            next: 0,
+        }
+    }
+    /// Connect channels opened by the peer to `backend`.
+    pub fn with_backend(
+        mut self,
+        backend: Box<dyn ForwardXBackend>,
+    ) -> ForwardXMux {
+        self.backend = Some(backend);
+        self
+    }
+    /// Client: Ask the server to forward X connections for `screen`.
+    pub fn request(&mut self, screen: u8) -> TelnetFrame {
+        self.screen = screen;
+        frame(ForwardX::Forward(screen))
+    }
+    /// Display screen the client asked to have forwarded.
+    pub fn screen(&self) -> u8 {
+        self.screen
+    }
+    /// Server: Open a new channel for an X client connection.
+    pub fn open(&mut self) -> Result<ForwardXChannel, TerminalError> {
+        let mut shared = self.shared.lock().expect("FORWARD_X state");
+        let start = self.next;
+        while shared.channels.contains_key(&self.next) {
+            self.next = self.next.wrapping_add(1);
+            if self.next == start {
+                return Err(TerminalError::ConfigurationError);
+            }
+        }
+        let id = self.next;
+        self.next = self.next.wrapping_add(1);
+        shared.channels.insert(id, Channel::default());
+        shared.send(ForwardX::Open(id));
+        Ok(ForwardXChannel {
+            id,
+            shared: self.shared.clone(),
+        })
+    }
+    /// Client: Take the next channel opened by the peer, if no backend is
+    /// configured.
+    pub fn accept(&mut self) -> Option<ForwardXChannel> {
+        self.accepted.pop_front()
+    }
+    /// Apply a received FORWARD_X subnegotiation, returning frames to send
+    /// in reply.
+    pub fn handle(
+        &mut self,
+        argument: ForwardX,
+    ) -> Result<Vec<TelnetFrame>, TerminalError> {
+        match argument {
+            ForwardX::Forward(screen) => self.screen = screen,
+            ForwardX::Open(id) => return Ok(self.accept_channel(id)),
+            ForwardX::Close(id) => self.update(id, |channel| {
+                channel.closed = true;
+            }),
+            ForwardX::Data(id, data) => {
+                let mut shared = self.shared.lock().expect("FORWARD_X state");
+                match shared.channels.get_mut(&id) {
+                    Some(channel) if !channel.closed => {
+                        channel.incoming.extend(data);
+                        if let Some(waker) = channel.reader.take() {
+                            waker.wake();
+                        }
+                    },
+                    _ => return Ok(vec![frame(ForwardX::Close(id))]),
+                }
+            },
+            // No optional features are supported.
+            ForwardX::Options(_) => {
+                return Ok(vec![frame(ForwardX::Options(vec![0]))])
+            },
+            ForwardX::OptionData(_) => {},
+            ForwardX::Xoff(id) => self.update(id, |channel| {
+                channel.paused = true;
+            }),
+            ForwardX::Xon(id) => self.update(id, |channel| {
+                channel.paused = false;
+            }),
+        }
+        Ok(Vec::new())
+    }
+    /// Copy data between backend connections and their channels, and take
+    /// the frames written by channels since the last call.
+    pub fn poll_frames(&mut self, cx: &mut Context<'_>) -> Vec<TelnetFrame> {
+        for bridge in &mut self.bridges {
+            bridge.poll(cx);
+        }
+        self.bridges.retain(|bridge| !bridge.done);
+        let mut shared = self.shared.lock().expect("FORWARD_X state");
+        shared.waker = Some(cx.waker().clone());
+        shared.frames.drain(..).collect()
+    }
+    fn accept_channel(&mut self, id: u16) -> Vec<TelnetFrame> {
+        let stream = match &mut self.backend {
+            Some(backend) => match backend.connect(self.screen) {
+                Ok(stream) => Some(stream),
+                Err(_) => return vec![frame(ForwardX::Close(id))],
+            },
+            None => None,
+        };
+        {
+            let mut shared = self.shared.lock().expect("FORWARD_X state");
+            if shared.channels.contains_key(&id) {
+                return vec![frame(ForwardX::Close(id))];
+            }
+            shared.channels.insert(id, Channel::default());
+        }
+        let channel = ForwardXChannel {
+            id,
+            shared: self.shared.clone(),
+        };
+        match stream {
+            Some(stream) => self.bridges.push(Bridge {
+                channel,
+                stream,
+                to_stream: Vec::new(),
+                to_channel: Vec::new(),
+                done: false,
+            }),
+            None => self.accepted.push_back(channel),
+        }
+        Vec::new()
+    }
+    fn update<F: FnOnce(&mut Channel)>(&mut self, id: u16, update: F) {
+        let mut shared = self.shared.lock().expect("FORWARD_X state");
+        if let Some(channel) = shared.channels.get_mut(&id) {
+            update(channel);
+            if let Some(waker) = channel.reader.take() {
+                waker.wake();
+            }
+            if !channel.paused || channel.closed {
+                if let Some(waker) = channel.writer.take() {
+                    waker.wake();
+                }
+            }
+        }
+    }
+}
+
+impl Default for ForwardXMux {
+    fn default() -> Self {
+        ForwardXMux::new()
+    }
+}
+
+///
+/// Backend connecting to an X server listening on a local Unix socket, such
+/// as `/tmp/.X11-unix/X0`.
+///
+/// Sockets are non-blocking and polled, there is no reactor to wake the task
+/// when data arrives.
+///
+#[cfg(unix)]
+pub struct UnixSocketBackend {
+    prefix: std::path::PathBuf,
+}
+
+#[cfg(unix)]
+impl UnixSocketBackend {
+    /// Connect display `screen` at `<prefix><screen>`.
+    pub fn new<P: Into<std::path::PathBuf>>(prefix: P) -> UnixSocketBackend {
+        UnixSocketBackend {
+            prefix: prefix.into(),
+        }
+    }
+}
+
+#[cfg(unix)]
+impl ForwardXBackend for UnixSocketBackend {
+    fn connect(&mut self, screen: u8) -> io::Result<Box<dyn ForwardXStream>> {
+        let mut path = self.prefix.clone().into_os_string();
+        path.push(screen.to_string());
+        let stream = std::os::unix::net::UnixStream::connect(path)?;
+        stream.set_nonblocking(true)?;
+        Ok(Box::new(UnixSocketStream(stream)))
+    }
+}
+
+#[cfg(unix)]
+struct UnixSocketStream(std::os::unix::net::UnixStream);
+
+#[cfg(unix)]
+fn poll_io<T>(
+    cx: &mut Context<'_>,
+    result: io::Result<T>,
+) -> Poll<io::Result<T>> {
+    match result {
+        Err(ref error) if error.kind() == io::ErrorKind::WouldBlock => {
+            cx.waker().wake_by_ref();
+            Poll::Pending
+        },
+        result => Poll::Ready(result),
+    }
+}
+
+#[cfg(unix)]
+impl AsyncRead for UnixSocketStream {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        poll_io(cx, io::Read::read(&mut self.0, buf))
+    }
+}
+
+#[cfg(unix)]
+impl AsyncWrite for UnixSocketStream {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        poll_io(cx, io::Write::write(&mut self.0, buf))
+    }
+
+    fn poll_flush(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<io::Result<()>> {
+        poll_io(cx, io::Write::flush(&mut self.0))
+    }
+
+    fn poll_close(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+    ) -> Poll<io::Result<()>> {
+        Poll::Ready(self.0.shutdown(std::net::Shutdown::Write))
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::{ForwardXMux, UnixSocketBackend};
+    use crate::codec::{TelnetArgument, TelnetFrame};
+    use futures::task::{noop_waker, Context, Poll};
+    use futures::{AsyncRead, AsyncWrite};
+    use std::io::{Read, Write};
+    use std::os::unix::net::UnixListener;
+    use std::pin::Pin;
+    use std::{fs, process, thread, time};
+
+    fn deliver(frames: Vec<TelnetFrame>, mux: &mut ForwardXMux) {
+        for frame in frames {
+            if let TelnetFrame::Subnegotiate(_, TelnetArgument::ForwardX(arg)) =
+                frame
+            {
+                assert!(mux.handle(arg).unwrap().is_empty());
+            }
+        }
+    }
+
+    #[test]
+    fn test_unix_socket_echo() {
+        let dir = std::env::temp_dir()
+            .join(format!("telnet-forwardx-{}", process::id()));
+        let _ = fs::create_dir(&dir);
+        let _ = fs::remove_file(dir.join("X0"));
+        let listener = UnixListener::bind(dir.join("X0")).unwrap();
+        thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut buffer = [0u8; 64];
+            loop {
+                match stream.read(&mut buffer).unwrap() {
+                    0 => break,
+                    count => stream.write_all(&buffer[..count]).unwrap(),
+                }
+            }
+        });
+
+        let waker = noop_waker();
+        let mut cx = Context::from_waker(&waker);
+        let backend = UnixSocketBackend::new(dir.join("X"));
+        let mut client = ForwardXMux::new().with_backend(Box::new(backend));
+        let mut server = ForwardXMux::new();
+        deliver(vec![client.request(0)], &mut server);
+
+        let mut channel = server.open().unwrap();
+        let written = Pin::new(&mut channel).poll_write(&mut cx, b"xhello");
+        assert!(matches!(written, Poll::Ready(Ok(6))));
+
+        let mut received = Vec::new();
+        for _ in 0..1000 {
+            deliver(server.poll_frames(&mut cx), &mut client);
+            deliver(client.poll_frames(&mut cx), &mut server);
+            let mut buffer = [0u8; 16];
+            let read = Pin::new(&mut channel).poll_read(&mut cx, &mut buffer);
+            if let Poll::Ready(Ok(count)) = read {
+                received.extend_from_slice(&buffer[..count]);
+            }
+            if received.len() == 6 {
+                break;
+            }
+            thread::sleep(time::Duration::from_millis(1));
+        }
+        assert_eq!(&b"xhello"[..], &received[..]);
+
+        drop(channel);
+        assert_eq!(1, server.poll_frames(&mut cx).len());
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,282 @@
+//
+// Copyright 2019 Hans W. Uhlig. All Rights Reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+use crate::codec::{
+    ExtendedOptions, TelnetArgument, TelnetFrame, TelnetOption,
+};
+use crate::terminal::{OptionId, TerminalEndpoint};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use tracing::{event, Level};
+
+///
+/// What the terminal does once a peer crosses a negotiation limit.
+///
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum StormResponse {
+    /// Stop answering the offending negotiation until the window passes.
+    Ignore,
+    /// Report the incident and end the session.
+    Disconnect,
+}
+
+///
+/// Incident reported when a peer crosses a negotiation limit.
+///
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct NegotiationStorm {
+    /// Option negotiated too often, or `None` if the limit for all options
+    /// together was crossed.
+    pub option: Option<OptionId>,
+    /// Negotiation commands received within the window.
+    pub count: usize,
+    /// Length of the window.
+    pub window: Duration,
+    /// Response taken.
+    pub response: StormResponse,
+}
+
+///
+/// How a received negotiation command should be treated.
+///
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum GuardVerdict {
+    /// Below the limits, answer normally.
+    Answer,
+    /// Over a limit already reported, drop without answering.
+    Drop,
+    /// A limit was just crossed. Drop without answering, and report.
+    Storm(NegotiationStorm),
+}
+
+///
+/// Commands counted in the current window.
+///
+#[derive(Clone, Copy, Debug)]
+struct Counter {
+    start: Instant,
+    count: usize,
+}
+
+impl Counter {
+    /// Count one command at `now`, starting a new window once `window` has
+    /// passed. Returns the count so far in the window.
+    fn count(&mut self, now: Instant, window: Duration) -> usize {
+        if now.duration_since(self.start) >= window {
+            self.start = now;
+            self.count = 0;
+        }
+        self.count = self.count.saturating_add(1);
+        self.count
+    }
+}
+
+///
+/// Negotiation storm and loop protection.
+///
+/// A buggy or hostile peer can bounce `DO` and `WILL` back and forth
+/// forever, or flood negotiation commands. Every `DO`, `DONT`, `WILL` and
+/// `WONT` received, classic or EXOPL, is counted per option and in total
+/// over a fixed time window, except answers to our own pending requests,
+/// which the terminal always lets through. Once either count crosses its
+/// limit the incident is reported once, and the commands beyond it are
+/// dropped or the session is ended, depending on the `StormResponse`.
+/// Counts start over with the next window.
+///
+/// Options are counted sparsely, so memory stays bounded by the number of
+/// options the peer named.
+///
+#[derive(Clone, Debug)]
+pub struct NegotiationGuard {
+    window: Duration,
+    option_limit: usize,
+    total_limit: usize,
+    response: StormResponse,
+    options: HashMap<OptionId, Counter>,
+    total: Option<Counter>,
+}
+
+impl NegotiationGuard {
+    /// Create a new `NegotiationGuard` allowing 20 commands per option and
+    /// 200 in total every 10 seconds, ignoring commands beyond that.
+    pub fn new() -> NegotiationGuard {
+        NegotiationGuard {
+            window: Duration::from_secs(10),
+            option_limit: 20,
+            total_limit: 200,
+            response: StormResponse::Ignore,
+            options: HashMap::new(),
+            total: None,
+        }
+    }
+    /// Length of the window commands are counted over.
+    pub fn with_window(mut self, window: Duration) -> NegotiationGuard {
+        self.window = window;
+        self
+    }
+    /// Commands allowed for any one option within the window.
+    pub fn with_option_limit(mut self, limit: usize) -> NegotiationGuard {
+        self.option_limit = limit;
+        self
+    }
+    /// Commands allowed for all options together within the window.
+    pub fn with_total_limit(mut self, limit: usize) -> NegotiationGuard {
+        self.total_limit = limit;
+        self
+    }
+    /// What to do once a limit is crossed.
+    pub fn with_response(
+        mut self,
+        response: StormResponse,
+    ) -> NegotiationGuard {
+        self.response = response;
+        self
+    }
+    /// Response taken once a limit is crossed.
+    pub fn response(&self) -> StormResponse {
+        self.response
+    }
+    /// Count a received negotiation command for `option` at `now`.
+    pub fn check(&mut self, option: OptionId, now: Instant) -> GuardVerdict {
+        let window = self.window;
+        let new = Counter {
+            start: now,
+            count: 0,
+        };
+        let count =
+            self.options.entry(option).or_insert(new).count(now, window);
+        let total = self.total.get_or_insert(new).count(now, window);
+        if count == self.option_limit.saturating_add(1) {
+            self.storm(Some(option), count)
+        } else if total == self.total_limit.saturating_add(1) {
+            self.storm(None, total)
+        } else if count > self.option_limit || total > self.total_limit {
+            GuardVerdict::Drop
+        } else {
+            GuardVerdict::Answer
+        }
+    }
+    /// Count `frame` if it is a negotiation command, at the current time.
+    pub fn check_frame(&mut self, frame: &TelnetFrame) -> GuardVerdict {
+        match negotiation(frame) {
+            Some((_, option)) => self.check(option, Instant::now()),
+            None => GuardVerdict::Answer,
+        }
+    }
+    /// Log and build the report for a crossed limit.
+    fn storm(&self, option: Option<OptionId>, count: usize) -> GuardVerdict {
+        event!(
+            Level::WARN,
+            "Negotiation storm: {} commands for {:?} within {:?}, {:?}",
+            count,
+            option,
+            self.window,
+            self.response
+        );
+        GuardVerdict::Storm(NegotiationStorm {
+            option,
+            count,
+            window: self.window,
+            response: self.response,
+        })
+    }
+}
+
+impl Default for NegotiationGuard {
+    fn default() -> Self {
+        NegotiationGuard::new()
+    }
+}
+
+///
+/// Side and option a negotiation command names, if `frame` is one. `DO` and
+/// `DONT` apply to our side, `WILL` and `WONT` to the peer's.
+///
+pub(crate) fn negotiation(
+    frame: &TelnetFrame,
+) -> Option<(TerminalEndpoint, OptionId)> {
+    use TerminalEndpoint::{Local, Remote};
+    match frame {
+        TelnetFrame::Do(option) | TelnetFrame::Dont(option) => {
+            Some((Local, OptionId::from(*option)))
+        },
+        TelnetFrame::Will(option) | TelnetFrame::Wont(option) => {
+            Some((Remote, OptionId::from(*option)))
+        },
+        TelnetFrame::Subnegotiate(
+            TelnetOption::EXOPL,
+            TelnetArgument::ExtendedOptions(argument),
+        ) => match argument {
+            ExtendedOptions::Do(option) | ExtendedOptions::Dont(option) => {
+                Some((Local, OptionId::Extended(*option)))
+            },
+            ExtendedOptions::Will(option) | ExtendedOptions::Wont(option) => {
+                Some((Remote, OptionId::Extended(*option)))
+            },
+            ExtendedOptions::Subnegotiate(_, _) => None,
+        },
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{GuardVerdict, NegotiationGuard, StormResponse};
+    use crate::codec::TelnetOption;
+    use crate::terminal::OptionId;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn test_option_limit() {
+        let mut guard = NegotiationGuard::new()
+            .with_option_limit(2)
+            .with_response(StormResponse::Disconnect);
+        let echo = OptionId::from(TelnetOption::Echo);
+        let naws = OptionId::from(TelnetOption::NAWS);
+        let start = Instant::now();
+        assert_eq!(GuardVerdict::Answer, guard.check(echo, start));
+        assert_eq!(GuardVerdict::Answer, guard.check(echo, start));
+        match guard.check(echo, start) {
+            GuardVerdict::Storm(storm) => {
+                assert_eq!(Some(echo), storm.option);
+                assert_eq!(3, storm.count);
+                assert_eq!(StormResponse::Disconnect, storm.response);
+            },
+            verdict => panic!("Unexpected verdict {:?}", verdict),
+        }
+        assert_eq!(GuardVerdict::Drop, guard.check(echo, start));
+        assert_eq!(GuardVerdict::Answer, guard.check(naws, start));
+
+        let later = start + Duration::from_secs(10);
+        assert_eq!(GuardVerdict::Answer, guard.check(echo, later));
+    }
+
+    #[test]
+    fn test_total_limit() {
+        let mut guard = NegotiationGuard::new().with_total_limit(3);
+        let start = Instant::now();
+        for option in 0..3 {
+            let option = OptionId::from(TelnetOption::from(option));
+            assert_eq!(GuardVerdict::Answer, guard.check(option, start));
+        }
+        let option = OptionId::from(TelnetOption::from(3));
+        match guard.check(option, start) {
+            GuardVerdict::Storm(storm) => assert_eq!(None, storm.option),
+            verdict => panic!("Unexpected verdict {:?}", verdict),
+        }
+        let option = OptionId::from(TelnetOption::from(4));
+        assert_eq!(GuardVerdict::Drop, guard.check(option, start));
+    }
+}
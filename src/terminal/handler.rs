@@ -0,0 +1,249 @@
+//
+// Copyright 2019 Hans W. Uhlig. All Rights Reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+use crate::codec::{GenericMudMessage, TelnetOption};
+use crate::terminal::{
+    NegotiationStorm, NetworkVirtualTerminal, TerminalEndpoint, TerminalError,
+    TerminalOutput,
+};
+use futures::{AsyncRead, AsyncWrite, StreamExt};
+use std::time::Duration;
+
+///
+/// Typed callbacks for `NetworkVirtualTerminal` output, an alternative to
+/// matching on `TerminalOutput`.
+///
+/// Every method does nothing by default, so a handler only implements the
+/// events it cares about. Each call gets the terminal, so a handler can send
+/// text or negotiate options in response; `TerminalDriver` flushes whatever
+/// was queued once the call returns.
+///
+#[allow(unused_variables)]
+pub trait TerminalHandler<S>
+where
+    S: AsyncRead + AsyncWrite,
+{
+    /// The driver started, before any output is read.
+    fn on_connect(&mut self, terminal: &mut NetworkVirtualTerminal<S>) {}
+    /// The session ended.
+    fn on_disconnect(&mut self, terminal: &mut NetworkVirtualTerminal<S>) {}
+    /// Received binary data, and by default NVT text.
+    fn on_data(
+        &mut self,
+        terminal: &mut NetworkVirtualTerminal<S>,
+        data: &[u8],
+    ) {
+    }
+    /// Received NVT text, up to and including its line end.
+    fn on_text(
+        &mut self,
+        terminal: &mut NetworkVirtualTerminal<S>,
+        text: &str,
+    ) {
+        self.on_data(terminal, text.as_bytes())
+    }
+    /// Line completed by the line editor.
+    fn on_line(
+        &mut self,
+        terminal: &mut NetworkVirtualTerminal<S>,
+        line: &str,
+    ) {
+    }
+    /// Peer finished its output and is waiting for input.
+    fn on_prompt(&mut self, terminal: &mut NetworkVirtualTerminal<S>) {}
+    /// Peer reported its window size with NAWS.
+    fn on_naws(
+        &mut self,
+        terminal: &mut NetworkVirtualTerminal<S>,
+        width: u16,
+        height: u16,
+    ) {
+    }
+    /// Peer reported its terminal type.
+    fn on_ttype(
+        &mut self,
+        terminal: &mut NetworkVirtualTerminal<S>,
+        name: &str,
+    ) {
+    }
+    /// Peer sent a GMCP message, with its data as JSON text.
+    fn on_gmcp(
+        &mut self,
+        terminal: &mut NetworkVirtualTerminal<S>,
+        package: &str,
+        data: Option<&str>,
+    ) {
+    }
+    /// Negotiation enabled `option` on one side.
+    fn on_option_enabled(
+        &mut self,
+        terminal: &mut NetworkVirtualTerminal<S>,
+        endpoint: TerminalEndpoint,
+        option: TelnetOption,
+    ) {
+    }
+    /// Negotiation disabled `option` on one side.
+    fn on_option_disabled(
+        &mut self,
+        terminal: &mut NetworkVirtualTerminal<S>,
+        endpoint: TerminalEndpoint,
+        option: TelnetOption,
+    ) {
+    }
+    /// Peer sent Break.
+    fn on_break(&mut self, terminal: &mut NetworkVirtualTerminal<S>) {}
+    /// Peer sent Interrupt Process.
+    fn on_interrupt(&mut self, terminal: &mut NetworkVirtualTerminal<S>) {}
+    /// Peer sent Abort Output.
+    fn on_abort_output(&mut self, terminal: &mut NetworkVirtualTerminal<S>) {}
+    /// Peer sent Are You There.
+    fn on_are_you_there(&mut self, terminal: &mut NetworkVirtualTerminal<S>) {}
+    /// Peer sent Erase Character, without a line editor to apply it.
+    fn on_erase_character(&mut self, terminal: &mut NetworkVirtualTerminal<S>) {
+    }
+    /// Peer sent Erase Line, without a line editor to apply it.
+    fn on_erase_line(&mut self, terminal: &mut NetworkVirtualTerminal<S>) {}
+    /// Peer asked to be logged out, or announced the session is ending.
+    fn on_logout(&mut self, terminal: &mut NetworkVirtualTerminal<S>) {}
+    /// Peer crossed a negotiation limit. With `StormResponse::Disconnect`
+    /// the session ends right after.
+    fn on_negotiation_storm(
+        &mut self,
+        terminal: &mut NetworkVirtualTerminal<S>,
+        storm: NegotiationStorm,
+    ) {
+    }
+    /// Any output without a method of its own.
+    fn on_output(
+        &mut self,
+        terminal: &mut NetworkVirtualTerminal<S>,
+        output: TerminalOutput,
+    ) {
+    }
+    /// Checked after each call, return true to log the peer out and end the
+    /// session.
+    fn finished(&self) -> bool {
+        false
+    }
+}
+
+///
+/// Runs a `NetworkVirtualTerminal`, dispatching its output to a
+/// `TerminalHandler` until the session ends.
+///
+pub struct TerminalDriver<S, H>
+where
+    S: AsyncRead + AsyncWrite,
+{
+    terminal: NetworkVirtualTerminal<S>,
+    handler: H,
+    logout_timeout: Duration,
+}
+
+impl<S, H> TerminalDriver<S, H>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+    H: TerminalHandler<S>,
+{
+    /// Create a new `TerminalDriver` feeding `terminal` output to `handler`.
+    pub fn new(
+        terminal: NetworkVirtualTerminal<S>,
+        handler: H,
+    ) -> TerminalDriver<S, H> {
+        TerminalDriver {
+            terminal,
+            handler,
+            logout_timeout: Duration::from_secs(5),
+        }
+    }
+    /// How long to wait for the peer to answer LOGOUT once the handler is
+    /// finished.
+    pub fn with_logout_timeout(mut self, timeout: Duration) -> Self {
+        self.logout_timeout = timeout;
+        self
+    }
+    /// Terminal being driven.
+    pub fn terminal(&mut self) -> &mut NetworkVirtualTerminal<S> {
+        &mut self.terminal
+    }
+    /// Handler receiving the output.
+    pub fn handler(&mut self) -> &mut H {
+        &mut self.handler
+    }
+    /// Take back the terminal and handler.
+    pub fn into_inner(self) -> (NetworkVirtualTerminal<S>, H) {
+        (self.terminal, self.handler)
+    }
+    /// Dispatch output until the peer closes the connection or the handler
+    /// is finished. Output queued by each call is flushed after it.
+    pub async fn run(&mut self) -> Result<(), TerminalError> {
+        self.handler.on_connect(&mut self.terminal);
+        self.terminal.flush_outgoing().await?;
+        while !self.handler.finished() {
+            let output = match self.terminal.next().await {
+                Some(output) => output,
+                None => break,
+            };
+            self.dispatch(output);
+            self.terminal.flush_outgoing().await?;
+        }
+        if self.handler.finished() {
+            self.terminal.logout(None, self.logout_timeout).await?;
+        }
+        self.handler.on_disconnect(&mut self.terminal);
+        Ok(())
+    }
+    /// Call the handler method for `output`.
+    fn dispatch(&mut self, output: TerminalOutput) {
+        let terminal = &mut self.terminal;
+        let handler = &mut self.handler;
+        match output {
+            TerminalOutput::AsciiData(text) => handler.on_text(terminal, &text),
+            TerminalOutput::BinaryData(data) => {
+                handler.on_data(terminal, &data)
+            },
+            TerminalOutput::Line(line) => handler.on_line(terminal, &line),
+            TerminalOutput::PromptComplete => handler.on_prompt(terminal),
+            TerminalOutput::WindowSize(width, height) => {
+                handler.on_naws(terminal, width, height)
+            },
+            TerminalOutput::TerminalType(name) => {
+                handler.on_ttype(terminal, &name)
+            },
+            TerminalOutput::MudMessage(GenericMudMessage { package, data }) => {
+                handler.on_gmcp(terminal, &package, data.as_deref())
+            },
+            TerminalOutput::OptionEnabled(endpoint, option) => {
+                handler.on_option_enabled(terminal, endpoint, option)
+            },
+            TerminalOutput::OptionDisabled(endpoint, option) => {
+                handler.on_option_disabled(terminal, endpoint, option)
+            },
+            TerminalOutput::Break => handler.on_break(terminal),
+            TerminalOutput::InterruptProcess => handler.on_interrupt(terminal),
+            TerminalOutput::AbortOutput => handler.on_abort_output(terminal),
+            TerminalOutput::AreYouThere => handler.on_are_you_there(terminal),
+            TerminalOutput::EraseCharacter => {
+                handler.on_erase_character(terminal)
+            },
+            TerminalOutput::EraseLine => handler.on_erase_line(terminal),
+            TerminalOutput::Logout => handler.on_logout(terminal),
+            TerminalOutput::NegotiationStorm(storm) => {
+                handler.on_negotiation_storm(terminal, storm)
+            },
+            output => handler.on_output(terminal, output),
+        }
+    }
+}
@@ -0,0 +1,350 @@
+//
+// Copyright 2019 Hans W. Uhlig. All Rights Reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+use crate::codec::{
+    SendLocation, TelnetArgument, TelnetFrame, TelnetOption, TerminalLocation,
+    TerminalSpeed, TerminalType, TerminalUserId, WindowSize, XDisplayLocation,
+};
+use crate::terminal::TerminalEndpoint;
+
+///
+/// Terminal details exchanged with TERMINAL-TYPE, NAWS, TERMINAL-SPEED,
+/// X-DISPLAY-LOCATION, TTYLOC, SEND-LOCATION and TUID.
+///
+/// Holds the values we answer with when the peer asks, and the values the
+/// peer reported about itself.
+///
+#[derive(Clone, Debug, Default)]
+pub struct TerminalInfo {
+    local_type: Option<String>,
+    local_speed: Option<(u32, u32)>,
+    local_display: Option<String>,
+    remote_type: Option<String>,
+    remote_window_size: Option<(u16, u16)>,
+    remote_speed: Option<(u32, u32)>,
+    remote_display: Option<String>,
+    remote_location: Option<TerminalLocation>,
+    remote_location_name: Option<String>,
+    remote_user_id: Option<u32>,
+}
+
+impl TerminalInfo {
+    /// Create a new `TerminalInfo` with nothing to report.
+    pub fn new() -> TerminalInfo {
+        TerminalInfo::default()
+    }
+    /// Terminal type to answer `TTYPE SEND` with.
+    pub fn with_terminal_type(mut self, name: &str) -> TerminalInfo {
+        self.local_type = Some(name.to_string());
+        self
+    }
+    /// Transmit and receive speed to answer `TSPEED SEND` with.
+    pub fn with_speed(mut self, transmit: u32, receive: u32) -> TerminalInfo {
+        self.local_speed = Some((transmit, receive));
+        self
+    }
+    /// X display to answer `XDISPLOC SEND` with.
+    pub fn with_display(mut self, display: &str) -> TerminalInfo {
+        self.local_display = Some(display.to_string());
+        self
+    }
+    /// True if the peer may enable `option` on `side`. We offer our own
+    /// details once they are set, and take every report from the peer.
+    pub fn accepts(
+        &self,
+        side: TerminalEndpoint,
+        option: TelnetOption,
+    ) -> bool {
+        match (side, option) {
+            (TerminalEndpoint::Local, TelnetOption::TTYPE) => {
+                self.local_type.is_some()
+            },
+            (TerminalEndpoint::Local, TelnetOption::TSPEED) => {
+                self.local_speed.is_some()
+            },
+            (TerminalEndpoint::Local, TelnetOption::XDISPLOC) => {
+                self.local_display.is_some()
+            },
+            (TerminalEndpoint::Local, _) => false,
+            (TerminalEndpoint::Remote, option) => matches!(
+                option,
+                TelnetOption::TTYPE
+                    | TelnetOption::NAWS
+                    | TelnetOption::TSPEED
+                    | TelnetOption::XDISPLOC
+                    | TelnetOption::TTYLOC
+                    | TelnetOption::SNDLOC
+                    | TelnetOption::TUID
+            ),
+        }
+    }
+    /// Terminal type reported by the peer.
+    pub fn terminal_type(&self) -> Option<&str> {
+        self.remote_type.as_deref()
+    }
+    /// Width and height of the peer's window reported with NAWS.
+    pub fn window_size(&self) -> Option<(u16, u16)> {
+        self.remote_window_size
+    }
+    /// Transmit and receive speed reported by the peer.
+    pub fn speed(&self) -> Option<(u32, u32)> {
+        self.remote_speed
+    }
+    /// X display reported by the peer.
+    pub fn display(&self) -> Option<&str> {
+        self.remote_display.as_deref()
+    }
+    /// Terminal location reported by the peer.
+    pub fn location(&self) -> Option<&TerminalLocation> {
+        self.remote_location.as_ref()
+    }
+    /// Location text sent by the peer with SEND-LOCATION.
+    pub fn location_name(&self) -> Option<&str> {
+        self.remote_location_name.as_deref()
+    }
+    /// TACACS user identifier sent by the peer with TUID.
+    pub fn user_id(&self) -> Option<u32> {
+        self.remote_user_id
+    }
+    /// Build the frame asking the peer for its terminal type.
+    pub fn request_terminal_type(&self) -> TelnetFrame {
+        TelnetFrame::Subnegotiate(
+            TelnetOption::TTYPE,
+            TelnetArgument::TerminalType(TerminalType::Send),
+        )
+    }
+    /// Build the frame asking the peer for its terminal speed.
+    pub fn request_speed(&self) -> TelnetFrame {
+        TelnetFrame::Subnegotiate(
+            TelnetOption::TSPEED,
+            TelnetArgument::TerminalSpeed(TerminalSpeed::Send),
+        )
+    }
+    /// Build the frame asking the peer for its X display location.
+    pub fn request_display(&self) -> TelnetFrame {
+        TelnetFrame::Subnegotiate(
+            TelnetOption::XDISPLOC,
+            TelnetArgument::XDisplayLocation(XDisplayLocation::Send),
+        )
+    }
+    /// Build the frame telling the peer our terminal location. TTYLOC has
+    /// no request, the location is sent once the option is enabled.
+    pub fn send_location(&self, location: TerminalLocation) -> TelnetFrame {
+        TelnetFrame::Subnegotiate(
+            TelnetOption::TTYLOC,
+            TelnetArgument::TerminalLocation(location),
+        )
+    }
+    /// Build the frame telling the peer where our user is. Like TTYLOC,
+    /// SEND-LOCATION has no request.
+    pub fn send_location_name(&self, location: &str) -> TelnetFrame {
+        TelnetFrame::Subnegotiate(
+            TelnetOption::SNDLOC,
+            TelnetArgument::SendLocation(SendLocation(location.to_string())),
+        )
+    }
+    /// Build the frame telling the peer our window size.
+    pub fn send_window_size(&self, width: u16, height: u16) -> TelnetFrame {
+        TelnetFrame::Subnegotiate(
+            TelnetOption::NAWS,
+            TelnetArgument::WindowSize(WindowSize { width, height }),
+        )
+    }
+    /// Build the frame telling the peer our user's TACACS identifier.
+    pub fn send_user_id(&self, user: u32) -> TelnetFrame {
+        TelnetFrame::Subnegotiate(
+            TelnetOption::TUID,
+            TelnetArgument::TerminalUserId(TerminalUserId(user)),
+        )
+    }
+    /// Handle a received Terminal Type subnegotiation, producing the answer
+    /// to a `SEND` if we have one.
+    pub fn handle_terminal_type(
+        &mut self,
+        ttype: TerminalType,
+    ) -> Option<TelnetFrame> {
+        match ttype {
+            TerminalType::Is(name) => {
+                self.remote_type = Some(name);
+                None
+            },
+            TerminalType::Send => self.local_type.as_ref().map(|name| {
+                TelnetFrame::Subnegotiate(
+                    TelnetOption::TTYPE,
+                    TelnetArgument::TerminalType(TerminalType::Is(
+                        name.clone(),
+                    )),
+                )
+            }),
+        }
+    }
+    /// Handle a received NAWS subnegotiation.
+    pub fn handle_window_size(&mut self, size: WindowSize) {
+        self.remote_window_size = Some((size.width, size.height));
+    }
+    /// Handle a received Terminal Speed subnegotiation, producing the answer
+    /// to a `SEND` if we have one.
+    pub fn handle_speed(
+        &mut self,
+        speed: TerminalSpeed,
+    ) -> Option<TelnetFrame> {
+        match speed {
+            TerminalSpeed::Is { transmit, receive } => {
+                self.remote_speed = Some((transmit, receive));
+                None
+            },
+            TerminalSpeed::Send => {
+                self.local_speed.map(|(transmit, receive)| {
+                    TelnetFrame::Subnegotiate(
+                        TelnetOption::TSPEED,
+                        TelnetArgument::TerminalSpeed(TerminalSpeed::Is {
+                            transmit,
+                            receive,
+                        }),
+                    )
+                })
+            },
+        }
+    }
+    /// Handle a received X Display Location subnegotiation, producing the
+    /// answer to a `SEND` if we have one.
+    pub fn handle_display(
+        &mut self,
+        display: XDisplayLocation,
+    ) -> Option<TelnetFrame> {
+        match display {
+            XDisplayLocation::Is(display) => {
+                self.remote_display = Some(display);
+                None
+            },
+            XDisplayLocation::Send => {
+                self.local_display.as_ref().map(|display| {
+                    TelnetFrame::Subnegotiate(
+                        TelnetOption::XDISPLOC,
+                        TelnetArgument::XDisplayLocation(XDisplayLocation::Is(
+                            display.clone(),
+                        )),
+                    )
+                })
+            },
+        }
+    }
+    /// Handle a received TTYLOC subnegotiation.
+    pub fn handle_location(&mut self, location: TerminalLocation) {
+        self.remote_location = Some(location);
+    }
+    /// Handle a received SEND-LOCATION subnegotiation.
+    pub fn handle_location_name(&mut self, location: SendLocation) {
+        self.remote_location_name = Some(location.0);
+    }
+    /// Handle a received TUID subnegotiation.
+    pub fn handle_user_id(&mut self, user: TerminalUserId) {
+        self.remote_user_id = Some(user.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TerminalInfo;
+    use crate::codec::{
+        SendLocation, TelnetArgument, TelnetFrame, TelnetOption,
+        TerminalLocation, TerminalSpeed, TerminalType, TerminalUserId,
+        WindowSize, XDisplayLocation,
+    };
+    use std::net::Ipv4Addr;
+
+    #[test]
+    fn test_answers_send() {
+        let mut info = TerminalInfo::new()
+            .with_terminal_type("XTERM")
+            .with_speed(38400, 9600)
+            .with_display("host:0.0");
+        assert_eq!(
+            Some(TelnetFrame::Subnegotiate(
+                TelnetOption::TTYPE,
+                TelnetArgument::TerminalType(TerminalType::Is(
+                    "XTERM".to_string()
+                )),
+            )),
+            info.handle_terminal_type(TerminalType::Send)
+        );
+        assert_eq!(
+            Some(TelnetFrame::Subnegotiate(
+                TelnetOption::TSPEED,
+                TelnetArgument::TerminalSpeed(TerminalSpeed::Is {
+                    transmit: 38400,
+                    receive: 9600,
+                }),
+            )),
+            info.handle_speed(TerminalSpeed::Send)
+        );
+        assert_eq!(
+            Some(TelnetFrame::Subnegotiate(
+                TelnetOption::XDISPLOC,
+                TelnetArgument::XDisplayLocation(XDisplayLocation::Is(
+                    "host:0.0".to_string()
+                )),
+            )),
+            info.handle_display(XDisplayLocation::Send)
+        );
+    }
+
+    #[test]
+    fn test_no_answer_without_value() {
+        let mut info = TerminalInfo::new();
+        assert_eq!(None, info.handle_terminal_type(TerminalType::Send));
+        assert_eq!(None, info.handle_speed(TerminalSpeed::Send));
+        assert_eq!(None, info.handle_display(XDisplayLocation::Send));
+    }
+
+    #[test]
+    fn test_records_peer_details() {
+        let mut info = TerminalInfo::new().with_terminal_type("XTERM");
+        assert_eq!(None, info.terminal_type());
+        let name = TerminalType::Is("VT100".to_string());
+        assert_eq!(None, info.handle_terminal_type(name));
+        assert_eq!(Some("VT100"), info.terminal_type());
+
+        let speed = TerminalSpeed::Is {
+            transmit: 9600,
+            receive: 2400,
+        };
+        assert_eq!(None, info.handle_speed(speed));
+        assert_eq!(Some((9600, 2400)), info.speed());
+
+        let display = XDisplayLocation::Is("remote:1".to_string());
+        assert_eq!(None, info.handle_display(display));
+        assert_eq!(Some("remote:1"), info.display());
+
+        info.handle_window_size(WindowSize {
+            width: 80,
+            height: 24,
+        });
+        assert_eq!(Some((80, 24)), info.window_size());
+
+        let location = TerminalLocation::Internet {
+            gateway: Ipv4Addr::new(10, 0, 0, 1),
+            terminal: 7,
+        };
+        info.handle_location(location.clone());
+        assert_eq!(Some(&location), info.location());
+
+        info.handle_location_name(SendLocation("Lab 3".to_string()));
+        assert_eq!(Some("Lab 3"), info.location_name());
+
+        info.handle_user_id(TerminalUserId(42));
+        assert_eq!(Some(42), info.user_id());
+    }
+}
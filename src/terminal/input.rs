@@ -14,9 +14,6 @@
 // limitations under the License.
 //
 
-use crate::terminal::options::{TerminalOption, TerminalOptionState};
-use crate::terminal::{OptionStatus, TerminalEndpoint};
-
 ///
 /// Valid Input Events to the Terminal
 ///
@@ -0,0 +1,591 @@
+//
+// Copyright 2019 Hans W. Uhlig. All Rights Reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+use std::collections::VecDeque;
+
+/// Default start of packet character, Ctrl-A.
+pub const KERMIT_SOH: u8 = 0x01;
+
+const MAXL: u8 = 94;
+const TIME: u8 = 5;
+const EOL: u8 = b'\r';
+const QCTL: u8 = b'#';
+const RETRY_LIMIT: usize = 5;
+
+fn tochar(value: u8) -> u8 {
+    value + 32
+}
+
+fn unchar(value: u8) -> u8 {
+    value.wrapping_sub(32)
+}
+
+fn ctl(value: u8) -> u8 {
+    value ^ 64
+}
+
+/// Type 1 block check over everything from LEN through the last data byte.
+fn checksum(bytes: &[u8]) -> u8 {
+    let sum = bytes.iter().fold(0u32, |sum, byte| sum + u32::from(*byte));
+    tochar(((sum + ((sum & 192) >> 6)) & 63) as u8)
+}
+
+///
+/// A single Kermit packet: `MARK LEN SEQ TYPE DATA CHECK EOL`
+///
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct KermitPacket {
+    /// Sequence number, modulo 64.
+    pub seq: u8,
+    /// Packet type, for example `S`, `F`, `D`, `Z`, `B`, `Y`, `N` or `E`.
+    pub kind: u8,
+    /// Encoded packet data.
+    pub data: Vec<u8>,
+}
+
+impl KermitPacket {
+    /// Create a new `KermitPacket`.
+    pub fn new(seq: u8, kind: u8, data: Vec<u8>) -> KermitPacket {
+        KermitPacket {
+            seq: seq % 64,
+            kind,
+            data,
+        }
+    }
+    /// Encode packet, starting with the `mark` character.
+    pub fn encode(&self, mark: u8) -> Vec<u8> {
+        let mut packet = Vec::with_capacity(self.data.len() + 6);
+        packet.push(mark);
+        packet.push(tochar(self.data.len() as u8 + 3));
+        packet.push(tochar(self.seq));
+        packet.push(self.kind);
+        packet.extend_from_slice(&self.data);
+        let check = checksum(&packet[1..]);
+        packet.push(check);
+        packet.push(EOL);
+        packet
+    }
+}
+
+///
+/// Collects received bytes into `KermitPacket`s. Bytes outside a packet are
+/// ignored, and a new `mark` always starts a new packet.
+///
+#[derive(Clone, Debug)]
+struct KermitReader {
+    mark: u8,
+    buffer: Vec<u8>,
+}
+
+impl KermitReader {
+    fn new(mark: u8) -> KermitReader {
+        KermitReader {
+            mark,
+            buffer: Vec::new(),
+        }
+    }
+    /// Returns a packet once complete, or `Err` if its check failed.
+    fn push(&mut self, byte: u8) -> Option<Result<KermitPacket, ()>> {
+        if byte == self.mark {
+            self.buffer.clear();
+        } else if self.buffer.is_empty() {
+            return None;
+        }
+        self.buffer.push(byte);
+        if self.buffer.len() < 2 {
+            return None;
+        }
+        let length = usize::from(unchar(self.buffer[1]));
+        if length < 3 || length > usize::from(MAXL) {
+            self.buffer.clear();
+            return None;
+        }
+        if self.buffer.len() < length + 2 {
+            return None;
+        }
+        let packet = std::mem::take(&mut self.buffer);
+        let check = packet[length + 1];
+        if check != checksum(&packet[1..=length]) {
+            return Some(Err(()));
+        }
+        Some(Ok(KermitPacket::new(
+            unchar(packet[2]),
+            packet[3],
+            packet[4..=length].to_vec(),
+        )))
+    }
+}
+
+/// Control prefix `data`, stopping before exceeding `max` bytes. Returns
+/// the encoded bytes and the number of input bytes consumed.
+fn encode_data(data: &[u8], max: usize) -> (Vec<u8>, usize) {
+    let mut encoded = Vec::with_capacity(max);
+    let mut consumed = 0;
+    for byte in data {
+        let low = byte & 0x7F;
+        let prefixed = low < 32 || low == 127 || low == QCTL;
+        let width = if prefixed { 2 } else { 1 };
+        if encoded.len() + width > max {
+            break;
+        }
+        if low == QCTL {
+            encoded.extend_from_slice(&[QCTL, *byte]);
+        } else if prefixed {
+            encoded.extend_from_slice(&[QCTL, ctl(*byte)]);
+        } else {
+            encoded.push(*byte);
+        }
+        consumed += 1;
+    }
+    (encoded, consumed)
+}
+
+/// Undo control prefixing done by the peer.
+fn decode_data(data: &[u8], qctl: u8) -> Vec<u8> {
+    let mut decoded = Vec::with_capacity(data.len());
+    let mut bytes = data.iter();
+    while let Some(byte) = bytes.next() {
+        if *byte != qctl {
+            decoded.push(*byte);
+            continue;
+        }
+        if let Some(byte) = bytes.next() {
+            let low = byte & 0x7F;
+            if (63..=95).contains(&low) {
+                decoded.push(ctl(*byte));
+            } else {
+                decoded.push(*byte);
+            }
+        }
+    }
+    decoded
+}
+
+/// Send-Init parameters: MAXL TIME NPAD PADC EOL QCTL QBIN CHKT. Eighth bit
+/// prefixing is declined, the transfer expects TRANSMIT-BINARY.
+fn send_init() -> Vec<u8> {
+    vec![
+        tochar(MAXL),
+        tochar(TIME),
+        tochar(0),
+        ctl(0),
+        tochar(EOL),
+        QCTL,
+        b'N',
+        b'1',
+    ]
+}
+
+///
+/// A file sent or received by a Kermit transfer.
+///
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct KermitFile {
+    /// File name as announced in the File-Header packet.
+    pub name: String,
+    /// File contents.
+    pub data: Vec<u8>,
+}
+
+impl KermitFile {
+    /// Create a new `KermitFile`.
+    pub fn new(name: &str, data: Vec<u8>) -> KermitFile {
+        KermitFile {
+            name: name.to_string(),
+            data,
+        }
+    }
+}
+
+///
+/// Progress of a Kermit transfer.
+///
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum KermitStatus {
+    /// Packets are being exchanged.
+    Active,
+    /// All files were transferred.
+    Complete,
+    /// Transfer was cancelled, with the reason given.
+    Failed(String),
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum SendState {
+    Init,
+    File,
+    Data,
+    Eof,
+    Break,
+}
+
+///
+/// Sending side of a Kermit transfer. Packets are written as plain data, so
+/// the connection must be in TRANSMIT-BINARY mode in both directions.
+///
+#[derive(Clone, Debug)]
+pub struct KermitSender {
+    mark: u8,
+    reader: KermitReader,
+    files: VecDeque<KermitFile>,
+    current: Option<KermitFile>,
+    offset: usize,
+    state: SendState,
+    seq: u8,
+    last: Vec<u8>,
+    maxl: u8,
+    retries: usize,
+    status: KermitStatus,
+}
+
+impl KermitSender {
+    /// Create a new `KermitSender` for `files`.
+    pub fn new(files: Vec<KermitFile>) -> KermitSender {
+        KermitSender {
+            mark: KERMIT_SOH,
+            reader: KermitReader::new(KERMIT_SOH),
+            files: files.into_iter().collect(),
+            current: None,
+            offset: 0,
+            state: SendState::Init,
+            seq: 0,
+            last: Vec::new(),
+            maxl: MAXL,
+            retries: 0,
+            status: KermitStatus::Active,
+        }
+    }
+    /// Use `mark` instead of Ctrl-A to start packets.
+    pub fn with_start_of_packet(mut self, mark: u8) -> KermitSender {
+        self.mark = mark;
+        self.reader = KermitReader::new(mark);
+        self
+    }
+    /// Current transfer status.
+    pub fn status(&self) -> &KermitStatus {
+        &self.status
+    }
+    /// Begin the transfer, returning the Send-Init packet.
+    pub fn start(&mut self) -> Vec<u8> {
+        self.send(b'S', send_init())
+    }
+    /// Feed bytes received from the peer, returning bytes to send back.
+    pub fn input(&mut self, bytes: &[u8]) -> Vec<u8> {
+        let mut output = Vec::new();
+        for byte in bytes {
+            if self.status != KermitStatus::Active {
+                break;
+            }
+            match self.reader.push(*byte) {
+                Some(Ok(packet)) => output.extend(self.handle(packet)),
+                Some(Err(())) => output.extend(self.retry()),
+                None => {},
+            }
+        }
+        output
+    }
+    fn handle(&mut self, packet: KermitPacket) -> Vec<u8> {
+        let next = (self.seq + 1) % 64;
+        match packet.kind {
+            b'Y' if packet.seq == self.seq => self.advance(&packet.data),
+            // A NAK for the next packet implies an ACK for this one.
+            b'N' if packet.seq == next => self.advance(&[]),
+            b'E' => {
+                let message = decode_data(&packet.data, QCTL);
+                self.status = KermitStatus::Failed(
+                    String::from_utf8_lossy(&message).into_owned(),
+                );
+                Vec::new()
+            },
+            _ => self.retry(),
+        }
+    }
+    fn advance(&mut self, data: &[u8]) -> Vec<u8> {
+        self.retries = 0;
+        self.seq = (self.seq + 1) % 64;
+        match self.state {
+            SendState::Init => {
+                if let Some(maxl) = data.first() {
+                    self.maxl = unchar(*maxl).clamp(10, MAXL);
+                }
+                self.next_file()
+            },
+            SendState::File | SendState::Data => {
+                let file = self.current.as_ref().expect("file in progress");
+                if self.offset < file.data.len() {
+                    let max = usize::from(self.maxl) - 3;
+                    let (data, consumed) =
+                        encode_data(&file.data[self.offset..], max);
+                    self.offset += consumed;
+                    self.state = SendState::Data;
+                    self.send(b'D', data)
+                } else {
+                    self.state = SendState::Eof;
+                    self.send(b'Z', Vec::new())
+                }
+            },
+            SendState::Eof => self.next_file(),
+            SendState::Break => {
+                self.status = KermitStatus::Complete;
+                Vec::new()
+            },
+        }
+    }
+    fn next_file(&mut self) -> Vec<u8> {
+        self.current = self.files.pop_front();
+        self.offset = 0;
+        match &self.current {
+            Some(file) => {
+                let max = usize::from(self.maxl) - 3;
+                let (name, _) = encode_data(file.name.as_bytes(), max);
+                self.state = SendState::File;
+                self.send(b'F', name)
+            },
+            None => {
+                self.state = SendState::Break;
+                self.send(b'B', Vec::new())
+            },
+        }
+    }
+    fn send(&mut self, kind: u8, data: Vec<u8>) -> Vec<u8> {
+        self.last = KermitPacket::new(self.seq, kind, data).encode(self.mark);
+        self.last.clone()
+    }
+    fn retry(&mut self) -> Vec<u8> {
+        self.retries += 1;
+        if self.retries > RETRY_LIMIT {
+            self.status = KermitStatus::Failed("Too many retries".to_string());
+            return Vec::new();
+        }
+        self.last.clone()
+    }
+}
+
+///
+/// Receiving side of a Kermit transfer. Packets are read from plain data, so
+/// the connection must be in TRANSMIT-BINARY mode in both directions.
+///
+#[derive(Clone, Debug)]
+pub struct KermitReceiver {
+    mark: u8,
+    reader: KermitReader,
+    seq: u8,
+    last: Vec<u8>,
+    qctl: u8,
+    current: Option<KermitFile>,
+    files: Vec<KermitFile>,
+    status: KermitStatus,
+}
+
+impl KermitReceiver {
+    /// Create a new `KermitReceiver`.
+    pub fn new() -> KermitReceiver {
+        KermitReceiver {
+            mark: KERMIT_SOH,
+            reader: KermitReader::new(KERMIT_SOH),
+            seq: 0,
+            last: Vec::new(),
+            qctl: QCTL,
+            current: None,
+            files: Vec::new(),
+            status: KermitStatus::Active,
+        }
+    }
+    /// Use `mark` instead of Ctrl-A to start packets.
+    pub fn with_start_of_packet(mut self, mark: u8) -> KermitReceiver {
+        self.mark = mark;
+        self.reader = KermitReader::new(mark);
+        self
+    }
+    /// Current transfer status.
+    pub fn status(&self) -> &KermitStatus {
+        &self.status
+    }
+    /// Take the files received so far.
+    pub fn take_files(&mut self) -> Vec<KermitFile> {
+        std::mem::take(&mut self.files)
+    }
+    /// Feed bytes received from the peer, returning bytes to send back.
+    pub fn input(&mut self, bytes: &[u8]) -> Vec<u8> {
+        let mut output = Vec::new();
+        for byte in bytes {
+            if self.status != KermitStatus::Active {
+                break;
+            }
+            match self.reader.push(*byte) {
+                Some(Ok(packet)) => output.extend(self.handle(packet)),
+                Some(Err(())) => output.extend(self.nak()),
+                None => {},
+            }
+        }
+        output
+    }
+    fn handle(&mut self, packet: KermitPacket) -> Vec<u8> {
+        if packet.seq != self.seq {
+            if (packet.seq + 1) % 64 == self.seq && !self.last.is_empty() {
+                return self.last.clone();
+            }
+            return self.nak();
+        }
+        let ack = match packet.kind {
+            b'S' => {
+                if let Some(qctl) = packet.data.get(5) {
+                    self.qctl = *qctl;
+                }
+                send_init()
+            },
+            b'F' => {
+                let name = decode_data(&packet.data, self.qctl);
+                let name = String::from_utf8_lossy(&name);
+                self.current = Some(KermitFile::new(&name, Vec::new()));
+                Vec::new()
+            },
+            b'D' => match &mut self.current {
+                Some(file) => {
+                    file.data.extend(decode_data(&packet.data, self.qctl));
+                    Vec::new()
+                },
+                None => return self.error("Data without File-Header"),
+            },
+            b'Z' => {
+                // `D` in an End-Of-File packet asks us to discard the file.
+                match self.current.take() {
+                    Some(_) if packet.data.first() == Some(&b'D') => {},
+                    Some(file) => self.files.push(file),
+                    None => return self.error("End-Of-File without file"),
+                }
+                Vec::new()
+            },
+            b'B' => {
+                self.status = KermitStatus::Complete;
+                Vec::new()
+            },
+            b'E' => {
+                let message = decode_data(&packet.data, self.qctl);
+                self.status = KermitStatus::Failed(
+                    String::from_utf8_lossy(&message).into_owned(),
+                );
+                return Vec::new();
+            },
+            _ => return self.error("Unexpected packet type"),
+        };
+        self.last = KermitPacket::new(self.seq, b'Y', ack).encode(self.mark);
+        self.seq = (self.seq + 1) % 64;
+        self.last.clone()
+    }
+    fn nak(&self) -> Vec<u8> {
+        KermitPacket::new(self.seq, b'N', Vec::new()).encode(self.mark)
+    }
+    fn error(&mut self, message: &str) -> Vec<u8> {
+        self.status = KermitStatus::Failed(message.to_string());
+        let (data, _) = encode_data(message.as_bytes(), usize::from(MAXL) - 3);
+        KermitPacket::new(self.seq, b'E', data).encode(self.mark)
+    }
+}
+
+impl Default for KermitReceiver {
+    fn default() -> Self {
+        KermitReceiver::new()
+    }
+}
+
+///
+/// Kermit transfer running over a `NetworkVirtualTerminal`.
+///
+#[derive(Clone, Debug)]
+pub enum KermitTransfer {
+    /// Sending files to the peer.
+    Send(KermitSender),
+    /// Receiving files from the peer.
+    Receive(KermitReceiver),
+}
+
+impl KermitTransfer {
+    /// Current transfer status.
+    pub fn status(&self) -> &KermitStatus {
+        match self {
+            KermitTransfer::Send(sender) => sender.status(),
+            KermitTransfer::Receive(receiver) => receiver.status(),
+        }
+    }
+    /// True until the transfer completes or fails.
+    pub fn active(&self) -> bool {
+        *self.status() == KermitStatus::Active
+    }
+    /// Feed bytes received from the peer, returning bytes to send back.
+    pub fn input(&mut self, bytes: &[u8]) -> Vec<u8> {
+        match self {
+            KermitTransfer::Send(sender) => sender.input(bytes),
+            KermitTransfer::Receive(receiver) => receiver.input(bytes),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        KermitFile, KermitPacket, KermitReceiver, KermitSender, KermitStatus,
+        KERMIT_SOH,
+    };
+
+    #[test]
+    fn test_packet_encode() {
+        let packet = KermitPacket::new(0, b'Y', Vec::new());
+        assert_eq!(
+            &[KERMIT_SOH, b'#', b' ', b'Y', b'>', b'\r'][..],
+            &packet.encode(KERMIT_SOH)[..]
+        );
+    }
+
+    #[test]
+    fn test_transfer() {
+        let files = vec![
+            KermitFile::new(
+                "binary.dat",
+                (0..=255).cycle().take(700).collect(),
+            ),
+            KermitFile::new("empty.txt", Vec::new()),
+        ];
+        let mut sender = KermitSender::new(files.clone());
+        let mut receiver = KermitReceiver::new();
+        let mut wire = sender.start();
+        while !wire.is_empty() {
+            let reply = receiver.input(&wire);
+            wire = sender.input(&reply);
+        }
+        assert_eq!(&KermitStatus::Complete, sender.status());
+        assert_eq!(&KermitStatus::Complete, receiver.status());
+        assert_eq!(files, receiver.take_files());
+    }
+
+    #[test]
+    fn test_corrupted_packet_is_resent() {
+        let files = vec![KermitFile::new("a", b"hello".to_vec())];
+        let mut sender = KermitSender::new(files.clone());
+        let mut receiver = KermitReceiver::new();
+        let mut wire = sender.start();
+        let mut corrupt = true;
+        while !wire.is_empty() {
+            if corrupt && wire.len() > 8 {
+                wire[5] ^= 1;
+                corrupt = false;
+            }
+            let reply = receiver.input(&wire);
+            wire = sender.input(&reply);
+        }
+        assert_eq!(&KermitStatus::Complete, sender.status());
+        assert_eq!(files, receiver.take_files());
+    }
+}
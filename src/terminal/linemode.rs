@@ -0,0 +1,153 @@
+//
+// Copyright 2019 Hans W. Uhlig. All Rights Reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+use crate::codec::TelnetFrame;
+use crate::terminal::LineEditor;
+
+const BACKSPACE: u8 = 0x08;
+const CTRL_U: u8 = 0x15;
+const DELETE: u8 = 0x7F;
+
+///
+/// Result of typed input under `KludgeLineMode`.
+///
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LineModeOutput {
+    /// Characters to display locally.
+    pub echo: Vec<u8>,
+    /// Frames to send to the server.
+    pub frames: Vec<TelnetFrame>,
+}
+
+///
+/// Client side line mode for servers without the LINEMODE option, the way
+/// BSD telnet's "kludge linemode" works.
+///
+/// While the server does not both echo and suppress go ahead, typed input
+/// is edited and echoed locally and each line is sent when it is complete.
+/// Once the server has agreed to `WILL ECHO` and `WILL SGA` every character
+/// is sent as it is typed, with Backspace and Delete sent as `IAC EC` and
+/// Ctrl-U as `IAC EL` so the server can apply them.
+///
+pub struct KludgeLineMode {
+    editor: LineEditor,
+    local_editing: bool,
+}
+
+impl KludgeLineMode {
+    /// Create a new `KludgeLineMode`, starting with local editing.
+    pub fn new() -> KludgeLineMode {
+        KludgeLineMode {
+            editor: LineEditor::new(),
+            local_editing: true,
+        }
+    }
+    /// Edit lines locally with `editor`, for its history or completion.
+    pub fn with_editor(mut self, editor: LineEditor) -> KludgeLineMode {
+        self.editor = editor;
+        self
+    }
+    /// True while lines are edited locally.
+    pub fn local_editing(&self) -> bool {
+        self.local_editing
+    }
+    /// True if the server's ECHO and SGA state calls for local editing.
+    pub fn wants_local_editing(remote_echo: bool, remote_sga: bool) -> bool {
+        !(remote_echo && remote_sga)
+    }
+    /// Switch between local editing and character at a time. A partly
+    /// typed line is sent as it is when leaving local editing.
+    pub fn set_local_editing(&mut self, local: bool) -> Vec<TelnetFrame> {
+        if self.local_editing == local {
+            return Vec::new();
+        }
+        self.local_editing = local;
+        if local {
+            return Vec::new();
+        }
+        self.editor
+            .take_line()
+            .bytes()
+            .map(TelnetFrame::Data)
+            .collect()
+    }
+    /// Handle typed input.
+    pub fn input(&mut self, data: &[u8]) -> LineModeOutput {
+        let mut output = LineModeOutput::default();
+        for byte in data {
+            if !self.local_editing {
+                output.frames.push(match *byte {
+                    BACKSPACE | DELETE => TelnetFrame::EraseCharacter,
+                    CTRL_U => TelnetFrame::EraseLine,
+                    byte => TelnetFrame::Data(byte),
+                });
+                continue;
+            }
+            let edited = self.editor.input(*byte);
+            output.echo.extend(edited.echo);
+            if let Some(line) = edited.line {
+                let line = line.bytes().chain(b"\r\n".iter().copied());
+                output.frames.extend(line.map(TelnetFrame::Data));
+            }
+        }
+        output
+    }
+}
+
+impl Default for KludgeLineMode {
+    fn default() -> Self {
+        KludgeLineMode::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::KludgeLineMode;
+    use crate::codec::TelnetFrame;
+
+    fn data(text: &[u8]) -> Vec<TelnetFrame> {
+        text.iter().copied().map(TelnetFrame::Data).collect()
+    }
+
+    #[test]
+    fn test_local_editing() {
+        let mut linemode = KludgeLineMode::new();
+        let output = linemode.input(b"lx\x7fook");
+        assert_eq!(b"lx\x08 \x08ook".to_vec(), output.echo);
+        assert!(output.frames.is_empty());
+        let output = linemode.input(b"\r\0");
+        assert_eq!(data(b"look\r\n"), output.frames);
+    }
+
+    #[test]
+    fn test_character_mode() {
+        let mut linemode = KludgeLineMode::new();
+        linemode.input(b"ab");
+        assert!(KludgeLineMode::wants_local_editing(true, false));
+        let local = KludgeLineMode::wants_local_editing(true, true);
+        assert_eq!(data(b"ab"), linemode.set_local_editing(local));
+
+        let output = linemode.input(b"c\x08\x15");
+        assert!(output.echo.is_empty());
+        assert_eq!(
+            vec![
+                TelnetFrame::Data(b'c'),
+                TelnetFrame::EraseCharacter,
+                TelnetFrame::EraseLine,
+            ],
+            output.frames
+        );
+    }
+}
@@ -0,0 +1,171 @@
+//
+// Copyright 2019 Hans W. Uhlig. All Rights Reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+use crate::codec::{TelnetFrame, TelnetOption};
+
+///
+/// Progress of a LOGOUT exchange.
+///
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum LogoutStatus {
+    /// No LOGOUT exchange has taken place.
+    Idle,
+    /// We sent WILL or DO LOGOUT and are waiting for the answer.
+    Pending,
+    /// Peer asked to be logged out, the session should be closed.
+    Requested,
+    /// Peer agreed the session is ending.
+    Acknowledged,
+    /// Peer refused the LOGOUT.
+    Refused,
+}
+
+///
+/// Logout Option state [RFC727](http://www.iana.org/go/rfc727)
+///
+/// A server announces it is about to close the session with `WILL LOGOUT`
+/// and the client acknowledges with `DO LOGOUT`. A client asks to be logged
+/// out with `DO LOGOUT`, and the server agrees with `WILL LOGOUT`.
+///
+#[derive(Clone, Debug)]
+pub struct Logout {
+    status: LogoutStatus,
+}
+
+impl Logout {
+    /// Create a new `Logout`.
+    pub fn new() -> Logout {
+        Logout {
+            status: LogoutStatus::Idle,
+        }
+    }
+    /// Current exchange status.
+    pub fn status(&self) -> LogoutStatus {
+        self.status
+    }
+    /// True once the session should be closed.
+    pub fn finished(&self) -> bool {
+        matches!(
+            self.status,
+            LogoutStatus::Requested | LogoutStatus::Acknowledged
+        )
+    }
+    /// Server: Announce the session is about to be closed.
+    pub fn announce(&mut self) -> TelnetFrame {
+        self.status = LogoutStatus::Pending;
+        TelnetFrame::Will(TelnetOption::Logout)
+    }
+    /// Client: Ask the server to log us out.
+    pub fn request(&mut self) -> TelnetFrame {
+        self.status = LogoutStatus::Pending;
+        TelnetFrame::Do(TelnetOption::Logout)
+    }
+    /// Apply a received LOGOUT negotiation, returning the reply if any.
+    pub fn handle(&mut self, frame: &TelnetFrame) -> Option<TelnetFrame> {
+        let pending = self.status == LogoutStatus::Pending;
+        match frame {
+            TelnetFrame::Do(TelnetOption::Logout) if pending => {
+                self.status = LogoutStatus::Acknowledged;
+                None
+            },
+            TelnetFrame::Do(TelnetOption::Logout) => {
+                self.status = LogoutStatus::Requested;
+                Some(TelnetFrame::Will(TelnetOption::Logout))
+            },
+            TelnetFrame::Will(TelnetOption::Logout) if pending => {
+                self.status = LogoutStatus::Acknowledged;
+                None
+            },
+            TelnetFrame::Will(TelnetOption::Logout) => {
+                self.status = LogoutStatus::Acknowledged;
+                Some(TelnetFrame::Do(TelnetOption::Logout))
+            },
+            TelnetFrame::Dont(TelnetOption::Logout)
+            | TelnetFrame::Wont(TelnetOption::Logout)
+                if pending =>
+            {
+                self.status = LogoutStatus::Refused;
+                None
+            },
+            _ => None,
+        }
+    }
+}
+
+impl Default for Logout {
+    fn default() -> Self {
+        Logout::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Logout, LogoutStatus};
+    use crate::codec::{TelnetFrame, TelnetOption};
+
+    #[test]
+    fn test_server_announce() {
+        let mut logout = Logout::new();
+        let frame = logout.announce();
+        assert_eq!(TelnetFrame::Will(TelnetOption::Logout), frame);
+        assert_eq!(LogoutStatus::Pending, logout.status());
+        assert!(!logout.finished());
+
+        let reply = logout.handle(&TelnetFrame::Do(TelnetOption::Logout));
+        assert_eq!(None, reply);
+        assert_eq!(LogoutStatus::Acknowledged, logout.status());
+        assert!(logout.finished());
+    }
+
+    #[test]
+    fn test_client_request() {
+        let mut server = Logout::new();
+        let mut client = Logout::new();
+        let request = client.request();
+        assert_eq!(TelnetFrame::Do(TelnetOption::Logout), request);
+
+        let reply = server.handle(&request);
+        assert_eq!(Some(TelnetFrame::Will(TelnetOption::Logout)), reply);
+        assert_eq!(LogoutStatus::Requested, server.status());
+        assert!(server.finished());
+
+        assert_eq!(None, client.handle(&reply.unwrap()));
+        assert_eq!(LogoutStatus::Acknowledged, client.status());
+    }
+
+    #[test]
+    fn test_refused() {
+        let mut logout = Logout::new();
+        logout.request();
+        assert_eq!(
+            None,
+            logout.handle(&TelnetFrame::Wont(TelnetOption::Logout))
+        );
+        assert_eq!(LogoutStatus::Refused, logout.status());
+        assert!(!logout.finished());
+
+        let mut idle = Logout::new();
+        assert_eq!(None, idle.handle(&TelnetFrame::Dont(TelnetOption::Logout)));
+        assert_eq!(LogoutStatus::Idle, idle.status());
+    }
+
+    #[test]
+    fn test_unprompted_announce() {
+        let mut logout = Logout::new();
+        let reply = logout.handle(&TelnetFrame::Will(TelnetOption::Logout));
+        assert_eq!(Some(TelnetFrame::Do(TelnetOption::Logout)), reply);
+        assert_eq!(LogoutStatus::Acknowledged, logout.status());
+    }
+}
@@ -14,55 +14,301 @@
 // limitations under the License.
 //
 
-use crate::codec::{TelnetFrame, TelnetOption};
-use crate::terminal::TerminalError;
+use crate::codec::{
+    ExtendedOption, ExtendedOptions, TelnetArgument, TelnetFrame, TelnetOption,
+};
+use crate::terminal::{NegotiationPolicy, OptionRule, TerminalEndpoint};
+use std::collections::HashMap;
+use std::sync::Arc;
 
 ///
 /// Network Virtual Terminal Option Manager
 ///
+/// Tracks every option with the Q method of
+/// [RFC1143](http://www.iana.org/go/rfc1143), so negotiation never loops
+/// and requests made while one is in flight are queued. The peer may only
+/// enable an option on a side the `NegotiationPolicy` accepts it on, or
+/// that the terminal's handlers accept when the policy has no rule, unless
+/// we asked for it.
+///
+/// Options are stored sparsely, anything never negotiated is `(No, No)`.
+///
 pub struct OptionManager {
-    options: [(State, State); 255],
+    options: HashMap<OptionId, (State, State)>,
+    policy: Arc<NegotiationPolicy>,
 }
 
 impl OptionManager {
-    pub fn allow(&mut self, endpoint: Endpoint) {}
-    pub fn disallow(&mut self, endpoint: Endpoint) {}
-    /// Handle Incoming
-    #[rustfmt::skip]
-    fn handle(
+    /// Current (Local State, Remote State) of `option`
+    pub fn state(&self, option: OptionId) -> (State, State) {
+        self.options
+            .get(&option)
+            .copied()
+            .unwrap_or((State::No, State::No))
+    }
+    /// Mutable (Local State, Remote State) of `option`
+    pub fn state_mut(&mut self, option: OptionId) -> &mut (State, State) {
+        self.options.entry(option).or_insert((State::No, State::No))
+    }
+    /// Policy answering the peer's requests.
+    pub fn policy(&self) -> &NegotiationPolicy {
+        &self.policy
+    }
+    /// Answer the peer's requests with `policy`.
+    pub fn set_policy(&mut self, policy: Arc<NegotiationPolicy>) {
+        self.policy = policy;
+    }
+    /// Agree when the peer asks to enable `option` on `side`. A shared
+    /// policy is copied first, so other sessions are not affected.
+    pub fn allow(&mut self, side: TerminalEndpoint, option: OptionId) {
+        Arc::make_mut(&mut self.policy).set_rule(
+            side,
+            option,
+            OptionRule::Accept,
+        );
+    }
+    /// Refuse when the peer asks to enable `option` on `side`. An option
+    /// already enabled stays enabled.
+    pub fn disallow(&mut self, side: TerminalEndpoint, option: OptionId) {
+        Arc::make_mut(&mut self.policy).set_rule(
+            side,
+            option,
+            OptionRule::Refuse,
+        );
+    }
+    /// True if `option` is enabled on `side`.
+    pub fn enabled(&self, side: TerminalEndpoint, option: OptionId) -> bool {
+        self.side(side, option) == State::Yes
+    }
+    /// True while a request for `option` on `side` awaits its answer.
+    pub fn pending(&self, side: TerminalEndpoint, option: OptionId) -> bool {
+        !matches!(self.side(side, option), State::No | State::Yes)
+    }
+    /// Ask to enable `option` on `side`, returning the frame to send if any.
+    /// While a disable is in flight the request is queued behind it. Options
+    /// the policy never allows are not asked for.
+    pub fn enable(
+        &mut self,
+        side: TerminalEndpoint,
+        option: OptionId,
+    ) -> Option<TelnetFrame> {
+        if self.policy.rule(side, option) == Some(OptionRule::Never) {
+            return None;
+        }
+        let state = self.side_mut(side, option);
+        match *state {
+            State::No => {
+                *state = State::WantYes;
+                Some(Self::frame(side, option, true))
+            },
+            State::WantNo => {
+                *state = State::WantNoOpposite;
+                None
+            },
+            State::WantYesOpposite => {
+                *state = State::WantYes;
+                None
+            },
+            State::Yes | State::WantYes | State::WantNoOpposite => None,
+        }
+    }
+    /// Ask to disable `option` on `side`, returning the frame to send if
+    /// any. While an enable is in flight the request is queued behind it.
+    pub fn disable(
+        &mut self,
+        side: TerminalEndpoint,
+        option: OptionId,
+    ) -> Option<TelnetFrame> {
+        let state = self.side_mut(side, option);
+        match *state {
+            State::Yes => {
+                *state = State::WantNo;
+                Some(Self::frame(side, option, false))
+            },
+            State::WantYes => {
+                *state = State::WantYesOpposite;
+                None
+            },
+            State::WantNoOpposite => {
+                *state = State::WantNo;
+                None
+            },
+            State::No | State::WantNo | State::WantYesOpposite => None,
+        }
+    }
+    /// Handle an Incoming EXOPL negotiation the same way as a classic one.
+    /// Extended subnegotiation parameters are not negotiation and produce
+    /// no reply.
+    pub fn handle_extended(
+        &mut self,
+        argument: ExtendedOptions,
+    ) -> Option<TelnetFrame> {
+        let (action, option) = match argument {
+            ExtendedOptions::Do(option) => (Action::Do, option),
+            ExtendedOptions::Dont(option) => (Action::Dont, option),
+            ExtendedOptions::Will(option) => (Action::Will, option),
+            ExtendedOptions::Wont(option) => (Action::Wont, option),
+            ExtendedOptions::Subnegotiate(_, _) => return None,
+        };
+        self.handle(action, OptionId::Extended(option))
+    }
+    /// Handle a negotiation received from the peer, returning the reply if
+    /// any. `DO` and `DONT` apply to our side, `WILL` and `WONT` to the
+    /// peer's.
+    pub fn handle(
+        &mut self,
+        action: Action,
+        option: OptionId,
+    ) -> Option<TelnetFrame> {
+        self.handle_with_default(action, option, false)
+    }
+    /// Handle a negotiation like `handle`, agreeing to requests the policy
+    /// has no rule for if `accept` is true.
+    pub fn handle_with_default(
         &mut self,
-        source: Endpoint,
         action: Action,
-        option: TelnetOption,
-    ) -> Result<Option<TelnetFrame>, TerminalError> {
-        use self::{Action::*, Endpoint::*, State::*};
-        // @formatter:off
-
-        match (self.options[option].0, self.options[option].1, source, action) {
-        //  (Local State, Remote State, Source, Action) => {  }
-            (      Never,            _,      _,      _) => { Ok(Some(TelnetFrame::Wont(option))) }
-            (          _,        Never,      _,      _) => { Ok(Some(TelnetFrame::Dont(option))) }
-            (_,_,_) => {panic!("Incomplete")}
+        option: OptionId,
+        accept: bool,
+    ) -> Option<TelnetFrame> {
+        use self::State::*;
+        let side = match action {
+            Action::Do | Action::Dont => TerminalEndpoint::Local,
+            Action::Will | Action::Wont => TerminalEndpoint::Remote,
+        };
+        let enable = action == Action::Do || action == Action::Will;
+        let allowed = match self.policy.rule(side, option) {
+            Some(_) => self.policy.accepts(side, option),
+            None => accept,
+        };
+        let agree = Self::frame(side, option, true);
+        let refuse = Self::frame(side, option, false);
+        let state = self.side_mut(side, option);
+        let (next, reply) = match (*state, enable) {
+            (No, true) if allowed => (Yes, Some(agree)),
+            (No, true) => (No, Some(refuse)),
+            (Yes, true) | (No, false) => (*state, None),
+            // The peer answered our disable by enabling. RFC 1143 treats
+            // this as an error and the option stays disabled.
+            (WantNo, true) => (No, None),
+            (WantNoOpposite, true) | (WantYes, true) => (Yes, None),
+            (WantYesOpposite, true) => (WantNo, Some(refuse)),
+            (Yes, false) => (No, Some(refuse)),
+            (WantNo, false) => (No, None),
+            (WantNoOpposite, false) => (WantYes, Some(agree)),
+            (WantYes, false) | (WantYesOpposite, false) => (No, None),
+        };
+        *state = next;
+        reply
+    }
+    /// State of `option` on `side`
+    fn side(&self, side: TerminalEndpoint, option: OptionId) -> State {
+        let (local, remote) = self.state(option);
+        match side {
+            TerminalEndpoint::Local => local,
+            TerminalEndpoint::Remote => remote,
+        }
+    }
+    /// Mutable state of `option` on `side`
+    fn side_mut(
+        &mut self,
+        side: TerminalEndpoint,
+        option: OptionId,
+    ) -> &mut State {
+        let (local, remote) = self.state_mut(option);
+        match side {
+            TerminalEndpoint::Local => local,
+            TerminalEndpoint::Remote => remote,
         }
-        // @formatter:on
+    }
+    /// Frame enabling or disabling `option` on `side`
+    fn frame(
+        side: TerminalEndpoint,
+        option: OptionId,
+        enable: bool,
+    ) -> TelnetFrame {
+        option.frame(match (side, enable) {
+            (TerminalEndpoint::Local, true) => Action::Will,
+            (TerminalEndpoint::Local, false) => Action::Wont,
+            (TerminalEndpoint::Remote, true) => Action::Do,
+            (TerminalEndpoint::Remote, false) => Action::Dont,
+        })
     }
 }
 
 impl Default for OptionManager {
     fn default() -> OptionManager {
         OptionManager {
-            options: [(State::No, State::No); 255],
+            options: HashMap::new(),
+            policy: Arc::new(NegotiationPolicy::new()),
+        }
+    }
+}
+
+///
+/// Option tracked by the `OptionManager`, either a classic option or an
+/// extended option negotiated through EXOPL.
+///
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum OptionId {
+    /// Options 0 through 255
+    Classic(TelnetOption),
+    /// Options 256 through 511
+    Extended(ExtendedOption),
+}
+
+impl OptionId {
+    /// Option from its number, 0 through 511.
+    pub fn from_number(number: u16) -> Option<OptionId> {
+        match number {
+            0..=255 => {
+                Some(OptionId::Classic(TelnetOption::from(number as u8)))
+            },
+            _ => ExtendedOption::from_number(number).map(OptionId::Extended),
+        }
+    }
+    /// Option number, 0 through 511.
+    pub fn number(self) -> u16 {
+        match self {
+            OptionId::Classic(option) => u16::from(u8::from(option)),
+            OptionId::Extended(option) => option.number(),
+        }
+    }
+    /// Negotiation frame for this option. Extended options are wrapped in
+    /// an EXOPL subnegotiation.
+    pub fn frame(self, action: Action) -> TelnetFrame {
+        match self {
+            OptionId::Classic(option) => match action {
+                Action::Do => TelnetFrame::Do(option),
+                Action::Dont => TelnetFrame::Dont(option),
+                Action::Will => TelnetFrame::Will(option),
+                Action::Wont => TelnetFrame::Wont(option),
+            },
+            OptionId::Extended(option) => TelnetFrame::Subnegotiate(
+                TelnetOption::EXOPL,
+                TelnetArgument::ExtendedOptions(match action {
+                    Action::Do => ExtendedOptions::Do(option),
+                    Action::Dont => ExtendedOptions::Dont(option),
+                    Action::Will => ExtendedOptions::Will(option),
+                    Action::Wont => ExtendedOptions::Wont(option),
+                }),
+            ),
         }
     }
 }
 
-pub enum Endpoint {
-    // Local Sent message
-    Local,
-    // Remote Sent message
-    Remote,
+impl From<TelnetOption> for OptionId {
+    fn from(option: TelnetOption) -> OptionId {
+        OptionId::Classic(option)
+    }
 }
 
+impl From<ExtendedOption> for OptionId {
+    fn from(option: ExtendedOption) -> OptionId {
+        OptionId::Extended(option)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum Action {
     Do,
     Dont,
@@ -70,8 +316,8 @@ pub enum Action {
     Wont,
 }
 
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum State {
-    Never,
     No,
     WantNo,
     WantNoOpposite,
@@ -79,3 +325,114 @@ pub enum State {
     WantYes,
     WantYesOpposite,
 }
+
+///
+/// Outcome of asking to enable an option.
+///
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum NegotiationResult {
+    /// The option is enabled.
+    Accepted,
+    /// The peer refused, the option is disabled.
+    Refused,
+    /// No answer arrived in time, or the session ended first.
+    TimedOut,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Action, OptionId, OptionManager};
+    use crate::codec::{
+        ExtendedOption, ExtendedOptions, TelnetFrame, TelnetOption,
+    };
+    use crate::terminal::{NegotiationPolicy, TerminalEndpoint};
+    use std::sync::Arc;
+
+    #[test]
+    fn test_allow() {
+        let mut manager = OptionManager::default();
+        let naws = OptionId::from(TelnetOption::NAWS);
+        let reply = manager.handle(Action::Will, naws);
+        assert_eq!(Some(TelnetFrame::Dont(TelnetOption::NAWS)), reply);
+
+        manager.allow(TerminalEndpoint::Remote, naws);
+        let reply = manager.handle(Action::Will, naws);
+        assert_eq!(Some(TelnetFrame::Do(TelnetOption::NAWS)), reply);
+        assert!(manager.enabled(TerminalEndpoint::Remote, naws));
+        let reply = manager.handle(Action::Will, naws);
+        assert_eq!(None, reply);
+    }
+
+    #[test]
+    fn test_enable() {
+        let mut manager = OptionManager::default();
+        let gmcp = OptionId::from(TelnetOption::GMCP);
+        let request = manager.enable(TerminalEndpoint::Local, gmcp);
+        assert_eq!(Some(TelnetFrame::Will(TelnetOption::GMCP)), request);
+        assert_eq!(None, manager.enable(TerminalEndpoint::Local, gmcp));
+        assert!(manager.pending(TerminalEndpoint::Local, gmcp));
+
+        let reply = manager.handle(Action::Do, gmcp);
+        assert_eq!(None, reply);
+        assert!(!manager.pending(TerminalEndpoint::Local, gmcp));
+        assert!(manager.enabled(TerminalEndpoint::Local, gmcp));
+    }
+
+    #[test]
+    fn test_queued_disable() {
+        let mut manager = OptionManager::default();
+        let ttype = OptionId::from(TelnetOption::TTYPE);
+        manager.enable(TerminalEndpoint::Remote, ttype);
+        assert_eq!(None, manager.disable(TerminalEndpoint::Remote, ttype));
+
+        let reply = manager.handle(Action::Will, ttype);
+        let refuse = TelnetFrame::Dont(TelnetOption::TTYPE);
+        assert_eq!(Some(refuse), reply);
+        assert!(manager.pending(TerminalEndpoint::Remote, ttype));
+
+        let reply = manager.handle(Action::Wont, ttype);
+        assert_eq!(None, reply);
+        assert!(!manager.pending(TerminalEndpoint::Remote, ttype));
+        assert!(!manager.enabled(TerminalEndpoint::Remote, ttype));
+    }
+
+    #[test]
+    fn test_extended() {
+        let mut manager = OptionManager::default();
+        let option = ExtendedOption::from_number(300).unwrap();
+        let id = OptionId::from(option);
+        let request = manager.enable(TerminalEndpoint::Local, id);
+        assert_eq!(Some(id.frame(Action::Will)), request);
+        assert!(manager.pending(TerminalEndpoint::Local, id));
+
+        let argument = ExtendedOptions::Do(option);
+        let reply = manager.handle_extended(argument);
+        assert_eq!(None, reply);
+        assert!(manager.enabled(TerminalEndpoint::Local, id));
+
+        let argument = ExtendedOptions::Will(option);
+        let reply = manager.handle_extended(argument);
+        assert_eq!(Some(id.frame(Action::Dont)), reply);
+    }
+
+    #[test]
+    fn test_policy() {
+        let policy = Arc::new(
+            NegotiationPolicy::new()
+                .never(TerminalEndpoint::Local, TelnetOption::Logout),
+        );
+        let logout = OptionId::from(TelnetOption::Logout);
+        let naws = OptionId::from(TelnetOption::NAWS);
+        let mut manager = OptionManager::default();
+        manager.set_policy(policy.clone());
+        assert_eq!(None, manager.enable(TerminalEndpoint::Local, logout));
+        assert!(!manager.pending(TerminalEndpoint::Local, logout));
+        let reply = manager.handle(Action::Do, logout);
+        let refuse = TelnetFrame::Wont(TelnetOption::Logout);
+        assert_eq!(Some(refuse), reply);
+
+        manager.allow(TerminalEndpoint::Remote, naws);
+        assert!(manager.policy().accepts(TerminalEndpoint::Remote, naws));
+        assert!(!policy.accepts(TerminalEndpoint::Remote, naws));
+    }
+}
@@ -14,8 +14,22 @@
 // limitations under the License.
 //
 
-use super::option::TerminalOption;
-use crate::terminal::{OptionStatus, TerminalEndpoint};
+use crate::codec::{
+    DataEntry, GenericMudMessage, OutputMarking, SUPDUPDisplay, TN3270ERecord,
+    TelnetOption,
+};
+use crate::terminal::{KermitFile, NegotiationStorm};
+
+///
+/// Side of the connection an option applies to
+///
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum TerminalEndpoint {
+    /// We perform the option (`WILL`)
+    Local,
+    /// The peer performs the option (`DO`)
+    Remote,
+}
 
 ///
 /// Output generated from the Telnet Network Virtual Terminal
@@ -40,12 +54,43 @@ pub enum TerminalOutput {
     EraseLine,
     /// End of input for half-duplex connections.
     GoAhead,
+    /// Peer finished its output and is waiting for input, marked by GA or,
+    /// once negotiated, EOR. Buffered data is returned before this.
+    PromptComplete,
     /// Terminal Received Line of Ascii
     AsciiData(String),
     /// Terminal Received Binary Data
     BinaryData(Vec<u8>),
-    /// Current Status of option. May have changed
-    TerminalOptionStatus(TerminalEndpoint, TerminalOption, OptionStatus),
-    /// Received Option Argument
-    TerminalOptionArgument(TerminalEndpoint, TerminalOptionArgument),
+    /// Line completed by the line editor
+    Line(String),
+    /// Terminal Received a TN3270E Record
+    Record(TN3270ERecord),
+    /// Terminal Received SUPDUP Display Operations
+    Display(Vec<SUPDUPDisplay>),
+    /// Received a Data Entry Terminal command or transmitted field
+    DataEntry(DataEntry),
+    /// Peer sent its location with SEND-LOCATION
+    Location(String),
+    /// Peer sent its TACACS user identifier with TUID
+    UserId(u32),
+    /// Peer sent an output marking banner, or answered ours
+    OutputMarking(OutputMarking),
+    /// Terminal Received a File over Kermit
+    File(KermitFile),
+    /// Peer asked to be logged out, or announced the session is ending
+    Logout,
+    /// Typed input to display locally, echoed under RCTE
+    LocalEcho(Vec<u8>),
+    /// Peer reported its window width and height with NAWS
+    WindowSize(u16, u16),
+    /// Peer reported its terminal type with TTYPE
+    TerminalType(String),
+    /// Peer sent a GMCP message
+    MudMessage(GenericMudMessage),
+    /// Negotiation enabled an option on one side
+    OptionEnabled(TerminalEndpoint, TelnetOption),
+    /// Negotiation disabled an option on one side
+    OptionDisabled(TerminalEndpoint, TelnetOption),
+    /// Peer crossed a negotiation limit
+    NegotiationStorm(NegotiationStorm),
 }
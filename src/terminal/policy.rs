@@ -0,0 +1,258 @@
+//
+// Copyright 2019 Hans W. Uhlig. All Rights Reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+use crate::codec::TelnetOption;
+use crate::terminal::{OptionId, TerminalEndpoint, TerminalError};
+use std::collections::HashMap;
+use std::path::Path;
+use std::str::FromStr;
+use tracing::{event, Level};
+
+///
+/// How an option is negotiated on one side of the connection.
+///
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum OptionRule {
+    /// Refuse when the peer asks, but agree if we asked first.
+    Refuse,
+    /// Agree when the peer asks.
+    Accept,
+    /// Ask for the option at start up, and agree when the peer asks.
+    Offer,
+    /// Always refuse, and never ask for it ourselves.
+    Never,
+}
+
+///
+/// Which options are accepted, offered or refused on each side, decided
+/// before connecting and answered automatically during negotiation.
+///
+/// Options without a rule keep their default: those with a dedicated
+/// handler, such as ECHO or TRANSMIT-BINARY, follow it, and everything else
+/// is refused. A policy is usually built once and shared between sessions
+/// behind an `Arc`.
+///
+/// Policies can be written as text, one rule per line with the side
+/// (`local`, `remote` or `both`), the option name or number, and the rule.
+/// Everything after `#` is a comment.
+///
+/// ```text
+/// remote NAWS   accept
+/// local  GMCP   offer
+/// both   Logout never
+/// ```
+///
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct NegotiationPolicy {
+    rules: HashMap<(TerminalEndpoint, OptionId), OptionRule>,
+}
+
+impl NegotiationPolicy {
+    /// Create a new `NegotiationPolicy` without any rules.
+    pub fn new() -> NegotiationPolicy {
+        NegotiationPolicy::default()
+    }
+    /// Read a policy from the file at `path`.
+    pub fn load<P: AsRef<Path>>(
+        path: P,
+    ) -> Result<NegotiationPolicy, TerminalError> {
+        std::fs::read_to_string(path)?.parse()
+    }
+    /// Negotiate `option` on `side` with `rule`.
+    pub fn with_rule<O: Into<OptionId>>(
+        mut self,
+        side: TerminalEndpoint,
+        option: O,
+        rule: OptionRule,
+    ) -> NegotiationPolicy {
+        self.set_rule(side, option.into(), rule);
+        self
+    }
+    /// Agree when the peer asks to enable `option` on `side`.
+    pub fn accept<O: Into<OptionId>>(
+        self,
+        side: TerminalEndpoint,
+        option: O,
+    ) -> NegotiationPolicy {
+        self.with_rule(side, option, OptionRule::Accept)
+    }
+    /// Ask for `option` on `side` at start up.
+    pub fn offer<O: Into<OptionId>>(
+        self,
+        side: TerminalEndpoint,
+        option: O,
+    ) -> NegotiationPolicy {
+        self.with_rule(side, option, OptionRule::Offer)
+    }
+    /// Never enable `option` on `side`.
+    pub fn never<O: Into<OptionId>>(
+        self,
+        side: TerminalEndpoint,
+        option: O,
+    ) -> NegotiationPolicy {
+        self.with_rule(side, option, OptionRule::Never)
+    }
+    /// Replace the rule for `option` on `side`.
+    pub fn set_rule(
+        &mut self,
+        side: TerminalEndpoint,
+        option: OptionId,
+        rule: OptionRule,
+    ) {
+        self.rules.insert((side, option), rule);
+    }
+    /// Rule for `option` on `side`, if one was given.
+    pub fn rule(
+        &self,
+        side: TerminalEndpoint,
+        option: OptionId,
+    ) -> Option<OptionRule> {
+        self.rules.get(&(side, option)).copied()
+    }
+    /// True if the peer may enable `option` on `side`.
+    pub fn accepts(&self, side: TerminalEndpoint, option: OptionId) -> bool {
+        matches!(
+            self.rule(side, option),
+            Some(OptionRule::Accept) | Some(OptionRule::Offer)
+        )
+    }
+    /// Options to ask for at start up, local side first, in option order.
+    pub fn offers(&self) -> Vec<(TerminalEndpoint, OptionId)> {
+        let mut offers: Vec<_> = self
+            .rules
+            .iter()
+            .filter(|(_, rule)| **rule == OptionRule::Offer)
+            .map(|(key, _)| *key)
+            .collect();
+        offers.sort_by_key(|(side, option)| {
+            (*side == TerminalEndpoint::Remote, option.number())
+        });
+        offers
+    }
+    /// Parse one `side option rule` line.
+    fn parse_line(&mut self, line: &str) -> Option<()> {
+        let mut words = line.split_whitespace();
+        let sides: &[TerminalEndpoint] = match words.next()? {
+            "local" => &[TerminalEndpoint::Local],
+            "remote" => &[TerminalEndpoint::Remote],
+            "both" => &[TerminalEndpoint::Local, TerminalEndpoint::Remote],
+            _ => return None,
+        };
+        let option = parse_option(words.next()?)?;
+        let rule = match words.next()? {
+            "accept" => OptionRule::Accept,
+            "offer" => OptionRule::Offer,
+            "refuse" => OptionRule::Refuse,
+            "never" => OptionRule::Never,
+            _ => return None,
+        };
+        if words.next().is_some() {
+            return None;
+        }
+        for side in sides {
+            self.set_rule(*side, option, rule);
+        }
+        Some(())
+    }
+}
+
+impl FromStr for NegotiationPolicy {
+    type Err = TerminalError;
+
+    fn from_str(text: &str) -> Result<NegotiationPolicy, TerminalError> {
+        let mut policy = NegotiationPolicy::new();
+        for (number, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+            if policy.parse_line(line).is_none() {
+                event!(
+                    Level::WARN,
+                    "Invalid negotiation policy line {}: {:?}",
+                    number + 1,
+                    line
+                );
+                return Err(TerminalError::ConfigurationError);
+            }
+        }
+        Ok(policy)
+    }
+}
+
+/// Option from its number, or its `TelnetOption` name in any case.
+fn parse_option(name: &str) -> Option<OptionId> {
+    if let Ok(number) = name.parse::<u16>() {
+        return OptionId::from_number(number);
+    }
+    (0..=u8::MAX)
+        .map(TelnetOption::from)
+        .find(|option| format!("{:?}", option).eq_ignore_ascii_case(name))
+        .map(OptionId::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{NegotiationPolicy, OptionRule};
+    use crate::codec::{ExtendedOption, TelnetOption};
+    use crate::terminal::{OptionId, TerminalEndpoint};
+
+    #[test]
+    fn test_parse() {
+        let text = "
+            # Window size and terminal type from the client
+            remote NAWS accept
+            remote ttype offer
+            local  201   offer  # GMCP
+            both   Logout never
+            local  300   refuse
+        ";
+        let policy: NegotiationPolicy = text.parse().unwrap();
+        let expected = NegotiationPolicy::new()
+            .accept(TerminalEndpoint::Remote, TelnetOption::NAWS)
+            .offer(TerminalEndpoint::Remote, TelnetOption::TTYPE)
+            .offer(TerminalEndpoint::Local, TelnetOption::GMCP)
+            .never(TerminalEndpoint::Local, TelnetOption::Logout)
+            .never(TerminalEndpoint::Remote, TelnetOption::Logout)
+            .with_rule(
+                TerminalEndpoint::Local,
+                ExtendedOption::new(44),
+                OptionRule::Refuse,
+            );
+        assert_eq!(expected, policy);
+        assert_eq!(
+            vec![
+                (TerminalEndpoint::Local, OptionId::from(TelnetOption::GMCP)),
+                (
+                    TerminalEndpoint::Remote,
+                    OptionId::from(TelnetOption::TTYPE)
+                ),
+            ],
+            policy.offers()
+        );
+        assert!("remote NAWS maybe".parse::<NegotiationPolicy>().is_err());
+        assert!("remote NOPE accept".parse::<NegotiationPolicy>().is_err());
+    }
+
+    #[test]
+    fn test_accepts() {
+        let policy = NegotiationPolicy::new()
+            .accept(TerminalEndpoint::Remote, TelnetOption::NAWS);
+        let naws = OptionId::from(TelnetOption::NAWS);
+        assert!(policy.accepts(TerminalEndpoint::Remote, naws));
+        assert!(!policy.accepts(TerminalEndpoint::Local, naws));
+        assert_eq!(None, policy.rule(TerminalEndpoint::Local, naws));
+    }
+}
@@ -0,0 +1,122 @@
+//
+// Copyright 2019 Hans W. Uhlig. All Rights Reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+use crate::codec::{TelnetFrame, TelnetOption};
+
+///
+/// Suppress Go Ahead [RFC858](http://www.iana.org/go/rfc858) and End of
+/// Record [RFC885](http://www.iana.org/go/rfc885) policy, deciding how the
+/// end of output is marked.
+///
+/// Until SGA is negotiated the connection is half duplex, and each output
+/// transmission ends with `IAC GA`. Once we agree to send EOR, output ends
+/// with `IAC EOR` instead, which is what MUD clients use to spot prompts.
+/// Received GA, or EOR once the peer agreed to send it, complete a prompt.
+/// The negotiated state is kept by the terminal with every other option.
+///
+#[derive(Clone, Debug)]
+pub struct PromptMarker {
+    accept_sga: bool,
+    accept_eor: bool,
+}
+
+impl PromptMarker {
+    /// Create a new `PromptMarker`. Requests to enable SGA or EOR in either
+    /// direction are agreed to.
+    pub fn new() -> PromptMarker {
+        PromptMarker {
+            accept_sga: true,
+            accept_eor: true,
+        }
+    }
+    /// Agree to peer requests to suppress go ahead.
+    pub fn with_suppress_go_ahead(mut self, accept: bool) -> PromptMarker {
+        self.accept_sga = accept;
+        self
+    }
+    /// Agree to peer requests for end of record marking.
+    pub fn with_end_of_record(mut self, accept: bool) -> PromptMarker {
+        self.accept_eor = accept;
+        self
+    }
+    /// True if peer requests to enable `option`, SGA or EOR, are agreed to.
+    pub fn accepts(&self, option: TelnetOption) -> bool {
+        match option {
+            TelnetOption::SuppressGoAhead => self.accept_sga,
+            TelnetOption::EOR => self.accept_eor,
+            _ => false,
+        }
+    }
+    /// Frame to send at the end of an output transmission, if any, given
+    /// whether we suppress GA and mark output with EOR.
+    pub fn end_of_output(
+        suppress_go_ahead: bool,
+        end_of_record: bool,
+    ) -> Option<TelnetFrame> {
+        if end_of_record {
+            Some(TelnetFrame::EndOfRecord)
+        } else if suppress_go_ahead {
+            None
+        } else {
+            Some(TelnetFrame::GoAhead)
+        }
+    }
+    /// True if a received `frame` completes a prompt, given whether the peer
+    /// marks its output with EOR.
+    pub fn prompt_complete(frame: &TelnetFrame, end_of_record: bool) -> bool {
+        match frame {
+            TelnetFrame::GoAhead => true,
+            TelnetFrame::EndOfRecord => end_of_record,
+            _ => false,
+        }
+    }
+}
+
+impl Default for PromptMarker {
+    fn default() -> Self {
+        PromptMarker::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PromptMarker;
+    use crate::codec::{TelnetFrame, TelnetOption};
+
+    #[test]
+    fn test_end_of_output() {
+        assert_eq!(
+            Some(TelnetFrame::GoAhead),
+            PromptMarker::end_of_output(false, false)
+        );
+        assert_eq!(None, PromptMarker::end_of_output(true, false));
+        assert_eq!(
+            Some(TelnetFrame::EndOfRecord),
+            PromptMarker::end_of_output(true, true)
+        );
+    }
+
+    #[test]
+    fn test_prompt_complete() {
+        let marker = PromptMarker::new().with_end_of_record(false);
+        assert!(marker.accepts(TelnetOption::SuppressGoAhead));
+        assert!(!marker.accepts(TelnetOption::EOR));
+
+        let eor = TelnetFrame::EndOfRecord;
+        assert!(PromptMarker::prompt_complete(&TelnetFrame::GoAhead, false));
+        assert!(!PromptMarker::prompt_complete(&eor, false));
+        assert!(PromptMarker::prompt_complete(&eor, true));
+    }
+}
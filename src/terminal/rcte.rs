@@ -0,0 +1,148 @@
+//
+// Copyright 2019 Hans W. Uhlig. All Rights Reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+use crate::codec::consts::option::rcte::class;
+use crate::codec::{
+    RCTEClasses, TelnetArgument, TelnetFrame, TelnetOption, RCTE,
+};
+
+///
+/// Result of typing one character under RCTE.
+///
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct RemoteEchoOutput {
+    /// Characters to echo on the local display.
+    pub echo: Vec<u8>,
+    /// Buffered input to transmit to the host, empty until a break or
+    /// transmit character is typed.
+    pub transmit: Vec<u8>,
+}
+
+///
+/// Terminal side of Remote Controlled Transmission and Echoing
+/// [RFC726](http://www.iana.org/go/rfc726)
+///
+/// Input is buffered until a break or transmit character is typed, and
+/// echoed locally as the host's last command says. After a break character
+/// echoing stops until the host sends its next command, which then decides
+/// how the characters typed in the meantime are echoed.
+///
+#[derive(Clone, Debug)]
+pub struct RemoteEcho {
+    skip_break: bool,
+    skip_text: bool,
+    break_classes: RCTEClasses,
+    transmit_classes: RCTEClasses,
+    waiting: bool,
+    buffer: Vec<u8>,
+    typeahead: Vec<u8>,
+}
+
+impl RemoteEcho {
+    /// Create a new `RemoteEcho`. Everything is echoed, and format
+    /// effectors such as CR break and transmit the line.
+    pub fn new() -> RemoteEcho {
+        RemoteEcho {
+            skip_break: false,
+            skip_text: false,
+            break_classes: RCTEClasses(class::FORMAT_EFFECTORS),
+            transmit_classes: RCTEClasses::default(),
+            waiting: false,
+            buffer: Vec::new(),
+            typeahead: Vec::new(),
+        }
+    }
+    /// Current break classes.
+    pub fn break_classes(&self) -> RCTEClasses {
+        self.break_classes
+    }
+    /// Current transmit classes.
+    pub fn transmit_classes(&self) -> RCTEClasses {
+        self.transmit_classes
+    }
+    /// True while echo is suspended waiting for the host's next command.
+    pub fn waiting(&self) -> bool {
+        self.waiting
+    }
+    /// Host: Build the frame sending `command` to the terminal.
+    pub fn command(command: RCTE) -> TelnetFrame {
+        TelnetFrame::Subnegotiate(
+            TelnetOption::RCTE,
+            TelnetArgument::RCTE(command),
+        )
+    }
+    /// Handle a character typed by the user.
+    pub fn input(&mut self, byte: u8) -> RemoteEchoOutput {
+        let mut output = RemoteEchoOutput::default();
+        self.buffer.push(byte);
+        if self.waiting {
+            self.typeahead.push(byte);
+        } else {
+            self.echo(byte, &mut output.echo);
+        }
+        if self.break_classes.matches(byte)
+            || self.transmit_classes.matches(byte)
+        {
+            output.transmit = std::mem::take(&mut self.buffer);
+        }
+        output
+    }
+    /// Apply a command from the host, returning the typeahead to echo now.
+    pub fn handle(&mut self, command: RCTE) -> Vec<u8> {
+        if let RCTE::Command {
+            skip_break,
+            skip_text,
+            break_classes,
+            transmit_classes,
+        } = command
+        {
+            self.skip_break = skip_break;
+            self.skip_text = skip_text;
+            if let Some(classes) = break_classes {
+                self.break_classes = classes;
+            }
+            if let Some(classes) = transmit_classes {
+                self.transmit_classes = classes;
+            }
+        }
+        self.waiting = false;
+        let mut echo = Vec::new();
+        let typeahead = std::mem::take(&mut self.typeahead);
+        for (index, byte) in typeahead.iter().enumerate() {
+            self.echo(*byte, &mut echo);
+            if self.waiting {
+                self.typeahead.extend_from_slice(&typeahead[index + 1..]);
+                break;
+            }
+        }
+        echo
+    }
+    fn echo(&mut self, byte: u8, echo: &mut Vec<u8>) {
+        if self.break_classes.matches(byte) {
+            if !self.skip_break {
+                echo.push(byte);
+            }
+            self.waiting = true;
+        } else if !self.skip_text {
+            echo.push(byte);
+        }
+    }
+}
+
+impl Default for RemoteEcho {
+    fn default() -> Self {
+        RemoteEcho::new()
+    }
+}
@@ -14,17 +14,35 @@
 // limitations under the License.
 //
 
-use super::manager::{Action, Endpoint, OptionManager};
+use super::encryption::encryption_side;
+use super::guard::negotiation;
+use super::manager::{Action, OptionManager};
 use crate::codec::{
-    DecodeError, EncodeError, TelnetCodec, TelnetFrame, TelnetOption,
+    ComPortControl, FlowControl, GenericMudMessage, Kermit, OutputMarking,
+    SUPDUPDisplayDecoder, SUPDUPOutput, SUPDUPParameters, TN3270EAssembler,
+    TelnetArgument, TelnetCodec, TelnetFrame, TelnetOption, TerminalLocation,
+    TerminalSpeed, TerminalType, X3Setting, XDisplayLocation, RCTE,
+};
+use crate::terminal::{
+    AuthenticationRole, Authenticator, BinaryMode, ComPortClient,
+    ComPortServer, DataEntryForm, DataReader, Delay, Echo, EncryptionEvent,
+    Encryptor, FlowController, ForwardXChannel, ForwardXMux, GuardVerdict,
+    KermitFile, KermitReceiver, KermitSender, KermitTransfer, KludgeLineMode,
+    LineEditor, Logout, LogoutStatus, MacroTable, NegotiationGuard,
+    NegotiationPolicy, NegotiationResult, OptionId, OptionRule, PromptMarker,
+    RemoteEcho, SerialPort, StormResponse, TN3270EClient, TerminalEndpoint,
+    TerminalError, TerminalInfo, TerminalInput, TerminalOutput, X3Profile,
+    KERMIT_SOH,
 };
-use crate::consts;
-use crate::terminal::{TerminalError, TerminalInput, TerminalOutput};
 
-use bytes::{BufMut, BytesMut};
-use futures::task::{Context, Poll};
-use futures::{AsyncRead, AsyncWrite, Future, Sink, Stream};
+use futures::future::{self, Either};
+use futures::task::{Context, Poll, Waker};
+use futures::{ready, AsyncRead, AsyncWrite, Future, Sink, Stream, StreamExt};
+use std::collections::VecDeque;
 use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{event, Level};
 
 ///
 /// Telnet Network Virtual Terminal Endpoint
@@ -35,10 +53,82 @@ where
 {
     /// Telnet Codec
     codec: TelnetCodec<S>,
-    /// Terminal "Screen" Buffer
-    buffer: BytesMut,
+    /// Received data assembled into lines or binary chunks
+    reader: DataReader,
+    /// Transmit Binary state in each direction
+    binary: BinaryMode,
+    /// Echo state, and echo of received characters while we hold it
+    echo: Echo,
+    /// Line discipline for character at a time input
+    editor: Option<LineEditor>,
+    /// Client side local line editing while the server does not echo
+    linemode: Option<KludgeLineMode>,
     /// Current Option Negotiation State
     options: OptionManager,
+    /// Frames generated while reading, waiting to be sent
+    outgoing: VecDeque<TelnetFrame>,
+    /// Authentication Exchange State
+    authenticator: Option<Authenticator>,
+    /// Encryption Exchange State
+    encryptor: Option<Encryptor>,
+    /// Com Port Control Access Server
+    com_port_server: Option<ComPortServer<Box<dyn SerialPort>>>,
+    /// Next check of the access server's port
+    com_port_poll: Option<Delay>,
+    /// Com Port Control Client
+    com_port_client: Option<ComPortClient>,
+    /// Remote Flow Control State
+    flow: Option<FlowController>,
+    /// Data held back while output is suspended by flow control
+    held: VecDeque<TelnetFrame>,
+    /// Task waiting in `poll_ready` for output to resume
+    resume_waker: Option<Waker>,
+    /// Terminal Speed, X Display and Location details
+    info: TerminalInfo,
+    /// TN3270E Session Negotiation
+    tn3270e: Option<TN3270EClient>,
+    /// TN3270E Record being received
+    records: TN3270EAssembler,
+    /// SUPDUP display operation being received, while in SUPDUP mode
+    supdup: Option<SUPDUPDisplayDecoder>,
+    /// Kermit file transfer in progress
+    kermit: Option<KermitTransfer>,
+    /// Start of packet character announced by the peer
+    kermit_sop: u8,
+    /// True while the peer reports being in Kermit server mode
+    kermit_server: bool,
+    /// X11 connections forwarded over the session
+    forward_x: Option<ForwardXMux>,
+    /// LOGOUT exchange state
+    logout: Logout,
+    /// Byte Macro definitions in both directions
+    macros: MacroTable,
+    /// Received data left over from expanding a macro
+    expanded: VecDeque<u8>,
+    /// Remote Controlled Transmission and Echoing of typed input
+    rcte: Option<RemoteEcho>,
+    /// Typed input echoed locally, waiting to be returned
+    local_echo: Vec<u8>,
+    /// X.3 PAD parameters applied to typed input
+    x3pad: Option<X3Profile>,
+    /// Typed input held by the PAD until a forwarding character
+    pad_buffer: Vec<u8>,
+    /// Idle timer forwarding the PAD buffer
+    pad_idle: Option<Delay>,
+    /// Suppress Go Ahead and End of Record policy
+    prompt: PromptMarker,
+    /// Data sent since the last end of output marker
+    unmarked: bool,
+    /// Option changes and prompt markers waiting to be returned
+    events: VecDeque<TerminalOutput>,
+    /// How long `negotiate` waits for options to settle
+    negotiation_timeout: Duration,
+    /// Negotiation storm and loop protection
+    guard: NegotiationGuard,
+    /// A negotiation storm ended the session
+    disconnected: bool,
+    /// Error that ended the stream
+    error: Option<TerminalError>,
 }
 
 impl<S> NetworkVirtualTerminal<S>
@@ -46,152 +136,2337 @@ where
     S: AsyncRead + AsyncWrite,
 {
     ///
-    /// Create a new Network Virtual Terminal
+    /// Create a new Network Virtual Terminal
+    ///
+    pub fn new(stream: S) -> NetworkVirtualTerminal<S> {
+        let codec = TelnetCodec::new(stream);
+        let reader = DataReader::new(4096);
+        let options = OptionManager::default();
+        let outgoing = VecDeque::new();
+        NetworkVirtualTerminal {
+            codec,
+            reader,
+            binary: BinaryMode::new(),
+            echo: Echo::new(),
+            editor: None,
+            linemode: None,
+            options,
+            outgoing,
+            authenticator: None,
+            encryptor: None,
+            com_port_server: None,
+            com_port_poll: None,
+            com_port_client: None,
+            flow: None,
+            held: VecDeque::new(),
+            resume_waker: None,
+            info: TerminalInfo::new(),
+            tn3270e: None,
+            records: TN3270EAssembler::new(),
+            supdup: None,
+            kermit: None,
+            kermit_sop: KERMIT_SOH,
+            kermit_server: false,
+            forward_x: None,
+            logout: Logout::new(),
+            macros: MacroTable::new(),
+            expanded: VecDeque::new(),
+            rcte: None,
+            local_echo: Vec::new(),
+            x3pad: None,
+            pad_buffer: Vec::new(),
+            pad_idle: None,
+            prompt: PromptMarker::new(),
+            unmarked: false,
+            events: VecDeque::new(),
+            negotiation_timeout: Duration::from_secs(5),
+            guard: NegotiationGuard::new(),
+            disconnected: false,
+            error: None,
+        }
+    }
+    ///
+    /// Use `authenticator` to answer Authentication subnegotiations.
+    ///
+    pub fn with_authenticator(
+        mut self,
+        authenticator: Authenticator,
+    ) -> NetworkVirtualTerminal<S> {
+        self.authenticator = Some(authenticator);
+        self
+    }
+    ///
+    /// Server: Ask the client to authenticate with any registered mechanism.
+    /// `DO AUTHENTICATION` is sent first unless the option is enabled, and
+    /// the mechanisms are offered once the client agrees.
+    ///
+    pub fn request_authentication(&mut self) -> Result<(), TerminalError> {
+        let remote = TerminalEndpoint::Remote;
+        let option = OptionId::from(TelnetOption::Authentication);
+        let enabled = self.option_enabled(remote, option);
+        match &mut self.authenticator {
+            Some(authenticator) if enabled => {
+                let frame = authenticator.request()?;
+                self.outgoing.push_back(frame);
+            },
+            Some(authenticator) => {
+                if authenticator.role() != AuthenticationRole::Server {
+                    return Err(TerminalError::NegotiationError);
+                }
+                self.request_option(remote, option);
+            },
+            None => return Err(TerminalError::ConfigurationError),
+        }
+        Ok(())
+    }
+    ///
+    /// Server: Offer the mechanisms once the client enabled AUTHENTICATION.
+    ///
+    fn start_authentication(
+        &mut self,
+        before: (bool, bool),
+    ) -> Result<(), TerminalError> {
+        let (_, remote) = self.option_state(TelnetOption::Authentication);
+        if let (false, true, Some(authenticator)) =
+            (before.1, remote, &mut self.authenticator)
+        {
+            if authenticator.role() == AuthenticationRole::Server {
+                let frame = authenticator.request()?;
+                self.outgoing.push_back(frame);
+            }
+        }
+        Ok(())
+    }
+    ///
+    /// Use `encryptor` to answer Encryption subnegotiations.
+    ///
+    pub fn with_encryptor(
+        mut self,
+        encryptor: Encryptor,
+    ) -> NetworkVirtualTerminal<S> {
+        self.encryptor = Some(encryptor);
+        self
+    }
+    ///
+    /// Offer the peer every registered cipher for its output. The peer must
+    /// have agreed to `WILL ENCRYPT`.
+    ///
+    pub fn request_encryption(&mut self) -> Result<(), TerminalError> {
+        let option = TelnetOption::Encryption;
+        if !self.option_enabled(TerminalEndpoint::Remote, option) {
+            return Err(TerminalError::NegotiationError);
+        }
+        match &mut self.encryptor {
+            Some(encryptor) => {
+                let frame = encryptor.support();
+                self.outgoing.push_back(frame);
+                Ok(())
+            },
+            None => Err(TerminalError::ConfigurationError),
+        }
+    }
+    ///
+    /// Stop encrypting our output.
+    ///
+    pub fn end_encryption(&mut self) -> Result<(), TerminalError> {
+        let events = match &mut self.encryptor {
+            Some(encryptor) => encryptor.end(),
+            None => return Err(TerminalError::ConfigurationError),
+        };
+        self.apply_encryption(events)
+    }
+    ///
+    /// Apply encryption events in order. Frames are encoded immediately so
+    /// that a `START` goes out in the clear before the transformer changes.
+    ///
+    fn apply_encryption(
+        &mut self,
+        events: Vec<EncryptionEvent>,
+    ) -> Result<(), TerminalError> {
+        while let Some(frame) = self.outgoing.pop_front() {
+            self.codec.queue(frame)?;
+        }
+        for event in events {
+            match event {
+                EncryptionEvent::Send(frame) => self.codec.queue(frame)?,
+                EncryptionEvent::StartEncrypting(transformer) => {
+                    self.codec.set_encoder_transformer(Some(transformer))
+                },
+                EncryptionEvent::StopEncrypting => {
+                    self.codec.set_encoder_transformer(None)
+                },
+                EncryptionEvent::StartDecrypting(transformer) => {
+                    self.codec.set_decoder_transformer(Some(transformer))
+                },
+                EncryptionEvent::StopDecrypting => {
+                    self.codec.set_decoder_transformer(None)
+                },
+            }
+        }
+        Ok(())
+    }
+    ///
+    /// Act as a Com Port Control access server for `port`. Once the client
+    /// enables the option, the port is checked for data and state changes
+    /// while the terminal is polled.
+    ///
+    pub fn with_com_port_server(
+        mut self,
+        server: ComPortServer<Box<dyn SerialPort>>,
+    ) -> NetworkVirtualTerminal<S> {
+        self.com_port_server = Some(server);
+        self
+    }
+    ///
+    /// Act as a Com Port Control client.
+    ///
+    pub fn with_com_port_client(mut self) -> NetworkVirtualTerminal<S> {
+        self.com_port_client = Some(ComPortClient::new());
+        self
+    }
+    ///
+    /// Com Port Control client state, to build commands and read back the
+    /// line parameters confirmed by the access server.
+    ///
+    pub fn com_port_client(&self) -> Option<&ComPortClient> {
+        self.com_port_client.as_ref()
+    }
+    ///
+    /// Send a Com Port Control command built with `com_port_client`. The
+    /// access server must have agreed to `WILL COM-PORT-OPTION`.
+    ///
+    pub fn send_com_port_command(
+        &mut self,
+        control: ComPortControl,
+    ) -> Result<(), TerminalError> {
+        if self.com_port_client.is_none() {
+            return Err(TerminalError::ConfigurationError);
+        }
+        let option = TelnetOption::CPCO;
+        if !self.option_enabled(TerminalEndpoint::Local, option) {
+            return Err(TerminalError::NegotiationError);
+        }
+        self.outgoing.push_back(TelnetFrame::Subnegotiate(
+            option,
+            TelnetArgument::ComPortControl(control),
+        ));
+        Ok(())
+    }
+    ///
+    /// Access Server: Report line and modem state changes to the client and
+    /// relay serial data unless the client suspended the flow. The port is
+    /// checked again after its poll interval.
+    ///
+    fn poll_com_port(&mut self, cx: &mut Context<'_>) {
+        let option = TelnetOption::CPCO;
+        if !self.option_enabled(TerminalEndpoint::Remote, option) {
+            return;
+        }
+        let server = match &mut self.com_port_server {
+            Some(server) => server,
+            None => return,
+        };
+        let due = match &mut self.com_port_poll {
+            Some(delay) => Pin::new(delay).poll(cx).is_ready(),
+            None => true,
+        };
+        if !due {
+            return;
+        }
+        match server.poll_state() {
+            Ok(frames) => self.outgoing.extend(frames),
+            Err(error) => {
+                event!(Level::WARN, "Com Port state unavailable: {:?}", error)
+            },
+        }
+        match server.read_serial() {
+            Ok(frames) => self.outgoing.extend(frames),
+            Err(error) => {
+                event!(Level::WARN, "Com Port read failed: {:?}", error)
+            },
+        }
+        let mut delay = Delay::new(server.poll_interval());
+        if Pin::new(&mut delay).poll(cx).is_ready() {
+            cx.waker().wake_by_ref();
+        }
+        self.com_port_poll = Some(delay);
+    }
+    ///
+    /// Honor XON and XOFF from the peer once LFLOW is negotiated. The
+    /// option is accepted unless the negotiation policy says otherwise.
+    ///
+    pub fn with_flow_control(mut self) -> NetworkVirtualTerminal<S> {
+        self.flow = Some(FlowController::new());
+        self
+    }
+    ///
+    /// Change the remote flow control mode and tell the peer.
+    ///
+    pub fn set_flow_control(
+        &mut self,
+        mode: FlowControl,
+    ) -> Result<(), TerminalError> {
+        if self.flow.is_none() {
+            return Err(TerminalError::ConfigurationError);
+        }
+        if !self.flow_negotiated() {
+            return Err(TerminalError::NegotiationError);
+        }
+        if let Some(flow) = &mut self.flow {
+            let frame = flow.command(mode);
+            self.outgoing.push_back(frame);
+            self.resume_output();
+        }
+        Ok(())
+    }
+    ///
+    /// True while output is suspended by an XOFF from the peer, or by a
+    /// FLOWCONTROL-SUSPEND from the Com Port Control access server.
+    ///
+    pub fn output_paused(&self) -> bool {
+        let xoff = self.flow_negotiated()
+            && self.flow.as_ref().is_some_and(|flow| flow.paused());
+        let option = TelnetOption::CPCO;
+        let suspended = self.option_enabled(TerminalEndpoint::Local, option)
+            && self
+                .com_port_client
+                .as_ref()
+                .is_some_and(|client| client.suspended());
+        xoff || suspended
+    }
+    ///
+    /// True once flow control is configured and LFLOW is enabled on either
+    /// side.
+    ///
+    fn flow_negotiated(&self) -> bool {
+        use TerminalEndpoint::{Local, Remote};
+        self.flow.is_some()
+            && (self.option_enabled(Local, TelnetOption::LFLOW)
+                || self.option_enabled(Remote, TelnetOption::LFLOW))
+    }
+    ///
+    /// Release data held back while output was suspended and wake the task
+    /// waiting to send more.
+    ///
+    fn resume_output(&mut self) {
+        if !self.output_paused() {
+            self.outgoing.extend(self.held.drain(..));
+            if let Some(waker) = self.resume_waker.take() {
+                waker.wake();
+            }
+        }
+    }
+    ///
+    /// Queue `frame` for sending, or hold it back while output is suspended.
+    ///
+    fn send_frame(&mut self, frame: TelnetFrame) {
+        if self.output_paused() {
+            self.held.push_back(frame);
+        } else {
+            self.outgoing.push_back(frame);
+        }
+    }
+    ///
+    /// How long `negotiate`, `enable_local` and `enable_remote` wait for the
+    /// peer to answer.
+    ///
+    pub fn with_negotiation_timeout(
+        mut self,
+        timeout: Duration,
+    ) -> NetworkVirtualTerminal<S> {
+        self.negotiation_timeout = timeout;
+        self
+    }
+    ///
+    /// Answer the peer's option requests with `policy`, which may be shared
+    /// with other sessions. Options the policy has no rule for follow the
+    /// accept setting of their dedicated handler, if any, and are refused
+    /// otherwise.
+    ///
+    pub fn with_negotiation_policy(
+        mut self,
+        policy: Arc<NegotiationPolicy>,
+    ) -> NetworkVirtualTerminal<S> {
+        self.options.set_policy(policy);
+        self
+    }
+    ///
+    /// Agree when the peer asks to enable `option` on `side`, for this
+    /// session only.
+    ///
+    pub fn allow_option<O: Into<OptionId>>(
+        &mut self,
+        side: TerminalEndpoint,
+        option: O,
+    ) {
+        self.options.allow(side, option.into());
+    }
+    ///
+    /// Refuse when the peer asks to enable `option` on `side`, for this
+    /// session only. An option already enabled stays enabled.
+    ///
+    pub fn disallow_option<O: Into<OptionId>>(
+        &mut self,
+        side: TerminalEndpoint,
+        option: O,
+    ) {
+        self.options.disallow(side, option.into());
+    }
+    ///
+    /// Limit how often the peer may negotiate. By default negotiation beyond
+    /// the limits is ignored; with `StormResponse::Disconnect` the stream
+    /// returns the `NegotiationStorm` report, then closes the connection and
+    /// ends.
+    ///
+    pub fn with_negotiation_guard(
+        mut self,
+        guard: NegotiationGuard,
+    ) -> NetworkVirtualTerminal<S> {
+        self.guard = guard;
+        self
+    }
+    ///
+    /// Use `info` to answer Terminal Speed and X Display Location requests.
+    ///
+    pub fn with_terminal_info(
+        mut self,
+        info: TerminalInfo,
+    ) -> NetworkVirtualTerminal<S> {
+        self.info = info;
+        self
+    }
+    ///
+    /// Terminal details reported by the peer.
+    ///
+    pub fn terminal_info(&self) -> &TerminalInfo {
+        &self.info
+    }
+    ///
+    /// Ask the peer for its terminal type. The answer arrives as
+    /// `TerminalOutput::TerminalType`.
+    ///
+    pub fn request_terminal_type(&mut self) {
+        let frame = self.info.request_terminal_type();
+        self.outgoing.push_back(frame);
+    }
+    ///
+    /// Client: Tell the peer our window size with NAWS.
+    ///
+    pub fn send_window_size(&mut self, width: u16, height: u16) {
+        let frame = self.info.send_window_size(width, height);
+        self.outgoing.push_back(frame);
+    }
+    ///
+    /// Send a GMCP message, with `data` as JSON text.
+    ///
+    pub fn send_gmcp(&mut self, package: &str, data: Option<&str>) {
+        self.outgoing.push_back(TelnetFrame::Subnegotiate(
+            TelnetOption::GMCP,
+            TelnetArgument::GMCP(GenericMudMessage {
+                package: package.to_string(),
+                data: data.map(str::to_string),
+            }),
+        ));
+    }
+    ///
+    /// Ask the peer for its terminal speed. The peer must have agreed to
+    /// `WILL TSPEED`.
+    ///
+    pub fn request_terminal_speed(&mut self) -> Result<(), TerminalError> {
+        let option = TelnetOption::TSPEED;
+        if !self.option_enabled(TerminalEndpoint::Remote, option) {
+            return Err(TerminalError::NegotiationError);
+        }
+        let frame = self.info.request_speed();
+        self.outgoing.push_back(frame);
+        Ok(())
+    }
+    ///
+    /// Ask the peer for its X display location. The peer must have agreed
+    /// to `WILL XDISPLOC`.
+    ///
+    pub fn request_x_display_location(&mut self) -> Result<(), TerminalError> {
+        let option = TelnetOption::XDISPLOC;
+        if !self.option_enabled(TerminalEndpoint::Remote, option) {
+            return Err(TerminalError::NegotiationError);
+        }
+        let frame = self.info.request_display();
+        self.outgoing.push_back(frame);
+        Ok(())
+    }
+    ///
+    /// Tell the peer our terminal location. We must have agreed to
+    /// `WILL TTYLOC`.
+    ///
+    pub fn send_terminal_location(
+        &mut self,
+        location: TerminalLocation,
+    ) -> Result<(), TerminalError> {
+        let option = TelnetOption::TTYLOC;
+        if !self.option_enabled(TerminalEndpoint::Local, option) {
+            return Err(TerminalError::NegotiationError);
+        }
+        let frame = self.info.send_location(location);
+        self.outgoing.push_back(frame);
+        Ok(())
+    }
+    ///
+    /// Tell the peer where our user is with SEND-LOCATION. We must have
+    /// agreed to `WILL SNDLOC`.
+    ///
+    pub fn send_location_name(
+        &mut self,
+        location: &str,
+    ) -> Result<(), TerminalError> {
+        let option = TelnetOption::SNDLOC;
+        if !self.option_enabled(TerminalEndpoint::Local, option) {
+            return Err(TerminalError::NegotiationError);
+        }
+        let frame = self.info.send_location_name(location);
+        self.outgoing.push_back(frame);
+        Ok(())
+    }
+    ///
+    /// Tell the peer our user's TACACS identifier. We must have agreed to
+    /// `WILL TUID`.
+    ///
+    pub fn send_user_id(&mut self, user: u32) -> Result<(), TerminalError> {
+        let option = TelnetOption::TUID;
+        if !self.option_enabled(TerminalEndpoint::Local, option) {
+            return Err(TerminalError::NegotiationError);
+        }
+        let frame = self.info.send_user_id(user);
+        self.outgoing.push_back(frame);
+        Ok(())
+    }
+    ///
+    /// Host: Mark output with a banner. The terminal's answer arrives as
+    /// `TerminalOutput::OutputMarking`. The terminal must have agreed to
+    /// `WILL OUTMRK`.
+    ///
+    pub fn mark_output(
+        &mut self,
+        control: u8,
+        text: &str,
+    ) -> Result<(), TerminalError> {
+        let option = TelnetOption::OUTMRK;
+        if !self.option_enabled(TerminalEndpoint::Remote, option) {
+            return Err(TerminalError::NegotiationError);
+        }
+        self.outgoing.push_back(TelnetFrame::Subnegotiate(
+            option,
+            TelnetArgument::OutputMarking(OutputMarking::Banner {
+                control,
+                text: text.to_string(),
+            }),
+        ));
+        Ok(())
+    }
+    ///
+    /// Terminal: Accept or refuse the host's output marking banner. We must
+    /// have agreed to `WILL OUTMRK`.
+    ///
+    pub fn answer_output_marking(
+        &mut self,
+        accept: bool,
+    ) -> Result<(), TerminalError> {
+        let option = TelnetOption::OUTMRK;
+        if !self.option_enabled(TerminalEndpoint::Local, option) {
+            return Err(TerminalError::NegotiationError);
+        }
+        let answer = if accept {
+            OutputMarking::Acknowledge
+        } else {
+            OutputMarking::Refuse
+        };
+        self.outgoing.push_back(TelnetFrame::Subnegotiate(
+            option,
+            TelnetArgument::OutputMarking(answer),
+        ));
+        Ok(())
+    }
+    ///
+    /// Negotiate a TN3270E session with `client` when the host asks.
+    ///
+    pub fn with_tn3270e(
+        mut self,
+        client: TN3270EClient,
+    ) -> NetworkVirtualTerminal<S> {
+        self.tn3270e = Some(client);
+        self
+    }
+    ///
+    /// TN3270E session state.
+    ///
+    pub fn tn3270e(&self) -> Option<&TN3270EClient> {
+        self.tn3270e.as_ref()
+    }
+    ///
+    /// True once data is exchanged as TN3270E records.
+    ///
+    fn tn3270e_ready(&self) -> bool {
+        let option = TelnetOption::TN3270E;
+        self.option_enabled(TerminalEndpoint::Local, option)
+            && self.tn3270e.as_ref().is_some_and(|client| client.ready())
+    }
+    ///
+    /// Switch the data stream to the SUPDUP display protocol once SUPDUP
+    /// is enabled on either side. Our terminal `parameters` are sent first,
+    /// and received data is decoded into display operations from then on.
+    ///
+    pub fn start_supdup(
+        &mut self,
+        parameters: SUPDUPParameters,
+    ) -> Result<(), TerminalError> {
+        use TerminalEndpoint::{Local, Remote};
+        if !self.option_enabled(Local, TelnetOption::SUPDUP)
+            && !self.option_enabled(Remote, TelnetOption::SUPDUP)
+        {
+            return Err(TerminalError::NegotiationError);
+        }
+        let mut encoded = Vec::with_capacity(parameters.len());
+        parameters.encode(&mut encoded)?;
+        self.outgoing
+            .extend(encoded.into_iter().map(TelnetFrame::Data));
+        self.supdup = Some(SUPDUPDisplayDecoder::new());
+        Ok(())
+    }
+    ///
+    /// Describe our display to a SUPDUP-OUTPUT server.
+    ///
+    pub fn send_supdup_terminal(&mut self, parameters: SUPDUPParameters) {
+        self.outgoing.push_back(TelnetFrame::Subnegotiate(
+            TelnetOption::SUPDUPOutput,
+            TelnetArgument::SUPDUPOutput(SUPDUPOutput::Terminal(parameters)),
+        ));
+    }
+    ///
+    /// Send `files` to the peer with Kermit. TRANSMIT-BINARY must be enabled
+    /// in both directions, packets are exchanged as plain data.
+    ///
+    pub fn send_files(
+        &mut self,
+        files: Vec<KermitFile>,
+    ) -> Result<(), TerminalError> {
+        let (local, remote) = self.option_state(TelnetOption::TransmitBinary);
+        if !local || !remote {
+            return Err(TerminalError::NegotiationError);
+        }
+        let mut sender =
+            KermitSender::new(files).with_start_of_packet(self.kermit_sop);
+        let packet = sender.start();
+        self.outgoing
+            .extend(packet.into_iter().map(TelnetFrame::Data));
+        self.kermit = Some(KermitTransfer::Send(sender));
+        Ok(())
+    }
+    ///
+    /// Receive files sent by the peer with Kermit. Each file is returned as
+    /// `TerminalOutput::File` once complete.
+    ///
+    pub fn receive_files(&mut self) {
+        let receiver =
+            KermitReceiver::new().with_start_of_packet(self.kermit_sop);
+        self.kermit = Some(KermitTransfer::Receive(receiver));
+    }
+    ///
+    /// Kermit transfer started by `send_files` or `receive_files`.
+    ///
+    pub fn kermit_transfer(&self) -> Option<&KermitTransfer> {
+        self.kermit.as_ref()
+    }
+    ///
+    /// True while the peer reports being in Kermit server mode.
+    ///
+    pub fn kermit_server(&self) -> bool {
+        self.kermit_server
+    }
+    ///
+    /// Ask the peer to enter or leave Kermit server mode. KERMIT must be
+    /// enabled in both directions.
+    ///
+    pub fn request_kermit_server(
+        &mut self,
+        start: bool,
+    ) -> Result<(), TerminalError> {
+        if !self.kermit_negotiated() {
+            return Err(TerminalError::NegotiationError);
+        }
+        let argument = if start {
+            Kermit::RequestStartServer
+        } else {
+            Kermit::RequestStopServer
+        };
+        self.outgoing.push_back(TelnetFrame::Subnegotiate(
+            TelnetOption::Kermit,
+            TelnetArgument::Kermit(argument),
+        ));
+        Ok(())
+    }
+    ///
+    /// True once KERMIT is enabled in both directions, as RFC2840 requires
+    /// before any Kermit subnegotiation.
+    ///
+    fn kermit_negotiated(&self) -> bool {
+        let (local, remote) = self.option_state(TelnetOption::Kermit);
+        local && remote
+    }
+    ///
+    /// Multiplex X11 connections over the session with `mux`.
+    ///
+    pub fn with_forward_x(
+        mut self,
+        mux: ForwardXMux,
+    ) -> NetworkVirtualTerminal<S> {
+        self.forward_x = Some(mux);
+        self
+    }
+    ///
+    /// Client: Ask the server to forward X connections for `screen`. We must
+    /// have agreed to `WILL FORWARD_X`.
+    ///
+    pub fn request_forward_x(
+        &mut self,
+        screen: u8,
+    ) -> Result<(), TerminalError> {
+        let option = TelnetOption::ForwardX;
+        if !self.option_enabled(TerminalEndpoint::Local, option) {
+            return Err(TerminalError::NegotiationError);
+        }
+        match &mut self.forward_x {
+            Some(mux) => {
+                let frame = mux.request(screen);
+                self.outgoing.push_back(frame);
+                Ok(())
+            },
+            None => Err(TerminalError::ConfigurationError),
+        }
+    }
+    ///
+    /// Server: Open a new X channel to the client. The client must have
+    /// agreed to `WILL FORWARD_X`.
+    ///
+    pub fn open_x_channel(&mut self) -> Result<ForwardXChannel, TerminalError> {
+        let option = TelnetOption::ForwardX;
+        if !self.option_enabled(TerminalEndpoint::Remote, option) {
+            return Err(TerminalError::NegotiationError);
+        }
+        match &mut self.forward_x {
+            Some(mux) => mux.open(),
+            None => Err(TerminalError::ConfigurationError),
+        }
+    }
+    ///
+    /// Client: Take the next X channel opened by the server, when no backend
+    /// is configured.
+    ///
+    pub fn accept_x_channel(&mut self) -> Option<ForwardXChannel> {
+        self.forward_x.as_mut().and_then(|mux| mux.accept())
+    }
+    ///
+    /// Move data written to X channels into the outgoing frames.
+    ///
+    fn poll_forward_x(&mut self, cx: &mut Context<'_>) {
+        if let Some(mux) = &mut self.forward_x {
+            let frames = mux.poll_frames(cx);
+            self.outgoing.extend(frames);
+        }
+    }
+    ///
+    /// Principal established by a successful Authentication exchange.
+    ///
+    pub fn authenticated_principal(&self) -> Option<&str> {
+        self.authenticator
+            .as_ref()
+            .and_then(|authenticator| authenticator.principal())
+    }
+    ///
+    /// Use `macros` for Byte Macro negotiation, for example to accept the
+    /// peer's definitions or compress outgoing data. Macros are only
+    /// exchanged and expanded while BM is enabled.
+    ///
+    pub fn with_byte_macros(
+        mut self,
+        macros: MacroTable,
+    ) -> NetworkVirtualTerminal<S> {
+        self.macros = macros;
+        self
+    }
+    ///
+    /// Propose that `byte` stands for `replacement` in data we send. BM
+    /// must be enabled on our side.
+    ///
+    pub fn define_macro(
+        &mut self,
+        byte: u8,
+        replacement: &[u8],
+    ) -> Result<(), TerminalError> {
+        let option = TelnetOption::ByteMacro;
+        if !self.option_enabled(TerminalEndpoint::Local, option) {
+            return Err(TerminalError::NegotiationError);
+        }
+        let frame = self.macros.define(byte, replacement)?;
+        self.outgoing.push_back(frame);
+        Ok(())
+    }
+    ///
+    /// Stop using `byte` as a macro in data we send.
+    ///
+    pub fn cancel_macro(&mut self, byte: u8) {
+        if let Some(frame) = self.macros.cancel(byte) {
+            self.outgoing.push_back(frame);
+        }
+    }
+    ///
+    /// Terminal: Buffer and echo typed input as the host directs with RCTE.
+    ///
+    pub fn with_remote_echo(
+        mut self,
+        rcte: RemoteEcho,
+    ) -> NetworkVirtualTerminal<S> {
+        self.rcte = Some(rcte);
+        self
+    }
+    ///
+    /// Host: Tell the terminal how to echo and transmit the next input. We
+    /// must have agreed to `WILL RCTE`.
+    ///
+    pub fn send_rcte(&mut self, command: RCTE) -> Result<(), TerminalError> {
+        if !self.option_enabled(TerminalEndpoint::Local, TelnetOption::RCTE) {
+            return Err(TerminalError::NegotiationError);
+        }
+        self.outgoing.push_back(RemoteEcho::command(command));
+        Ok(())
+    }
+    ///
+    /// Act as an X.3 PAD with `profile`, answering the host's X.3-PAD
+    /// subnegotiations and applying echo, forwarding and idle timer
+    /// parameters to typed input.
+    ///
+    pub fn with_x3pad(
+        mut self,
+        profile: X3Profile,
+    ) -> NetworkVirtualTerminal<S> {
+        self.x3pad = Some(profile);
+        self
+    }
+    ///
+    /// X.3 PAD parameters in effect.
+    ///
+    pub fn x3pad(&self) -> Option<&X3Profile> {
+        self.x3pad.as_ref()
+    }
+    ///
+    /// Host: Set X.3 PAD parameters on the terminal. The terminal must have
+    /// agreed to `WILL X3-PAD`.
+    ///
+    pub fn set_x3pad(
+        &mut self,
+        settings: Vec<X3Setting>,
+    ) -> Result<(), TerminalError> {
+        let option = TelnetOption::X3PAD;
+        if !self.option_enabled(TerminalEndpoint::Remote, option) {
+            return Err(TerminalError::NegotiationError);
+        }
+        self.outgoing.push_back(X3Profile::set(settings));
+        Ok(())
+    }
+    ///
+    /// Host: Send a Data Entry Terminal screen. Commands the terminal sends
+    /// back arrive as `TerminalOutput::DataEntry`. The terminal must have
+    /// agreed to `WILL DET`.
+    ///
+    pub fn send_form(
+        &mut self,
+        form: DataEntryForm,
+    ) -> Result<(), TerminalError> {
+        if !self.option_enabled(TerminalEndpoint::Remote, TelnetOption::DET) {
+            return Err(TerminalError::NegotiationError);
+        }
+        self.outgoing.extend(form.frames());
+        Ok(())
+    }
+    ///
+    /// Queue program output for the peer, with NVT line ends unless binary
+    /// transmission is enabled. The end of output is marked when it is
+    /// flushed.
+    ///
+    pub fn send_text(&mut self, text: &str) {
+        let data = BinaryMode::encode_text(text, self.local_binary());
+        let frames = self.data_frames(&data);
+        self.unmarked |= !frames.is_empty();
+        if self.output_paused() {
+            self.held.extend(frames);
+        } else {
+            self.outgoing.extend(frames);
+        }
+    }
+    ///
+    /// Pass typed input through RCTE and the X.3 PAD, returning the bytes
+    /// to transmit now.
+    ///
+    fn typed(&mut self, data: &[u8]) -> Vec<u8> {
+        let remote_echo =
+            self.option_enabled(TerminalEndpoint::Remote, TelnetOption::RCTE);
+        let data = match &mut self.rcte {
+            Some(rcte) if remote_echo => {
+                let mut transmit = Vec::new();
+                for byte in data {
+                    let output = rcte.input(*byte);
+                    self.local_echo.extend(output.echo);
+                    transmit.extend(output.transmit);
+                }
+                transmit
+            },
+            _ => data.to_vec(),
+        };
+        let pad_enabled =
+            self.option_enabled(TerminalEndpoint::Local, TelnetOption::X3PAD);
+        let pad = match &self.x3pad {
+            Some(pad) if pad_enabled => pad,
+            _ => return data,
+        };
+        let mut transmit = Vec::new();
+        for byte in data {
+            if pad.echo() && !remote_echo {
+                self.local_echo.push(byte);
+            }
+            self.pad_buffer.push(byte);
+            if pad.forwards(byte) {
+                transmit.append(&mut self.pad_buffer);
+            }
+        }
+        self.pad_idle = match pad.idle_timer() {
+            Some(idle) if !self.pad_buffer.is_empty() => Some(Delay::new(idle)),
+            _ => None,
+        };
+        transmit
+    }
+    ///
+    /// Forward the PAD buffer once the idle timer expires.
+    ///
+    fn poll_pad_idle(&mut self, cx: &mut Context<'_>) {
+        let expired = match &mut self.pad_idle {
+            Some(delay) => Pin::new(delay).poll(cx).is_ready(),
+            None => false,
+        };
+        if expired {
+            self.pad_idle = None;
+            let data = std::mem::take(&mut self.pad_buffer);
+            self.outgoing.extend(self.data_frames(&data));
+        }
+    }
+    ///
+    /// Use `mode` to answer TRANSMIT-BINARY negotiation.
+    ///
+    pub fn with_binary_mode(
+        mut self,
+        mode: BinaryMode,
+    ) -> NetworkVirtualTerminal<S> {
+        self.binary = mode;
+        self
+    }
+    ///
+    /// Transmit Binary policy.
+    ///
+    pub fn binary_mode(&self) -> &BinaryMode {
+        &self.binary
+    }
+    ///
+    /// True if our output is binary.
+    ///
+    fn local_binary(&self) -> bool {
+        let option = TelnetOption::TransmitBinary;
+        self.options.enabled(TerminalEndpoint::Local, option.into())
+    }
+    ///
+    /// Ask for binary transmission in both directions.
+    ///
+    pub fn request_binary(&mut self) {
+        let option = OptionId::from(TelnetOption::TransmitBinary);
+        self.request_option(TerminalEndpoint::Local, option);
+        self.request_option(TerminalEndpoint::Remote, option);
+    }
+    ///
+    /// Use `echo` to answer ECHO negotiation.
+    ///
+    pub fn with_echo(mut self, echo: Echo) -> NetworkVirtualTerminal<S> {
+        self.echo = echo;
+        self
+    }
+    ///
+    /// Echo policy and hidden input state.
+    ///
+    pub fn echo(&self) -> &Echo {
+        &self.echo
+    }
+    ///
+    /// Server: Echo the client's input ourselves, so the client stops
+    /// echoing locally.
+    ///
+    pub fn server_echo(&mut self) {
+        let option = OptionId::from(TelnetOption::Echo);
+        self.request_option(TerminalEndpoint::Local, option);
+    }
+    ///
+    /// Server: Hand echo back to the client.
+    ///
+    pub fn client_echo(&mut self) {
+        let option = OptionId::from(TelnetOption::Echo);
+        self.outgoing
+            .extend(self.options.disable(TerminalEndpoint::Local, option));
+    }
+    ///
+    /// Server: Switch the client to character mode, where each character is
+    /// sent as it is typed and echoed by us.
+    ///
+    pub fn character_mode(&mut self) {
+        let echo = OptionId::from(TelnetOption::Echo);
+        let sga = OptionId::from(TelnetOption::SuppressGoAhead);
+        self.request_option(TerminalEndpoint::Local, echo);
+        self.request_option(TerminalEndpoint::Local, sga);
+    }
+    ///
+    /// Server: Edit received keystrokes with `editor`, which echoes them and
+    /// returns completed lines as `TerminalOutput::Line`. Usually paired
+    /// with `character_mode`.
+    ///
+    pub fn with_line_editor(
+        mut self,
+        editor: LineEditor,
+    ) -> NetworkVirtualTerminal<S> {
+        self.editor = Some(editor);
+        self
+    }
+    ///
+    /// Line editor in use, to set its prompt or read its history.
+    ///
+    pub fn line_editor(&mut self) -> Option<&mut LineEditor> {
+        self.editor.as_mut()
+    }
+    ///
+    /// Client: Edit typed lines locally with `linemode` until the server
+    /// agrees to both ECHO and SGA, then send each character as typed.
+    ///
+    pub fn with_kludge_linemode(
+        mut self,
+        linemode: KludgeLineMode,
+    ) -> NetworkVirtualTerminal<S> {
+        self.linemode = Some(linemode);
+        self
+    }
+    ///
+    /// True while typed lines are edited locally.
+    ///
+    pub fn local_editing(&self) -> bool {
+        self.linemode
+            .as_ref()
+            .is_some_and(|linemode| linemode.local_editing())
+    }
+    ///
+    /// Follow the server's ECHO and SGA state with the kludge line mode.
+    ///
+    fn update_linemode(&mut self) {
+        let (_, echo) = self.option_state(TelnetOption::Echo);
+        let (_, sga) = self.option_state(TelnetOption::SuppressGoAhead);
+        let local = KludgeLineMode::wants_local_editing(echo, sga);
+        if let Some(linemode) = &mut self.linemode {
+            self.outgoing.extend(linemode.set_local_editing(local));
+        }
+    }
+    ///
+    /// Frames to send for typed input, edited locally under the kludge line
+    /// mode, or passed through RCTE, the X.3 PAD and byte macros.
+    ///
+    fn typed_frames(&mut self, data: &[u8]) -> Vec<TelnetFrame> {
+        if let Some(linemode) = &mut self.linemode {
+            let output = linemode.input(data);
+            self.local_echo.extend(output.echo);
+            return output.frames;
+        }
+        let data = self.typed(data);
+        self.data_frames(&data)
+    }
+    ///
+    /// Frames sending `data`, compressed with byte macros while BM is
+    /// enabled on our side.
+    ///
+    fn data_frames(&self, data: &[u8]) -> Vec<TelnetFrame> {
+        let option = TelnetOption::ByteMacro;
+        if self.option_enabled(TerminalEndpoint::Local, option) {
+            return self.macros.encode(data);
+        }
+        data.iter().copied().map(TelnetFrame::Data).collect()
+    }
+    ///
+    /// Use `marker` to answer SGA and EOR negotiation.
+    ///
+    pub fn with_prompt_marker(
+        mut self,
+        marker: PromptMarker,
+    ) -> NetworkVirtualTerminal<S> {
+        self.prompt = marker;
+        self
+    }
+    ///
+    /// Suppress Go Ahead and End of Record policy.
+    ///
+    pub fn prompt_marker(&self) -> &PromptMarker {
+        &self.prompt
+    }
+    ///
+    /// Ask to run full duplex, suppressing GA in both directions.
+    ///
+    pub fn suppress_go_ahead(&mut self) {
+        let option = OptionId::from(TelnetOption::SuppressGoAhead);
+        self.request_option(TerminalEndpoint::Local, option);
+        self.request_option(TerminalEndpoint::Remote, option);
+    }
+    ///
+    /// Server: Offer to mark the end of each output transmission, such as a
+    /// prompt, with EOR instead of GA.
+    ///
+    pub fn offer_end_of_record(&mut self) {
+        let option = OptionId::from(TelnetOption::EOR);
+        self.request_option(TerminalEndpoint::Local, option);
+    }
+    ///
+    /// Client: Ask the server to mark prompts with EOR.
+    ///
+    pub fn request_end_of_record(&mut self) {
+        let option = OptionId::from(TelnetOption::EOR);
+        self.request_option(TerminalEndpoint::Remote, option);
+    }
+    ///
+    /// Frame marking the end of an output transmission, if one is due.
+    /// TN3270E records carry their own EOR, so nothing is added to them.
+    ///
+    fn end_of_output(&mut self) -> Option<TelnetFrame> {
+        if !std::mem::take(&mut self.unmarked) || self.tn3270e_ready() {
+            return None;
+        }
+        let (sga, _) = self.option_state(TelnetOption::SuppressGoAhead);
+        let (eor, _) = self.option_state(TelnetOption::EOR);
+        PromptMarker::end_of_output(sga, eor)
+    }
+    ///
+    /// Negotiated (local, remote) state of `option`.
+    ///
+    fn option_state(&self, option: TelnetOption) -> (bool, bool) {
+        let option = OptionId::from(option);
+        (
+            self.options.enabled(TerminalEndpoint::Local, option),
+            self.options.enabled(TerminalEndpoint::Remote, option),
+        )
+    }
+    ///
+    /// True if `option` is enabled on `side`.
+    ///
+    pub fn option_enabled<O: Into<OptionId>>(
+        &self,
+        side: TerminalEndpoint,
+        option: O,
+    ) -> bool {
+        self.options.enabled(side, option.into())
+    }
+    ///
+    /// True while our request for `option` on `side` awaits its answer.
+    ///
+    fn option_pending(&self, side: TerminalEndpoint, option: OptionId) -> bool {
+        self.options.pending(side, option)
+    }
+    ///
+    /// Ask to enable `option` on `side`. The request goes out with the next
+    /// flush, and `OptionEnabled` is returned once the peer agrees. Options
+    /// the policy never allows are not asked for.
+    ///
+    pub fn request_option<O: Into<OptionId>>(
+        &mut self,
+        side: TerminalEndpoint,
+        option: O,
+    ) {
+        self.outgoing
+            .extend(self.options.enable(side, option.into()));
+    }
+    ///
+    /// True if the peer may enable `option` on `side` when the policy has no
+    /// rule for it. Options with a handler are accepted on the side it
+    /// serves once it is configured. Kermit, DET, OUTMRK and SUPDUP are
+    /// always handled, so they are accepted on both sides. Everything else
+    /// is refused.
+    ///
+    fn default_accepts(
+        &self,
+        side: TerminalEndpoint,
+        option: TelnetOption,
+    ) -> bool {
+        match option {
+            TelnetOption::TransmitBinary => self.binary.accepts(),
+            TelnetOption::Echo => self.echo.accepts(),
+            TelnetOption::SuppressGoAhead | TelnetOption::EOR => {
+                self.prompt.accepts(option)
+            },
+            TelnetOption::LFLOW => self.flow.is_some(),
+            TelnetOption::Encryption => self.encryptor.is_some(),
+            TelnetOption::TN3270E => {
+                side == TerminalEndpoint::Local && self.tn3270e.is_some()
+            },
+            TelnetOption::TTYPE
+            | TelnetOption::NAWS
+            | TelnetOption::TSPEED
+            | TelnetOption::XDISPLOC
+            | TelnetOption::TTYLOC
+            | TelnetOption::SNDLOC
+            | TelnetOption::TUID => self.info.accepts(side, option),
+            TelnetOption::CPCO => match side {
+                TerminalEndpoint::Local => self.com_port_client.is_some(),
+                TerminalEndpoint::Remote => self.com_port_server.is_some(),
+            },
+            TelnetOption::ForwardX => self.forward_x.is_some(),
+            TelnetOption::ByteMacro => self.macros.accepts(side),
+            TelnetOption::RCTE => {
+                side == TerminalEndpoint::Remote && self.rcte.is_some()
+            },
+            TelnetOption::X3PAD => {
+                side == TerminalEndpoint::Local && self.x3pad.is_some()
+            },
+            TelnetOption::Kermit
+            | TelnetOption::DET
+            | TelnetOption::OUTMRK
+            | TelnetOption::SUPDUP => true,
+            TelnetOption::Authentication => self
+                .authenticator
+                .as_ref()
+                .is_some_and(|auth| auth.role().endpoint() == side),
+            _ => false,
+        }
+    }
+    ///
+    /// Answer a received negotiation, then apply the behaviour that follows
+    /// the option's state, returning data buffered under the previous
+    /// transmission mode if any.
+    ///
+    fn receive_negotiation(
+        &mut self,
+        action: Action,
+        option: TelnetOption,
+    ) -> Result<Option<TerminalOutput>, TerminalError> {
+        let before = self.option_state(option);
+        let side = match action {
+            Action::Do | Action::Dont => TerminalEndpoint::Local,
+            Action::Will | Action::Wont => TerminalEndpoint::Remote,
+        };
+        let accept = self.default_accepts(side, option);
+        let reply =
+            self.options
+                .handle_with_default(action, option.into(), accept);
+        self.outgoing.extend(reply);
+        self.report_option(option, before);
+        match option {
+            TelnetOption::TransmitBinary => {
+                let (_, remote) = self.option_state(option);
+                Ok(self.reader.set_binary(remote))
+            },
+            TelnetOption::Echo | TelnetOption::SuppressGoAhead => {
+                self.update_linemode();
+                Ok(None)
+            },
+            TelnetOption::LFLOW => {
+                self.resume_output();
+                Ok(None)
+            },
+            TelnetOption::Authentication => {
+                self.start_authentication(before)?;
+                Ok(None)
+            },
+            _ => Ok(None),
+        }
+    }
+    ///
+    /// Queue `OptionEnabled` or `OptionDisabled` for each side of `option`
+    /// that changed since `before`.
+    ///
+    fn report_option(&mut self, option: TelnetOption, before: (bool, bool)) {
+        use TerminalEndpoint::{Local, Remote};
+        let (local, remote) = self.option_state(option);
+        let sides = [(Local, before.0, local), (Remote, before.1, remote)];
+        for &(endpoint, was, now) in &sides {
+            let output = match (was, now) {
+                (false, true) => TerminalOutput::OptionEnabled,
+                (true, false) => TerminalOutput::OptionDisabled,
+                _ => continue,
+            };
+            self.events.push_back(output(endpoint, option));
+        }
+    }
+    ///
+    /// Return the buffered data ahead of a received prompt marker, then
+    /// `GoAhead` if it was a GA, then `PromptComplete`.
+    ///
+    fn receive_prompt(
+        &mut self,
+        frame: &TelnetFrame,
+    ) -> Option<TerminalOutput> {
+        if *frame == TelnetFrame::GoAhead {
+            self.events.push_back(TerminalOutput::GoAhead);
+        }
+        self.events.push_back(TerminalOutput::PromptComplete);
+        self.reader.flush().or_else(|| self.events.pop_front())
+    }
+    ///
+    /// Pass data bytes left by macro expansion to the application, stopping
+    /// at the first byte that produces output.
+    ///
+    fn receive_expanded(&mut self) -> Option<TerminalOutput> {
+        while let Some(ch) = self.expanded.pop_front() {
+            if let Some(output) = self.receive_data(ch) {
+                return Some(output);
+            }
+        }
+        None
+    }
+    ///
+    /// Route one received data byte to whichever mode consumes it.
+    ///
+    fn receive_data(&mut self, ch: u8) -> Option<TerminalOutput> {
+        if self.tn3270e_ready() {
+            self.records.push(ch);
+            return None;
+        }
+        let kermit = self.kermit.as_mut();
+        if let Some(transfer) = kermit.filter(|transfer| transfer.active()) {
+            let reply = transfer.input(&[ch]);
+            self.outgoing
+                .extend(reply.into_iter().map(TelnetFrame::Data));
+            if let KermitTransfer::Receive(receiver) = transfer {
+                if let Some(file) = receiver.take_files().pop() {
+                    return Some(TerminalOutput::File(file));
+                }
+            }
+            return None;
+        }
+        if let Some(supdup) = &mut self.supdup {
+            match supdup.push(ch) {
+                Some(operation) => {
+                    return Some(TerminalOutput::Display(vec![operation]));
+                },
+                None => return None,
+            }
+        }
+        let hidden = self.echo.hidden();
+        if let Some(editor) = &mut self.editor {
+            editor.set_hidden(hidden);
+            let output = editor.input(ch);
+            self.outgoing
+                .extend(output.echo.into_iter().map(TelnetFrame::Data));
+            return output.line.map(TerminalOutput::Line);
+        }
+        let (echoing, _) = self.option_state(TelnetOption::Echo);
+        let echo = self.echo.input(ch, echoing);
+        self.outgoing
+            .extend(echo.into_iter().map(TelnetFrame::Data));
+        self.reader.push(ch)
+    }
+    ///
+    /// LOGOUT exchange state.
+    ///
+    pub fn logout_status(&self) -> LogoutStatus {
+        self.logout.status()
+    }
+    ///
+    /// Underlying stream.
+    ///
+    pub fn get_ref(&self) -> &S {
+        self.codec.get_ref()
+    }
+    ///
+    /// Underlying stream. Reading or writing it directly corrupts the
+    /// session.
+    ///
+    pub fn get_mut(&mut self) -> &mut S {
+        self.codec.get_mut()
+    }
+    ///
+    /// Consume the terminal, returning the underlying stream. Frames not yet
+    /// written are lost.
+    ///
+    pub fn into_inner(self) -> S {
+        self.codec.into_inner()
+    }
+    ///
+    /// Error that ended the output stream, if any.
+    ///
+    pub fn error(&self) -> Option<&TerminalError> {
+        self.error.as_ref()
+    }
+    ///
+    /// Keep `error` and end the output stream.
+    ///
+    fn fail(&mut self, error: TerminalError) -> Option<TerminalOutput> {
+        event!(Level::WARN, "Terminal stream failed: {:?}", error);
+        self.error = Some(error);
+        None
+    }
+    ///
+    /// Act on one received frame, returning the output it produced if any.
+    ///
+    fn receive_frame(
+        &mut self,
+        frame: TelnetFrame,
+    ) -> Result<Option<TerminalOutput>, TerminalError> {
+        // Answers to our own requests are never a storm.
+        let answer = negotiation(&frame)
+            .is_some_and(|(side, option)| self.options.pending(side, option));
+        let verdict = match answer {
+            true => GuardVerdict::Answer,
+            false => self.guard.check_frame(&frame),
+        };
+        match verdict {
+            GuardVerdict::Answer => {},
+            GuardVerdict::Drop => return Ok(None),
+            GuardVerdict::Storm(storm) => {
+                self.disconnected = storm.response == StormResponse::Disconnect;
+                return Ok(Some(TerminalOutput::NegotiationStorm(storm)));
+            },
+        }
+        match frame {
+            TelnetFrame::Data(ch) => {
+                let negotiated = self.flow_negotiated();
+                if let (true, Some(flow)) = (negotiated, &mut self.flow) {
+                    let data = flow.input(ch);
+                    self.resume_output();
+                    if !data {
+                        return Ok(None);
+                    }
+                }
+                let option = TelnetOption::CPCO;
+                let relayed =
+                    self.option_enabled(TerminalEndpoint::Remote, option);
+                if let (true, Some(server)) =
+                    (relayed, &mut self.com_port_server)
+                {
+                    if let Err(error) = server.write_serial(&[ch]) {
+                        event!(
+                            Level::WARN,
+                            "Com Port write failed: {:?}",
+                            error
+                        );
+                    }
+                    return Ok(None);
+                }
+                let option = TelnetOption::ByteMacro;
+                if self.option_enabled(TerminalEndpoint::Remote, option) {
+                    let expanded = self.macros.expand(ch);
+                    self.expanded.extend(expanded);
+                } else {
+                    self.expanded.push_back(ch);
+                }
+                if let Some(output) = self.receive_expanded() {
+                    return Ok(Some(output));
+                }
+            },
+            TelnetFrame::AbortOutput => {
+                return Ok(Some(TerminalOutput::AbortOutput));
+            },
+            TelnetFrame::AreYouThere => {
+                return Ok(Some(TerminalOutput::AreYouThere));
+            },
+            TelnetFrame::Break => {
+                return Ok(Some(TerminalOutput::Break));
+            },
+            TelnetFrame::DataMark => {
+                return Ok(Some(TerminalOutput::DataMark));
+            },
+            TelnetFrame::EraseCharacter => {
+                if let Some(editor) = &mut self.editor {
+                    let echo = editor.erase_character();
+                    self.outgoing
+                        .extend(echo.into_iter().map(TelnetFrame::Data));
+                    return Ok(None);
+                }
+                return Ok(Some(TerminalOutput::EraseCharacter));
+            },
+            TelnetFrame::EraseLine => {
+                if let Some(editor) = &mut self.editor {
+                    let echo = editor.erase_line();
+                    self.outgoing
+                        .extend(echo.into_iter().map(TelnetFrame::Data));
+                    return Ok(None);
+                }
+                return Ok(Some(TerminalOutput::EraseLine));
+            },
+            TelnetFrame::GoAhead => {
+                return Ok(self.receive_prompt(&frame));
+            },
+            TelnetFrame::InterruptProcess => {
+                return Ok(Some(TerminalOutput::InterruptProcess));
+            },
+            TelnetFrame::EndOfRecord => {
+                if self.tn3270e_ready() {
+                    match self.records.finish() {
+                        Ok(record) => {
+                            return Ok(Some(TerminalOutput::Record(record)))
+                        },
+                        Err(error) => {
+                            event!(
+                                Level::WARN,
+                                "Record discarded: {:?}",
+                                error
+                            );
+                            return Ok(None);
+                        },
+                    }
+                }
+                let (_, eor) = self.option_state(TelnetOption::EOR);
+                if PromptMarker::prompt_complete(&frame, eor) {
+                    return Ok(self.receive_prompt(&frame));
+                }
+            },
+            TelnetFrame::NoOperation => {
+                return Ok(Some(TerminalOutput::NoOperation));
+            },
+            TelnetFrame::Do(TelnetOption::Logout)
+            | TelnetFrame::Dont(TelnetOption::Logout)
+            | TelnetFrame::Will(TelnetOption::Logout)
+            | TelnetFrame::Wont(TelnetOption::Logout) => {
+                let (side, refusal) = match frame {
+                    TelnetFrame::Do(_) | TelnetFrame::Dont(_) => (
+                        TerminalEndpoint::Local,
+                        TelnetFrame::Wont(TelnetOption::Logout),
+                    ),
+                    _ => (
+                        TerminalEndpoint::Remote,
+                        TelnetFrame::Dont(TelnetOption::Logout),
+                    ),
+                };
+                let option = OptionId::from(TelnetOption::Logout);
+                let rule = self.options.policy().rule(side, option);
+                if rule == Some(OptionRule::Never) {
+                    if let TelnetFrame::Do(_) | TelnetFrame::Will(_) = frame {
+                        self.outgoing.push_back(refusal);
+                    }
+                    return Ok(None);
+                }
+                let waiting = self.logout.status() == LogoutStatus::Pending;
+                if let Some(reply) = self.logout.handle(&frame) {
+                    self.outgoing.push_back(reply);
+                }
+                if !waiting && self.logout.finished() {
+                    return Ok(Some(TerminalOutput::Logout));
+                }
+            },
+            TelnetFrame::Do(option) => {
+                return self.receive_negotiation(Action::Do, option);
+            },
+            TelnetFrame::Dont(option) => {
+                return self.receive_negotiation(Action::Dont, option);
+            },
+            TelnetFrame::Will(option) => {
+                return self.receive_negotiation(Action::Will, option);
+            },
+            TelnetFrame::Wont(option) => {
+                return self.receive_negotiation(Action::Wont, option);
+            },
+            TelnetFrame::Subnegotiate(
+                TelnetOption::Authentication,
+                TelnetArgument::Authentication(argument),
+            ) => {
+                let option = TelnetOption::Authentication;
+                let side = self
+                    .authenticator
+                    .as_ref()
+                    .map(|auth| auth.role().endpoint());
+                let enabled =
+                    side.is_some_and(|side| self.option_enabled(side, option));
+                if let (true, Some(authenticator)) =
+                    (enabled, &mut self.authenticator)
+                {
+                    match authenticator.handle(argument) {
+                        Ok(frames) => self.outgoing.extend(frames),
+                        Err(error) => event!(
+                            Level::WARN,
+                            "AUTHENTICATION subnegotiation ignored: {:?}",
+                            error
+                        ),
+                    }
+                }
+            },
+            TelnetFrame::Subnegotiate(
+                TelnetOption::Encryption,
+                TelnetArgument::Encryption(argument),
+            ) => {
+                let side = encryption_side(&argument);
+                let enabled =
+                    self.option_enabled(side, TelnetOption::Encryption);
+                if let (true, Some(encryptor)) = (enabled, &mut self.encryptor)
+                {
+                    match encryptor.handle(argument) {
+                        Ok(events) => self.apply_encryption(events)?,
+                        Err(error) => event!(
+                            Level::WARN,
+                            "ENCRYPT subnegotiation ignored: {:?}",
+                            error
+                        ),
+                    }
+                }
+            },
+            TelnetFrame::Subnegotiate(
+                TelnetOption::CPCO,
+                TelnetArgument::ComPortControl(control),
+            ) => {
+                use TerminalEndpoint::{Local, Remote};
+                let option = TelnetOption::CPCO;
+                let server = self.option_enabled(Remote, option);
+                let client = self.option_enabled(Local, option);
+                if let (true, Some(server)) =
+                    (server, &mut self.com_port_server)
+                {
+                    let frames = server.handle(control);
+                    self.outgoing.extend(frames);
+                } else if let (true, Some(client)) =
+                    (client, &mut self.com_port_client)
+                {
+                    client.handle(control);
+                    self.resume_output();
+                } else {
+                    event!(
+                        Level::DEBUG,
+                        "COM-PORT-OPTION subnegotiation ignored"
+                    );
+                }
+            },
+            TelnetFrame::Subnegotiate(
+                TelnetOption::LFLOW,
+                TelnetArgument::FlowControl(mode),
+            ) => {
+                let negotiated = self.flow_negotiated();
+                if let (true, Some(flow)) = (negotiated, &mut self.flow) {
+                    flow.handle(mode);
+                    self.resume_output();
+                }
+            },
+            TelnetFrame::Subnegotiate(
+                TelnetOption::TTYPE,
+                TelnetArgument::TerminalType(ttype),
+            ) => {
+                let name = match &ttype {
+                    TerminalType::Is(name) => Some(name.clone()),
+                    TerminalType::Send => None,
+                };
+                self.outgoing.extend(self.info.handle_terminal_type(ttype));
+                if let Some(name) = name {
+                    return Ok(Some(TerminalOutput::TerminalType(name)));
+                }
+            },
+            TelnetFrame::Subnegotiate(
+                TelnetOption::NAWS,
+                TelnetArgument::WindowSize(size),
+            ) => {
+                self.info.handle_window_size(size);
+                return Ok(Some(TerminalOutput::WindowSize(
+                    size.width,
+                    size.height,
+                )));
+            },
+            TelnetFrame::Subnegotiate(
+                TelnetOption::GMCP,
+                TelnetArgument::GMCP(message),
+            ) => {
+                return Ok(Some(TerminalOutput::MudMessage(message)));
+            },
+            TelnetFrame::Subnegotiate(
+                TelnetOption::TSPEED,
+                TelnetArgument::TerminalSpeed(speed),
+            ) => {
+                // SEND is answered by our side, IS describes the peer
+                let side = match speed {
+                    TerminalSpeed::Send => TerminalEndpoint::Local,
+                    TerminalSpeed::Is { .. } => TerminalEndpoint::Remote,
+                };
+                if self.option_enabled(side, TelnetOption::TSPEED) {
+                    self.outgoing.extend(self.info.handle_speed(speed));
+                } else {
+                    event!(Level::DEBUG, "TSPEED subnegotiation ignored");
+                }
+            },
+            TelnetFrame::Subnegotiate(
+                TelnetOption::XDISPLOC,
+                TelnetArgument::XDisplayLocation(display),
+            ) => {
+                let side = match display {
+                    XDisplayLocation::Send => TerminalEndpoint::Local,
+                    XDisplayLocation::Is(_) => TerminalEndpoint::Remote,
+                };
+                if self.option_enabled(side, TelnetOption::XDISPLOC) {
+                    self.outgoing.extend(self.info.handle_display(display));
+                } else {
+                    event!(Level::DEBUG, "XDISPLOC subnegotiation ignored");
+                }
+            },
+            TelnetFrame::Subnegotiate(
+                TelnetOption::TTYLOC,
+                TelnetArgument::TerminalLocation(location),
+            ) => {
+                let option = TelnetOption::TTYLOC;
+                if self.option_enabled(TerminalEndpoint::Remote, option) {
+                    self.info.handle_location(location);
+                } else {
+                    event!(Level::DEBUG, "TTYLOC subnegotiation ignored");
+                }
+            },
+            TelnetFrame::Subnegotiate(
+                TelnetOption::SNDLOC,
+                TelnetArgument::SendLocation(location),
+            ) => {
+                let option = TelnetOption::SNDLOC;
+                if !self.option_enabled(TerminalEndpoint::Remote, option) {
+                    event!(Level::DEBUG, "SNDLOC subnegotiation ignored");
+                    return Ok(None);
+                }
+                event!(Level::INFO, "SNDLOC {}", location.0);
+                let output = TerminalOutput::Location(location.0.clone());
+                self.info.handle_location_name(location);
+                return Ok(Some(output));
+            },
+            TelnetFrame::Subnegotiate(
+                TelnetOption::TUID,
+                TelnetArgument::TerminalUserId(user),
+            ) => {
+                let option = TelnetOption::TUID;
+                if !self.option_enabled(TerminalEndpoint::Remote, option) {
+                    event!(Level::DEBUG, "TUID subnegotiation ignored");
+                    return Ok(None);
+                }
+                event!(Level::INFO, "TUID {:#010X}", user.0);
+                self.info.handle_user_id(user);
+                return Ok(Some(TerminalOutput::UserId(user.0)));
+            },
+            TelnetFrame::Subnegotiate(
+                TelnetOption::OUTMRK,
+                TelnetArgument::OutputMarking(marking),
+            ) => {
+                // Banners go to the terminal, answers come back to the host
+                let side = match marking {
+                    OutputMarking::Banner { .. } => TerminalEndpoint::Local,
+                    OutputMarking::Acknowledge | OutputMarking::Refuse => {
+                        TerminalEndpoint::Remote
+                    },
+                };
+                if !self.option_enabled(side, TelnetOption::OUTMRK) {
+                    event!(Level::DEBUG, "OUTMRK subnegotiation ignored");
+                    return Ok(None);
+                }
+                event!(Level::INFO, "OUTMRK {:?}", marking);
+                return Ok(Some(TerminalOutput::OutputMarking(marking)));
+            },
+            TelnetFrame::Subnegotiate(
+                TelnetOption::EXOPL,
+                TelnetArgument::ExtendedOptions(argument),
+            ) => {
+                let (local, remote) = self.option_state(TelnetOption::EXOPL);
+                if !local && !remote {
+                    event!(Level::DEBUG, "EXOPL subnegotiation ignored");
+                    return Ok(None);
+                }
+                let reply = self.options.handle_extended(argument);
+                self.outgoing.extend(reply);
+            },
+            TelnetFrame::Subnegotiate(
+                TelnetOption::TN3270E,
+                TelnetArgument::TN3270E(argument),
+            ) => {
+                let option = TelnetOption::TN3270E;
+                let enabled =
+                    self.option_enabled(TerminalEndpoint::Local, option);
+                if let (true, Some(client)) = (enabled, &mut self.tn3270e) {
+                    match client.handle(argument) {
+                        Ok(frames) => self.outgoing.extend(frames),
+                        Err(error) => event!(
+                            Level::WARN,
+                            "TN3270E subnegotiation ignored: {:?}",
+                            error
+                        ),
+                    }
+                }
+            },
+            TelnetFrame::Subnegotiate(
+                TelnetOption::SUPDUPOutput,
+                TelnetArgument::SUPDUPOutput(SUPDUPOutput::Output {
+                    operations,
+                    ..
+                }),
+            ) => {
+                return Ok(Some(TerminalOutput::Display(operations)));
+            },
+            TelnetFrame::Subnegotiate(
+                TelnetOption::Kermit,
+                TelnetArgument::Kermit(_),
+            ) if !self.kermit_negotiated() => {
+                event!(Level::DEBUG, "KERMIT subnegotiation ignored");
+            },
+            TelnetFrame::Subnegotiate(
+                TelnetOption::Kermit,
+                TelnetArgument::Kermit(argument),
+            ) => match argument {
+                Kermit::StartServer => self.kermit_server = true,
+                Kermit::StopServer => self.kermit_server = false,
+                Kermit::StartOfPacket(sop) => self.kermit_sop = sop,
+                // We never act as a Kermit server ourselves.
+                Kermit::RequestStartServer => {
+                    self.outgoing.push_back(TelnetFrame::Subnegotiate(
+                        TelnetOption::Kermit,
+                        TelnetArgument::Kermit(Kermit::ResponseStartServer(
+                            false,
+                        )),
+                    ));
+                },
+                Kermit::RequestStopServer => {
+                    self.outgoing.push_back(TelnetFrame::Subnegotiate(
+                        TelnetOption::Kermit,
+                        TelnetArgument::Kermit(Kermit::ResponseStopServer(
+                            true,
+                        )),
+                    ));
+                },
+                Kermit::ResponseStartServer(started) => {
+                    self.kermit_server = started
+                },
+                Kermit::ResponseStopServer(stopped) => {
+                    self.kermit_server = !stopped
+                },
+            },
+            TelnetFrame::Subnegotiate(
+                TelnetOption::ForwardX,
+                TelnetArgument::ForwardX(argument),
+            ) => {
+                use TerminalEndpoint::{Local, Remote};
+                let option = TelnetOption::ForwardX;
+                if !self.option_enabled(Local, option)
+                    && !self.option_enabled(Remote, option)
+                {
+                    event!(Level::DEBUG, "FORWARD_X subnegotiation ignored");
+                } else if let Some(mux) = &mut self.forward_x {
+                    match mux.handle(argument) {
+                        Ok(reply) => self.outgoing.extend(reply),
+                        Err(error) => event!(
+                            Level::WARN,
+                            "FORWARD_X subnegotiation ignored: {:?}",
+                            error
+                        ),
+                    }
+                }
+            },
+            TelnetFrame::Subnegotiate(
+                TelnetOption::ByteMacro,
+                TelnetArgument::ByteMacro(argument),
+            ) => {
+                use TerminalEndpoint::{Local, Remote};
+                let option = TelnetOption::ByteMacro;
+                if !self.option_enabled(Local, option)
+                    && !self.option_enabled(Remote, option)
+                {
+                    event!(Level::DEBUG, "BM subnegotiation ignored");
+                } else if let Some(reply) = self.macros.handle(argument) {
+                    self.outgoing.push_back(reply);
+                }
+            },
+            TelnetFrame::Subnegotiate(
+                TelnetOption::RCTE,
+                TelnetArgument::RCTE(argument),
+            ) => {
+                let option = TelnetOption::RCTE;
+                if !self.option_enabled(TerminalEndpoint::Remote, option) {
+                    event!(Level::DEBUG, "RCTE subnegotiation ignored");
+                    return Ok(None);
+                }
+                if let Some(rcte) = &mut self.rcte {
+                    let echo = rcte.handle(argument);
+                    if !echo.is_empty() {
+                        return Ok(Some(TerminalOutput::LocalEcho(echo)));
+                    }
+                }
+            },
+            TelnetFrame::Subnegotiate(
+                TelnetOption::X3PAD,
+                TelnetArgument::X3PAD(argument),
+            ) => {
+                let option = TelnetOption::X3PAD;
+                if !self.option_enabled(TerminalEndpoint::Local, option) {
+                    event!(Level::DEBUG, "X3-PAD subnegotiation ignored");
+                    return Ok(None);
+                }
+                if let Some(pad) = &mut self.x3pad {
+                    if let Some(reply) = pad.handle(argument) {
+                        self.outgoing.push_back(reply);
+                    }
+                }
+            },
+            TelnetFrame::Subnegotiate(
+                TelnetOption::DET,
+                TelnetArgument::DataEntry(argument),
+            ) => {
+                let (local, remote) = self.option_state(TelnetOption::DET);
+                if !local && !remote {
+                    event!(Level::DEBUG, "DET subnegotiation ignored");
+                    return Ok(None);
+                }
+                return Ok(Some(TerminalOutput::DataEntry(argument)));
+            },
+            TelnetFrame::Subnegotiate(_, _) => {},
+        }
+        Ok(None)
+    }
+}
+
+impl<S> NetworkVirtualTerminal<S>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    ///
+    /// Server: Prompt for input without echoing it, such as a password.
+    ///
+    /// Echo is taken over with `WILL ECHO` so the client does not echo
+    /// either, `prompt` is sent, and the next line is returned without its
+    /// line end. The previous echo state is restored afterwards. Under
+    /// binary transmission the line ends at the first CR or LF, and any data
+    /// after it is kept. Other output received while waiting is queued again
+    /// and returned by the stream afterwards. A line editor edits the line
+    /// without echo and leaves it out of the history. Returns `None` if the
+    /// session ends first.
+    ///
+    pub async fn read_hidden(
+        &mut self,
+        prompt: &str,
+    ) -> Result<Option<String>, TerminalError> {
+        let echo = OptionId::from(TelnetOption::Echo);
+        let echoing = self.options.enabled(TerminalEndpoint::Local, echo)
+            || self.options.pending(TerminalEndpoint::Local, echo);
+        self.echo.hide(echoing);
+        self.request_option(TerminalEndpoint::Local, echo);
+        let prompt = BinaryMode::encode_text(prompt, self.local_binary());
+        self.outgoing
+            .extend(prompt.into_iter().map(TelnetFrame::Data));
+        self.unmarked = true;
+        self.flush_outgoing().await?;
+
+        let mut line = Vec::new();
+        let mut received = Vec::new();
+        let complete = loop {
+            match self.next().await {
+                Some(TerminalOutput::AsciiData(data)) => {
+                    line.extend(data.bytes());
+                    if data.ends_with(&['\r', '\n'][..]) {
+                        break true;
+                    }
+                },
+                Some(TerminalOutput::Line(data)) => {
+                    line.extend(data.bytes());
+                    break true;
+                },
+                Some(TerminalOutput::BinaryData(data)) => {
+                    let end = data
+                        .iter()
+                        .position(|&byte| byte == b'\r' || byte == b'\n');
+                    let end = match end {
+                        Some(end) => end,
+                        None => {
+                            line.extend(data);
+                            continue;
+                        },
+                    };
+                    line.extend(&data[..end]);
+                    let rest = &data[end..];
+                    let rest = rest
+                        .strip_prefix(b"\r\n")
+                        .or_else(|| rest.strip_prefix(b"\r\0"))
+                        .unwrap_or(&rest[1..]);
+                    if !rest.is_empty() {
+                        received
+                            .push(TerminalOutput::BinaryData(rest.to_vec()));
+                    }
+                    break true;
+                },
+                Some(output) => received.push(output),
+                None => break false,
+            }
+        };
+        for output in received.into_iter().rev() {
+            self.events.push_front(output);
+        }
+
+        if self.echo.reveal() {
+            self.outgoing
+                .extend(self.options.disable(TerminalEndpoint::Local, echo));
+        }
+        self.outgoing
+            .extend(b"\r\n".iter().copied().map(TelnetFrame::Data));
+        self.flush_outgoing().await?;
+        if !complete {
+            return Ok(None);
+        }
+        let line = String::from_utf8_lossy(&line);
+        Ok(Some(line.trim_end_matches(&['\r', '\n'][..]).to_string()))
+    }
+    ///
+    /// Ask the peer to enable `option` on its side, such as `DO NAWS`, and
+    /// wait for the answer.
+    ///
+    pub async fn enable_remote(
+        &mut self,
+        option: TelnetOption,
+    ) -> Result<NegotiationResult, TerminalError> {
+        let results = self
+            .negotiate(&[(TerminalEndpoint::Remote, option)])
+            .await?;
+        Ok(results[0])
+    }
+    ///
+    /// Offer to enable `option` on our side, such as `WILL GMCP`, and wait
+    /// for the answer.
+    ///
+    pub async fn enable_local(
+        &mut self,
+        option: TelnetOption,
+    ) -> Result<NegotiationResult, TerminalError> {
+        let results =
+            self.negotiate(&[(TerminalEndpoint::Local, option)]).await?;
+        Ok(results[0])
+    }
+    ///
+    /// Ask for every option in `options` at once, and wait until all of them
+    /// settle or the negotiation timeout passes. Results are returned in
+    /// the same order. Options already enabled are accepted immediately.
+    ///
+    /// Output received while waiting is kept, and returned by the stream
+    /// afterwards.
+    ///
+    pub async fn negotiate(
+        &mut self,
+        options: &[(TerminalEndpoint, TelnetOption)],
+    ) -> Result<Vec<NegotiationResult>, TerminalError> {
+        let options: Vec<(TerminalEndpoint, OptionId)> = options
+            .iter()
+            .map(|&(side, option)| (side, option.into()))
+            .collect();
+        self.settle(&options).await
+    }
+    ///
+    /// Ask for every option the negotiation policy offers, and wait for the
+    /// answers as `negotiate` does. Each result is returned with its side
+    /// and option, local options first.
+    ///
+    pub async fn negotiate_policy(
+        &mut self,
+    ) -> Result<
+        Vec<(TerminalEndpoint, OptionId, NegotiationResult)>,
+        TerminalError,
+    > {
+        let offers = self.options.policy().offers();
+        let results = self.settle(&offers).await?;
+        let results = offers.into_iter().zip(results);
+        Ok(results
+            .map(|((side, option), result)| (side, option, result))
+            .collect())
+    }
+    ///
+    /// Request `options` and wait until each is answered or the negotiation
+    /// timeout passes. Options the policy never allows come back refused.
+    ///
+    async fn settle(
+        &mut self,
+        options: &[(TerminalEndpoint, OptionId)],
+    ) -> Result<Vec<NegotiationResult>, TerminalError> {
+        for &(side, option) in options {
+            self.request_option(side, option);
+        }
+        self.flush_outgoing().await?;
+
+        let mut timeout = Delay::new(self.negotiation_timeout);
+        let mut received = Vec::new();
+        while options
+            .iter()
+            .any(|&(side, option)| self.option_pending(side, option))
+        {
+            match future::select(self.next(), &mut timeout).await {
+                Either::Left((Some(output), _)) => received.push(output),
+                Either::Left((None, _)) | Either::Right(_) => break,
+            }
+            self.flush_outgoing().await?;
+        }
+        for output in received.into_iter().rev() {
+            self.events.push_front(output);
+        }
+
+        let results = options.iter().map(|&(side, option)| {
+            if self.option_pending(side, option) {
+                NegotiationResult::TimedOut
+            } else if self.option_enabled(side, option) {
+                NegotiationResult::Accepted
+            } else {
+                NegotiationResult::Refused
+            }
+        });
+        Ok(results.collect())
+    }
+    ///
+    /// Lines completed by the line editor. Other output is discarded. The
+    /// stream ends with the session, including on a LOGOUT from the peer or
+    /// a negotiation storm that disconnects it.
     ///
-    pub fn new(stream: S) -> NetworkVirtualTerminal<S> {
-        let codec = TelnetCodec::new(stream);
-        let buffer = BytesMut::with_capacity(4096);
-        let options = OptionManager::default();
-        NetworkVirtualTerminal {
-            codec,
-            buffer,
-            options,
+    pub fn lines(&mut self) -> impl Stream<Item = String> + '_ {
+        self.take_while(|output| {
+            let ended = match output {
+                TerminalOutput::Logout => true,
+                TerminalOutput::NegotiationStorm(storm) => {
+                    storm.response == StormResponse::Disconnect
+                },
+                _ => false,
+            };
+            future::ready(!ended)
+        })
+        .filter_map(|output| async move {
+            match output {
+                TerminalOutput::Line(line) => Some(line),
+                _ => None,
+            }
+        })
+    }
+    ///
+    /// Mark the end of any output sent since the last marker, then send
+    /// every queued frame and flush the codec.
+    ///
+    pub(crate) async fn flush_outgoing(&mut self) -> Result<(), TerminalError> {
+        self.queue_outgoing()?;
+        future::poll_fn(|cx| Pin::new(&mut self.codec).poll_flush(cx)).await?;
+        Ok(())
+    }
+    ///
+    /// Mark the end of any output sent since the last marker, then encode
+    /// every queued frame into the codec.
+    ///
+    fn queue_outgoing(&mut self) -> Result<(), TerminalError> {
+        if let Some(marker) = self.end_of_output() {
+            if self.output_paused() {
+                self.held.push_back(marker);
+            } else {
+                self.outgoing.push_back(marker);
+            }
+        }
+        self.queue_frames()
+    }
+    ///
+    /// Encode every queued frame into the codec, without marking the end
+    /// of output.
+    ///
+    fn queue_frames(&mut self) -> Result<(), TerminalError> {
+        while let Some(frame) = self.outgoing.pop_front() {
+            self.codec.queue(frame)?;
+        }
+        Ok(())
+    }
+    ///
+    /// Queue every outgoing frame and start writing it without waiting, so
+    /// replies go out while the application is only reading.
+    ///
+    fn poll_send_outgoing(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Result<(), TerminalError> {
+        self.queue_outgoing()?;
+        if self.codec.pending_write() == 0 {
+            return Ok(());
+        }
+        match Pin::new(&mut self.codec).poll_flush(cx) {
+            Poll::Ready(Err(error)) => Err(error.into()),
+            _ => Ok(()),
+        }
+    }
+    ///
+    /// Next output for the application, reading frames from the codec as
+    /// needed.
+    ///
+    fn poll_output(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<TerminalOutput>> {
+        self.poll_forward_x(cx);
+        self.poll_pad_idle(cx);
+        self.poll_com_port(cx);
+        loop {
+            if !self.local_echo.is_empty() {
+                let echo = std::mem::take(&mut self.local_echo);
+                return Poll::Ready(Some(TerminalOutput::LocalEcho(echo)));
+            }
+            if let Some(output) = self.events.pop_front() {
+                return Poll::Ready(Some(output));
+            }
+            if let Some(output) = self.receive_expanded() {
+                return Poll::Ready(Some(output));
+            }
+            let frame = match Pin::new(&mut self.codec).poll_next(cx) {
+                // Deliver a prompt or partial chunk while the peer is quiet
+                Poll::Pending => {
+                    return match self.reader.partial() {
+                        Some(output) => Poll::Ready(Some(output)),
+                        None => Poll::Pending,
+                    }
+                },
+                Poll::Ready(Some(Ok(frame))) => frame,
+                Poll::Ready(Some(Err(error))) => {
+                    return Poll::Ready(self.fail(error.into()))
+                },
+                Poll::Ready(None) => return Poll::Ready(self.reader.flush()),
+            };
+            match self.receive_frame(frame) {
+                Ok(Some(output)) => return Poll::Ready(Some(output)),
+                Ok(None) => {},
+                Err(error) => return Poll::Ready(self.fail(error)),
+            }
+        }
+    }
+    ///
+    /// Close the session gracefully.
+    ///
+    /// Pending output is flushed, including data held back by an XOFF from
+    /// the peer, then `message` is sent if given, encoded as text for the
+    /// current transmission mode, followed by `WILL LOGOUT`. Input is
+    /// discarded while waiting up to `timeout` for the peer to answer, then
+    /// the transport is shut down. Returns true if the peer acknowledged the
+    /// LOGOUT in time.
+    ///
+    pub async fn logout(
+        &mut self,
+        message: Option<&str>,
+        timeout: Duration,
+    ) -> Result<bool, TerminalError> {
+        self.outgoing.extend(self.held.drain(..));
+        if let Some(message) = message {
+            let data = BinaryMode::encode_text(message, self.local_binary());
+            self.outgoing
+                .extend(data.into_iter().map(TelnetFrame::Data));
+        }
+        if !self.logout.finished() {
+            let frame = self.logout.announce();
+            self.outgoing.push_back(frame);
+        }
+        self.flush_outgoing().await?;
+
+        if self.logout.status() == LogoutStatus::Pending {
+            let answer = future::poll_fn(|cx| loop {
+                if self.logout.status() != LogoutStatus::Pending {
+                    return Poll::Ready(());
+                }
+                match self.poll_next_unpin(cx) {
+                    Poll::Ready(Some(_)) => {},
+                    Poll::Ready(None) => return Poll::Ready(()),
+                    Poll::Pending
+                        if self.logout.status() == LogoutStatus::Pending =>
+                    {
+                        return Poll::Pending
+                    },
+                    Poll::Pending => return Poll::Ready(()),
+                }
+            });
+            futures::pin_mut!(answer);
+            if let Either::Right(_) =
+                future::select(answer, Delay::new(timeout)).await
+            {
+                event!(Level::DEBUG, "LOGOUT not answered before timeout");
+            }
         }
+        let acknowledged = self.logout.finished();
+
+        while let Some(frame) = self.outgoing.pop_front() {
+            self.codec.queue(frame)?;
+        }
+        future::poll_fn(|cx| Pin::new(&mut self.codec).poll_close(cx)).await?;
+        Ok(acknowledged)
     }
 }
 
 impl<S> Stream for NetworkVirtualTerminal<S>
 where
-    S: AsyncRead + AsyncWrite,
+    S: AsyncRead + AsyncWrite + Unpin,
 {
     type Item = TerminalOutput;
 
     fn poll_next(
-        self: Pin<&mut Self>,
+        mut self: Pin<&mut Self>,
         cx: &mut Context<'_>,
     ) -> Poll<Option<Self::Item>> {
-        loop {
-            match frame {
-                TelnetFrame::Data(ch) => {
-                    // TODO: Handle Data Stream
-                    self.buffer.put_u8(ch);
-                    if self.buffer.len() >= self.buffer.capacity() {
-                        let data = Vec::from(self.buffer.as_ref());
-                        return Poll::Ready(Some(TerminalOutput::BinaryData(
-                            data,
-                        )));
-                    }
-                }
-                TelnetFrame::AbortOutput => {
-                    return Poll::Ready(Some(TerminalOutput::AbortOutput));
-                }
-                TelnetFrame::AreYouThere => {
-                    return Poll::Ready(Some(TerminalOutput::AreYouThere));
-                }
-                TelnetFrame::Break => {
-                    return Poll::Ready(Some(TerminalOutput::Break));
-                }
-                TelnetFrame::DataMark => {
-                    return Poll::Ready(Some(TerminalOutput::DataMark));
-                }
-                TelnetFrame::EraseCharacter => {
-                    return Poll::Ready(Some(TerminalOutput::EraseCharacter));
-                }
-                TelnetFrame::EraseLine => {
-                    return Poll::Ready(Some(TerminalOutput::EraseLine));
-                }
-                TelnetFrame::GoAhead => {
-                    return Poll::Ready(Some(TerminalOutput::GoAhead));
-                }
-                TelnetFrame::InterruptProcess => {
-                    return Poll::Ready(Some(TerminalOutput::InterruptProcess));
-                }
-                TelnetFrame::NoOperation => {
-                    return Poll::Ready(Some(TerminalOutput::NoOperation));
-                }
-                TelnetFrame::Do(opt) => {
-                    let option = TelnetOption::from(opt);
-                    if self.options.0.is_none() {
-                        self.options.0 = (option, Side::Local)
-                    }
-                    self.options[opt]
-                }
-                TelnetFrame::Dont(opt) => {
-                    let option = TelnetOption::from(opt);
-                }
-                TelnetFrame::Will(opt) => {
-                    let option = TelnetOption::from(opt);
-                }
-                TelnetFrame::Wont(opt) => {
-                    let option = TelnetOption::from(opt);
-                }
-                TelnetFrame::Subnegotiate(opt, arguments) => {
-                    let option = TelnetOption::from(opt);
-                }
+        if self.disconnected {
+            let closed = ready!(Pin::new(&mut self.codec).poll_close(cx));
+            if let Err(error) = closed {
+                event!(Level::DEBUG, "Error closing connection: {:?}", error);
+            }
+            return Poll::Ready(None);
+        }
+        if self.error.is_some() {
+            return Poll::Ready(None);
+        }
+        let this = self.get_mut();
+        let output = this.poll_output(cx);
+        if let Err(error) = this.poll_send_outgoing(cx) {
+            let failed = this.fail(error);
+            if output.is_pending() {
+                return Poll::Ready(failed);
             }
         }
+        output
     }
 }
 
 impl<S> Sink<TerminalInput> for NetworkVirtualTerminal<S>
 where
-    S: AsyncRead + AsyncWrite,
+    S: AsyncRead + AsyncWrite + Unpin,
 {
-    type Error = EncodeError;
+    type Error = TerminalError;
 
     fn poll_ready(
         self: Pin<&mut Self>,
         cx: &mut Context<'_>,
     ) -> Poll<Result<(), Self::Error>> {
-        self.framed.poll_ready(cx)
+        let this = self.get_mut();
+        if this.output_paused() {
+            this.resume_waker = Some(cx.waker().clone());
+            return Poll::Pending;
+        }
+        this.queue_frames()?;
+        Pin::new(&mut this.codec).poll_ready(cx).map_err(Into::into)
     }
 
     fn start_send(
         self: Pin<&mut Self>,
         item: TerminalInput,
     ) -> Result<(), Self::Error> {
-        match item {
-            TerminalInput::NoOperation => {
-                self.framed.start_send(TelnetFrame::NoOperation)
-            }
-            TerminalInput::DataMark => {
-                self.framed.start_send(TelnetFrame::DataMark)
-            }
-            TerminalInput::Break => self.framed.start_send(TelnetFrame::Break),
+        let this = self.get_mut();
+        let frames = match item {
+            TerminalInput::NoOperation => vec![TelnetFrame::NoOperation],
+            TerminalInput::DataMark => vec![TelnetFrame::DataMark],
+            TerminalInput::Break => vec![TelnetFrame::Break],
             TerminalInput::InterruptProcess => {
-                self.framed.start_send(TelnetFrame::InterruptProcess)
-            }
-            TerminalInput::AbortOutput => {
-                self.framed.start_send(TelnetFrame::AbortOutput)
-            }
-            TerminalInput::AreYouThere => {
-                self.framed.start_send(TelnetFrame::AreYouThere)
-            }
-            TerminalInput::EraseCharacter => {
-                self.framed.start_send(TelnetFrame::EraseCharacter)
-            }
-            TerminalInput::EraseLine => {
-                self.framed.start_send(TelnetFrame::EraseLine)
-            }
-            TerminalInput::GoAhead => {
-                self.framed.start_send(TelnetFrame::GoAhead)
-            }
+                vec![TelnetFrame::InterruptProcess]
+            },
+            TerminalInput::AbortOutput => vec![TelnetFrame::AbortOutput],
+            TerminalInput::AreYouThere => vec![TelnetFrame::AreYouThere],
+            TerminalInput::EraseCharacter => vec![TelnetFrame::EraseCharacter],
+            TerminalInput::EraseLine => vec![TelnetFrame::EraseLine],
+            TerminalInput::GoAhead => vec![TelnetFrame::GoAhead],
             TerminalInput::AsciiData(data) => {
-                // TODO: Handle Compatability settings
-                for byte in data.as_bytes() {
-                    self.framed.start_send(TelnetFrame::Data(*byte))
-                }
-            }
+                let data = BinaryMode::encode_text(&data, this.local_binary());
+                let frames = this.typed_frames(&data);
+                this.unmarked |= !frames.is_empty();
+                frames
+            },
             TerminalInput::BinaryData(data) => {
                 // TODO: Handle Compatability settings
-                for byte in data.as_bytes() {
-                    self.framed.start_send(TelnetFrame::Data(*byte))
-                }
-            }
-            TerminalInput::EnableLocalOption(TelnetOption) => {}
-            TerminalInput::DisableLocalOption(TelnetOption) => {}
-            TerminalInput::EnableRemoteOption(TelnetOption) => {}
-            TerminalInput::DisableRemoteOption(TelnetOption) => {}
+                let frames = this.typed_frames(&data);
+                this.unmarked |= !frames.is_empty();
+                frames
+            },
+        };
+        for frame in frames {
+            this.send_frame(frame);
         }
         Ok(())
     }
@@ -200,28 +2475,1520 @@ where
         self: Pin<&mut Self>,
         cx: &mut Context<'_>,
     ) -> Poll<Result<(), Self::Error>> {
-        self.framed.poll_flush(cx)
+        let this = self.get_mut();
+        this.queue_outgoing()?;
+        Pin::new(&mut this.codec).poll_flush(cx).map_err(Into::into)
     }
 
     fn poll_close(
         self: Pin<&mut Self>,
         cx: &mut Context<'_>,
     ) -> Poll<Result<(), Self::Error>> {
-        self.framed.poll_close(cx)
+        let this = self.get_mut();
+        this.queue_outgoing()?;
+        Pin::new(&mut this.codec).poll_close(cx).map_err(Into::into)
     }
 }
 
-pub struct Compatibility {
-    send_unicode_while_normal: bool,
-    recv_unicode_while_normal: bool,
-}
+#[cfg(test)]
+mod tests {
+    use super::NetworkVirtualTerminal;
+    use crate::codec::{
+        consts, Authentication, AuthenticationType, ByteMacro, ComPortCommand,
+        ComPortControl, DataEntry, Encryption, EncryptionType, ExtendedOption,
+        ExtendedOptions, FlowControl, ForwardX, Kermit, OutputMarking,
+        SUPDUPParameters, SendLocation, TN3270EFunction, TelnetArgument,
+        TelnetEncoder, TelnetFrame, TelnetOption, TerminalLocation,
+        TerminalSpeed, TerminalUserId, X3Parameter, X3Setting, RCTE, TN3270E,
+    };
+    use crate::terminal::{
+        AuthenticationRole, Authenticator, DataEntryForm, Encryptor,
+        ForwardXMux, LineEditor, MacroTable, NegotiationGuard,
+        NegotiationPolicy, NegotiationResult, OptionId, OptionRule, RemoteEcho,
+        SharedSecret, StormResponse, TN3270EClient, TN3270EStatus,
+        TerminalEndpoint, TerminalError, TerminalInfo, TerminalInput,
+        TerminalOutput, X3Profile,
+    };
+    use bytes::BytesMut;
+    use futures::executor::block_on;
+    use futures::io::{self, Cursor};
+    use futures::task::{self, noop_waker, ArcWake, Context, Poll};
+    use futures::{AsyncRead, AsyncWrite, Sink, SinkExt, StreamExt};
+    use std::net::Ipv4Addr;
+    use std::pin::Pin;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+    use std::time::{Duration, Instant};
+
+    type Terminal = NetworkVirtualTerminal<Cursor<Vec<u8>>>;
+
+    /// Peer that sends `input`, then stays silent until closed.
+    #[derive(Default)]
+    struct Peer {
+        input: Vec<u8>,
+        output: Vec<u8>,
+        closed: bool,
+    }
+
+    impl AsyncRead for Peer {
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            _cx: &mut Context<'_>,
+            buf: &mut [u8],
+        ) -> Poll<io::Result<usize>> {
+            if self.input.is_empty() {
+                return Poll::Pending;
+            }
+            let length = buf.len().min(self.input.len());
+            buf[..length].copy_from_slice(&self.input[..length]);
+            self.input.drain(..length);
+            Poll::Ready(Ok(length))
+        }
+    }
+
+    impl AsyncWrite for Peer {
+        fn poll_write(
+            mut self: Pin<&mut Self>,
+            _cx: &mut Context<'_>,
+            buf: &[u8],
+        ) -> Poll<io::Result<usize>> {
+            self.output.extend_from_slice(buf);
+            Poll::Ready(Ok(buf.len()))
+        }
+
+        fn poll_flush(
+            self: Pin<&mut Self>,
+            _cx: &mut Context<'_>,
+        ) -> Poll<io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn poll_close(
+            mut self: Pin<&mut Self>,
+            _cx: &mut Context<'_>,
+        ) -> Poll<io::Result<()>> {
+            self.closed = true;
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    /// Read `terminal` to the end of its input, returning the output and
+    /// the bytes it wrote back.
+    fn run(mut terminal: Terminal) -> (Vec<TerminalOutput>, Vec<u8>) {
+        let length = terminal.get_ref().get_ref().len();
+        let outputs = block_on(terminal.by_ref().collect());
+        let written = terminal.into_inner().into_inner().split_off(length);
+        (outputs, written)
+    }
+
+    /// Wire form of `frames`.
+    fn encode(frames: Vec<TelnetFrame>) -> Vec<u8> {
+        let mut encoder = TelnetEncoder::default();
+        let mut buffer = BytesMut::new();
+        for frame in frames {
+            encoder.encode(frame, &mut buffer).unwrap();
+        }
+        buffer.to_vec()
+    }
+
+    /// Authenticator for `role` with a `SharedSecret` of an unassigned type.
+    fn authenticator(role: AuthenticationRole) -> Authenticator {
+        let kind = AuthenticationType::Unknown(200);
+        Authenticator::new(role)
+            .with_name("alice")
+            .with_mechanism(SharedSecret::new(kind, "alice", b"secret"))
+    }
+
+    #[test]
+    fn test_request_authentication() {
+        let input =
+            vec![consts::IAC, consts::WILL, consts::option::AUTHENTICATION];
+        let role = AuthenticationRole::Server;
+        let mut terminal = NetworkVirtualTerminal::new(Cursor::new(input))
+            .with_authenticator(authenticator(role));
+        terminal.request_authentication().unwrap();
+        let (outputs, written) = run(terminal);
+        assert!(matches!(
+            outputs[..],
+            [TerminalOutput::OptionEnabled(
+                TerminalEndpoint::Remote,
+                TelnetOption::Authentication
+            )]
+        ));
+        let send = authenticator(role).request().unwrap();
+        let expected =
+            encode(vec![TelnetFrame::Do(TelnetOption::Authentication), send]);
+        assert_eq!(expected, written);
+    }
+
+    #[test]
+    fn test_authentication_needs_option() {
+        let role = AuthenticationRole::Client;
+        let send = match authenticator(AuthenticationRole::Server).request() {
+            Ok(TelnetFrame::Subnegotiate(
+                _,
+                TelnetArgument::Authentication(send),
+            )) => send,
+            frame => panic!("Unexpected frame {:?}", frame),
+        };
+        let sb = |argument| {
+            TelnetFrame::Subnegotiate(
+                TelnetOption::Authentication,
+                TelnetArgument::Authentication(argument),
+            )
+        };
+        let mut input = encode(vec![
+            sb(send.clone()),
+            TelnetFrame::Do(TelnetOption::Authentication),
+            sb(send.clone()),
+        ]);
+        // NAME is only meant for the server, the client ignores it.
+        input.extend(encode(vec![sb(Authentication::Name("bob".into()))]));
+        input.extend(&[consts::IAC, consts::AYT]);
+        let terminal = NetworkVirtualTerminal::new(Cursor::new(input))
+            .with_authenticator(authenticator(role));
+        let (outputs, written) = run(terminal);
+        assert!(matches!(
+            outputs[..],
+            [
+                TerminalOutput::OptionEnabled(
+                    TerminalEndpoint::Local,
+                    TelnetOption::Authentication
+                ),
+                TerminalOutput::AreYouThere,
+            ]
+        ));
+        let mut expected =
+            encode(vec![TelnetFrame::Will(TelnetOption::Authentication)]);
+        expected.extend(encode(authenticator(role).handle(send).unwrap()));
+        assert_eq!(expected, written);
+    }
+
+    #[test]
+    fn test_tn3270e_session() {
+        let functions = vec![TN3270EFunction::Responses];
+        let sb = |argument| {
+            TelnetFrame::Subnegotiate(
+                TelnetOption::TN3270E,
+                TelnetArgument::TN3270E(argument),
+            )
+        };
+        let input = encode(vec![
+            TelnetFrame::Do(TelnetOption::TN3270E),
+            sb(TN3270E::SendDeviceType),
+            sb(TN3270E::DeviceTypeIs("IBM-3278-2-E".into(), "TERM1".into())),
+            sb(TN3270E::FunctionsIs(functions.clone())),
+        ]);
+        let client = TN3270EClient::new("IBM-3278-2-E")
+            .with_functions(functions.clone());
+        let mut terminal = NetworkVirtualTerminal::new(Cursor::new(input))
+            .with_tn3270e(client);
+        let length = terminal.get_ref().get_ref().len();
+        let outputs: Vec<_> = block_on(terminal.by_ref().collect());
+        assert!(matches!(
+            outputs[..],
+            [TerminalOutput::OptionEnabled(
+                TerminalEndpoint::Local,
+                TelnetOption::TN3270E
+            )]
+        ));
+        let tn3270e = terminal.tn3270e().unwrap();
+        assert_eq!(&TN3270EStatus::Ready, tn3270e.status());
+        assert_eq!(Some("TERM1"), tn3270e.device_name());
+        let written = terminal.into_inner().into_inner().split_off(length);
+        let expected = encode(vec![
+            TelnetFrame::Will(TelnetOption::TN3270E),
+            sb(TN3270E::DeviceTypeRequest("IBM-3278-2-E".into(), None)),
+            sb(TN3270E::FunctionsRequest(functions)),
+        ]);
+        assert_eq!(expected, written);
+    }
+
+    #[test]
+    fn test_tn3270e_ignores_bad_input() {
+        let functions = vec![TN3270EFunction::Responses];
+        let sb = |argument| {
+            TelnetFrame::Subnegotiate(
+                TelnetOption::TN3270E,
+                TelnetArgument::TN3270E(argument),
+            )
+        };
+        let mut input = encode(vec![
+            sb(TN3270E::SendDeviceType),
+            TelnetFrame::Do(TelnetOption::TN3270E),
+            sb(TN3270E::DeviceTypeRequest("IBM-3278-2-E".into(), None)),
+            sb(TN3270E::SendDeviceType),
+            sb(TN3270E::DeviceTypeIs("IBM-3278-2-E".into(), "TERM1".into())),
+            sb(TN3270E::FunctionsIs(functions.clone())),
+        ]);
+        input.extend(&[0, 0, consts::IAC, consts::EOR]);
+        let client = TN3270EClient::new("IBM-3278-2-E")
+            .with_functions(functions.clone());
+        let terminal = NetworkVirtualTerminal::new(Cursor::new(input))
+            .with_tn3270e(client);
+        let (outputs, written) = run(terminal);
+        assert!(matches!(
+            outputs[..],
+            [TerminalOutput::OptionEnabled(
+                TerminalEndpoint::Local,
+                TelnetOption::TN3270E
+            )]
+        ));
+        let expected = encode(vec![
+            TelnetFrame::Will(TelnetOption::TN3270E),
+            sb(TN3270E::DeviceTypeRequest("IBM-3278-2-E".into(), None)),
+            sb(TN3270E::FunctionsRequest(functions)),
+        ]);
+        assert_eq!(expected, written);
+    }
+
+    #[test]
+    fn test_replies_while_reading() {
+        let input = vec![
+            consts::IAC,
+            consts::WILL,
+            consts::option::GMCP,
+            consts::IAC,
+            consts::WILL,
+            consts::option::MSSP,
+        ];
+        let policy = NegotiationPolicy::new()
+            .accept(TerminalEndpoint::Remote, TelnetOption::GMCP);
+        let terminal = NetworkVirtualTerminal::new(Cursor::new(input))
+            .with_negotiation_policy(Arc::new(policy));
+        let (outputs, written) = run(terminal);
+        assert_eq!(1, outputs.len());
+        assert!(matches!(
+            outputs[0],
+            TerminalOutput::OptionEnabled(
+                TerminalEndpoint::Remote,
+                TelnetOption::GMCP
+            )
+        ));
+        let expected = [
+            consts::IAC,
+            consts::DO,
+            consts::option::GMCP,
+            consts::IAC,
+            consts::DONT,
+            consts::option::MSSP,
+        ];
+        assert_eq!(&expected[..], &written[..]);
+    }
+
+    #[test]
+    fn test_allow_option() {
+        let input = vec![
+            consts::IAC,
+            consts::WILL,
+            consts::option::NAWS,
+            consts::IAC,
+            consts::DO,
+            consts::option::ECHO,
+        ];
+        let policy = Arc::new(NegotiationPolicy::new());
+        let mut terminal = NetworkVirtualTerminal::new(Cursor::new(input))
+            .with_negotiation_policy(policy.clone());
+        terminal.allow_option(TerminalEndpoint::Remote, TelnetOption::NAWS);
+        terminal.disallow_option(TerminalEndpoint::Local, TelnetOption::Echo);
+        let (_, written) = run(terminal);
+        let expected = [
+            consts::IAC,
+            consts::DO,
+            consts::option::NAWS,
+            consts::IAC,
+            consts::WONT,
+            consts::option::ECHO,
+        ];
+        assert_eq!(&expected[..], &written[..]);
+        assert_eq!(NegotiationPolicy::new(), *policy);
+    }
+
+    #[test]
+    fn test_start_supdup() {
+        let input = vec![consts::IAC, consts::WILL, consts::option::SUPDUP];
+        let mut terminal = NetworkVirtualTerminal::new(Cursor::new(input));
+        let parameters = SUPDUPParameters::new(80, 24);
+        assert!(matches!(
+            terminal.start_supdup(parameters.clone()),
+            Err(TerminalError::NegotiationError)
+        ));
+        terminal.allow_option(TerminalEndpoint::Remote, TelnetOption::SUPDUP);
+        assert!(block_on(terminal.next()).is_some());
+        terminal.start_supdup(parameters).unwrap();
+    }
 
-enum NegotationState {
-    Never,
-    No,
-    WantNo,
-    WantNoOpposite,
-    Yes,
-    WantYes,
-    WantYesOpposite,
+    #[test]
+    fn test_byte_macros_need_option() {
+        let define = TelnetFrame::Subnegotiate(
+            TelnetOption::ByteMacro,
+            TelnetArgument::ByteMacro(ByteMacro::Define(1, b"hi".to_vec())),
+        );
+        let mut input = encode(vec![define.clone(), TelnetFrame::Data(1)]);
+        input.extend(encode(vec![
+            TelnetFrame::Will(TelnetOption::ByteMacro),
+            define,
+            TelnetFrame::Data(1),
+        ]));
+        input.extend(b"\r\n");
+        let macros = MacroTable::new().with_definitions(true);
+        let mut terminal = NetworkVirtualTerminal::new(Cursor::new(input))
+            .with_byte_macros(macros);
+        let bm = TelnetOption::ByteMacro;
+        assert!(matches!(
+            terminal.define_macro(2, b"bye"),
+            Err(TerminalError::NegotiationError)
+        ));
+        terminal.allow_option(TerminalEndpoint::Remote, bm);
+        let (outputs, written) = run(terminal);
+        assert!(matches!(
+            &outputs[..],
+            [
+                TerminalOutput::OptionEnabled(TerminalEndpoint::Remote, _),
+                TerminalOutput::AsciiData(text),
+            ] if text == "\u{1}hi\n"
+        ));
+        let accept = TelnetFrame::Subnegotiate(
+            bm,
+            TelnetArgument::ByteMacro(ByteMacro::Accept(1)),
+        );
+        assert_eq!(encode(vec![TelnetFrame::Do(bm), accept]), written);
+    }
+
+    #[test]
+    fn test_byte_macros_stop_with_option() {
+        let bm = TelnetOption::ByteMacro;
+        let peer = Peer {
+            input: vec![consts::IAC, consts::DO, consts::option::BM],
+            ..Peer::default()
+        };
+        let macros = MacroTable::new().with_compression(true);
+        let mut terminal =
+            NetworkVirtualTerminal::new(peer).with_byte_macros(macros);
+        read_available(&mut terminal);
+        terminal.define_macro(1, b"hello").unwrap();
+        let accept = encode(vec![TelnetFrame::Subnegotiate(
+            bm,
+            TelnetArgument::ByteMacro(ByteMacro::Accept(1)),
+        )]);
+        terminal.get_mut().input.extend(accept);
+        read_available(&mut terminal);
+        terminal.send_text("hello");
+        block_on(terminal.flush()).unwrap();
+        let output = &terminal.get_ref().output;
+        assert!(output.ends_with(&[1, consts::IAC, consts::GA]));
+
+        let dont = [consts::IAC, consts::DONT, consts::option::BM];
+        terminal.get_mut().input.extend(&dont);
+        read_available(&mut terminal);
+        assert!(!terminal.option_enabled(TerminalEndpoint::Local, bm));
+        terminal.send_text("hello");
+        block_on(terminal.flush()).unwrap();
+        let output = &terminal.get_ref().output;
+        assert!(output.ends_with(b"hello\xFF\xF9"));
+    }
+
+    #[test]
+    fn test_remote_echo_needs_option() {
+        let rcte = TelnetOption::RCTE;
+        let command = RCTE::Command {
+            skip_break: false,
+            skip_text: true,
+            break_classes: None,
+            transmit_classes: None,
+        };
+        let sb = encode(vec![TelnetFrame::Subnegotiate(
+            rcte,
+            TelnetArgument::RCTE(command),
+        )]);
+        let peer = Peer {
+            input: sb.clone(),
+            ..Peer::default()
+        };
+        let mut terminal = NetworkVirtualTerminal::new(peer)
+            .with_remote_echo(RemoteEcho::new());
+        read_available(&mut terminal);
+        assert!(matches!(
+            terminal.send_rcte(command),
+            Err(TerminalError::NegotiationError)
+        ));
+        block_on(terminal.send(TerminalInput::BinaryData(b"ab".to_vec())))
+            .unwrap();
+        assert!(terminal.get_ref().output.ends_with(b"ab\xFF\xF9"));
+
+        let will = [consts::IAC, consts::WILL, consts::option::RCTE];
+        terminal.get_mut().input.extend(&will);
+        read_available(&mut terminal);
+        assert!(terminal.option_enabled(TerminalEndpoint::Remote, rcte));
+        let length = terminal.get_ref().output.len();
+        block_on(terminal.send(TerminalInput::BinaryData(b"cd".to_vec())))
+            .unwrap();
+        assert_eq!(length, terminal.get_ref().output.len());
+
+        let policy = NegotiationPolicy::new().with_rule(
+            TerminalEndpoint::Local,
+            rcte,
+            OptionRule::Accept,
+        );
+        let peer = Peer {
+            input: vec![consts::IAC, consts::DO, consts::option::RCTE],
+            ..Peer::default()
+        };
+        let mut host = NetworkVirtualTerminal::new(peer)
+            .with_negotiation_policy(Arc::new(policy));
+        read_available(&mut host);
+        host.send_rcte(command).unwrap();
+        block_on(host.flush()).unwrap();
+        assert!(host.get_ref().output.ends_with(&sb));
+    }
+
+    #[test]
+    fn test_kermit_needs_options() {
+        let option = TelnetOption::Kermit;
+        let start = encode(vec![TelnetFrame::Subnegotiate(
+            option,
+            TelnetArgument::Kermit(Kermit::StartServer),
+        )]);
+        let peer = Peer {
+            input: start.clone(),
+            ..Peer::default()
+        };
+        let mut terminal = NetworkVirtualTerminal::new(peer);
+        read_available(&mut terminal);
+        assert!(!terminal.kermit_server());
+        assert!(matches!(
+            terminal.request_kermit_server(true),
+            Err(TerminalError::NegotiationError)
+        ));
+        assert!(matches!(
+            terminal.send_files(Vec::new()),
+            Err(TerminalError::NegotiationError)
+        ));
+
+        let mut input =
+            encode(vec![TelnetFrame::Do(option), TelnetFrame::Will(option)]);
+        input.extend(start);
+        terminal.get_mut().input.extend(input);
+        read_available(&mut terminal);
+        assert!(terminal.kermit_server());
+        assert!(terminal.request_kermit_server(false).is_ok());
+    }
+
+    #[test]
+    fn test_flush_sends_queued_frames() {
+        let mut terminal = NetworkVirtualTerminal::new(Cursor::new(Vec::new()));
+        terminal.request_terminal_type();
+        block_on(terminal.send(TerminalInput::AsciiData("hi".into()))).unwrap();
+        let written = terminal.into_inner().into_inner();
+        let expected = [
+            consts::IAC,
+            consts::SB,
+            consts::option::TTYPE,
+            consts::option::ttype::SEND,
+            consts::IAC,
+            consts::SE,
+            b'h',
+            b'i',
+            consts::IAC,
+            consts::GA,
+        ];
+        assert_eq!(&expected[..], &written[..]);
+    }
+
+    #[test]
+    fn test_end_of_record() {
+        let input = vec![
+            consts::IAC,
+            consts::DO,
+            consts::option::SGA,
+            consts::IAC,
+            consts::DO,
+            consts::option::EOR,
+        ];
+        let length = input.len();
+        let mut terminal = NetworkVirtualTerminal::new(Cursor::new(input));
+        let outputs: Vec<_> = block_on(terminal.by_ref().collect());
+        assert_eq!(2, outputs.len());
+        assert!(
+            terminal.option_enabled(TerminalEndpoint::Local, TelnetOption::EOR)
+        );
+
+        terminal.send_text("> ");
+        block_on(terminal.flush()).unwrap();
+        let written = terminal.into_inner().into_inner().split_off(length);
+        let expected = [
+            consts::IAC,
+            consts::WILL,
+            consts::option::SGA,
+            consts::IAC,
+            consts::WILL,
+            consts::option::EOR,
+            b'>',
+            b' ',
+            consts::IAC,
+            consts::EOR,
+        ];
+        assert_eq!(&expected[..], &written[..]);
+    }
+
+    #[test]
+    fn test_received_go_ahead() {
+        let input = vec![b'>', b' ', consts::IAC, consts::GA];
+        let terminal = NetworkVirtualTerminal::new(Cursor::new(input));
+        let (outputs, _) = run(terminal);
+        assert_eq!(3, outputs.len());
+        assert!(
+            matches!(&outputs[0], TerminalOutput::AsciiData(text) if text == "> ")
+        );
+        assert!(matches!(outputs[1], TerminalOutput::GoAhead));
+        assert!(matches!(outputs[2], TerminalOutput::PromptComplete));
+    }
+
+    #[test]
+    fn test_dedicated_options_follow_policy() {
+        let input = vec![
+            consts::IAC,
+            consts::WILL,
+            consts::option::BINARY,
+            0x80,
+            consts::IAC,
+            consts::WILL,
+            consts::option::ECHO,
+        ];
+        let policy = NegotiationPolicy::new().with_rule(
+            TerminalEndpoint::Remote,
+            TelnetOption::Echo,
+            OptionRule::Refuse,
+        );
+        let terminal = NetworkVirtualTerminal::new(Cursor::new(input))
+            .with_negotiation_policy(Arc::new(policy));
+        let (outputs, written) = run(terminal);
+        assert!(matches!(
+            outputs[0],
+            TerminalOutput::OptionEnabled(
+                TerminalEndpoint::Remote,
+                TelnetOption::TransmitBinary
+            )
+        ));
+        assert!(
+            matches!(&outputs[1], TerminalOutput::BinaryData(data) if data == &[0x80])
+        );
+        let expected = [
+            consts::IAC,
+            consts::DO,
+            consts::option::BINARY,
+            consts::IAC,
+            consts::DONT,
+            consts::option::ECHO,
+        ];
+        assert_eq!(&expected[..], &written[..]);
+    }
+
+    /// Bytes written back by a terminal set up with `configure` after
+    /// reading `request`.
+    fn answer<F>(request: TelnetFrame, configure: F) -> Vec<u8>
+    where
+        F: FnOnce(Terminal) -> Terminal,
+    {
+        let input = encode(vec![request]);
+        let terminal = NetworkVirtualTerminal::new(Cursor::new(input));
+        run(configure(terminal)).1
+    }
+
+    /// Replies agreeing to and refusing `request`.
+    fn replies(request: &TelnetFrame) -> (Vec<u8>, Vec<u8>) {
+        let (agree, refuse) = match *request {
+            TelnetFrame::Do(option) => {
+                (TelnetFrame::Will(option), TelnetFrame::Wont(option))
+            },
+            TelnetFrame::Will(option) => {
+                (TelnetFrame::Do(option), TelnetFrame::Dont(option))
+            },
+            ref frame => panic!("Not a request {:?}", frame),
+        };
+        (encode(vec![agree]), encode(vec![refuse]))
+    }
+
+    /// Check `request` is refused by default and agreed to once the
+    /// terminal is set up with `configure`.
+    fn assert_configured_accepts<F>(request: TelnetFrame, configure: F)
+    where
+        F: FnOnce(Terminal) -> Terminal,
+    {
+        let (agree, refuse) = replies(&request);
+        assert_eq!(refuse, answer(request.clone(), |terminal| terminal));
+        assert!(answer(request, configure).starts_with(&agree));
+    }
+
+    #[test]
+    fn test_terminal_info_accepted() {
+        let info = || {
+            TerminalInfo::new()
+                .with_terminal_type("VT100")
+                .with_speed(9600, 9600)
+                .with_display("host:0")
+        };
+        let local = [
+            TelnetOption::TTYPE,
+            TelnetOption::TSPEED,
+            TelnetOption::XDISPLOC,
+        ];
+        for &option in &local {
+            assert_configured_accepts(TelnetFrame::Do(option), |terminal| {
+                terminal.with_terminal_info(info())
+            });
+        }
+        let remote = [
+            TelnetOption::TTYPE,
+            TelnetOption::NAWS,
+            TelnetOption::TSPEED,
+            TelnetOption::XDISPLOC,
+            TelnetOption::TTYLOC,
+            TelnetOption::SNDLOC,
+            TelnetOption::TUID,
+        ];
+        for &option in &remote {
+            let request = TelnetFrame::Will(option);
+            let (agree, _) = replies(&request);
+            assert_eq!(agree, answer(request, |terminal| terminal));
+        }
+    }
+
+    #[test]
+    fn test_terminal_info_needs_options() {
+        let sb = |option, argument| TelnetFrame::Subnegotiate(option, argument);
+        let send = sb(
+            TelnetOption::TSPEED,
+            TelnetArgument::TerminalSpeed(TerminalSpeed::Send),
+        );
+        let location = TerminalLocation::Internet {
+            gateway: Ipv4Addr::new(10, 0, 0, 1),
+            terminal: 7,
+        };
+        let ttyloc = sb(
+            TelnetOption::TTYLOC,
+            TelnetArgument::TerminalLocation(location.clone()),
+        );
+        let input = encode(vec![
+            send.clone(),
+            ttyloc,
+            TelnetFrame::Do(TelnetOption::TSPEED),
+            send,
+        ]);
+        let info = TerminalInfo::new().with_speed(9600, 4800);
+        let mut terminal = NetworkVirtualTerminal::new(Cursor::new(input))
+            .with_terminal_info(info);
+        for result in [
+            terminal.request_terminal_speed(),
+            terminal.request_x_display_location(),
+            terminal.send_terminal_location(location),
+        ] {
+            assert!(matches!(result, Err(TerminalError::NegotiationError)));
+        }
+        let length = terminal.get_ref().get_ref().len();
+        block_on(terminal.by_ref().collect::<Vec<_>>());
+        assert!(terminal.terminal_info().location().is_none());
+        let written = terminal.into_inner().into_inner().split_off(length);
+        let speed = TerminalSpeed::Is {
+            transmit: 9600,
+            receive: 4800,
+        };
+        let expected = encode(vec![
+            TelnetFrame::Will(TelnetOption::TSPEED),
+            sb(TelnetOption::TSPEED, TelnetArgument::TerminalSpeed(speed)),
+        ]);
+        assert_eq!(expected, written);
+    }
+
+    #[test]
+    fn test_location_events_need_options() {
+        let banner = OutputMarking::Banner {
+            control: 0,
+            text: "SECRET".to_string(),
+        };
+        let events = vec![
+            TelnetFrame::Subnegotiate(
+                TelnetOption::SNDLOC,
+                TelnetArgument::SendLocation(SendLocation("lab".into())),
+            ),
+            TelnetFrame::Subnegotiate(
+                TelnetOption::TUID,
+                TelnetArgument::TerminalUserId(TerminalUserId(42)),
+            ),
+            TelnetFrame::Subnegotiate(
+                TelnetOption::OUTMRK,
+                TelnetArgument::OutputMarking(banner.clone()),
+            ),
+        ];
+        let mut frames = events.clone();
+        frames.extend(vec![
+            TelnetFrame::Will(TelnetOption::SNDLOC),
+            TelnetFrame::Will(TelnetOption::TUID),
+            TelnetFrame::Do(TelnetOption::OUTMRK),
+        ]);
+        frames.extend(events);
+        let mut terminal =
+            NetworkVirtualTerminal::new(Cursor::new(encode(frames)));
+        for result in [
+            terminal.send_location_name("lab"),
+            terminal.send_user_id(42),
+            terminal.mark_output(0, "SECRET"),
+            terminal.answer_output_marking(true),
+        ] {
+            assert!(matches!(result, Err(TerminalError::NegotiationError)));
+        }
+        let (outputs, _) = run(terminal);
+        assert!(matches!(
+            &outputs[..],
+            [
+                TerminalOutput::OptionEnabled(
+                    TerminalEndpoint::Remote,
+                    TelnetOption::SNDLOC
+                ),
+                TerminalOutput::OptionEnabled(
+                    TerminalEndpoint::Remote,
+                    TelnetOption::TUID
+                ),
+                TerminalOutput::OptionEnabled(
+                    TerminalEndpoint::Local,
+                    TelnetOption::OUTMRK
+                ),
+                TerminalOutput::Location(location),
+                TerminalOutput::UserId(42),
+                TerminalOutput::OutputMarking(marking),
+            ] if location == "lab" && *marking == banner
+        ));
+    }
+
+    #[test]
+    fn test_com_port_client_accepted() {
+        let request = TelnetFrame::Do(TelnetOption::CPCO);
+        assert_configured_accepts(request, |terminal| {
+            terminal.with_com_port_client()
+        });
+    }
+
+    #[test]
+    fn test_encryption_accepted() {
+        for request in [
+            TelnetFrame::Do(TelnetOption::Encryption),
+            TelnetFrame::Will(TelnetOption::Encryption),
+        ] {
+            assert_configured_accepts(request, |terminal| {
+                terminal.with_encryptor(Encryptor::new())
+            });
+        }
+    }
+
+    #[test]
+    fn test_encryption_needs_option() {
+        let encryption = TelnetOption::Encryption;
+        let kind = EncryptionType::Unknown(200);
+        let frame = |argument| {
+            TelnetFrame::Subnegotiate(
+                encryption,
+                TelnetArgument::Encryption(argument),
+            )
+        };
+        let mut input = encode(vec![
+            frame(Encryption::Support(vec![kind])),
+            TelnetFrame::Do(encryption),
+            frame(Encryption::Reply(kind, vec![2])),
+        ]);
+        input.extend(b"hi\r\n");
+        let mut terminal = NetworkVirtualTerminal::new(Cursor::new(input))
+            .with_encryptor(Encryptor::new());
+        assert!(matches!(
+            terminal.request_encryption(),
+            Err(TerminalError::NegotiationError)
+        ));
+        let (outputs, written) = run(terminal);
+        assert!(matches!(
+            &outputs[..],
+            [
+                TerminalOutput::OptionEnabled(TerminalEndpoint::Local, _),
+                TerminalOutput::AsciiData(text),
+            ] if text == "hi\n"
+        ));
+        assert_eq!(encode(vec![TelnetFrame::Will(encryption)]), written);
+    }
+
+    #[test]
+    fn test_forward_x_accepted() {
+        for request in [
+            TelnetFrame::Do(TelnetOption::ForwardX),
+            TelnetFrame::Will(TelnetOption::ForwardX),
+        ] {
+            assert_configured_accepts(request, |terminal| {
+                terminal.with_forward_x(ForwardXMux::new())
+            });
+        }
+    }
+
+    #[test]
+    fn test_forward_x_needs_option() {
+        let option = TelnetOption::ForwardX;
+        let open = |id| {
+            TelnetFrame::Subnegotiate(
+                option,
+                TelnetArgument::ForwardX(ForwardX::Open(id)),
+            )
+        };
+        let input = encode(vec![open(1), TelnetFrame::Do(option), open(2)]);
+        let mut terminal = NetworkVirtualTerminal::new(Cursor::new(input))
+            .with_forward_x(ForwardXMux::new());
+        assert!(matches!(
+            terminal.request_forward_x(0),
+            Err(TerminalError::NegotiationError)
+        ));
+        let outputs: Vec<_> = block_on(terminal.by_ref().collect());
+        assert_eq!(1, outputs.len());
+        assert_eq!(2, terminal.accept_x_channel().unwrap().id());
+        assert!(terminal.accept_x_channel().is_none());
+        assert!(matches!(
+            terminal.open_x_channel(),
+            Err(TerminalError::NegotiationError)
+        ));
+    }
+
+    #[test]
+    fn test_byte_macros_accepted() {
+        let request = TelnetFrame::Will(TelnetOption::ByteMacro);
+        assert_configured_accepts(request, |terminal| {
+            terminal.with_byte_macros(MacroTable::new().with_definitions(true))
+        });
+        let request = TelnetFrame::Do(TelnetOption::ByteMacro);
+        assert_configured_accepts(request, |terminal| {
+            terminal.with_byte_macros(MacroTable::new().with_compression(true))
+        });
+    }
+
+    #[test]
+    fn test_remote_echo_accepted() {
+        let request = TelnetFrame::Will(TelnetOption::RCTE);
+        assert_configured_accepts(request, |terminal| {
+            terminal.with_remote_echo(RemoteEcho::new())
+        });
+    }
+
+    #[test]
+    fn test_x3pad_accepted() {
+        let request = TelnetFrame::Do(TelnetOption::X3PAD);
+        assert_configured_accepts(request, |terminal| {
+            terminal.with_x3pad(X3Profile::new())
+        });
+    }
+
+    #[test]
+    fn test_x3pad_needs_option() {
+        let x3pad = TelnetOption::X3PAD;
+        let settings = vec![X3Setting::new(X3Parameter::Echo, 0)];
+        let set = encode(vec![X3Profile::set(settings.clone())]);
+        let peer = Peer {
+            input: set.clone(),
+            ..Peer::default()
+        };
+        let mut terminal =
+            NetworkVirtualTerminal::new(peer).with_x3pad(X3Profile::new());
+        read_available(&mut terminal);
+        assert!(terminal.x3pad().unwrap().echo());
+        assert!(terminal.get_ref().output.is_empty());
+        assert!(matches!(
+            terminal.set_x3pad(settings.clone()),
+            Err(TerminalError::NegotiationError)
+        ));
+
+        let mut input = vec![consts::IAC, consts::DO, consts::option::X3PAD];
+        input.extend(set);
+        terminal.get_mut().input.extend(input);
+        read_available(&mut terminal);
+        assert!(!terminal.x3pad().unwrap().echo());
+
+        let policy = NegotiationPolicy::new().with_rule(
+            TerminalEndpoint::Remote,
+            x3pad,
+            OptionRule::Accept,
+        );
+        let peer = Peer {
+            input: vec![consts::IAC, consts::WILL, consts::option::X3PAD],
+            ..Peer::default()
+        };
+        let mut host = NetworkVirtualTerminal::new(peer)
+            .with_negotiation_policy(Arc::new(policy));
+        read_available(&mut host);
+        assert!(host.set_x3pad(settings).is_ok());
+    }
+
+    #[test]
+    fn test_data_entry_needs_option() {
+        let det = TelnetOption::DET;
+        let home = encode(vec![TelnetFrame::Subnegotiate(
+            det,
+            TelnetArgument::DataEntry(DataEntry::Home),
+        )]);
+        let peer = Peer {
+            input: home.clone(),
+            ..Peer::default()
+        };
+        let mut terminal = NetworkVirtualTerminal::new(peer);
+        assert!(read_available(&mut terminal).is_empty());
+        assert!(matches!(
+            terminal.send_form(DataEntryForm::new().home()),
+            Err(TerminalError::NegotiationError)
+        ));
+
+        let mut input = vec![consts::IAC, consts::WILL, consts::option::DET];
+        input.extend(home);
+        terminal.get_mut().input.extend(input);
+        assert!(matches!(
+            read_available(&mut terminal)[..],
+            [
+                TerminalOutput::OptionEnabled(TerminalEndpoint::Remote, _),
+                TerminalOutput::DataEntry(DataEntry::Home),
+            ]
+        ));
+        assert!(terminal.send_form(DataEntryForm::new().home()).is_ok());
+    }
+
+    #[test]
+    fn test_extended_options_need_option() {
+        let exopl = TelnetOption::EXOPL;
+        let extended = |argument| {
+            TelnetFrame::Subnegotiate(
+                exopl,
+                TelnetArgument::ExtendedOptions(argument),
+            )
+        };
+        let option = ExtendedOption::new(7);
+        let request = encode(vec![extended(ExtendedOptions::Do(option))]);
+        let policy = NegotiationPolicy::new().with_rule(
+            TerminalEndpoint::Remote,
+            exopl,
+            OptionRule::Accept,
+        );
+        let peer = Peer {
+            input: request.clone(),
+            ..Peer::default()
+        };
+        let mut terminal = NetworkVirtualTerminal::new(peer)
+            .with_negotiation_policy(Arc::new(policy));
+        read_available(&mut terminal);
+        assert!(terminal.get_ref().output.is_empty());
+
+        let mut input = vec![consts::IAC, consts::WILL, consts::option::EXOPL];
+        input.extend(request);
+        terminal.get_mut().input.extend(input);
+        read_available(&mut terminal);
+        let expected = encode(vec![
+            TelnetFrame::Do(exopl),
+            extended(ExtendedOptions::Wont(option)),
+        ]);
+        assert_eq!(expected, terminal.get_ref().output);
+    }
+
+    #[test]
+    fn test_builtin_options_accepted() {
+        let options = [
+            TelnetOption::Kermit,
+            TelnetOption::DET,
+            TelnetOption::OUTMRK,
+            TelnetOption::SUPDUP,
+        ];
+        for &option in &options {
+            let requests = [TelnetFrame::Do(option), TelnetFrame::Will(option)];
+            for request in requests {
+                let (agree, _) = replies(&request);
+                assert!(
+                    answer(request, |terminal| terminal).starts_with(&agree)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_logout_never() {
+        let input = encode(vec![
+            TelnetFrame::Do(TelnetOption::Logout),
+            TelnetFrame::Will(TelnetOption::Logout),
+        ]);
+        let policy = NegotiationPolicy::new()
+            .with_rule(
+                TerminalEndpoint::Local,
+                TelnetOption::Logout,
+                OptionRule::Never,
+            )
+            .with_rule(
+                TerminalEndpoint::Remote,
+                TelnetOption::Logout,
+                OptionRule::Never,
+            );
+        let terminal = NetworkVirtualTerminal::new(Cursor::new(input))
+            .with_negotiation_policy(Arc::new(policy));
+        let (outputs, written) = run(terminal);
+        assert!(outputs.is_empty());
+        let expected = encode(vec![
+            TelnetFrame::Wont(TelnetOption::Logout),
+            TelnetFrame::Dont(TelnetOption::Logout),
+        ]);
+        assert_eq!(expected, written);
+    }
+
+    #[test]
+    fn test_logout_acknowledged() {
+        let peer = Peer {
+            input: vec![consts::IAC, consts::DO, consts::option::LOGOUT],
+            ..Peer::default()
+        };
+        let mut terminal = NetworkVirtualTerminal::new(peer);
+        let timeout = Duration::from_secs(60);
+        let acknowledged =
+            block_on(terminal.logout(Some("bye\n"), timeout)).unwrap();
+        assert!(acknowledged);
+        let peer = terminal.into_inner();
+        assert!(peer.closed);
+        let mut expected = b"bye\r\n".to_vec();
+        expected.extend(&[consts::IAC, consts::WILL, consts::option::LOGOUT]);
+        assert_eq!(expected, peer.output);
+    }
+
+    #[test]
+    fn test_logout_timeout() {
+        let mut terminal = NetworkVirtualTerminal::new(Peer::default());
+        let start = Instant::now();
+        let timeout = Duration::from_millis(20);
+        let acknowledged = block_on(terminal.logout(None, timeout)).unwrap();
+        assert!(!acknowledged);
+        assert!(start.elapsed() >= timeout);
+        let peer = terminal.into_inner();
+        assert!(peer.closed);
+        let expected = [consts::IAC, consts::WILL, consts::option::LOGOUT];
+        assert_eq!(&expected[..], &peer.output[..]);
+    }
+
+    /// Waker recording whether it was woken.
+    #[derive(Default)]
+    struct Flag(AtomicBool);
+
+    impl ArcWake for Flag {
+        fn wake_by_ref(arc_self: &Arc<Self>) {
+            arc_self.0.store(true, Ordering::SeqCst);
+        }
+    }
+
+    /// Poll `terminal` until it waits for more input.
+    fn read_available(
+        terminal: &mut NetworkVirtualTerminal<Peer>,
+    ) -> Vec<TerminalOutput> {
+        let waker = noop_waker();
+        let mut cx = Context::from_waker(&waker);
+        let mut outputs = Vec::new();
+        while let Poll::Ready(Some(output)) = terminal.poll_next_unpin(&mut cx)
+        {
+            outputs.push(output);
+        }
+        outputs
+    }
+
+    #[test]
+    fn test_xoff_blocks_sending() {
+        let input =
+            vec![consts::IAC, consts::DO, consts::option::LFLOW, consts::XOFF];
+        let peer = Peer {
+            input,
+            ..Peer::default()
+        };
+        let mut terminal =
+            NetworkVirtualTerminal::new(peer).with_flow_control();
+        read_available(&mut terminal);
+        assert!(terminal
+            .option_enabled(TerminalEndpoint::Local, TelnetOption::LFLOW));
+        assert!(terminal.output_paused());
+        let expected = [consts::IAC, consts::WILL, consts::option::LFLOW];
+        assert_eq!(&expected[..], &terminal.get_ref().output[..]);
+
+        let flag = Arc::new(Flag::default());
+        let waker = task::waker(flag.clone());
+        let mut cx = Context::from_waker(&waker);
+        let ready = Pin::new(&mut terminal).poll_ready(&mut cx);
+        assert!(ready.is_pending());
+
+        terminal.get_mut().input.push(consts::XON);
+        read_available(&mut terminal);
+        assert!(flag.0.load(Ordering::SeqCst));
+        assert!(!terminal.output_paused());
+        let ready = Pin::new(&mut terminal).poll_ready(&mut cx);
+        assert!(matches!(ready, Poll::Ready(Ok(()))));
+    }
+
+    #[test]
+    fn test_com_port_suspend_holds_output() {
+        let option = TelnetOption::CPCO;
+        let sb = |command| {
+            encode(vec![TelnetFrame::Subnegotiate(
+                option,
+                TelnetArgument::ComPortControl(ComPortControl::server(command)),
+            )])
+        };
+        let mut input = sb(ComPortCommand::FlowControlSuspend);
+        input.extend(&[consts::IAC, consts::DO, consts::option::CPCO]);
+        input.extend(sb(ComPortCommand::FlowControlSuspend));
+        let peer = Peer {
+            input,
+            ..Peer::default()
+        };
+        let mut terminal =
+            NetworkVirtualTerminal::new(peer).with_com_port_client();
+        let client = terminal.com_port_client().unwrap().clone();
+        assert!(matches!(
+            terminal.send_com_port_command(client.request_signature()),
+            Err(TerminalError::NegotiationError)
+        ));
+        read_available(&mut terminal);
+        assert!(terminal.output_paused());
+        terminal.send_text("hi");
+        block_on(terminal.flush()).unwrap();
+        let will = [consts::IAC, consts::WILL, consts::option::CPCO];
+        assert_eq!(&will[..], &terminal.get_ref().output[..]);
+
+        let resume = sb(ComPortCommand::FlowControlResume);
+        terminal.get_mut().input.extend(resume);
+        read_available(&mut terminal);
+        assert!(!terminal.output_paused());
+        block_on(terminal.flush()).unwrap();
+        let mut expected = will.to_vec();
+        expected.extend(b"hi");
+        expected.extend(&[consts::IAC, consts::GA]);
+        assert_eq!(expected, terminal.get_ref().output);
+    }
+
+    #[test]
+    fn test_xoff_ignored_without_lflow() {
+        let peer = Peer {
+            input: vec![consts::XOFF],
+            ..Peer::default()
+        };
+        let mut terminal =
+            NetworkVirtualTerminal::new(peer).with_flow_control();
+        read_available(&mut terminal);
+        assert!(!terminal.output_paused());
+        let waker = noop_waker();
+        let mut cx = Context::from_waker(&waker);
+        let ready = Pin::new(&mut terminal).poll_ready(&mut cx);
+        assert!(matches!(ready, Poll::Ready(Ok(()))));
+        let mode = FlowControl::Off;
+        assert!(matches!(
+            terminal.set_flow_control(mode),
+            Err(TerminalError::NegotiationError)
+        ));
+    }
+
+    #[test]
+    fn test_prompt_delivered_while_waiting() {
+        let mut input = vec![consts::IAC, consts::WILL, consts::option::SGA];
+        input.extend(b"login: ");
+        let peer = Peer {
+            input,
+            ..Peer::default()
+        };
+        let mut terminal = NetworkVirtualTerminal::new(peer);
+        let outputs = read_available(&mut terminal);
+        assert!(matches!(
+            &outputs[..],
+            [
+                TerminalOutput::OptionEnabled(TerminalEndpoint::Remote, _),
+                TerminalOutput::AsciiData(text),
+            ] if text == "login: "
+        ));
+    }
+
+    #[test]
+    fn test_binary_chunk_delivered_while_waiting() {
+        let mut input = vec![consts::IAC, consts::WILL, consts::option::BINARY];
+        input.extend(&[0x00, 0xFF, 0xFF, 0x80]);
+        let peer = Peer {
+            input,
+            ..Peer::default()
+        };
+        let mut terminal = NetworkVirtualTerminal::new(peer);
+        let outputs = read_available(&mut terminal);
+        assert!(matches!(
+            &outputs[..],
+            [
+                TerminalOutput::OptionEnabled(TerminalEndpoint::Remote, _),
+                TerminalOutput::BinaryData(data),
+            ] if data[..] == [0x00, 0xFF, 0x80]
+        ));
+    }
+
+    #[test]
+    fn test_read_hidden_keeps_other_output() {
+        let mut input = vec![consts::IAC, consts::DO, consts::option::ECHO];
+        input.extend(&[consts::IAC, consts::AYT]);
+        input.extend(b"secret\r\n");
+        let peer = Peer {
+            input,
+            ..Peer::default()
+        };
+        let mut terminal = NetworkVirtualTerminal::new(peer);
+        let line = block_on(terminal.read_hidden("Password: ")).unwrap();
+        assert_eq!(Some("secret".to_string()), line);
+
+        let outputs = read_available(&mut terminal);
+        assert!(matches!(
+            outputs[..],
+            [
+                TerminalOutput::OptionEnabled(
+                    TerminalEndpoint::Local,
+                    TelnetOption::Echo
+                ),
+                TerminalOutput::AreYouThere,
+            ]
+        ));
+        let wont = [consts::IAC, consts::WONT, consts::option::ECHO];
+        let output = &terminal.get_ref().output;
+        assert!(output.windows(3).any(|window| window == wont));
+    }
+
+    #[test]
+    fn test_read_hidden_with_editor() {
+        let mut input = vec![consts::IAC, consts::DO, consts::option::ECHO];
+        input.extend(b"secrex\x7ft\r\n");
+        let peer = Peer {
+            input,
+            ..Peer::default()
+        };
+        let mut terminal = NetworkVirtualTerminal::new(peer)
+            .with_line_editor(LineEditor::new());
+        let line = block_on(terminal.read_hidden("Password: ")).unwrap();
+        assert_eq!(Some("secret".to_string()), line);
+        assert!(terminal.line_editor().unwrap().history().is_empty());
+        let output = &terminal.get_ref().output;
+        assert!(!output.windows(3).any(|window| window == b"sec"));
+    }
+
+    #[test]
+    fn test_read_hidden_binary() {
+        let mut input = vec![consts::IAC, consts::WILL, consts::option::BINARY];
+        input.extend(&[consts::IAC, consts::DO, consts::option::ECHO]);
+        input.extend(b"secret\r\nmore");
+        input.extend(&[consts::IAC, consts::GA]);
+        let peer = Peer {
+            input,
+            ..Peer::default()
+        };
+        let mut terminal = NetworkVirtualTerminal::new(peer);
+        let binary = TelnetOption::TransmitBinary;
+        terminal.allow_option(TerminalEndpoint::Remote, binary);
+        let line = block_on(terminal.read_hidden("Password: ")).unwrap();
+        assert_eq!(Some("secret".to_string()), line);
+
+        let outputs = read_available(&mut terminal);
+        assert!(matches!(
+            &outputs[..],
+            [
+                TerminalOutput::OptionEnabled(TerminalEndpoint::Remote, _),
+                TerminalOutput::OptionEnabled(TerminalEndpoint::Local, _),
+                TerminalOutput::BinaryData(data),
+                TerminalOutput::GoAhead,
+                TerminalOutput::PromptComplete,
+            ] if data == b"more"
+        ));
+    }
+
+    #[test]
+    fn test_lines_end_at_logout() {
+        let mut input = b"look\r\n".to_vec();
+        input.extend(&[consts::IAC, consts::DO, consts::option::LOGOUT]);
+        input.extend(b"north\r\n");
+        let mut terminal = NetworkVirtualTerminal::new(Cursor::new(input))
+            .with_line_editor(LineEditor::new());
+        let lines: Vec<_> = block_on(terminal.lines().collect());
+        assert_eq!(vec!["look".to_string()], lines);
+    }
+
+    #[test]
+    fn test_answers_not_counted() {
+        let input = encode(vec![
+            TelnetFrame::Will(TelnetOption::NAWS),
+            TelnetFrame::Will(TelnetOption::GMCP),
+        ]);
+        let guard = NegotiationGuard::new().with_option_limit(0);
+        let mut terminal = NetworkVirtualTerminal::new(Cursor::new(input))
+            .with_negotiation_guard(guard);
+        let naws = OptionId::from(TelnetOption::NAWS);
+        terminal.request_option(TerminalEndpoint::Remote, naws);
+        let (outputs, _) = run(terminal);
+        assert!(matches!(
+            &outputs[..],
+            [
+                TerminalOutput::OptionEnabled(
+                    TerminalEndpoint::Remote,
+                    TelnetOption::NAWS
+                ),
+                TerminalOutput::NegotiationStorm(storm),
+            ] if storm.option == Some(OptionId::from(TelnetOption::GMCP))
+        ));
+    }
+
+    /// Terminal receiving 20 `DO` for an option it refuses, allowed 4 per
+    /// option.
+    fn flooded(response: StormResponse) -> NetworkVirtualTerminal<Peer> {
+        let input = [consts::IAC, consts::DO, 200].repeat(20);
+        let peer = Peer {
+            input,
+            ..Peer::default()
+        };
+        let guard = NegotiationGuard::new()
+            .with_window(Duration::from_secs(60))
+            .with_option_limit(4)
+            .with_response(response);
+        NetworkVirtualTerminal::new(peer).with_negotiation_guard(guard)
+    }
+
+    #[test]
+    fn test_negotiation_storm_dropped() {
+        let mut terminal = flooded(StormResponse::Ignore);
+        let outputs = read_available(&mut terminal);
+        let storms: Vec<_> = outputs
+            .iter()
+            .filter_map(|output| match output {
+                TerminalOutput::NegotiationStorm(storm) => Some(storm),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(1, storms.len());
+        let option = OptionId::from(TelnetOption::from(200));
+        assert_eq!(Some(option), storms[0].option);
+        assert_eq!(5, storms[0].count);
+        assert_eq!(StormResponse::Ignore, storms[0].response);
+
+        let peer = terminal.get_ref();
+        assert_eq!([consts::IAC, consts::WONT, 200].repeat(4), peer.output);
+        assert!(peer.input.is_empty());
+        assert!(!peer.closed);
+    }
+
+    #[test]
+    fn test_negotiation_storm_disconnects() {
+        let mut terminal = flooded(StormResponse::Disconnect);
+        let outputs = read_available(&mut terminal);
+        assert!(matches!(
+            outputs[..],
+            [TerminalOutput::NegotiationStorm(storm)]
+                if storm.response == StormResponse::Disconnect
+        ));
+        let waker = noop_waker();
+        let mut cx = Context::from_waker(&waker);
+        let next = terminal.poll_next_unpin(&mut cx);
+        assert!(matches!(next, Poll::Ready(None)));
+
+        let peer = terminal.get_ref();
+        assert_eq!([consts::IAC, consts::WONT, 200].repeat(4), peer.output);
+        assert!(peer.closed);
+    }
+
+    /// Terminal reading `input`, waiting 20 milliseconds for answers.
+    fn answering(input: Vec<u8>) -> NetworkVirtualTerminal<Peer> {
+        let peer = Peer {
+            input,
+            ..Peer::default()
+        };
+        NetworkVirtualTerminal::new(peer)
+            .with_negotiation_timeout(Duration::from_millis(20))
+    }
+
+    #[test]
+    fn test_enable_remote_accepted() {
+        let input = vec![consts::IAC, consts::WILL, consts::option::NAWS];
+        let mut terminal = answering(input);
+        let naws = TelnetOption::NAWS;
+        let result = block_on(terminal.enable_remote(naws)).unwrap();
+        assert_eq!(NegotiationResult::Accepted, result);
+        assert!(terminal.option_enabled(TerminalEndpoint::Remote, naws));
+        let expected = [consts::IAC, consts::DO, consts::option::NAWS];
+        assert_eq!(&expected[..], &terminal.get_ref().output[..]);
+    }
+
+    #[test]
+    fn test_enable_local_refused() {
+        let input = vec![consts::IAC, consts::DONT, consts::option::SGA];
+        let mut terminal = answering(input);
+        let sga = TelnetOption::SuppressGoAhead;
+        let result = block_on(terminal.enable_local(sga)).unwrap();
+        assert_eq!(NegotiationResult::Refused, result);
+        assert!(!terminal.option_enabled(TerminalEndpoint::Local, sga));
+        let expected = [consts::IAC, consts::WILL, consts::option::SGA];
+        assert_eq!(&expected[..], &terminal.get_ref().output[..]);
+    }
+
+    #[test]
+    fn test_enable_remote_timeout() {
+        let mut terminal = answering(Vec::new());
+        let naws = TelnetOption::NAWS;
+        let result = block_on(terminal.enable_remote(naws)).unwrap();
+        assert_eq!(NegotiationResult::TimedOut, result);
+        assert!(!terminal.option_enabled(TerminalEndpoint::Remote, naws));
+    }
+
+    #[test]
+    fn test_enable_remote_already_enabled() {
+        let input = vec![consts::IAC, consts::WILL, consts::option::SGA];
+        let mut terminal = answering(input);
+        read_available(&mut terminal);
+        let sga = TelnetOption::SuppressGoAhead;
+        assert!(terminal.option_enabled(TerminalEndpoint::Remote, sga));
+        let result = block_on(terminal.enable_remote(sga)).unwrap();
+        assert_eq!(NegotiationResult::Accepted, result);
+        let expected = [consts::IAC, consts::DO, consts::option::SGA];
+        assert_eq!(&expected[..], &terminal.get_ref().output[..]);
+    }
+
+    #[test]
+    fn test_negotiate_results_in_order() {
+        let input = vec![
+            consts::IAC,
+            consts::DONT,
+            consts::option::SGA,
+            consts::IAC,
+            consts::WILL,
+            consts::option::NAWS,
+        ];
+        let mut terminal = answering(input);
+        let options = [
+            (TerminalEndpoint::Remote, TelnetOption::NAWS),
+            (TerminalEndpoint::Local, TelnetOption::SuppressGoAhead),
+            (TerminalEndpoint::Remote, TelnetOption::TTYPE),
+        ];
+        let results = block_on(terminal.negotiate(&options)).unwrap();
+        let expected = vec![
+            NegotiationResult::Accepted,
+            NegotiationResult::Refused,
+            NegotiationResult::TimedOut,
+        ];
+        assert_eq!(expected, results);
+    }
+
+    #[test]
+    fn test_negotiate_keeps_received_output() {
+        let mut input = b"one\r\n".to_vec();
+        input.extend(&[consts::IAC, consts::AYT]);
+        input.extend(b"two\r\n");
+        input.extend(&[consts::IAC, consts::WILL, consts::option::NAWS]);
+        input.extend(b"three\r\n");
+        let mut terminal = answering(input);
+        let naws = TelnetOption::NAWS;
+        let result = block_on(terminal.enable_remote(naws)).unwrap();
+        assert_eq!(NegotiationResult::Accepted, result);
+        let outputs = read_available(&mut terminal);
+        assert!(matches!(
+            &outputs[..],
+            [
+                TerminalOutput::AsciiData(one),
+                TerminalOutput::AreYouThere,
+                TerminalOutput::AsciiData(two),
+                TerminalOutput::OptionEnabled(TerminalEndpoint::Remote, _),
+                TerminalOutput::AsciiData(three),
+            ] if one == "one\n" && two == "two\n" && three == "three\n"
+        ));
+    }
 }
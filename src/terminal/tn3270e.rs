@@ -0,0 +1,153 @@
+//
+// Copyright 2019 Hans W. Uhlig. All Rights Reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+use crate::codec::{
+    TN3270EFunction, TN3270EReason, TN3270EResource, TelnetArgument,
+    TelnetFrame, TelnetOption, TN3270E,
+};
+use crate::terminal::TerminalError;
+
+///
+/// Current state of TN3270E session negotiation.
+///
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum TN3270EStatus {
+    /// Waiting for the server to ask for our device type.
+    Idle,
+    /// Device type requested, waiting for the server's answer.
+    DeviceType,
+    /// Functions requested, waiting for the server's answer.
+    Functions,
+    /// Session is established, data is exchanged as TN3270E records.
+    Ready,
+    /// Server rejected our device type.
+    Rejected(TN3270EReason),
+}
+
+///
+/// Client side of TN3270E session negotiation [RFC2355](http://www.iana.org/go/rfc2355)
+///
+pub struct TN3270EClient {
+    device_type: String,
+    resource: Option<TN3270EResource>,
+    functions: Vec<TN3270EFunction>,
+    device_name: Option<String>,
+    status: TN3270EStatus,
+}
+
+impl TN3270EClient {
+    /// Create a new `TN3270EClient` requesting `device_type`, for example
+    /// `IBM-3278-2-E`.
+    pub fn new(device_type: &str) -> TN3270EClient {
+        TN3270EClient {
+            device_type: device_type.to_string(),
+            resource: None,
+            functions: Vec::new(),
+            device_name: None,
+            status: TN3270EStatus::Idle,
+        }
+    }
+    /// Request a specific terminal or printer resource.
+    pub fn with_resource(mut self, resource: TN3270EResource) -> TN3270EClient {
+        self.resource = Some(resource);
+        self
+    }
+    /// Functions to propose once the device type is accepted.
+    pub fn with_functions(
+        mut self,
+        functions: Vec<TN3270EFunction>,
+    ) -> TN3270EClient {
+        self.functions = functions;
+        self
+    }
+    /// Current state of the negotiation
+    pub fn status(&self) -> &TN3270EStatus {
+        &self.status
+    }
+    /// True once data is exchanged as TN3270E records.
+    pub fn ready(&self) -> bool {
+        self.status == TN3270EStatus::Ready
+    }
+    /// Device name assigned by the server.
+    pub fn device_name(&self) -> Option<&str> {
+        self.device_name.as_deref()
+    }
+    /// Functions agreed with the server.
+    pub fn functions(&self) -> &[TN3270EFunction] {
+        &self.functions
+    }
+    /// Handle a received TN3270E subnegotiation and produce the frames to
+    /// send in response.
+    pub fn handle(
+        &mut self,
+        argument: TN3270E,
+    ) -> Result<Vec<TelnetFrame>, TerminalError> {
+        match argument {
+            TN3270E::SendDeviceType => {
+                self.status = TN3270EStatus::DeviceType;
+                Ok(vec![frame(TN3270E::DeviceTypeRequest(
+                    self.device_type.clone(),
+                    self.resource.clone(),
+                ))])
+            },
+            TN3270E::DeviceTypeIs(device_type, device_name) => {
+                self.device_type = device_type;
+                self.device_name = Some(device_name);
+                self.status = TN3270EStatus::Functions;
+                Ok(vec![frame(TN3270E::FunctionsRequest(
+                    self.functions.clone(),
+                ))])
+            },
+            TN3270E::DeviceTypeReject(reason) => {
+                self.status = TN3270EStatus::Rejected(reason);
+                Ok(Vec::new())
+            },
+            TN3270E::FunctionsRequest(functions) => {
+                // Agree to any subset of what we proposed, otherwise counter
+                // with the functions we both support.
+                let common: Vec<TN3270EFunction> = functions
+                    .iter()
+                    .copied()
+                    .filter(|function| self.functions.contains(function))
+                    .collect();
+                if common.len() == functions.len() {
+                    self.functions = common;
+                    self.status = TN3270EStatus::Ready;
+                    Ok(vec![frame(TN3270E::FunctionsIs(functions))])
+                } else {
+                    self.functions = common;
+                    Ok(vec![frame(TN3270E::FunctionsRequest(
+                        self.functions.clone(),
+                    ))])
+                }
+            },
+            TN3270E::FunctionsIs(functions) => {
+                self.functions = functions;
+                self.status = TN3270EStatus::Ready;
+                Ok(Vec::new())
+            },
+            TN3270E::DeviceTypeRequest(_, _) => {
+                Err(TerminalError::NegotiationError)
+            },
+        }
+    }
+}
+
+fn frame(argument: TN3270E) -> TelnetFrame {
+    TelnetFrame::Subnegotiate(
+        TelnetOption::TN3270E,
+        TelnetArgument::TN3270E(argument),
+    )
+}
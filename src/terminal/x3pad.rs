@@ -0,0 +1,185 @@
+//
+// Copyright 2019 Hans W. Uhlig. All Rights Reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+use crate::codec::consts::option::x3pad::{forward, param};
+use crate::codec::{
+    TelnetArgument, TelnetFrame, TelnetOption, X3Parameter, X3Setting, X3PAD,
+};
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+///
+/// X.3 PAD parameter profile [RFC1053](http://www.iana.org/go/rfc1053)
+///
+/// Answers SET and SEND from the host, and records values the peer reports.
+/// Echo, data forwarding characters and the idle timer are applied to typed
+/// input by the `NetworkVirtualTerminal`.
+///
+#[derive(Clone, Debug)]
+pub struct X3Profile {
+    values: BTreeMap<u8, u8>,
+}
+
+impl X3Profile {
+    /// Create a new `X3Profile` with the X.28 simple standard profile.
+    pub fn new() -> X3Profile {
+        let values = [
+            (param::PAD_RECALL, 1),
+            (param::ECHO, 1),
+            (param::FORWARDING, 126),
+            (param::IDLE_TIMER, 0),
+            (param::ANCILLARY_DEVICE_CONTROL, 1),
+            (param::SERVICE_SIGNALS, 1),
+            (param::BREAK, 2),
+            (param::DISCARD_OUTPUT, 0),
+            (param::CR_PADDING, 0),
+            (param::LINE_FOLDING, 0),
+            (param::FLOW_CONTROL, 1),
+            (param::LF_INSERTION, 0),
+            (param::LF_PADDING, 0),
+            (param::EDITING, 0),
+            (param::CHARACTER_DELETE, 127),
+            (param::LINE_DELETE, 24),
+            (param::LINE_DISPLAY, 18),
+            (param::EDITING_SIGNALS, 1),
+            (param::ECHO_MASK, 0),
+            (param::PARITY, 0),
+            (param::PAGE_WAIT, 0),
+        ];
+        X3Profile {
+            values: values.iter().copied().collect(),
+        }
+    }
+    /// Set `parameter` to `value`.
+    pub fn with(mut self, parameter: X3Parameter, value: u8) -> X3Profile {
+        self.values.insert(u8::from(parameter), value);
+        self
+    }
+    /// Current value of `parameter`.
+    pub fn get(&self, parameter: X3Parameter) -> Option<u8> {
+        self.values.get(&u8::from(parameter)).copied()
+    }
+    /// True if typed characters are echoed locally.
+    pub fn echo(&self) -> bool {
+        self.get(X3Parameter::Echo) == Some(1)
+    }
+    /// True if typing `byte` forwards the buffered input.
+    pub fn forwards(&self, byte: u8) -> bool {
+        let class = match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' => forward::ALPHANUMERIC,
+            b'\r' => forward::CR,
+            0x1B | 0x07 | 0x05 | 0x06 => forward::ESCAPE,
+            0x7F | 0x18 | 0x12 => forward::EDITING,
+            0x03 | 0x04 => forward::TERMINATORS,
+            0x09..=0x0C => forward::FORMAT_EFFECTORS,
+            0x00..=0x1F => forward::CONTROL,
+            _ => 0,
+        };
+        self.get(X3Parameter::Forwarding).unwrap_or(0) & class != 0
+    }
+    /// Delay after which buffered input is forwarded, if any.
+    pub fn idle_timer(&self) -> Option<Duration> {
+        match self.get(X3Parameter::IdleTimer).unwrap_or(0) {
+            0 => None,
+            twentieths => {
+                Some(Duration::from_millis(u64::from(twentieths) * 50))
+            },
+        }
+    }
+    /// Host: Build the frame setting `settings` on the PAD.
+    pub fn set(settings: Vec<X3Setting>) -> TelnetFrame {
+        frame(X3PAD::Set(settings))
+    }
+    /// Host: Build the frame asking for `parameters`, or all if empty.
+    pub fn request(parameters: Vec<X3Parameter>) -> TelnetFrame {
+        frame(X3PAD::Send(parameters))
+    }
+    /// Apply a received X.3-PAD subnegotiation, returning the reply if any.
+    pub fn handle(&mut self, argument: X3PAD) -> Option<TelnetFrame> {
+        match argument {
+            X3PAD::Set(settings) => {
+                let applied = settings
+                    .into_iter()
+                    .filter_map(|setting| self.apply(setting))
+                    .collect();
+                Some(frame(X3PAD::ResponseSet(applied)))
+            },
+            X3PAD::Send(parameters) => {
+                let settings = if parameters.is_empty() {
+                    self.values
+                        .iter()
+                        .map(|(parameter, value)| {
+                            X3Setting::new(
+                                X3Parameter::from(*parameter),
+                                *value,
+                            )
+                        })
+                        .collect()
+                } else {
+                    parameters
+                        .into_iter()
+                        .filter_map(|parameter| {
+                            let value = self.get(parameter)?;
+                            Some(X3Setting::new(parameter, value))
+                        })
+                        .collect()
+                };
+                Some(frame(X3PAD::Is(settings)))
+            },
+            X3PAD::Is(settings) => {
+                self.record(&settings);
+                Some(frame(X3PAD::ResponseIs(settings)))
+            },
+            X3PAD::ResponseSet(settings) | X3PAD::ResponseIs(settings) => {
+                self.record(&settings);
+                None
+            },
+        }
+    }
+    /// Apply one setting from the host, returning the value now in effect.
+    /// Line speed is read only and unknown parameters are left out.
+    fn apply(&mut self, setting: X3Setting) -> Option<X3Setting> {
+        let parameter = u8::from(setting.parameter);
+        match setting.parameter {
+            X3Parameter::Unknown(_) => return None,
+            X3Parameter::BinarySpeed => {},
+            _ => {
+                self.values.insert(parameter, setting.value);
+            },
+        }
+        let value = self.values.get(&parameter).copied()?;
+        Some(X3Setting::new(setting.parameter, value))
+    }
+    /// Record values reported by the peer.
+    fn record(&mut self, settings: &[X3Setting]) {
+        for setting in settings {
+            self.values
+                .insert(u8::from(setting.parameter), setting.value);
+        }
+    }
+}
+
+impl Default for X3Profile {
+    fn default() -> Self {
+        X3Profile::new()
+    }
+}
+
+fn frame(argument: X3PAD) -> TelnetFrame {
+    TelnetFrame::Subnegotiate(
+        TelnetOption::X3PAD,
+        TelnetArgument::X3PAD(argument),
+    )
+}
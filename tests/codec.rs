@@ -14,4 +14,5 @@
 // limitations under the License.
 //
 
+#[allow(unused_imports, clippy::single_component_path_imports)]
 use telnet;